@@ -43,8 +43,12 @@ impl ExampleNode {
     pub fn run(&mut self) {
         while let Ok(event) = self.node.next_ev() {
             match event {
-                Event::Request { request, src, dst } => self.handle_request(request, src, dst),
-                Event::Response { response, src, dst } => self.handle_response(response, src, dst),
+                Event::Request {
+                    request, src, dst, ..
+                } => self.handle_request(request, src, dst),
+                Event::Response {
+                    response, src, dst, ..
+                } => self.handle_response(response, src, dst),
                 Event::NodeAdded(name, _routing_table) => {
                     trace!(
                         "{} Received NodeAdded event {:?}",
@@ -0,0 +1,35 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use routing::mock_crust::{self, Network};
+use routing::{Config, DevConfig, FullId, Node, MIN_SECTION_SIZE};
+
+// Feeds the input to a fresh first node, as if a peer sent it. This covers the handlers behind
+// the decoding, including connection info requests, which arrive as routing messages.
+fuzz_target!(|data: &[u8]| {
+    let network = Network::new(MIN_SECTION_SIZE, Some([1, 2, 3, 4]));
+    let handle = network.new_service_handle(None, None);
+    let mut node = mock_crust::make_current(&handle, || {
+        Node::builder()
+            .first(true)
+            .config(Config {
+                dev: Some(DevConfig {
+                    // Check the signatures right away, so that the input reaches the handlers.
+                    verifier_threads: Some(0),
+                    ..DevConfig::default()
+                }),
+            })
+            .create()
+            .expect("failed to create node")
+    });
+    let src = *FullId::new().public_id();
+    let _ = mock_crust::make_current(&handle, || node.fuzz_dispatch(src, data.to_vec()));
+});
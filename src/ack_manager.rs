@@ -15,6 +15,7 @@ use fake_clock::FakeClock as Instant;
 use maidsafe_utilities::serialisation;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::mem;
 use std::time::Duration;
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
@@ -100,6 +101,14 @@ impl AckManager {
         Some((unacked_msg, timed_out_ack))
     }
 
+    /// Removes and returns all pending messages.
+    pub fn take_pending(&mut self) -> Vec<UnacknowledgedMessage> {
+        mem::replace(&mut self.pending, BTreeMap::new())
+            .into_iter()
+            .map(|(_, unacked_msg)| unacked_msg)
+            .collect()
+    }
+
     // Removes a pending `UnacknowledgedMessage` and returns the same if found.
     pub fn remove(&mut self, ack: &Ack) -> Option<UnacknowledgedMessage> {
         self.pending.remove(ack)
@@ -6,14 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::endpoint_acl::IpRange;
 use crate::error::InterfaceError;
 use crate::id::PublicId;
-use crate::messages::DirectMessage;
+use crate::messages::{DirectMessage, HopMessage};
 use crate::messages::{Request, UserMessage};
-use crate::routing_table::Authority;
+use crate::peer_manager::PeerInfo;
+use crate::routing_table::{Authority, AuthorityExplanation};
+use crate::signature_accumulator::RefreshPolicy;
 use crate::xor_name::XorName;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
 use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::path::PathBuf;
 use std::sync::mpsc::Sender;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
 
 /// An Action initiates a message flow < A | B > where we are (a part of) A.
 ///    1. `Action::SendMessage` hands a fully formed `SignedMessage` over to `Core`
@@ -31,17 +40,80 @@ pub enum Action {
         priority: u8,
         result_tx: Sender<Result<(), InterfaceError>>,
     },
+    SendToGroup {
+        name: XorName,
+        content: UserMessage,
+        priority: u8,
+        result_tx: Sender<Result<(), InterfaceError>>,
+    },
+    SendToNode {
+        name: XorName,
+        content: UserMessage,
+        priority: u8,
+        result_tx: Sender<Result<(), InterfaceError>>,
+    },
     ClientSendRequest {
         content: Request,
         dst: Authority<XorName>,
         priority: u8,
+        /// If set, overrides the client's default message expiry.
+        expires_at: Option<Instant>,
         result_tx: Sender<Result<(), InterfaceError>>,
     },
     Id {
         result_tx: Sender<PublicId>,
     },
+    ProxyName {
+        result_tx: Sender<Option<XorName>>,
+    },
+    ListenerPort {
+        result_tx: Sender<Option<u16>>,
+    },
+    GetPeerInfo {
+        result_tx: Sender<Vec<PeerInfo>>,
+    },
+    ExplainAuthority {
+        auth: Authority<XorName>,
+        result_tx: Sender<AuthorityExplanation>,
+    },
+    IsNaeManagerFor {
+        name: XorName,
+        result_tx: Sender<Result<bool, InterfaceError>>,
+    },
+    SendRefresh {
+        src: Authority<XorName>,
+        type_tag: u64,
+        payload: Vec<u8>,
+        cause: Option<XorName>,
+        result_tx: Sender<Result<(), InterfaceError>>,
+    },
+    SetRefreshPolicy {
+        type_tag: u64,
+        policy: Option<RefreshPolicy>,
+    },
     Timeout(u64),
     ResourceProofResult(PublicId, Vec<DirectMessage>),
+    HopMessageVerified {
+        id: u64,
+        pub_id: PublicId,
+        hop_msg: HopMessage,
+        valid: bool,
+    },
+    AddContacts(Vec<PublicId>),
+    ResetRelayAccounting,
+    GetInternalState,
+    GetNetworkSizeEstimate {
+        result_tx: Sender<Option<(u64, bool)>>,
+    },
+    SetIpRanges {
+        allowed: Vec<IpRange>,
+        denied: Vec<IpRange>,
+    },
+    StartCapture {
+        path: PathBuf,
+        result_tx: Sender<io::Result<()>>,
+    },
+    StopCapture,
     Terminate,
 }
 
@@ -53,6 +125,24 @@ impl Debug for Action {
                 "Action::NodeSendMessage {{ {:?}, result_tx }}",
                 content
             ),
+            Action::SendToGroup {
+                ref name,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendToGroup {{ {:?}, name: {:?}, result_tx }}",
+                content, name
+            ),
+            Action::SendToNode {
+                ref name,
+                ref content,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendToNode {{ {:?}, name: {:?}, result_tx }}",
+                content, name
+            ),
             Action::ClientSendRequest {
                 ref content,
                 ref dst,
@@ -63,10 +153,69 @@ impl Debug for Action {
                 content, dst
             ),
             Action::Id { .. } => write!(formatter, "Action::Id"),
+            Action::ProxyName { .. } => write!(formatter, "Action::ProxyName"),
+            Action::ListenerPort { .. } => write!(formatter, "Action::ListenerPort"),
+            Action::GetPeerInfo { .. } => write!(formatter, "Action::GetPeerInfo"),
+            Action::ExplainAuthority { ref auth, .. } => {
+                write!(formatter, "Action::ExplainAuthority({:?})", auth)
+            }
+            Action::IsNaeManagerFor { ref name, .. } => {
+                write!(formatter, "Action::IsNaeManagerFor({:?})", name)
+            }
+            Action::SendRefresh {
+                ref src,
+                type_tag,
+                ref cause,
+                ..
+            } => write!(
+                formatter,
+                "Action::SendRefresh {{ {:?}, type_tag: {}, cause: {:?}, result_tx }}",
+                src, type_tag, cause
+            ),
+            Action::SetRefreshPolicy {
+                type_tag,
+                ref policy,
+            } => write!(
+                formatter,
+                "Action::SetRefreshPolicy {{ type_tag: {}, policy: {:?} }}",
+                type_tag, policy
+            ),
             Action::Timeout(token) => write!(formatter, "Action::Timeout({})", token),
             Action::ResourceProofResult(pub_id, _) => {
                 write!(formatter, "Action::ResourceProofResult({:?}, ...)", pub_id)
             }
+            Action::HopMessageVerified {
+                id,
+                ref pub_id,
+                valid,
+                ..
+            } => write!(
+                formatter,
+                "Action::HopMessageVerified {{ id: {}, pub_id: {:?}, valid: {}, .. }}",
+                id, pub_id, valid
+            ),
+            Action::AddContacts(ref contacts) => {
+                write!(formatter, "Action::AddContacts({:?})", contacts)
+            }
+            Action::SetIpRanges {
+                ref allowed,
+                ref denied,
+            } => write!(
+                formatter,
+                "Action::SetIpRanges {{ allowed: {:?}, denied: {:?} }}",
+                allowed, denied
+            ),
+            Action::ResetRelayAccounting => write!(formatter, "Action::ResetRelayAccounting"),
+            Action::GetInternalState => write!(formatter, "Action::GetInternalState"),
+            Action::GetNetworkSizeEstimate { .. } => {
+                write!(formatter, "Action::GetNetworkSizeEstimate")
+            }
+            Action::StartCapture { ref path, .. } => write!(
+                formatter,
+                "Action::StartCapture {{ path: {} }}",
+                path.display()
+            ),
+            Action::StopCapture => write!(formatter, "Action::StopCapture"),
             Action::Terminate => write!(formatter, "Action::Terminate"),
         }
     }
@@ -0,0 +1,382 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client::Client;
+use crate::data::ImmutableData;
+use crate::error::{InterfaceError, RoutingError};
+use crate::event::Event;
+use crate::id::FullId;
+use crate::messages::Response;
+use crate::routing_table::Authority;
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use crate::BootstrapConfig;
+use futures_core::Stream;
+use maidsafe_utilities::thread::{self, Joiner};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// How often the dispatcher checks for futures whose response is overdue.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A response we are waiting for, and the task to wake once it arrives.
+struct Slot {
+    /// The response, or the error the future resolves to instead.
+    result: Option<Result<Response, InterfaceError>>,
+    waker: Option<Waker>,
+    /// When we give up waiting for the response.
+    expires_at: Instant,
+}
+
+impl Slot {
+    fn new(expires_at: Instant) -> Self {
+        Slot {
+            result: None,
+            waker: None,
+            expires_at,
+        }
+    }
+
+    fn resolve(&mut self, result: Result<Response, InterfaceError>) {
+        self.result = Some(result);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// State shared between the dispatcher thread, the response futures and the event stream.
+#[derive(Default)]
+struct Shared {
+    pending: HashMap<MessageId, Slot>,
+    events: VecDeque<Event>,
+    events_waker: Option<Waker>,
+    /// Whether the client's event channel has been closed.
+    terminated: bool,
+}
+
+/// An asynchronous front-end to `Client`.
+///
+/// Requests return a `ResponseFuture` which resolves to the `Response` carrying the request's
+/// `MessageId`. All other events, including responses nobody waits for, are delivered by the
+/// `Events` stream returned from `new`. The client itself still runs on its own thread.
+pub struct AsyncClient {
+    client: Client,
+    shared: Arc<Mutex<Shared>>,
+    /// How long a future waits for its response.
+    response_timeout: Duration,
+    _joiner: Joiner,
+}
+
+impl AsyncClient {
+    /// Creates a new `Client` and starts dispatching its events. See `Client::new` for the
+    /// arguments. A future whose response didn't arrive within `msg_expiry_dur` resolves to
+    /// `InterfaceError::Timeout`.
+    pub fn new(
+        keys: Option<FullId>,
+        bootstrap_config: Option<BootstrapConfig>,
+        msg_expiry_dur: Duration,
+    ) -> Result<(AsyncClient, Events), RoutingError> {
+        let (event_tx, event_rx) = mpsc::channel();
+        let client = Client::new(event_tx, keys, bootstrap_config, msg_expiry_dur)?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let dispatcher_shared = Arc::clone(&shared);
+        let joiner = thread::named("Client event dispatcher", move || {
+            dispatch(&event_rx, &dispatcher_shared)
+        });
+        let events = Events {
+            shared: Arc::clone(&shared),
+        };
+        let async_client = AsyncClient {
+            client,
+            shared,
+            response_timeout: msg_expiry_dur,
+            _joiner: joiner,
+        };
+        Ok((async_client, events))
+    }
+
+    /// Returns the underlying `Client`.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Fetches `ImmutableData` with the given name from its `NaeManager`.
+    pub fn get(&mut self, name: XorName) -> Result<ResponseFuture, InterfaceError> {
+        self.send(|client, msg_id| client.get_idata(Authority::NaeManager(name), name, msg_id))
+    }
+
+    /// Stores `ImmutableData` via our `ClientManager`.
+    pub fn put(&mut self, data: ImmutableData) -> Result<ResponseFuture, InterfaceError> {
+        let dst = Authority::ClientManager(*self.client.id()?.name());
+        self.send(|client, msg_id| client.put_idata(dst, data, msg_id))
+    }
+
+    /// Sends any request via `send`, which is given a new `MessageId` to use for it, and returns
+    /// a future resolving to the response with that ID.
+    pub fn send<F>(&mut self, send: F) -> Result<ResponseFuture, InterfaceError>
+    where
+        F: FnOnce(&mut Client, MessageId) -> Result<(), InterfaceError>,
+    {
+        // Register first, so that the dispatcher can't miss a quick response.
+        let msg_id = MessageId::new();
+        let _ = unwrap!(self.shared.lock())
+            .pending
+            .insert(msg_id, Slot::new(Instant::now() + self.response_timeout));
+        if let Err(error) = send(&mut self.client, msg_id) {
+            let _ = unwrap!(self.shared.lock()).pending.remove(&msg_id);
+            return Err(error);
+        }
+        Ok(ResponseFuture {
+            msg_id,
+            shared: Arc::clone(&self.shared),
+        })
+    }
+}
+
+/// A future resolving to the response to a request sent via `AsyncClient`. It resolves to
+/// `InterfaceError::NotConnected` instead if the client terminates or the request fails to be sent,
+/// and to `InterfaceError::Timeout` if the response doesn't arrive in time.
+pub struct ResponseFuture {
+    msg_id: MessageId,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Future for ResponseFuture {
+    type Output = Result<Response, InterfaceError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut shared = unwrap!(self.shared.lock());
+        let result = shared
+            .pending
+            .get_mut(&self.msg_id)
+            .and_then(|slot| slot.result.take());
+        if let Some(result) = result {
+            let _ = shared.pending.remove(&self.msg_id);
+            return Poll::Ready(result);
+        }
+        if shared.terminated {
+            let _ = shared.pending.remove(&self.msg_id);
+            return Poll::Ready(Err(InterfaceError::NotConnected));
+        }
+        if let Some(slot) = shared.pending.get_mut(&self.msg_id) {
+            slot.waker = Some(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+impl Drop for ResponseFuture {
+    fn drop(&mut self) {
+        // A response arriving after this is delivered as an ordinary event.
+        if let Ok(mut shared) = self.shared.lock() {
+            let _ = shared.pending.remove(&self.msg_id);
+        }
+    }
+}
+
+/// The stream of events raised by an `AsyncClient`, except for the responses its futures resolve
+/// to. It ends once the client has terminated.
+pub struct Events {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Stream for Events {
+    type Item = Event;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Event>> {
+        let mut shared = unwrap!(self.shared.lock());
+        if let Some(event) = shared.events.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if shared.terminated {
+            return Poll::Ready(None);
+        }
+        shared.events_waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+// Hands each event to the future waiting for it, or queues it for the event stream, until the
+// client closes its event channel. Futures whose response is overdue are resolved with an error.
+fn dispatch(event_rx: &Receiver<Event>, shared: &Mutex<Shared>) {
+    let mut last_expiry_check = Instant::now();
+    loop {
+        // Checked on every iteration, so that a steady stream of events can't delay it.
+        if last_expiry_check.elapsed() >= EXPIRY_CHECK_INTERVAL {
+            expire(shared);
+            last_expiry_check = Instant::now();
+        }
+        let event = match event_rx.recv_timeout(EXPIRY_CHECK_INTERVAL) {
+            Ok(event) => event,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let mut shared = unwrap!(shared.lock());
+        let resolution = match event {
+            Event::Response { ref response, .. } => {
+                Some((*response.message_id(), Ok(response.clone())))
+            }
+            Event::RequestExpired { message_id, .. } => {
+                Some((message_id, Err(InterfaceError::Timeout)))
+            }
+            Event::RequestFailed { message_id, .. } => {
+                Some((message_id, Err(InterfaceError::NotConnected)))
+            }
+            _ => None,
+        };
+        if let Some((msg_id, result)) = resolution {
+            if let Some(slot) = shared.pending.get_mut(&msg_id) {
+                if slot.result.is_none() {
+                    slot.resolve(result);
+                    continue;
+                }
+            }
+        }
+        shared.events.push_back(event);
+        if let Some(waker) = shared.events_waker.take() {
+            waker.wake();
+        }
+    }
+
+    let mut shared = unwrap!(shared.lock());
+    shared.terminated = true;
+    for slot in shared.pending.values_mut() {
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+    if let Some(waker) = shared.events_waker.take() {
+        waker.wake();
+    }
+}
+
+// Resolves the futures whose response didn't arrive in time to `InterfaceError::Timeout`.
+fn expire(shared: &Mutex<Shared>) {
+    let now = Instant::now();
+    let mut shared = unwrap!(shared.lock());
+    for slot in shared.pending.values_mut() {
+        if slot.result.is_none() && slot.expires_at <= now {
+            slot.resolve(Err(InterfaceError::Timeout));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use std::collections::BTreeSet;
+    use std::task::Wake;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn dispatch_resolves_pending_responses() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let (event_tx, event_rx) = mpsc::channel();
+
+        let awaited_id = MessageId::new();
+        let other_id = MessageId::new();
+        let _ = unwrap!(shared.lock()).pending.insert(
+            awaited_id,
+            Slot::new(Instant::now() + Duration::from_secs(60)),
+        );
+        let mut future = ResponseFuture {
+            msg_id: awaited_id,
+            shared: Arc::clone(&shared),
+        };
+        let mut events = Events {
+            shared: Arc::clone(&shared),
+        };
+        assert!(Pin::new(&mut future).poll(&mut cx).is_pending());
+        assert!(Pin::new(&mut events).poll_next(&mut cx).is_pending());
+
+        let response = |msg_id| Event::Response {
+            response: Response::PutIData {
+                res: Ok(()),
+                msg_id,
+            },
+            src: Authority::ClientManager(rand::random()),
+            dst: Authority::ClientManager(rand::random()),
+            signers: BTreeSet::new(),
+        };
+        unwrap!(event_tx.send(response(other_id)));
+        unwrap!(event_tx.send(response(awaited_id)));
+        drop(event_tx);
+        dispatch(&event_rx, &shared);
+
+        match Pin::new(&mut future).poll(&mut cx) {
+            Poll::Ready(Ok(response)) => assert_eq!(*response.message_id(), awaited_id),
+            _ => panic!("Expected the awaited response."),
+        }
+        match Pin::new(&mut events).poll_next(&mut cx) {
+            Poll::Ready(Some(Event::Response { response, .. })) => {
+                assert_eq!(*response.message_id(), other_id)
+            }
+            _ => panic!("Expected the other response as an event."),
+        }
+        assert!(match Pin::new(&mut events).poll_next(&mut cx) {
+            Poll::Ready(None) => true,
+            _ => false,
+        });
+    }
+
+    #[test]
+    fn overdue_futures_resolve_to_errors() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let future = |msg_id, expires_at| {
+            let _ = unwrap!(shared.lock())
+                .pending
+                .insert(msg_id, Slot::new(expires_at));
+            ResponseFuture {
+                msg_id,
+                shared: Arc::clone(&shared),
+            }
+        };
+
+        let now = Instant::now();
+        let mut overdue = future(MessageId::new(), now);
+        let mut waiting = future(MessageId::new(), now + Duration::from_secs(60));
+        let expired_id = MessageId::new();
+        let mut expired = future(expired_id, now + Duration::from_secs(60));
+        expire(&shared);
+        match Pin::new(&mut overdue).poll(&mut cx) {
+            Poll::Ready(Err(InterfaceError::Timeout)) => (),
+            _ => panic!("Expected the overdue future to time out."),
+        }
+        assert!(Pin::new(&mut waiting).poll(&mut cx).is_pending());
+
+        // A request the client gave up on resolves its future right away.
+        let (event_tx, event_rx) = mpsc::channel();
+        unwrap!(event_tx.send(Event::RequestExpired {
+            message_id: expired_id,
+            dst: Authority::ClientManager(rand::random()),
+        }));
+        drop(event_tx);
+        dispatch(&event_rx, &shared);
+        match Pin::new(&mut expired).poll(&mut cx) {
+            Poll::Ready(Err(InterfaceError::Timeout)) => (),
+            _ => panic!("Expected the expired request's future to time out."),
+        }
+        assert!(unwrap!(shared.lock()).events.is_empty());
+    }
+}
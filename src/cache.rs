@@ -6,7 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::data::ImmutableData;
 use crate::messages::{Request, Response};
+use crate::routing_table::Prefix;
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use crate::RoutingError;
+use config_file_handler::{self, FileHandler};
+
+/// Maximum number of `ImmutableData` chunks written to disk by `persist`.
+const MAX_PERSISTED_CHUNKS: usize = 100;
 
 /// A cache that stores `Response`s keyed by `Requests`. Should be implemented
 /// by layers above routing.
@@ -16,6 +25,140 @@ pub trait Cache: Send {
 
     /// Cache the given response.
     fn put(&self, response: Response);
+
+    /// Removes any cached responses concerning the `MutableData` with the given name and type tag,
+    /// after we observed it being modified. The default implementation does nothing.
+    fn invalidate(&self, _name: &XorName, _tag: u64) {}
+
+    /// Returns whether responses carrying `MutableData` should be put into the cache. A cache
+    /// returning `true` should implement `mdata_version` and `invalidate`, so that it doesn't serve
+    /// outdated versions. The default implementation returns `false`.
+    fn caches_mdata(&self) -> bool {
+        false
+    }
+
+    /// Returns the version of the `MutableData` with the given name and type tag the cached
+    /// responses carry, if any. Responses carrying an older version are not put into the cache.
+    /// The default implementation returns `None`.
+    fn mdata_version(&self, _name: &XorName, _tag: u64) -> Option<u64> {
+        None
+    }
+
+    /// Returns the number of cached responses, which is reported to the other members of our
+    /// section as part of our load. The default implementation returns 0.
+    fn entry_count(&self) -> usize {
+        0
+    }
+
+    /// Returns the cached `ImmutableData` chunks, so that they can be persisted across restarts.
+    /// The default implementation returns none, i.e. nothing is persisted.
+    fn immutable_data(&self) -> Vec<ImmutableData> {
+        Vec::new()
+    }
+
+    /// Returns the cached `ImmutableData` chunks whose names match `prefix`, the most requested
+    /// ones first, with contents of at most `max_bytes` in total. They are sent to a new member of
+    /// our section to prime its cache. The default implementation returns the matching chunks of
+    /// `immutable_data` in their order, as long as they fit.
+    fn hottest_immutable_data(
+        &self,
+        prefix: &Prefix<XorName>,
+        max_bytes: u64,
+    ) -> Vec<ImmutableData> {
+        let mut remaining = max_bytes;
+        self.immutable_data()
+            .into_iter()
+            .filter(|data| prefix.matches(data.name()))
+            .filter(|data| {
+                let len = data.value().len() as u64;
+                if len > remaining {
+                    return false;
+                }
+                remaining -= len;
+                true
+            })
+            .collect()
+    }
+}
+
+/// Is notified whenever routing changes the contents of its `Cache`, e.g. so that layers above
+/// routing can mirror the cached data in their own stores or pre-warm them.
+pub trait CacheObserver: Send {
+    /// Called when the given response is put into the cache, right before the cache receives it.
+    fn on_put(&self, _response: &Response) {}
+
+    /// Called after the cached responses concerning the `MutableData` with the given name and type
+    /// tag were invalidated.
+    fn on_invalidate(&self, _name: &XorName, _tag: u64) {}
+}
+
+/// Statistics about how routing used its `Cache`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    /// The number of cacheable requests we looked up in the cache.
+    pub lookups: u64,
+    /// The number of those requests we found a cached response to.
+    pub hits: u64,
+    /// The number of responses we put into the cache.
+    pub puts: u64,
+    /// The number of responses we didn't cache, because the cache holds a newer version.
+    pub stale_responses: u64,
+    /// The number of mutations for which we invalidated cached responses.
+    pub invalidations: u64,
+    /// The number of cached responses, as reported by `Cache::entry_count`.
+    pub entries: usize,
+}
+
+/// A `Cache` which passes all calls on to another one and notifies an observer of changes.
+pub struct ObservedCache {
+    cache: Box<Cache>,
+    observer: Box<CacheObserver>,
+}
+
+impl ObservedCache {
+    pub fn new(cache: Box<Cache>, observer: Box<CacheObserver>) -> Self {
+        ObservedCache { cache, observer }
+    }
+}
+
+impl Cache for ObservedCache {
+    fn get(&self, request: &Request) -> Option<Response> {
+        self.cache.get(request)
+    }
+
+    fn put(&self, response: Response) {
+        self.observer.on_put(&response);
+        self.cache.put(response);
+    }
+
+    fn invalidate(&self, name: &XorName, tag: u64) {
+        self.cache.invalidate(name, tag);
+        self.observer.on_invalidate(name, tag);
+    }
+
+    fn caches_mdata(&self) -> bool {
+        self.cache.caches_mdata()
+    }
+
+    fn mdata_version(&self, name: &XorName, tag: u64) -> Option<u64> {
+        self.cache.mdata_version(name, tag)
+    }
+
+    fn entry_count(&self) -> usize {
+        self.cache.entry_count()
+    }
+
+    fn immutable_data(&self) -> Vec<ImmutableData> {
+        self.cache.immutable_data()
+    }
+
+    fn hottest_immutable_data(
+        &self,
+        prefix: &Prefix<XorName>,
+        max_bytes: u64,
+    ) -> Vec<ImmutableData> {
+        self.cache.hottest_immutable_data(prefix, max_bytes)
+    }
 }
 
 /// A no-op implementation of the `Cache` trait. Throws everything away on put
@@ -28,3 +171,116 @@ impl Cache for NullCache {
     }
     fn put(&self, _: Response) {}
 }
+
+/// Writes up to `MAX_PERSISTED_CHUNKS` of the chunks held by `cache` to disk.
+pub fn persist(cache: &Cache) -> Result<(), RoutingError> {
+    let chunks = cache
+        .immutable_data()
+        .into_iter()
+        .filter(ImmutableData::validate_size)
+        .take(MAX_PERSISTED_CHUNKS)
+        .collect::<Vec<_>>();
+    file_handler()?.write_file(&chunks)?;
+    Ok(())
+}
+
+/// Puts the chunks written by `persist` back into `cache` and returns how many there were.
+///
+/// Names are recomputed from the chunks' contents on deserialisation, so a corrupt entry can't be
+/// served in response to a request for the data it replaced. Oversized chunks are dropped.
+pub fn restore(cache: &Cache) -> Result<usize, RoutingError> {
+    let chunks: Vec<ImmutableData> = file_handler()?.read_file()?;
+    let mut count = 0;
+    for data in chunks.into_iter().take(MAX_PERSISTED_CHUNKS) {
+        if !data.validate_size() {
+            continue;
+        }
+        cache.put(Response::GetIData {
+            res: Ok(data),
+            msg_id: MessageId::new(),
+        });
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn file_handler() -> Result<FileHandler<Vec<ImmutableData>>, RoutingError> {
+    let mut name = config_file_handler::exe_file_stem()?;
+    name.push(".routing.cache");
+    Ok(FileHandler::new(&name, true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct CountingObserver {
+        puts: Arc<AtomicUsize>,
+        invalidations: Arc<AtomicUsize>,
+    }
+
+    impl CacheObserver for CountingObserver {
+        fn on_put(&self, _response: &Response) {
+            let _ = self.puts.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn on_invalidate(&self, _name: &XorName, _tag: u64) {
+            let _ = self.invalidations.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn observer_is_notified() {
+        let puts = Arc::new(AtomicUsize::new(0));
+        let invalidations = Arc::new(AtomicUsize::new(0));
+        let observer = CountingObserver {
+            puts: Arc::clone(&puts),
+            invalidations: Arc::clone(&invalidations),
+        };
+        let cache = ObservedCache::new(Box::new(NullCache), Box::new(observer));
+
+        cache.put(Response::PutIData {
+            res: Ok(()),
+            msg_id: MessageId::new(),
+        });
+        cache.invalidate(&XorName::default(), 0);
+        cache.invalidate(&XorName::default(), 1);
+        assert_eq!(puts.load(Ordering::SeqCst), 1);
+        assert_eq!(invalidations.load(Ordering::SeqCst), 2);
+    }
+
+    struct ChunkCache(Vec<ImmutableData>);
+
+    impl Cache for ChunkCache {
+        fn get(&self, _: &Request) -> Option<Response> {
+            None
+        }
+
+        fn put(&self, _: Response) {}
+
+        fn immutable_data(&self) -> Vec<ImmutableData> {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn hottest_immutable_data_fits_budget() {
+        let chunks: Vec<_> = (0..20u8)
+            .map(|i| ImmutableData::new(vec![i; 100]))
+            .collect();
+        let cache = ChunkCache(chunks.clone());
+
+        let prefix = Prefix::new(1, *chunks[0].name());
+        let matching: Vec<_> = chunks
+            .iter()
+            .filter(|data| prefix.matches(data.name()))
+            .cloned()
+            .collect();
+        assert_eq!(cache.hottest_immutable_data(&prefix, 10_000), matching);
+
+        let hottest = cache.hottest_immutable_data(&Prefix::default(), 250);
+        assert_eq!(hottest, chunks[..2].to_vec());
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Capture files of the raw messages a node sends and receives, for offline analysis.
+//!
+//! A capture is started with `Node::start_capture` and stopped with `Node::stop_capture`. The file
+//! starts with the 8 bytes `MAGIC`, followed by one record per message:
+//!
+//! | Field     | Size     | Content                                                |
+//! |-----------|----------|--------------------------------------------------------|
+//! | timestamp | 8        | Microseconds since the Unix epoch, little endian       |
+//! | direction | 1        | 0 if we received the message, 1 if we sent it         |
+//! | peer      | 32       | The name of the peer we received it from or sent it to |
+//! | length    | 4        | The number of bytes of the message, little endian      |
+//! | message   | `length` | The message as serialised on the wire                  |
+//!
+//! Records are appended, so a capture restarted with the same path continues the file.
+//! `CaptureReader` iterates over the records of a capture.
+
+use crate::id::PublicId;
+use crate::xor_name::{XorName, XOR_NAME_LEN};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The bytes every capture file starts with.
+pub const MAGIC: [u8; 8] = *b"RTCAP\x00\x00\x01";
+
+/// Whether a captured message was received or sent.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// We received the message.
+    Received,
+    /// We sent the message.
+    Sent,
+}
+
+/// A message read from a capture file.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CaptureRecord {
+    /// When the message was captured, in microseconds since the Unix epoch.
+    pub timestamp_micros: u64,
+    /// Whether we received or sent the message.
+    pub direction: Direction,
+    /// The peer the message was received from or sent to.
+    pub peer: XorName,
+    /// The message as serialised on the wire.
+    pub bytes: Vec<u8>,
+}
+
+/// Iterates over the records of a capture file.
+pub struct CaptureReader<R> {
+    inner: R,
+}
+
+impl CaptureReader<File> {
+    /// Opens the capture file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Reads a capture from `inner`, checking that it starts with `MAGIC`.
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut magic = [0; 8];
+        inner.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(io::Error::new(ErrorKind::InvalidData, "not a capture file"));
+        }
+        Ok(CaptureReader { inner })
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        let mut timestamp = [0; 8];
+        match self.inner.read_exact(&mut timestamp) {
+            Ok(()) => (),
+            Err(ref error) if error.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(error) => return Err(error),
+        }
+        let mut direction = [0; 1];
+        self.inner.read_exact(&mut direction)?;
+        let direction = match direction[0] {
+            0 => Direction::Received,
+            1 => Direction::Sent,
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, "invalid direction")),
+        };
+        let mut peer = [0; XOR_NAME_LEN];
+        self.inner.read_exact(&mut peer)?;
+        let mut len = [0; 4];
+        self.inner.read_exact(&mut len)?;
+        let mut bytes = vec![0; u32::from_le_bytes(len) as usize];
+        self.inner.read_exact(&mut bytes)?;
+        Ok(Some(CaptureRecord {
+            timestamp_micros: u64::from_le_bytes(timestamp),
+            direction,
+            peer: XorName(peer),
+            bytes,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_record() {
+            Ok(Some(record)) => Some(Ok(record)),
+            Ok(None) => None,
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+/// The capture a state writes the messages it sends and receives to, if one was started.
+#[derive(Default)]
+pub(crate) struct Capture {
+    writer: Option<BufWriter<File>>,
+}
+
+impl Capture {
+    /// Starts capturing to the file at `path`, stopping any capture in progress.
+    pub fn start(&mut self, path: &Path) -> io::Result<()> {
+        self.stop();
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut writer = BufWriter::new(file);
+        if writer.get_ref().metadata()?.len() == 0 {
+            writer.write_all(&MAGIC)?;
+        }
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    /// Stops capturing, flushing the file.
+    pub fn stop(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            if let Err(error) = writer.flush() {
+                warn!("Failed to flush the message capture: {:?}", error);
+            }
+        }
+    }
+
+    /// Appends a record of the message `bytes` to the capture, if one is in progress. If writing
+    /// fails, the capture is stopped.
+    pub fn record(&mut self, direction: Direction, peer: &PublicId, bytes: &[u8]) {
+        let result = match self.writer {
+            Some(ref mut writer) => write_record(writer, direction, peer.name(), bytes),
+            None => return,
+        };
+        if let Err(error) = result {
+            warn!("Stopping the message capture: {:?}", error);
+            self.writer = None;
+        }
+    }
+}
+
+fn write_record<W: Write>(
+    writer: &mut W,
+    direction: Direction,
+    peer: &XorName,
+    bytes: &[u8],
+) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros()))
+        .unwrap_or(0);
+    writer.write_all(&timestamp.to_le_bytes())?;
+    writer.write_all(&[match direction {
+        Direction::Received => 0,
+        Direction::Sent => 1,
+    }])?;
+    writer.write_all(&peer.0)?;
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn records_round_trip() {
+        let peer = rand::random();
+        let mut buf = MAGIC.to_vec();
+        unwrap!(write_record(&mut buf, Direction::Sent, &peer, &[1, 2, 3]));
+        unwrap!(write_record(&mut buf, Direction::Received, &peer, &[]));
+
+        let reader = unwrap!(CaptureReader::new(&buf[..]));
+        let records = unwrap!(reader.collect::<io::Result<Vec<_>>>());
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].direction, Direction::Sent);
+        assert_eq!(records[0].peer, peer);
+        assert_eq!(records[0].bytes, vec![1, 2, 3]);
+        assert_eq!(records[1].direction, Direction::Received);
+        assert!(records[1].bytes.is_empty());
+
+        assert!(CaptureReader::new(&[0; 8][..]).is_err());
+    }
+}
@@ -12,6 +12,8 @@ use crate::config_handler::{self, Config};
 use crate::data::{EntryAction, ImmutableData, MutableData, PermissionSet, User};
 use crate::error::{InterfaceError, RoutingError};
 use crate::event::Event;
+#[cfg(not(feature = "use-mock-crust"))]
+use crate::event_channel::EventSender;
 #[cfg(feature = "use-mock-crust")]
 use crate::event_stream::{EventStepper, EventStream};
 use crate::id::{FullId, PublicId};
@@ -26,15 +28,20 @@ use crate::xor_name::XorName;
 use crate::{BootstrapConfig, MIN_SECTION_SIZE};
 #[cfg(not(feature = "use-mock-crust"))]
 use crust::read_config_file as read_bootstrap_config_file;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
 #[cfg(not(feature = "use-mock-crust"))]
 use maidsafe_utilities::thread::{self, Joiner};
 #[cfg(not(feature = "use-mock-crust"))]
 use rust_sodium;
 use std::collections::{BTreeMap, BTreeSet};
+use std::iter;
 use std::sync::mpsc::{channel, Receiver, Sender};
 #[cfg(feature = "use-mock-crust")]
 use std::sync::mpsc::{RecvError, TryRecvError};
 use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
 
 /// Interface for sending and receiving messages to and from a network of nodes in the role of a
 /// client.
@@ -69,22 +76,26 @@ impl Client {
         let config = config.unwrap_or_else(config_handler::get_config);
         let dev_config = config.dev.unwrap_or_default();
         let min_section_size = dev_config.min_section_size.unwrap_or(MIN_SECTION_SIZE);
+        let lan_discovery = dev_config.lan_discovery_enabled();
 
         StateMachine::new(
             move |action_sender, crust_service, timer, _outbox2| {
                 Bootstrapping::new(
                     action_sender,
                     Box::new(NullCache),
+                    None,
                     BootstrappingTargetState::Client { msg_expiry_dur },
                     crust_service,
                     full_id,
                     min_section_size,
+                    dev_config,
                     timer,
                 )
                 .map_or(State::Terminated, State::Bootstrapping)
             },
             pub_id,
             bootstrap_config,
+            lan_discovery,
             outbox,
         )
     }
@@ -407,6 +418,95 @@ impl Client {
 
         self.send_request(dst, request, DEFAULT_PRIORITY)
     }
+
+    /// Fetches `ImmutableData` with the given name from its `NaeManager`.
+    ///
+    /// Returns the `MessageId` of the request, which the corresponding `Response::GetIData` event
+    /// will carry.
+    pub fn get_data(&mut self, name: XorName) -> Result<MessageId, InterfaceError> {
+        let msg_id = MessageId::new();
+        self.get_idata(Authority::NaeManager(name), name, msg_id)?;
+        Ok(msg_id)
+    }
+
+    /// Stores `ImmutableData` via our `ClientManager`.
+    ///
+    /// Returns the `MessageId` of the request, which the corresponding `Response::PutIData` event
+    /// will carry.
+    pub fn put_data(&mut self, data: ImmutableData) -> Result<MessageId, InterfaceError> {
+        let msg_id = MessageId::new();
+        let dst = Authority::ClientManager(self.our_name()?);
+        self.put_idata(dst, data, msg_id)?;
+        Ok(msg_id)
+    }
+
+    /// Applies the given entry actions to `MutableData` via our `ClientManager`.
+    ///
+    /// Returns the `MessageId` of the request, which the corresponding
+    /// `Response::MutateMDataEntries` event will carry.
+    pub fn post_data(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        actions: BTreeMap<Vec<u8>, EntryAction>,
+        requester: sign::PublicKey,
+    ) -> Result<MessageId, InterfaceError> {
+        let msg_id = MessageId::new();
+        let dst = Authority::ClientManager(self.our_name()?);
+        self.mutate_mdata_entries(dst, name, tag, actions, msg_id, requester)?;
+        Ok(msg_id)
+    }
+
+    /// Deletes the entry with the given key and version from `MutableData` via our
+    /// `ClientManager`.
+    ///
+    /// Returns the `MessageId` of the request, which the corresponding
+    /// `Response::MutateMDataEntries` event will carry.
+    pub fn delete_data(
+        &mut self,
+        name: XorName,
+        tag: u64,
+        key: Vec<u8>,
+        version: u64,
+        requester: sign::PublicKey,
+    ) -> Result<MessageId, InterfaceError> {
+        let actions = iter::once((key, EntryAction::Del(version))).collect();
+        self.post_data(name, tag, actions, requester)
+    }
+
+    /// Sends `request` to the group closest to `name`, e.g. the group managing the data called
+    /// `name`.
+    pub fn send_request_to_group(
+        &mut self,
+        name: XorName,
+        request: Request,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send_request(Authority::NaeManager(name), request, priority)
+    }
+
+    /// Sends `request` to the node called `name`.
+    pub fn send_request_to_node(
+        &mut self,
+        name: XorName,
+        request: Request,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send_request(Authority::ManagedNode(name), request, priority)
+    }
+
+    /// Sends the given request, but gives up on it once `ttl` has passed instead of after the
+    /// client's message expiry duration. This includes the time the request may be held back while
+    /// bootstrapping. If it expires before being acknowledged, `Event::RequestExpired` is raised.
+    pub fn send_request_with_ttl(
+        &mut self,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+        ttl: Duration,
+    ) -> Result<(), InterfaceError> {
+        self.send_request_until(dst, request, priority, Some(Instant::now() + ttl))
+    }
 }
 
 #[cfg(not(feature = "use-mock-crust"))]
@@ -427,6 +527,24 @@ impl Client {
         keys: Option<FullId>,
         bootstrap_config: Option<BootstrapConfig>,
         msg_expiry_dur: Duration,
+    ) -> Result<Client, RoutingError> {
+        Self::with_event_sender(
+            EventSender::from(event_sender),
+            keys,
+            bootstrap_config,
+            msg_expiry_dur,
+        )
+    }
+
+    /// Create a new `Client` which passes its events to the given `EventSender`.
+    ///
+    /// This is `new`, but it also accepts a bounded `SyncSender`, whose capacity then limits the
+    /// backlog of events the client keeps while the receiver doesn't keep up.
+    pub fn with_event_sender(
+        mut event_sender: EventSender,
+        keys: Option<FullId>,
+        bootstrap_config: Option<BootstrapConfig>,
+        msg_expiry_dur: Duration,
     ) -> Result<Client, RoutingError> {
         let _ = rust_sodium::init(); // enable shared global (i.e. safe to multithread now)
 
@@ -445,7 +563,9 @@ impl Client {
             );
 
             for ev in event_buffer.take_all() {
-                unwrap!(event_sender.send(ev));
+                if !event_sender.send(ev) {
+                    return;
+                }
             }
 
             unwrap!(get_action_sender_tx.send(action_sender));
@@ -455,7 +575,7 @@ impl Client {
             while Ok(()) == machine.step(&mut event_buffer) {
                 for ev in event_buffer.take_all() {
                     // If sending the event fails, terminate this thread.
-                    if event_sender.send(ev).is_err() {
+                    if !event_sender.send(ev) {
                         return;
                     }
                 }
@@ -482,21 +602,44 @@ impl Client {
         Ok(result_rx.recv()?)
     }
 
+    /// Returns the name of the proxy node relaying our messages, or `None` if we are not connected
+    /// yet. The proxy doesn't change during a session: if we lose it, `Event::Terminate` is raised.
+    pub fn proxy_name(&self) -> Result<Option<XorName>, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        self.action_sender.send(Action::ProxyName { result_tx })?;
+        Ok(result_rx.recv()?)
+    }
+
     /// Returns the bootstrap config that this client was created with.
     pub fn bootstrap_config() -> Result<BootstrapConfig, RoutingError> {
         Ok(read_bootstrap_config_file()?)
     }
 
+    fn our_name(&self) -> Result<XorName, InterfaceError> {
+        Ok(*self.id()?.name())
+    }
+
     fn send_request(
         &self,
         dst: Authority<XorName>,
         request: Request,
         priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send_request_until(dst, request, priority, None)
+    }
+
+    fn send_request_until(
+        &self,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+        expires_at: Option<Instant>,
     ) -> Result<(), InterfaceError> {
         let action = Action::ClientSendRequest {
             content: request,
             dst,
             priority,
+            expires_at,
             result_tx: self.interface_result_tx.clone(),
         };
 
@@ -539,12 +682,42 @@ impl Client {
         self.machine.id().ok_or(RoutingError::Terminated)
     }
 
+    /// Returns the name of the proxy node relaying our messages, or `None` if we are not connected
+    /// yet. The proxy doesn't change during a session: if we lose it, `Event::Terminate` is raised.
+    pub fn proxy_name(&mut self) -> Result<Option<XorName>, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::ProxyName { result_tx }, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        Ok(result_rx.recv()?)
+    }
+
+    fn our_name(&self) -> Result<XorName, InterfaceError> {
+        self.machine
+            .id()
+            .map(|id| *id.name())
+            .ok_or(InterfaceError::NotConnected)
+    }
+
     /// FIXME: Review the usage poll here
     pub fn send_request(
         &mut self,
         dst: Authority<XorName>,
         request: Request,
         priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send_request_until(dst, request, priority, None)
+    }
+
+    fn send_request_until(
+        &mut self,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+        expires_at: Option<Instant>,
     ) -> Result<(), InterfaceError> {
         // Make sure the state machine has processed any outstanding crust events.
         let _ = self.poll();
@@ -553,6 +726,7 @@ impl Client {
             content: request,
             dst,
             priority,
+            expires_at,
             result_tx: self.interface_result_tx.clone(),
         };
 
@@ -569,6 +743,12 @@ impl Client {
     pub fn get_user_msg_parts_count(&self) -> u64 {
         self.machine.current().get_user_msg_parts_count()
     }
+
+    /// Makes the messages we send claim to have been relayed by a second proxy, as a misbehaving
+    /// client might, so that tests can check that our proxy rejects them.
+    pub fn set_claim_relayed(&mut self, claim_relayed: bool) {
+        self.machine.current_mut().set_claim_relayed(claim_relayed);
+    }
 }
 
 #[cfg(feature = "use-mock-crust")]
@@ -0,0 +1,176 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::data::MAX_IMMUTABLE_DATA_SIZE_IN_BYTES;
+use crate::id::PublicId;
+use crate::messages::SignedMessage;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
+use maidsafe_utilities::serialisation;
+use std::collections::HashMap;
+use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
+
+/// The maximum number of bytes of messages we hold for a disconnected client.
+const MAX_BYTES_PER_CLIENT: u64 = 4 * MAX_IMMUTABLE_DATA_SIZE_IN_BYTES;
+/// The maximum number of bytes of messages we hold for all disconnected clients together.
+const MAX_TOTAL_BYTES: u64 = 16 * MAX_BYTES_PER_CLIENT;
+/// The maximum number of disconnected clients we hold messages for.
+const MAX_CLIENTS: usize = 100;
+
+struct Held {
+    dropped_at: Instant,
+    bytes: u64,
+    msgs: Vec<SignedMessage>,
+}
+
+/// Holds the messages for clients which recently lost their connection to us as their proxy.
+///
+/// A client which bootstraps off us again within the grace period, with the same public ID, gets
+/// the messages which arrived in the meantime, instead of them being dropped.
+pub struct ClientBuffer {
+    grace_period: Duration,
+    clients: HashMap<PublicId, Held>,
+    /// The number of bytes of all the messages held.
+    total_bytes: u64,
+}
+
+impl ClientBuffer {
+    pub fn new(grace_period: Duration) -> Self {
+        ClientBuffer {
+            grace_period,
+            clients: HashMap::new(),
+            total_bytes: 0,
+        }
+    }
+
+    /// Starts holding messages for `pub_id`, whose connection was lost.
+    pub fn client_dropped(&mut self, pub_id: PublicId) {
+        self.remove_expired();
+        if self.grace_period == Duration::from_secs(0) || self.clients.len() >= MAX_CLIENTS {
+            return;
+        }
+        let held = Held {
+            dropped_at: Instant::now(),
+            bytes: 0,
+            msgs: Vec::new(),
+        };
+        if let Some(old) = self.clients.insert(pub_id, held) {
+            self.total_bytes -= old.bytes;
+        }
+    }
+
+    /// Holds `msg` if `pub_id` is a client we hold messages for, and returns whether we do. The
+    /// message is not held if it would exceed `MAX_BYTES_PER_CLIENT` or `MAX_TOTAL_BYTES`.
+    pub fn push(&mut self, pub_id: &PublicId, msg: &SignedMessage) -> bool {
+        self.remove_expired();
+        let size = serialisation::serialised_size(msg);
+        if self.total_bytes + size > MAX_TOTAL_BYTES {
+            return false;
+        }
+        match self.clients.get_mut(pub_id) {
+            Some(held) if held.bytes + size <= MAX_BYTES_PER_CLIENT => {
+                held.bytes += size;
+                held.msgs.push(msg.clone());
+                self.total_bytes += size;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the messages held for `pub_id`, which reconnected, in the order they arrived.
+    pub fn take(&mut self, pub_id: &PublicId) -> Vec<SignedMessage> {
+        self.remove_expired();
+        match self.clients.remove(pub_id) {
+            Some(held) => {
+                self.total_bytes -= held.bytes;
+                held.msgs
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn remove_expired(&mut self) {
+        let grace_period = self.grace_period;
+        let total_bytes = &mut self.total_bytes;
+        self.clients.retain(|_, held| {
+            let keep = held.dropped_at.elapsed() < grace_period;
+            if !keep {
+                *total_bytes -= held.bytes;
+            }
+            keep
+        });
+    }
+}
+
+#[cfg(all(test, feature = "use-mock-crust"))]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use crate::messages::{MessageContent, RoutingMessage};
+    use crate::routing_table::{Authority, Prefix};
+    use fake_clock::FakeClock;
+    use rand;
+
+    fn message(full_id: &FullId) -> SignedMessage {
+        let routing_msg = RoutingMessage {
+            src: Authority::ClientManager(rand::random()),
+            dst: Authority::ClientManager(rand::random()),
+            content: MessageContent::SectionSplit(
+                Prefix::new(0, rand::random()).with_version(0),
+                rand::random(),
+            ),
+            trace: false,
+        };
+        unwrap!(SignedMessage::new(routing_msg, full_id, vec![]))
+    }
+
+    #[test]
+    fn holds_messages_within_grace_period() {
+        let full_id = FullId::new();
+        let client_id = *FullId::new().public_id();
+        let mut buffer = ClientBuffer::new(Duration::from_secs(10));
+        let msg = message(&full_id);
+
+        // We only hold messages for clients which were connected to us.
+        assert!(!buffer.push(&client_id, &msg));
+        buffer.client_dropped(client_id);
+        assert!(buffer.push(&client_id, &msg));
+        assert_eq!(buffer.take(&client_id), vec![msg.clone()]);
+        assert!(buffer.take(&client_id).is_empty());
+
+        // Once the grace period is over, the messages are dropped.
+        buffer.client_dropped(client_id);
+        assert!(buffer.push(&client_id, &msg));
+        FakeClock::advance_time(10 * 1000);
+        assert!(!buffer.push(&client_id, &msg));
+        assert!(buffer.take(&client_id).is_empty());
+        assert_eq!(buffer.total_bytes, 0);
+    }
+
+    #[test]
+    fn limits_bytes_per_client() {
+        let full_id = FullId::new();
+        let client_id = *FullId::new().public_id();
+        let mut buffer = ClientBuffer::new(Duration::from_secs(10));
+        let msg = message(&full_id);
+        let size = serialisation::serialised_size(&msg);
+
+        buffer.client_dropped(client_id);
+        let count = (MAX_BYTES_PER_CLIENT / size) as usize;
+        for _ in 0..count {
+            assert!(buffer.push(&client_id, &msg));
+        }
+        assert!(!buffer.push(&client_id, &msg));
+        assert_eq!(buffer.total_bytes, count as u64 * size);
+        assert_eq!(buffer.take(&client_id).len(), count);
+        assert_eq!(buffer.total_bytes, 0);
+    }
+}
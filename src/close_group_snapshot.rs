@@ -0,0 +1,79 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::crust::read_config_file as read_bootstrap_config_file;
+use crate::error::RoutingError;
+use crate::id::{FullId, PublicId};
+#[cfg(feature = "use-mock-crust")]
+use crate::mock_crust::to_endpoint;
+use crate::rust_sodium::crypto::sign::{self, Signature};
+use crate::BootstrapConfig;
+use config_file_handler::{self, FileHandler};
+use maidsafe_utilities::serialisation;
+use std::net::SocketAddr;
+
+/// The members of our section when we last shut down, with the addresses they listened on if we
+/// knew them, signed with the keys the node started with.
+///
+/// Only a node started with the same keys accepts the snapshot, so it can't be replaced by anyone
+/// who merely has write access to the file.
+#[derive(Serialize, Deserialize)]
+struct CloseGroupSnapshot {
+    members: Vec<(PublicId, Option<SocketAddr>)>,
+    signature: Signature,
+}
+
+/// Writes `members` to disk, signed by `full_id`.
+pub fn persist(
+    full_id: &FullId,
+    members: Vec<(PublicId, Option<SocketAddr>)>,
+) -> Result<(), RoutingError> {
+    let serialised = serialisation::serialise(&members)?;
+    let snapshot = CloseGroupSnapshot {
+        members,
+        signature: sign::sign_detached(&serialised, full_id.signing_private_key()),
+    };
+    file_handler()?.write_file(&snapshot)?;
+    Ok(())
+}
+
+/// Reads the members written by `persist`, if they were signed by `full_id`.
+pub fn restore(full_id: &FullId) -> Result<Vec<(PublicId, Option<SocketAddr>)>, RoutingError> {
+    let snapshot: CloseGroupSnapshot = file_handler()?.read_file()?;
+    let serialised = serialisation::serialise(&snapshot.members)?;
+    if !sign::verify_detached(
+        &snapshot.signature,
+        &serialised,
+        full_id.public_id().signing_public_key(),
+    ) {
+        return Err(RoutingError::FailedSignature);
+    }
+    Ok(snapshot.members)
+}
+
+/// Returns the Crust config which bootstraps off `contacts` in addition to the configured ones, or
+/// `None` if there are no `contacts`.
+pub fn bootstrap_config(contacts: Vec<SocketAddr>) -> Option<BootstrapConfig> {
+    if contacts.is_empty() {
+        return None;
+    }
+    let mut config = read_bootstrap_config_file().unwrap_or_else(|_| BootstrapConfig::default());
+    #[cfg(not(feature = "use-mock-crust"))]
+    config.hard_coded_contacts.extend(contacts);
+    #[cfg(feature = "use-mock-crust")]
+    config
+        .hard_coded_contacts
+        .extend(contacts.into_iter().map(to_endpoint));
+    Some(config)
+}
+
+fn file_handler() -> Result<FileHandler<CloseGroupSnapshot>, RoutingError> {
+    let mut name = config_file_handler::exe_file_stem()?;
+    name.push(".routing.close_group");
+    Ok(FileHandler::new(&name, true)?)
+}
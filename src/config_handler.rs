@@ -6,18 +6,22 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::endpoint_acl::IpRange;
+use crate::id::PublicId;
+use crate::messages::Quorum;
+use crate::rust_sodium::crypto::sign;
 use crate::RoutingError;
 use config_file_handler::{self, FileHandler};
 
 /// Configuration for routing
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Config {
     /// Developer options
     pub dev: Option<DevConfig>,
 }
 
 /// Extra configuration options intended for developers
-#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct DevConfig {
     /// Allow multiple nodes to run on a single machine or LAN
     pub allow_multiple_lan_nodes: bool,
@@ -27,6 +31,150 @@ pub struct DevConfig {
     pub disable_resource_proof: bool,
     /// Overrides default `MIN_SECTION_SIZE`
     pub min_section_size: Option<usize>,
+    /// Requests a `TraceReport` from every node handling the user messages we send
+    pub trace_user_messages: bool,
+    /// Overrides the default maximum number of clients we act as a proxy for
+    pub max_clients: Option<usize>,
+    /// Overrides the default maximum number of joining nodes we act as a proxy for at the same time
+    pub max_joining_nodes: Option<usize>,
+    /// Clients with these keys can always use us as their proxy, regardless of the client limit
+    pub reserved_client_keys: Vec<sign::PublicKey>,
+    /// Overrides the default maximum number of peers we try to connect to at the same time
+    pub max_concurrent_connects: Option<usize>,
+    /// Saves cached immutable data to disk on shutdown and restores it on startup
+    pub persist_cache: bool,
+    /// Saves our section's members and their addresses to disk on shutdown, and bootstraps off
+    /// them when restarted with the same `NodeBuilder::keys`
+    pub persist_close_group: bool,
+    /// Raises `Event::MessageDropped` whenever a received message is discarded
+    pub report_dropped_messages: bool,
+    /// Nodes we connect to as soon as they fit into our routing table, instead of waiting to
+    /// discover them
+    pub static_contacts: Vec<PublicId>,
+    /// Overrides the default maximum number of nodes our section admits within the join throttling
+    /// interval
+    pub max_joins_per_interval: Option<usize>,
+    /// If not empty, we only accept connections from peers whose IP lies in one of these ranges
+    pub allowed_ip_ranges: Vec<IpRange>,
+    /// We never accept connections from peers whose IP lies in one of these ranges
+    pub denied_ip_ranges: Vec<IpRange>,
+    /// Overrides the default maximum size in bytes of a message we accept from a peer
+    pub max_message_size: Option<usize>,
+    /// Overrides the default number of routing table changes within the churn storm interval
+    /// which is treated as a churn storm
+    pub churn_storm_changes: Option<usize>,
+    /// Overrides the default weights with which own messages, routing forwards and client relays
+    /// share our outgoing bandwidth
+    pub send_weights: Option<[u32; 3]>,
+    /// Overrides the default number of bytes we send per send interval before holding messages
+    /// back
+    pub send_budget: Option<u64>,
+    /// Overrides the default number of seconds for which signatures of a new routing table entry
+    /// don't count towards a quorum, unless it passed our resource proof
+    pub quarantine_secs: Option<u64>,
+    /// Overrides the default number of times we retry to bootstrap after failing, before giving up
+    pub bootstrap_retry_rounds: Option<usize>,
+    /// Overrides whether we use Crust's service discovery to find and be found by peers on the
+    /// LAN, which is enabled by default
+    pub lan_discovery: Option<bool>,
+    /// Overrides whether we raise `Event::SectionLoad` with the load reported by the other members
+    /// of our section, which is enabled by default
+    pub section_load_events: Option<bool>,
+    /// Overrides the default quorum of signatures a message from a group or section authority
+    /// needs to be accepted. Quorums below one half are ignored
+    pub accumulation_quorum: Option<Quorum>,
+    /// Overrides the quorum of signatures a refresh, i.e. a user message a group sends to itself,
+    /// needs to be accepted. Defaults to the accumulation quorum
+    pub refresh_quorum: Option<Quorum>,
+    /// Overrides whether we count the traffic we relay for each client and periodically raise
+    /// `Event::RelayAccounting`, which is enabled by default
+    pub relay_accounting_events: Option<bool>,
+    /// Writes outgoing client requests to a journal on disk until they are answered or expire
+    pub journal_client_requests: bool,
+    /// Saves a filter of the messages we handled to disk on shutdown, so that we don't handle them
+    /// again if we restart shortly afterwards
+    pub persist_message_filter: bool,
+    /// Overrides whether a client which loses its proxy bootstraps again instead of terminating,
+    /// which is enabled by default
+    pub client_rebootstrap: Option<bool>,
+    /// Once our section is complete after joining, asks its members for the cached immutable data
+    /// in our range, so that we can serve it from our cache right away
+    pub prime_cache: bool,
+    /// Overrides whether we keep the tunnel to a peer open once we connect to it directly, and
+    /// fail over to it if the direct connection is lost, which is disabled by default
+    pub standby_tunnels: Option<bool>,
+    /// Overrides whether we disconnect from our proxy once our section is complete, unless the
+    /// proxy is in our routing table, which is enabled by default
+    pub retire_proxy: Option<bool>,
+    /// Overrides whether we raise `Event::RefreshNeeded` whenever our close group changes, which
+    /// is enabled by default
+    pub refresh_needed_events: Option<bool>,
+    /// Overrides the default number of seconds for which we hold the messages for a client that
+    /// lost its connection to us, in case it bootstraps off us again
+    pub client_grace_period_secs: Option<u64>,
+    /// Overrides the default number of threads which check the signatures of incoming messages.
+    /// With none, they are checked on the event loop
+    pub verifier_threads: Option<usize>,
+}
+
+impl DevConfig {
+    /// Returns whether we use Crust's service discovery.
+    pub fn lan_discovery_enabled(&self) -> bool {
+        self.lan_discovery.unwrap_or(true)
+    }
+
+    /// Returns whether we raise `Event::SectionLoad`.
+    pub fn section_load_events_enabled(&self) -> bool {
+        self.section_load_events.unwrap_or(true)
+    }
+
+    /// Returns whether we raise `Event::RelayAccounting`.
+    pub fn relay_accounting_events_enabled(&self) -> bool {
+        self.relay_accounting_events.unwrap_or(true)
+    }
+
+    /// Returns whether a client bootstraps again after losing its proxy.
+    pub fn client_rebootstrap_enabled(&self) -> bool {
+        self.client_rebootstrap.unwrap_or(true)
+    }
+
+    /// Returns whether we keep tunnels as standby routes. As each one takes up capacity of its
+    /// tunnel node, it is opt-in.
+    pub fn standby_tunnels_enabled(&self) -> bool {
+        self.standby_tunnels.unwrap_or(false)
+    }
+
+    /// Returns whether we disconnect from our proxy once we don't need it anymore.
+    pub fn retire_proxy_enabled(&self) -> bool {
+        self.retire_proxy.unwrap_or(true)
+    }
+
+    /// Returns whether we raise `Event::RefreshNeeded`.
+    pub fn refresh_needed_events_enabled(&self) -> bool {
+        self.refresh_needed_events.unwrap_or(true)
+    }
+
+    /// Returns the configured accumulation quorum, or the default if it is unset or invalid.
+    pub fn accumulation_quorum(&self) -> Quorum {
+        valid_quorum(self.accumulation_quorum).unwrap_or_default()
+    }
+
+    /// Returns the configured refresh quorum, or the accumulation quorum if it is unset or
+    /// invalid.
+    pub fn refresh_quorum(&self) -> Quorum {
+        valid_quorum(self.refresh_quorum).unwrap_or_else(|| self.accumulation_quorum())
+    }
+}
+
+fn valid_quorum(quorum: Option<Quorum>) -> Option<Quorum> {
+    quorum.and_then(|quorum| {
+        if quorum.is_valid() {
+            Some(quorum)
+        } else {
+            warn!("Ignoring invalid quorum {:?} in routing config.", quorum);
+            None
+        }
+    })
 }
 
 /// Reads the routing config file and returns it or a default if this fails
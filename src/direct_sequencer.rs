@@ -0,0 +1,264 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::id::PublicId;
+use crate::messages::DirectMessage;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
+
+/// The maximum number of direct messages we hold back per peer while waiting for an earlier one.
+const MAX_EARLY_MESSAGES: usize = 32;
+/// How far ahead of the one we expect a sequence number can be. Messages further ahead can't have
+/// just been overtaken, so we drop them instead of skipping everything before them.
+const MAX_SEQ_GAP: u32 = 1024;
+/// Time (in seconds) after which we stop waiting for a missing direct message.
+const MISSING_MESSAGE_TIMEOUT_SECS: u64 = 10;
+
+/// Restores the order in which a peer sent us its direct messages.
+///
+/// Crust sends messages by priority, and a peer's messages may arrive both via a tunnel and
+/// directly, so they can overtake each other. Handlers like the one for `BootstrapRequest` expect
+/// to see the first message first, though. Every direct message carries the sender's sequence
+/// number for the messages to us, and messages which arrive early are held back until the missing
+/// ones arrive. As crust may also drop messages, we only wait for a missing one for a while.
+pub struct DirectSequencer {
+    next_outgoing: HashMap<PublicId, u32>,
+    incoming: HashMap<PublicId, Incoming>,
+}
+
+#[derive(Default)]
+struct Incoming {
+    /// The sequence number of the next message we expect.
+    next: u32,
+    /// The messages which arrived before the one we expect, by sequence number.
+    early: BTreeMap<u32, DirectMessage>,
+    /// When we started waiting for the expected message, if we hold back any.
+    waiting_since: Option<Instant>,
+}
+
+impl Incoming {
+    // Removes the messages which are no longer preceded by a missing one, in order.
+    fn take_ready(&mut self) -> Vec<DirectMessage> {
+        let mut ready = Vec::new();
+        while let Some(msg) = self.early.remove(&self.next) {
+            ready.push(msg);
+            self.next = self.next.wrapping_add(1);
+        }
+        self.waiting_since = if self.early.is_empty() {
+            None
+        } else {
+            self.waiting_since.or_else(|| Some(Instant::now()))
+        };
+        ready
+    }
+
+    // Stops waiting for the missing messages, returning all the early ones in order.
+    fn skip_missing(&mut self) -> Vec<DirectMessage> {
+        let early = mem::replace(&mut self.early, BTreeMap::new());
+        if let Some(&seq) = early.keys().next_back() {
+            self.next = seq.wrapping_add(1);
+        }
+        self.waiting_since = None;
+        early.into_iter().map(|(_, msg)| msg).collect()
+    }
+}
+
+impl DirectSequencer {
+    pub fn new() -> Self {
+        DirectSequencer {
+            next_outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+        }
+    }
+
+    /// Returns the sequence number for the next direct message to `pub_id`.
+    pub fn next_seq(&mut self, pub_id: PublicId) -> u32 {
+        let next = self.next_outgoing.entry(pub_id).or_insert(0);
+        let seq = *next;
+        *next = next.wrapping_add(1);
+        seq
+    }
+
+    /// Sets the sequence number of the next message we expect from `pub_id`, if the ones before
+    /// were handled before we had a sequencer.
+    pub fn expect(&mut self, pub_id: PublicId, seq: u32) {
+        let _ = self.incoming.insert(
+            pub_id,
+            Incoming {
+                next: seq,
+                ..Incoming::default()
+            },
+        );
+    }
+
+    /// Accepts the message `msg` with sequence number `seq` from `pub_id`, and returns the
+    /// messages which can now be handled, in order.
+    pub fn receive(
+        &mut self,
+        pub_id: PublicId,
+        seq: u32,
+        msg: DirectMessage,
+    ) -> Vec<DirectMessage> {
+        let incoming = self
+            .incoming
+            .entry(pub_id)
+            .or_insert_with(Incoming::default);
+        if seq < incoming.next {
+            // We gave up waiting for this one, or the peer restarted its count: handle it now.
+            return vec![msg];
+        }
+        if seq - incoming.next > MAX_SEQ_GAP {
+            debug!(
+                "Dropping {:?} from {}: sequence number {} is too far ahead of {}.",
+                msg, pub_id, seq, incoming.next
+            );
+            return vec![];
+        }
+        let _ = incoming.early.insert(seq, msg);
+        if incoming.early.len() > MAX_EARLY_MESSAGES {
+            incoming.skip_missing()
+        } else {
+            incoming.take_ready()
+        }
+    }
+
+    /// Returns the time until the first of the messages we hold back was held for too long, or
+    /// `None` if we don't hold back any.
+    pub fn time_to_expiry(&self) -> Option<Duration> {
+        let timeout = Duration::from_secs(MISSING_MESSAGE_TIMEOUT_SECS);
+        self.incoming
+            .values()
+            .filter_map(|incoming| incoming.waiting_since)
+            .map(|since| {
+                timeout
+                    .checked_sub(since.elapsed())
+                    .unwrap_or_else(|| Duration::from_secs(0))
+            })
+            .min()
+    }
+
+    /// Returns the messages which were held back for too long, in order for each peer.
+    pub fn take_expired(&mut self) -> Vec<(PublicId, DirectMessage)> {
+        let timeout = Duration::from_secs(MISSING_MESSAGE_TIMEOUT_SECS);
+        let mut expired = Vec::new();
+        for (pub_id, incoming) in &mut self.incoming {
+            if incoming
+                .waiting_since
+                .map_or(false, |since| since.elapsed() >= timeout)
+            {
+                expired.extend(
+                    incoming
+                        .skip_missing()
+                        .into_iter()
+                        .map(|msg| (*pub_id, msg)),
+                );
+            }
+        }
+        expired
+    }
+
+    /// Forgets the sequence numbers of `pub_id`, whose connection was lost. Messages held back
+    /// from it are dropped, as the missing ones won't arrive anymore.
+    pub fn remove(&mut self, pub_id: &PublicId) {
+        let _ = self.next_outgoing.remove(pub_id);
+        let _ = self.incoming.remove(pub_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+
+    #[test]
+    fn restores_order() {
+        let pub_id = *FullId::new().public_id();
+        let mut sequencer = DirectSequencer::new();
+        assert_eq!(sequencer.next_seq(pub_id), 0);
+        assert_eq!(sequencer.next_seq(pub_id), 1);
+
+        assert!(sequencer
+            .receive(pub_id, 1, DirectMessage::ResourceProofResponseReceipt)
+            .is_empty());
+        assert!(sequencer
+            .receive(pub_id, 2, DirectMessage::Disconnecting)
+            .is_empty());
+        let ready = sequencer.receive(pub_id, 0, DirectMessage::CandidateNameTaken);
+        assert_eq!(ready.len(), 3);
+        match (&ready[0], &ready[1], &ready[2]) {
+            (
+                &DirectMessage::CandidateNameTaken,
+                &DirectMessage::ResourceProofResponseReceipt,
+                &DirectMessage::Disconnecting,
+            ) => (),
+            messages => panic!("Unexpected order {:?}", messages),
+        }
+
+        // Once the connection is lost, the count starts anew. A number we have seen before means
+        // the peer did the same, so the message is handled right away.
+        sequencer.remove(&pub_id);
+        assert_eq!(sequencer.next_seq(pub_id), 0);
+        assert_eq!(
+            sequencer
+                .receive(pub_id, 0, DirectMessage::Disconnecting)
+                .len(),
+            1
+        );
+        assert_eq!(
+            sequencer
+                .receive(pub_id, 0, DirectMessage::Disconnecting)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn skips_missing_message() {
+        let pub_id = *FullId::new().public_id();
+        let mut sequencer = DirectSequencer::new();
+        for seq in 1..(MAX_EARLY_MESSAGES as u32 + 1) {
+            assert!(sequencer
+                .receive(pub_id, seq, DirectMessage::Disconnecting)
+                .is_empty());
+        }
+        let count = MAX_EARLY_MESSAGES + 1;
+        let ready = sequencer.receive(pub_id, count as u32, DirectMessage::Disconnecting);
+        assert_eq!(ready.len(), count);
+        assert_eq!(
+            sequencer
+                .receive(pub_id, 0, DirectMessage::Disconnecting)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn drops_messages_too_far_ahead() {
+        let pub_id = *FullId::new().public_id();
+        let mut sequencer = DirectSequencer::new();
+        for _ in 0..(MAX_EARLY_MESSAGES + 1) {
+            assert!(sequencer
+                .receive(pub_id, std::u32::MAX, DirectMessage::Disconnecting)
+                .is_empty());
+        }
+        assert!(sequencer
+            .receive(pub_id, MAX_SEQ_GAP + 1, DirectMessage::Disconnecting)
+            .is_empty());
+        assert_eq!(
+            sequencer
+                .receive(pub_id, 0, DirectMessage::Disconnecting)
+                .len(),
+            1
+        );
+    }
+}
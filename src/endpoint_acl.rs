@@ -0,0 +1,173 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// A range of IP addresses in CIDR notation, e.g. `10.0.0.0/8` or `fd00::/8`.
+///
+/// A range without a prefix length, e.g. `192.168.0.1`, contains only that single address.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    /// Creates a new range of all addresses sharing the first `prefix_len` bits with `addr`.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Result<IpRange, RoutingError> {
+        if prefix_len > max_prefix_len(&addr) {
+            return Err(RoutingError::InvalidIpRange(format!(
+                "{}/{}",
+                addr, prefix_len
+            )));
+        }
+        Ok(IpRange { addr, prefix_len })
+    }
+
+    /// Returns whether `ip` lies within this range. IPv4 ranges never contain IPv6 addresses and
+    /// vice versa.
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.addr, *ip) {
+            (IpAddr::V4(addr), IpAddr::V4(ip)) => {
+                let shift = 32 - u32::from(self.prefix_len);
+                shift == 32 || u32::from(addr) >> shift == u32::from(ip) >> shift
+            }
+            (IpAddr::V6(addr), IpAddr::V6(ip)) => {
+                let shift = 128 - u32::from(self.prefix_len);
+                shift == 128 || u128::from(addr) >> shift == u128::from(ip) >> shift
+            }
+            _ => false,
+        }
+    }
+}
+
+fn max_prefix_len(addr: &IpAddr) -> u8 {
+    match *addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = RoutingError;
+
+    fn from_str(s: &str) -> Result<IpRange, RoutingError> {
+        let invalid = || RoutingError::InvalidIpRange(s.to_owned());
+        let mut parts = s.splitn(2, '/');
+        let addr: IpAddr = parts
+            .next()
+            .unwrap_or("")
+            .trim()
+            .parse()
+            .map_err(|_| invalid())?;
+        let prefix_len = match parts.next() {
+            Some(len) => len.trim().parse().map_err(|_| invalid())?,
+            None => max_prefix_len(&addr),
+        };
+        IpRange::new(addr, prefix_len).map_err(|_| invalid())
+    }
+}
+
+impl Display for IpRange {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl Debug for IpRange {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "IpRange({})", self)
+    }
+}
+
+impl Serialize for IpRange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for IpRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IpRange, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| de::Error::custom(format!("invalid IP range: {}", s)))
+    }
+}
+
+/// Decides which peers' IP addresses we accept connections from.
+#[derive(Clone, Debug, Default)]
+pub struct EndpointAcl {
+    allowed: Vec<IpRange>,
+    denied: Vec<IpRange>,
+}
+
+impl EndpointAcl {
+    pub fn new(allowed: Vec<IpRange>, denied: Vec<IpRange>) -> EndpointAcl {
+        EndpointAcl { allowed, denied }
+    }
+
+    /// Returns `true` if no ranges are allowed or denied, i.e. all addresses are allowed.
+    pub fn is_empty(&self) -> bool {
+        self.allowed.is_empty() && self.denied.is_empty()
+    }
+
+    /// Returns `false` if `ip` is in a denied range, or if there are allowed ranges and `ip` is in
+    /// none of them.
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        if self.denied.iter().any(|range| range.contains(ip)) {
+            return false;
+        }
+        self.allowed.is_empty() || self.allowed.iter().any(|range| range.contains(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        unwrap!(s.parse())
+    }
+
+    fn range(s: &str) -> IpRange {
+        unwrap!(s.parse())
+    }
+
+    #[test]
+    fn parse_and_contains() {
+        assert!(range("10.0.0.0/8").contains(&ip("10.1.2.3")));
+        assert!(!range("10.0.0.0/8").contains(&ip("11.0.0.1")));
+        assert!(range("0.0.0.0/0").contains(&ip("192.168.0.1")));
+        assert!(!range("0.0.0.0/0").contains(&ip("::1")));
+        assert!(range("192.168.0.1").contains(&ip("192.168.0.1")));
+        assert!(!range("192.168.0.1").contains(&ip("192.168.0.2")));
+        assert!(range("fd00::/8").contains(&ip("fd12::1")));
+        assert!(!range("fd00::/8").contains(&ip("fe80::1")));
+
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("10.0.0/8".parse::<IpRange>().is_err());
+        assert!("10.0.0.0/x".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn deny_overrides_allow() {
+        let acl = EndpointAcl::new(vec![range("10.0.0.0/8")], vec![range("10.0.1.0/24")]);
+        assert!(acl.is_allowed(&ip("10.0.0.1")));
+        assert!(!acl.is_allowed(&ip("10.0.1.1")));
+        assert!(!acl.is_allowed(&ip("192.168.0.1")));
+
+        let acl = EndpointAcl::new(vec![], vec![range("10.0.1.0/24")]);
+        assert!(acl.is_allowed(&ip("192.168.0.1")));
+        assert!(!acl.is_allowed(&ip("10.0.1.1")));
+    }
+}
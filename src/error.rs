@@ -11,12 +11,38 @@ use crate::action::Action;
 use crate::crust::CrustError;
 use crate::event::Event;
 use crate::id::PublicId;
+use crate::routing_table::Authority;
 use crate::sha3::Digest256;
+use crate::types::MessageId;
+use crate::xor_name::XorName;
 use config_file_handler::Error as ConfigFileHandlerError;
 use maidsafe_utilities::event_sender::{EventSenderError, MaidSafeEventCategory};
 use maidsafe_utilities::serialisation;
 use std::sync::mpsc::{RecvError, SendError};
 
+/// How an error should be treated by the caller, e.g. when deciding whether to retry.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ErrorCategory {
+    /// The operation may succeed if retried later, e.g. once connected or after churn settled.
+    Transient,
+    /// Retrying the same operation will fail again.
+    Permanent,
+    /// A peer or message failed a check, e.g. of its signatures; it shouldn't be retried, and
+    /// may warrant distrusting the peer.
+    Security,
+}
+
+/// Where and on what a `RoutingError` occurred.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorContext {
+    /// What we were doing, e.g. `"dispatching"`.
+    pub stage: &'static str,
+    /// The ID of the message being handled, if it has one.
+    pub message_id: Option<MessageId>,
+    /// The peer the message was received from, if known.
+    pub peer: Option<PublicId>,
+}
+
 /// The type of errors that can occur if routing is unable to handle a send request.
 #[derive(Debug)]
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
@@ -30,6 +56,35 @@ pub enum InterfaceError {
     ChannelRxError(RecvError),
     /// Error while trying to transmit an event via a channel
     EventSenderError(EventSenderError<MaidSafeEventCategory, Action>),
+    /// The message can't be sent from `src` to `dst`.
+    BadAuthority {
+        /// The requested source authority.
+        src: Authority<XorName>,
+        /// The requested destination authority.
+        dst: Authority<XorName>,
+        /// Why this combination of authorities was rejected.
+        reason: &'static str,
+    },
+    /// The node's thread didn't answer in time. It may be waiting for the receiver of its events,
+    /// which must not wait for the node in turn.
+    Timeout,
+    /// An I/O operation the node was asked to perform failed, e.g. opening a capture file.
+    Io(::std::io::Error),
+}
+
+impl InterfaceError {
+    /// Returns how the error should be treated by the caller.
+    pub fn category(&self) -> ErrorCategory {
+        match *self {
+            InterfaceError::NotConnected
+            | InterfaceError::InvalidState
+            | InterfaceError::Timeout => ErrorCategory::Transient,
+            InterfaceError::ChannelRxError(_)
+            | InterfaceError::EventSenderError(_)
+            | InterfaceError::BadAuthority { .. }
+            | InterfaceError::Io(_) => ErrorCategory::Permanent,
+        }
+    }
 }
 
 impl From<EventSenderError<MaidSafeEventCategory, Action>> for InterfaceError {
@@ -44,6 +99,12 @@ impl From<RecvError> for InterfaceError {
     }
 }
 
+impl From<::std::io::Error> for InterfaceError {
+    fn from(error: ::std::io::Error) -> InterfaceError {
+        InterfaceError::Io(error)
+    }
+}
+
 /// The type of errors that can occur during handling of routing events.
 #[derive(Debug)]
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
@@ -128,6 +189,93 @@ pub enum RoutingError {
     ExceedsRateLimit(Digest256),
     /// Invalid configuration
     ConfigError(ConfigFileHandlerError),
+    /// A string could not be parsed as an IP range
+    InvalidIpRange(String),
+    /// A received message, or a list or payload within it, exceeds the size limit
+    MessageTooLarge,
+    /// The current state can't make the requested state transition
+    InvalidTransition,
+    /// The crypto library could not be initialised with the given seed
+    InitialisationFailed,
+    /// Another error, together with where and on what it occurred
+    WithContext(Box<RoutingError>, ErrorContext),
+}
+
+impl RoutingError {
+    /// Attaches the given context to the error. If it already has one, that is kept instead, as
+    /// it is the more specific.
+    pub fn with_context(self, context: ErrorContext) -> RoutingError {
+        match self {
+            RoutingError::WithContext(..) => self,
+            error => RoutingError::WithContext(Box::new(error), context),
+        }
+    }
+
+    /// Returns the context of the error, if any.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match *self {
+            RoutingError::WithContext(_, ref context) => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its context.
+    pub fn root(&self) -> &RoutingError {
+        match *self {
+            RoutingError::WithContext(ref error, _) => error.root(),
+            ref error => error,
+        }
+    }
+
+    /// Returns how the error should be treated by the caller.
+    pub fn category(&self) -> ErrorCategory {
+        match *self {
+            RoutingError::NotBootstrapped
+            | RoutingError::AlreadyConnected
+            | RoutingError::AlreadyHandlingJoinRequest
+            | RoutingError::FailedToBootstrap
+            | RoutingError::Io(_)
+            | RoutingError::Crust(_)
+            | RoutingError::InvalidStateForOperation
+            | RoutingError::UnknownConnection(_)
+            | RoutingError::ProxyConnectionNotFound
+            | RoutingError::ClientConnectionNotFound
+            | RoutingError::UnknownCandidate
+            | RoutingError::TimedOut
+            | RoutingError::CandidateIsTunnelling
+            | RoutingError::ExceedsRateLimit(_) => ErrorCategory::Transient,
+            RoutingError::Terminated
+            | RoutingError::BadAuthority
+            | RoutingError::UnknownMessageType
+            | RoutingError::InvalidOwners
+            | RoutingError::FilterCheckFailed
+            | RoutingError::RoutingTable(_)
+            | RoutingError::Utf8(_)
+            | RoutingError::SendEventError(_)
+            | RoutingError::SerialisationError(_)
+            | RoutingError::InvalidDestination
+            | RoutingError::CannotTunnelThroughTunnel
+            | RoutingError::InvalidSuccessor
+            | RoutingError::ConfigError(_)
+            | RoutingError::InvalidIpRange(_)
+            | RoutingError::InvalidTransition
+            | RoutingError::InitialisationFailed => ErrorCategory::Permanent,
+            RoutingError::FailedSignature
+            | RoutingError::NotEnoughSignatures
+            | RoutingError::DuplicateSignatures
+            | RoutingError::InvalidRelocationTargetRange
+            | RoutingError::RejectedClientMessage
+            | RoutingError::AsymmetricDecryptionFailure
+            | RoutingError::InvalidSource
+            | RoutingError::HashMismatch
+            | RoutingError::FailedResourceProofValidation
+            | RoutingError::InvalidMessage
+            | RoutingError::InvalidPeer
+            | RoutingError::MessageTooLarge => ErrorCategory::Security,
+            RoutingError::Interface(ref error) => error.category(),
+            RoutingError::WithContext(ref error, _) => error.category(),
+        }
+    }
 }
 
 impl From<RoutingTableError> for RoutingError {
@@ -194,5 +342,41 @@ quick_error! {
             display("The chosen proxy node already has connections to the maximum number of \
                      clients allowed per proxy.")
         }
+        JoiningNodeLimit {
+            description("Proxy has max. joining nodes")
+            display("The chosen proxy node is already relaying for the maximum number of \
+                     joining nodes allowed per proxy.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_keeps_root_and_category() {
+        let message_id = Some(MessageId::new());
+        let inner = ErrorContext {
+            stage: "dispatching",
+            message_id,
+            peer: None,
+        };
+        let error = RoutingError::FailedSignature
+            .with_context(inner)
+            .with_context(ErrorContext::default());
+
+        assert_eq!(error.category(), ErrorCategory::Security);
+        assert!(match *error.root() {
+            RoutingError::FailedSignature => true,
+            _ => false,
+        });
+        let context = unwrap!(error.context());
+        assert_eq!(context.stage, "dispatching");
+        assert_eq!(context.message_id, message_id);
+
+        let error = RoutingError::Interface(InterfaceError::NotConnected);
+        assert_eq!(error.category(), ErrorCategory::Transient);
+        assert!(error.context().is_none());
     }
 }
@@ -6,11 +6,16 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::messages::{Request, Response};
+use crate::id::PublicId;
+use crate::messages::{NodeLoad, RelocationDenial, Request, Response};
+use crate::relay_accounting::RelayedTraffic;
 use crate::routing_table::Authority;
 use crate::routing_table::{Prefix, RoutingTable};
+use crate::types::MessageId;
 use crate::xor_name::XorName;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use std::net::SocketAddr;
 
 /// An Event raised by a `Node` or `Client` via its event sender.
 ///
@@ -31,6 +36,9 @@ pub enum Event {
         src: Authority<XorName>,
         /// The destination authority that receives the request.
         dst: Authority<XorName>,
+        /// The nodes whose signatures the request carried. For a refresh, these are the members of
+        /// our section or group which contributed to it.
+        signers: BTreeSet<PublicId>,
     },
     /// Received a response message.
     Response {
@@ -40,25 +48,235 @@ pub enum Event {
         src: Authority<XorName>,
         /// The destination authority that receives the response.
         dst: Authority<XorName>,
+        /// The nodes whose signatures the response carried. If `src` is a group or section, these
+        /// are the members which backed the response, so that members who diverged can be told
+        /// apart.
+        signers: BTreeSet<PublicId>,
+    },
+    /// Received a message of a user-defined kind.
+    UserMessage {
+        /// The user-defined kind of the message.
+        tag: u64,
+        /// The message content.
+        payload: Vec<u8>,
+        /// The source authority that sent the message.
+        src: Authority<XorName>,
+        /// The destination authority that receives the message.
+        dst: Authority<XorName>,
     },
     /// A node has connected to us.
     NodeAdded(XorName, RoutingTable<XorName>),
     /// A node has disconnected from us.
     NodeLost(XorName, RoutingTable<XorName>),
+    /// A node joined our own or a neighbouring section and made it split, so we dropped a node from
+    /// our routing table which is no longer our neighbour.
+    NodeReplaced {
+        /// The name of the node we dropped.
+        evicted: XorName,
+        /// The name of the node whose joining caused the split.
+        added: XorName,
+    },
+    /// A node joined or left our close group, i.e. the `min_section_size` nodes closest to us,
+    /// which are responsible for the same data. Raised once for each node which joined or left,
+    /// unless disabled in the config. The user should republish the state it holds via
+    /// `Node::send_refresh` with the given cause, the name of the node which joined or left, rather
+    /// than waiting for another member's refresh.
+    RefreshNeeded {
+        /// The name of the node whose churn changed our close group.
+        cause: XorName,
+    },
     /// Our own section has been split, resulting in the included `Prefix` for our new section.
     SectionSplit(Prefix<XorName>),
     /// Our own section requires merged with others, resulting in the included `Prefix` for our new
     /// section.
     SectionMerge(Prefix<XorName>),
+    /// Our routing table changed many times within a short interval. Section messages are more
+    /// likely to fail to reach quorum until it settles, so the user may want to defer writes.
+    ChurnStormDetected {
+        /// The number of routing table changes within the interval.
+        changes: usize,
+        /// The interval in seconds.
+        interval_secs: u64,
+    },
+    /// The load reported by the other members of our section, raised periodically if enabled in
+    /// the config.
+    SectionLoad {
+        /// The number of members whose reports are included.
+        members: usize,
+        /// The sum of their reported loads.
+        total: NodeLoad,
+    },
+    /// The traffic we relayed for each of our clients since the counters were last reset via
+    /// `Node::reset_relay_accounting`. Raised periodically if enabled in the config.
+    RelayAccounting(BTreeMap<PublicId, RelayedTraffic>),
+    /// A snapshot of our state, raised in response to `Node::request_internal_state`, e.g. for
+    /// monitoring tools.
+    InternalState {
+        /// Our current name, which changes when we are relocated.
+        name: XorName,
+        /// The state we are in.
+        state: StateKind,
+        /// The number of entries in our routing table.
+        table_size: usize,
+        /// Our estimate of the number of nodes in the network, if we have a routing table.
+        network_size: Option<u64>,
+        /// The `min_section_size` nodes closest to our name, including us.
+        close_group: Vec<XorName>,
+        /// The name of the proxy node relaying our messages, if we have one.
+        proxy: Option<XorName>,
+    },
+    /// A node was added to our routing table while our section is still smaller than the group
+    /// size. Raised after `Connected`, until the first time our section is complete, so that the
+    /// progress of joining can be monitored.
+    RoutingTableGrowth {
+        /// The number of entries in our routing table.
+        size: usize,
+        /// The number of members our section still needs to reach the group size. Once this is
+        /// zero, the event isn't raised anymore.
+        needed_for_group: usize,
+    },
     /// The client has successfully connected to a proxy node on the network.
     Connected,
+    /// The seed node has started listening and a new network has been started with us as its
+    /// only member.
+    NetworkStarted,
+    /// The seed node lost its last routing connection. Other nodes raise `RestartRequired` instead,
+    /// but the seed node keeps running, so that the network can be re-formed around it.
+    Disconnected,
     /// Disconnected or failed to connect - restart required.
     RestartRequired,
+    /// Our proxy node with the given name didn't present signatures of its public ID from a quorum
+    /// of its section. It may not be a genuine member of the network, though this is also
+    /// expected while the network is smaller than the minimum section size.
+    ProxyUnrecognised(XorName),
+    /// We bootstrapped off a peer on our LAN. Only raised if LAN discovery is enabled. Crust
+    /// doesn't tell whether the peer was found via service discovery or configured, so this is
+    /// meant for diagnostics.
+    LanPeerFound(SocketAddr),
+    /// We gave up connecting to the node with the given name: neither a direct connection nor a
+    /// tunnel was established in time. We retry later, after a backoff, if it is still needed.
+    ConnectFailed(XorName),
+    /// Bootstrapping failed in every retry round. It is followed by `Terminate`.
+    BootstrapFailed,
+    /// Our proxy node is shutting down. It is followed by `Terminate`. The endpoints are those of
+    /// other members of the proxy's section, which can be used as bootstrap contacts when
+    /// restarting the client.
+    ProxyClosing(Vec<SocketAddr>),
+    /// The network refused to relocate us while joining. It is followed by `RestartRequired`.
+    JoinDenied(RelocationDenial),
+    /// A request or response we sent as part of a section or group authority didn't collect a
+    /// quorum of signatures in time, and so was dropped. The user may want to retry it or report
+    /// the error.
+    AccumulationFailed {
+        /// The unique identifier of the failed message.
+        message_id: MessageId,
+        /// The source authority of the failed message.
+        src: Authority<XorName>,
+        /// The destination authority of the failed message.
+        dst: Authority<XorName>,
+    },
     /// Startup failed - terminate.
     Terminate,
     // TODO: Find a better solution for periodic tasks.
     /// This event is sent periodically every time Routing sends the `Heartbeat` messages.
     Tick,
+    /// A client's request expired before it was acknowledged, and so won't be resent. The expiry
+    /// is the client's message expiry duration, unless the request was sent with its own TTL.
+    RequestExpired {
+        /// The request's unique identifier.
+        message_id: MessageId,
+        /// The destination authority of the request.
+        dst: Authority<XorName>,
+    },
+    /// A client's request wasn't acknowledged yet when we lost our proxy, and so won't be resent.
+    /// It is raised before the client bootstraps again; the request can be retried once we are
+    /// `Connected`.
+    RequestFailed {
+        /// The request's unique identifier.
+        message_id: MessageId,
+        /// The destination authority of the request.
+        dst: Authority<XorName>,
+    },
+    /// A message was discarded. Only raised if `report_dropped_messages` is set in the config.
+    MessageDropped {
+        /// The message's unique identifier, if it carries one.
+        message_id: Option<MessageId>,
+        /// Why the message was discarded.
+        reason: String,
+        /// The point at which the message was discarded.
+        stage: DropStage,
+    },
+    /// The bounded event channel was full, so events were dropped. Only events for which
+    /// `is_droppable` is true are ever dropped.
+    EventOverflow {
+        /// The number of events dropped since the last `EventOverflow`.
+        dropped: usize,
+    },
+}
+
+impl Event {
+    /// Returns whether the event may be dropped when a bounded event channel is full.
+    ///
+    /// These are periodic or informational events a slow consumer can afford to miss. Events that
+    /// carry messages or change the node's state are never dropped.
+    pub fn is_droppable(&self) -> bool {
+        match *self {
+            Event::ChurnStormDetected { .. }
+            | Event::SectionLoad { .. }
+            | Event::RelayAccounting(..)
+            | Event::InternalState { .. }
+            | Event::RoutingTableGrowth { .. }
+            | Event::LanPeerFound(..)
+            | Event::ConnectFailed(..)
+            | Event::Tick
+            | Event::MessageDropped { .. } => true,
+            Event::Request { .. }
+            | Event::Response { .. }
+            | Event::UserMessage { .. }
+            | Event::NodeAdded(..)
+            | Event::NodeLost(..)
+            | Event::NodeReplaced { .. }
+            | Event::RefreshNeeded { .. }
+            | Event::SectionSplit(..)
+            | Event::SectionMerge(..)
+            | Event::Connected
+            | Event::NetworkStarted
+            | Event::Disconnected
+            | Event::RestartRequired
+            | Event::ProxyUnrecognised(..)
+            | Event::BootstrapFailed
+            | Event::ProxyClosing(..)
+            | Event::JoinDenied(..)
+            | Event::AccumulationFailed { .. }
+            | Event::Terminate
+            | Event::RequestExpired { .. }
+            | Event::RequestFailed { .. }
+            | Event::EventOverflow { .. } => false,
+        }
+    }
+}
+
+/// The states a node or client can be in, as reported by `Event::InternalState`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum StateKind {
+    /// Connecting to a proxy node.
+    Bootstrapping,
+    /// Connected to the network as a client.
+    Client,
+    /// Connected to a proxy node, waiting to be relocated.
+    JoiningNode,
+    /// Relocated, and either still joining our section or a full member of it.
+    Node,
+}
+
+/// The point at which a message was discarded, as reported by `Event::MessageDropped`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DropStage {
+    /// The message was rejected on receipt, e.g. as a duplicate, or because its sender or
+    /// signatures were invalid.
+    Receiving,
+    /// The message was addressed to us, but handling it failed.
+    Dispatching,
 }
 
 impl Debug for Event {
@@ -68,19 +286,34 @@ impl Debug for Event {
                 ref request,
                 ref src,
                 ref dst,
+                ref signers,
             } => write!(
                 formatter,
-                "Event::Request {{ request: {:?}, src: {:?}, dst: {:?} }}",
-                request, src, dst
+                "Event::Request {{ request: {:?}, src: {:?}, dst: {:?}, signers: {:?} }}",
+                request, src, dst, signers
             ),
             Event::Response {
                 ref response,
                 ref src,
                 ref dst,
+                ref signers,
+            } => write!(
+                formatter,
+                "Event::Response {{ response: {:?}, src: {:?}, dst: {:?}, signers: {:?} }}",
+                response, src, dst, signers
+            ),
+            Event::UserMessage {
+                tag,
+                ref payload,
+                ref src,
+                ref dst,
             } => write!(
                 formatter,
-                "Event::Response {{ response: {:?}, src: {:?}, dst: {:?} }}",
-                response, src, dst
+                "Event::UserMessage {{ tag: {}, payload: {} bytes, src: {:?}, dst: {:?} }}",
+                tag,
+                payload.len(),
+                src,
+                dst
             ),
             Event::NodeAdded(ref node_name, _) => write!(
                 formatter,
@@ -90,16 +323,113 @@ impl Debug for Event {
             Event::NodeLost(ref node_name, _) => {
                 write!(formatter, "Event::NodeLost({:?}, routing_table)", node_name)
             }
+            Event::NodeReplaced {
+                ref evicted,
+                ref added,
+            } => write!(
+                formatter,
+                "Event::NodeReplaced {{ evicted: {:?}, added: {:?} }}",
+                evicted, added
+            ),
+            Event::RefreshNeeded { ref cause } => {
+                write!(formatter, "Event::RefreshNeeded {{ cause: {:?} }}", cause)
+            }
             Event::SectionSplit(ref prefix) => {
                 write!(formatter, "Event::SectionSplit({:?})", prefix)
             }
             Event::SectionMerge(ref prefix) => {
                 write!(formatter, "Event::SectionMerge({:?})", prefix)
             }
+            Event::ChurnStormDetected {
+                changes,
+                interval_secs,
+            } => write!(
+                formatter,
+                "Event::ChurnStormDetected {{ changes: {}, interval_secs: {} }}",
+                changes, interval_secs
+            ),
+            Event::SectionLoad { members, ref total } => write!(
+                formatter,
+                "Event::SectionLoad {{ members: {}, total: {:?} }}",
+                members, total
+            ),
+            Event::InternalState {
+                ref name,
+                state,
+                table_size,
+                network_size,
+                ref close_group,
+                ref proxy,
+            } => write!(
+                formatter,
+                "Event::InternalState {{ name: {:?}, state: {:?}, table_size: {}, network_size: \
+                 {:?}, close_group: {:?}, proxy: {:?} }}",
+                name, state, table_size, network_size, close_group, proxy
+            ),
+            Event::RelayAccounting(ref traffic) => {
+                write!(formatter, "Event::RelayAccounting({:?})", traffic)
+            }
+            Event::RoutingTableGrowth {
+                size,
+                needed_for_group,
+            } => write!(
+                formatter,
+                "Event::RoutingTableGrowth {{ size: {}, needed_for_group: {} }}",
+                size, needed_for_group
+            ),
             Event::Connected => write!(formatter, "Event::Connected"),
+            Event::NetworkStarted => write!(formatter, "Event::NetworkStarted"),
+            Event::Disconnected => write!(formatter, "Event::Disconnected"),
             Event::RestartRequired => write!(formatter, "Event::RestartRequired"),
+            Event::ProxyUnrecognised(ref name) => {
+                write!(formatter, "Event::ProxyUnrecognised({:?})", name)
+            }
+            Event::LanPeerFound(ref addr) => write!(formatter, "Event::LanPeerFound({})", addr),
+            Event::ConnectFailed(ref name) => write!(formatter, "Event::ConnectFailed({:?})", name),
+            Event::BootstrapFailed => write!(formatter, "Event::BootstrapFailed"),
+            Event::ProxyClosing(ref endpoints) => {
+                write!(formatter, "Event::ProxyClosing({:?})", endpoints)
+            }
+            Event::JoinDenied(reason) => write!(formatter, "Event::JoinDenied({:?})", reason),
+            Event::AccumulationFailed {
+                ref message_id,
+                ref src,
+                ref dst,
+            } => write!(
+                formatter,
+                "Event::AccumulationFailed {{ message_id: {:?}, src: {:?}, dst: {:?} }}",
+                message_id, src, dst
+            ),
             Event::Terminate => write!(formatter, "Event::Terminate"),
             Event::Tick => write!(formatter, "Event::Tick"),
+            Event::RequestExpired {
+                ref message_id,
+                ref dst,
+            } => write!(
+                formatter,
+                "Event::RequestExpired {{ message_id: {:?}, dst: {:?} }}",
+                message_id, dst
+            ),
+            Event::RequestFailed {
+                ref message_id,
+                ref dst,
+            } => write!(
+                formatter,
+                "Event::RequestFailed {{ message_id: {:?}, dst: {:?} }}",
+                message_id, dst
+            ),
+            Event::MessageDropped {
+                ref message_id,
+                ref reason,
+                stage,
+            } => write!(
+                formatter,
+                "Event::MessageDropped {{ message_id: {:?}, reason: {}, stage: {:?} }}",
+                message_id, reason, stage
+            ),
+            Event::EventOverflow { dropped } => {
+                write!(formatter, "Event::EventOverflow {{ dropped: {} }}", dropped)
+            }
         }
     }
 }
@@ -0,0 +1,175 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::event::Event;
+use std::sync::mpsc::{Sender, SyncSender, TrySendError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long to wait for room in a full channel between two attempts to send an event.
+const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+/// How long an event waits for room in a full channel before we warn about a stuck receiver.
+const STUCK_WARNING: Duration = Duration::from_secs(10);
+
+/// The channel a `Node` or `Client` running on its own thread passes its events to.
+///
+/// With an unbounded `Sender`, events queue up for as long as the receiver doesn't keep up. With a
+/// bounded `SyncSender`, the capacity it was created with limits the backlog: once the channel is
+/// full, events for which `Event::is_droppable` is true are dropped, and all others block the
+/// thread until there is room. Dropped events are reported by an `Event::EventOverflow`, sent as
+/// soon as the channel has room again.
+///
+/// # Deadlocks
+///
+/// While the thread waits for room for an event which can't be dropped, the node doesn't handle
+/// anything else. The receiver must therefore never wait for the node while the channel is full,
+/// e.g. by calling one of the `NodeHandle` or `Client` methods which return a result from the
+/// thread handling its events: neither side would make progress again. If an event waits for longer
+/// than a few seconds, a warning is logged pointing to such a receiver.
+pub struct EventSender {
+    inner: Inner,
+    /// The number of events dropped since the last `EventOverflow` we sent.
+    dropped: usize,
+}
+
+enum Inner {
+    Unbounded(Sender<Event>),
+    Bounded(SyncSender<Event>),
+}
+
+impl EventSender {
+    /// Passes the event to the receiver. Returns `false` if the receiver was dropped.
+    // Only the threads spawned outside of mock-crust tests send events this way.
+    #[cfg_attr(feature = "use-mock-crust", allow(dead_code))]
+    pub(crate) fn send(&mut self, event: Event) -> bool {
+        let sender = match self.inner {
+            Inner::Unbounded(ref sender) => return sender.send(event).is_ok(),
+            Inner::Bounded(ref sender) => sender,
+        };
+
+        if self.dropped > 0 {
+            let overflow = Event::EventOverflow {
+                dropped: self.dropped,
+            };
+            // Before blocking for an important event, the receiver learns what it missed.
+            let result = if event.is_droppable() {
+                sender.try_send(overflow)
+            } else {
+                send_blocking(sender, overflow)
+            };
+            match result {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => (),
+                Err(TrySendError::Disconnected(_)) => return false,
+            }
+        }
+
+        if !event.is_droppable() {
+            return send_blocking(sender, event).is_ok();
+        }
+        match sender.try_send(event) {
+            Ok(()) => true,
+            Err(TrySendError::Full(event)) => {
+                if self.dropped == 0 {
+                    warn!(
+                        "Event channel is full, dropping events such as {:?}.",
+                        event
+                    );
+                }
+                self.dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+}
+
+// Waits until there is room for `event` in the channel, warning once if that takes suspiciously
+// long. This only returns `Err(TrySendError::Disconnected)` if the receiver was dropped.
+fn send_blocking(sender: &SyncSender<Event>, mut event: Event) -> Result<(), TrySendError<Event>> {
+    let start = Instant::now();
+    let mut warned = false;
+    loop {
+        match sender.try_send(event) {
+            Err(TrySendError::Full(returned)) => event = returned,
+            result => return result,
+        }
+        if !warned && start.elapsed() > STUCK_WARNING {
+            warn!(
+                "Event channel has been full for {:?}, waiting to send {:?}. The receiver must not \
+                 wait for the node while handling its events.",
+                STUCK_WARNING, event
+            );
+            warned = true;
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+impl From<Sender<Event>> for EventSender {
+    fn from(sender: Sender<Event>) -> EventSender {
+        EventSender {
+            inner: Inner::Unbounded(sender),
+            dropped: 0,
+        }
+    }
+}
+
+impl From<SyncSender<Event>> for EventSender {
+    fn from(sender: SyncSender<Event>) -> EventSender {
+        EventSender {
+            inner: Inner::Bounded(sender),
+            dropped: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn droppable_events_are_shed_and_reported() {
+        let (tx, rx) = mpsc::sync_channel(2);
+        let mut sender = EventSender::from(tx);
+        for _ in 0..5 {
+            assert!(sender.send(Event::Tick));
+        }
+        assert_eq!(unwrap!(rx.try_recv()), Event::Tick);
+        assert_eq!(unwrap!(rx.try_recv()), Event::Tick);
+        assert!(rx.try_recv().is_err());
+
+        // Once there is room again, the receiver learns how many events it missed.
+        assert!(sender.send(Event::Connected));
+        assert_eq!(unwrap!(rx.try_recv()), Event::EventOverflow { dropped: 3 });
+        assert_eq!(unwrap!(rx.try_recv()), Event::Connected);
+
+        drop(rx);
+        assert!(!sender.send(Event::Connected));
+    }
+
+    #[test]
+    fn important_events_wait_for_room() {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let mut sender = EventSender::from(tx);
+        assert!(sender.send(Event::Connected));
+
+        let receiver = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let events: Vec<_> = rx.iter().collect();
+            events
+        });
+        assert!(sender.send(Event::Terminate));
+        drop(sender);
+        assert_eq!(
+            unwrap!(receiver.join()),
+            vec![Event::Connected, Event::Terminate]
+        );
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! A C API to create and drive a routing `Node` from other languages.
+//!
+//! A node is an opaque handle created by `routing_node_create` and released by
+//! `routing_node_free`. Authorities, requests and responses are passed as byte buffers holding
+//! their serialisation via `maidsafe_utilities::serialisation`. Events are passed to a callback
+//! in the same form, as an `FfiEvent`. All functions return `ROUTING_FFI_OK` or one of the
+//! negative error codes below.
+//!
+//! The functions are unsafe: pointer arguments must either be null or valid for the given
+//! lengths, and node handles must come from `routing_node_create` and not have been freed.
+//!
+//! A panic never unwinds into the caller: it is caught and reported as `ROUTING_FFI_PANIC`. The
+//! node it happened in may be left in an inconsistent state, and should only be freed afterwards.
+
+#![allow(unsafe_code)]
+
+use crate::event::Event;
+use crate::event_stream::EventStream;
+use crate::messages::{Request, Response, UserMessage};
+use crate::node::Node;
+use crate::routing_table::{Authority, Prefix};
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use maidsafe_utilities::serialisation;
+use serde::de::DeserializeOwned;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+use std::sync::mpsc::TryRecvError;
+
+/// The call succeeded.
+pub const ROUTING_FFI_OK: i32 = 0;
+/// A required pointer argument was null.
+pub const ROUTING_FFI_NULL_POINTER: i32 = -1;
+/// A byte buffer could not be deserialised, or an event could not be serialised.
+pub const ROUTING_FFI_SERIALISATION: i32 = -2;
+/// Routing failed to create the node or to send the message.
+pub const ROUTING_FFI_ROUTING_ERROR: i32 = -3;
+/// The node has terminated and won't raise any more events.
+pub const ROUTING_FFI_TERMINATED: i32 = -4;
+/// Routing panicked while handling the call.
+pub const ROUTING_FFI_PANIC: i32 = -5;
+
+/// The callback receiving serialised `FfiEvent`s. The buffer is only valid during the call.
+pub type EventCallback = extern "C" fn(user_data: *mut c_void, event: *const u8, event_len: usize);
+
+/// The serialisable form of an `Event` handed to the event callback.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum FfiEvent {
+    /// See `Event::Request`.
+    Request {
+        /// The request message.
+        request: Request,
+        /// The source authority that sent the request.
+        src: Authority<XorName>,
+        /// The destination authority that receives the request.
+        dst: Authority<XorName>,
+    },
+    /// See `Event::Response`.
+    Response {
+        /// The response message.
+        response: Response,
+        /// The source authority that sent the response.
+        src: Authority<XorName>,
+        /// The destination authority that receives the response.
+        dst: Authority<XorName>,
+    },
+    /// See `Event::NodeAdded`.
+    NodeAdded(XorName),
+    /// See `Event::NodeLost`.
+    NodeLost(XorName),
+    /// See `Event::SectionSplit`.
+    SectionSplit(Prefix<XorName>),
+    /// See `Event::SectionMerge`.
+    SectionMerge(Prefix<XorName>),
+    /// See `Event::Connected`.
+    Connected,
+    /// See `Event::RestartRequired`.
+    RestartRequired,
+    /// See `Event::Terminate`.
+    Terminate,
+    /// See `Event::MessageDropped`.
+    MessageDropped {
+        /// The message's unique identifier, if it carries one.
+        message_id: Option<MessageId>,
+        /// Why the message was discarded.
+        reason: String,
+    },
+    /// Any other event, in its debug representation.
+    Other(String),
+}
+
+impl From<Event> for FfiEvent {
+    fn from(event: Event) -> FfiEvent {
+        match event {
+            Event::Request {
+                request, src, dst, ..
+            } => FfiEvent::Request { request, src, dst },
+            Event::Response {
+                response, src, dst, ..
+            } => FfiEvent::Response { response, src, dst },
+            Event::NodeAdded(name, _) => FfiEvent::NodeAdded(name),
+            Event::NodeLost(name, _) => FfiEvent::NodeLost(name),
+            Event::SectionSplit(prefix) => FfiEvent::SectionSplit(prefix),
+            Event::SectionMerge(prefix) => FfiEvent::SectionMerge(prefix),
+            Event::Connected => FfiEvent::Connected,
+            Event::RestartRequired => FfiEvent::RestartRequired,
+            Event::Terminate => FfiEvent::Terminate,
+            Event::MessageDropped {
+                message_id, reason, ..
+            } => FfiEvent::MessageDropped { message_id, reason },
+            event => FfiEvent::Other(format!("{:?}", event)),
+        }
+    }
+}
+
+/// Creates a new node, which starts a new network if `first` is `true` and otherwise joins an
+/// existing one, and writes its handle to `o_node`.
+#[no_mangle]
+pub unsafe extern "C" fn routing_node_create(first: bool, o_node: *mut *mut Node) -> i32 {
+    catch_panic(|| {
+        if o_node.is_null() {
+            return ROUTING_FFI_NULL_POINTER;
+        }
+        match Node::builder().first(first).create() {
+            Ok(node) => {
+                *o_node = Box::into_raw(Box::new(node));
+                ROUTING_FFI_OK
+            }
+            Err(error) => {
+                error!("Failed to create node via FFI: {:?}", error);
+                ROUTING_FFI_ROUTING_ERROR
+            }
+        }
+    })
+}
+
+/// Terminates the node and releases its handle, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn routing_node_free(node: *mut Node) {
+    if !node.is_null() {
+        let _ = catch_panic(|| {
+            drop(Box::from_raw(node));
+            ROUTING_FFI_OK
+        });
+    }
+}
+
+/// Sends a serialised `Request` from `src` to `dst`, both serialised `Authority`s.
+#[no_mangle]
+#[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+pub unsafe extern "C" fn routing_node_send_request(
+    node: *mut Node,
+    src: *const u8,
+    src_len: usize,
+    dst: *const u8,
+    dst_len: usize,
+    request: *const u8,
+    request_len: usize,
+    priority: u8,
+) -> i32 {
+    catch_panic(|| {
+        let request = match deserialise::<Request>(request, request_len) {
+            Ok(request) => request,
+            Err(code) => return code,
+        };
+        send(
+            node,
+            (src, src_len),
+            (dst, dst_len),
+            UserMessage::Request(request),
+            priority,
+        )
+    })
+}
+
+/// Sends a serialised `Response` from `src` to `dst`, both serialised `Authority`s.
+#[no_mangle]
+#[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+pub unsafe extern "C" fn routing_node_send_response(
+    node: *mut Node,
+    src: *const u8,
+    src_len: usize,
+    dst: *const u8,
+    dst_len: usize,
+    response: *const u8,
+    response_len: usize,
+    priority: u8,
+) -> i32 {
+    catch_panic(|| {
+        let response = match deserialise::<Response>(response, response_len) {
+            Ok(response) => response,
+            Err(code) => return code,
+        };
+        send(
+            node,
+            (src, src_len),
+            (dst, dst_len),
+            UserMessage::Response(response),
+            priority,
+        )
+    })
+}
+
+/// Handles pending network events and passes each resulting event to `callback`, together with
+/// `user_data`. If `block` is `true`, waits until there is at least one event.
+#[no_mangle]
+pub unsafe extern "C" fn routing_node_poll(
+    node: *mut Node,
+    block: bool,
+    user_data: *mut c_void,
+    callback: EventCallback,
+) -> i32 {
+    catch_panic(|| {
+        let node = match node.as_mut() {
+            Some(node) => node,
+            None => return ROUTING_FFI_NULL_POINTER,
+        };
+        if block {
+            match node.next_ev() {
+                Ok(event) => {
+                    let code = deliver(event, user_data, callback);
+                    if code != ROUTING_FFI_OK {
+                        return code;
+                    }
+                }
+                Err(_) => return ROUTING_FFI_TERMINATED,
+            }
+        }
+        loop {
+            match node.try_next_ev() {
+                Ok(event) => {
+                    let code = deliver(event, user_data, callback);
+                    if code != ROUTING_FFI_OK {
+                        return code;
+                    }
+                }
+                Err(TryRecvError::Empty) => return ROUTING_FFI_OK,
+                Err(TryRecvError::Disconnected) => return ROUTING_FFI_TERMINATED,
+            }
+        }
+    })
+}
+
+// Runs `f`, turning a panic into `ROUTING_FFI_PANIC`, as unwinding across the FFI boundary is
+// undefined behaviour.
+fn catch_panic<F: FnOnce() -> i32>(f: F) -> i32 {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        error!("Panic while handling an FFI call.");
+        ROUTING_FFI_PANIC
+    })
+}
+
+unsafe fn send(
+    node: *mut Node,
+    src: (*const u8, usize),
+    dst: (*const u8, usize),
+    user_msg: UserMessage,
+    priority: u8,
+) -> i32 {
+    let node = match node.as_mut() {
+        Some(node) => node,
+        None => return ROUTING_FFI_NULL_POINTER,
+    };
+    let src = match deserialise::<Authority<XorName>>(src.0, src.1) {
+        Ok(src) => src,
+        Err(code) => return code,
+    };
+    let dst = match deserialise::<Authority<XorName>>(dst.0, dst.1) {
+        Ok(dst) => dst,
+        Err(code) => return code,
+    };
+    match node.send_action(src, dst, user_msg, priority) {
+        Ok(()) => ROUTING_FFI_OK,
+        Err(error) => {
+            debug!("Failed to send message via FFI: {:?}", error);
+            ROUTING_FFI_ROUTING_ERROR
+        }
+    }
+}
+
+fn deliver(event: Event, user_data: *mut c_void, callback: EventCallback) -> i32 {
+    match serialisation::serialise(&FfiEvent::from(event)) {
+        Ok(bytes) => {
+            callback(user_data, bytes.as_ptr(), bytes.len());
+            ROUTING_FFI_OK
+        }
+        Err(error) => {
+            error!("Failed to serialise event for FFI: {:?}", error);
+            ROUTING_FFI_SERIALISATION
+        }
+    }
+}
+
+// The caller must ensure `ptr` points to `len` readable bytes.
+unsafe fn deserialise<T: DeserializeOwned>(ptr: *const u8, len: usize) -> Result<T, i32> {
+    if ptr.is_null() {
+        return Err(ROUTING_FFI_NULL_POINTER);
+    }
+    serialisation::deserialise(slice::from_raw_parts(ptr, len))
+        .map_err(|_| ROUTING_FFI_SERIALISATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use std::collections::BTreeSet;
+    use std::ptr;
+
+    #[test]
+    fn null_pointers_are_rejected() {
+        assert_eq!(
+            unsafe { routing_node_create(false, ptr::null_mut()) },
+            ROUTING_FFI_NULL_POINTER
+        );
+        let src = unwrap!(serialisation::serialise(&Authority::NaeManager(
+            rand::random::<XorName>()
+        )));
+        let result = unsafe {
+            routing_node_send_request(
+                ptr::null_mut(),
+                src.as_ptr(),
+                src.len(),
+                src.as_ptr(),
+                src.len(),
+                ptr::null(),
+                0,
+                0,
+            )
+        };
+        assert_eq!(result, ROUTING_FFI_NULL_POINTER);
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        assert_eq!(catch_panic(|| ROUTING_FFI_OK), ROUTING_FFI_OK);
+        assert_eq!(catch_panic(|| panic!("Unexpected")), ROUTING_FFI_PANIC);
+    }
+
+    #[test]
+    fn events_round_trip() {
+        let msg_id = MessageId::new();
+        let event = Event::Response {
+            response: Response::PutIData {
+                res: Ok(()),
+                msg_id,
+            },
+            src: Authority::NaeManager(rand::random()),
+            dst: Authority::ClientManager(rand::random()),
+            signers: BTreeSet::new(),
+        };
+        let bytes = unwrap!(serialisation::serialise(&FfiEvent::from(event)));
+        match unwrap!(serialisation::deserialise(&bytes)) {
+            FfiEvent::Response { response, .. } => assert_eq!(*response.message_id(), msg_id),
+            event => panic!("Unexpected event {:?}", event),
+        }
+        match FfiEvent::from(Event::Tick) {
+            FfiEvent::Other(ref debug) => assert_eq!(debug, "Event::Tick"),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+}
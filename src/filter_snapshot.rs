@@ -0,0 +1,116 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use config_file_handler::{self, FileHandler};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The number of bits of the filter: 16 KiB, which keeps false positives below 1% for the
+/// messages a node typically handles within the filter's expiry.
+const BITS: u64 = 1 << 17;
+/// The number of bits set for each message.
+const BIT_COUNT_PER_HASH: u64 = 4;
+
+/// A bloom filter of the hashes of the routing messages we handled, kept across a restart.
+///
+/// A false positive makes us treat a new message as one we already handled, i.e. relay and
+/// acknowledge it, but not act on it. The hashes are only comparable between runs of the same
+/// build, which is the case for a quick restart.
+pub struct FilterSnapshot {
+    bits: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSnapshot {
+    /// When the snapshot was written, in seconds since the Unix epoch.
+    written_at: u64,
+    bits: Vec<u64>,
+}
+
+impl FilterSnapshot {
+    pub fn new() -> Self {
+        FilterSnapshot {
+            bits: vec![0; (BITS / 64) as usize],
+        }
+    }
+
+    pub fn insert(&mut self, hash: u64) {
+        for index in indices(hash) {
+            self.bits[(index / 64) as usize] |= 1 << (index % 64);
+        }
+    }
+
+    pub fn contains(&self, hash: u64) -> bool {
+        indices(hash).all(|index| self.bits[(index / 64) as usize] & (1 << (index % 64)) != 0)
+    }
+}
+
+// The bits set for `hash`, derived from its two halves by double hashing.
+fn indices(hash: u64) -> impl Iterator<Item = u64> {
+    let first = hash & 0xffff_ffff;
+    let second = (hash >> 32) | 1;
+    (0..BIT_COUNT_PER_HASH).map(move |i| first.wrapping_add(i.wrapping_mul(second)) % BITS)
+}
+
+/// Writes `snapshot` to disk.
+pub fn persist(snapshot: &FilterSnapshot) -> Result<(), RoutingError> {
+    let stored = StoredSnapshot {
+        written_at: unix_secs(),
+        bits: snapshot.bits.clone(),
+    };
+    file_handler()?.write_file(&stored)?;
+    Ok(())
+}
+
+/// Reads the snapshot written by `persist`, unless it is older than `max_age`. Returns it together
+/// with the remainder of `max_age`.
+pub fn restore(max_age: Duration) -> Result<(FilterSnapshot, Duration), RoutingError> {
+    let stored: StoredSnapshot = file_handler()?.read_file()?;
+    let age = Duration::from_secs(unix_secs().saturating_sub(stored.written_at));
+    if age >= max_age {
+        return Err(RoutingError::TimedOut);
+    }
+    if stored.bits.len() as u64 != BITS / 64 {
+        return Err(RoutingError::InvalidMessage);
+    }
+    Ok((FilterSnapshot { bits: stored.bits }, max_age - age))
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn file_handler() -> Result<FileHandler<StoredSnapshot>, RoutingError> {
+    let mut name = config_file_handler::exe_file_stem()?;
+    name.push(".routing.filter");
+    Ok(FileHandler::new(&name, true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    #[test]
+    fn contains_inserted_hashes() {
+        let mut snapshot = FilterSnapshot::new();
+        let hashes: Vec<u64> = (0..1000).map(|_| rand::random()).collect();
+        for hash in &hashes {
+            snapshot.insert(*hash);
+        }
+        assert!(hashes.iter().all(|hash| snapshot.contains(*hash)));
+
+        let false_positives = (0..1000)
+            .filter(|_| snapshot.contains(rand::random()))
+            .count();
+        assert!(false_positives < 10);
+    }
+}
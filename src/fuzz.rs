@@ -0,0 +1,50 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Entry points for fuzzing the path raw network bytes take through a node.
+//!
+//! The decode functions run the checks a node applies to a message before handling it. To also
+//! fuzz the handlers, feed the bytes to a mock node with `Node::fuzz_dispatch`. The harnesses are
+//! in the `fuzz` directory and are run with `cargo fuzz`.
+
+use crate::error::RoutingError;
+use crate::messages::{DirectMessage, Message, Quorum, SignedMessage};
+use maidsafe_utilities::serialisation;
+
+/// Decodes a `Message` as received from a peer and checks its field sizes and, if it is a hop
+/// message, its signatures.
+pub fn decode_message(bytes: &[u8], min_section_size: usize) -> Result<(), RoutingError> {
+    let message: Message = serialisation::deserialise(bytes)?;
+    message.check_field_sizes()?;
+    match message {
+        Message::Hop(hop_msg)
+        | Message::TunnelHop {
+            content: hop_msg, ..
+        } => hop_msg
+            .content
+            .check_integrity(min_section_size, Quorum::default()),
+        Message::Direct { .. } | Message::TunnelDirect { .. } | Message::Sealed { .. } => Ok(()),
+    }
+}
+
+/// Decodes a `SignedMessage`, including the routing header in its envelope, and checks its
+/// signatures.
+pub fn decode_signed_message(bytes: &[u8], min_section_size: usize) -> Result<(), RoutingError> {
+    let signed_msg: SignedMessage = serialisation::deserialise(bytes)?;
+    signed_msg.check_integrity(min_section_size, Quorum::default())
+}
+
+/// Decodes a `DirectMessage` and checks its field sizes.
+pub fn decode_direct_message(bytes: &[u8]) -> Result<(), RoutingError> {
+    let direct_msg: DirectMessage = serialisation::deserialise(bytes)?;
+    Message::Direct {
+        content: direct_msg,
+        seq: 0,
+    }
+    .check_field_sizes()
+}
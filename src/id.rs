@@ -93,6 +93,10 @@ impl Default for FullId {
 ///
 /// Note that the `name` member is omitted when serialising `PublicId` and is calculated from the
 /// `public_sign_key` when deserialising.
+///
+/// Since the name is derived from the signing key, a node can't replace its keys while keeping its
+/// name and its place in the network. To use new keys, a node has to rejoin with a new `FullId`
+/// and be relocated like any other joining node.
 #[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
 pub struct PublicId {
     name: XorName,
@@ -84,6 +84,19 @@
 //! that authority send the same message.
 //!
 //!
+//! # Logging
+//!
+//! Log messages of a node are prefixed with its name and prefix. Besides the module paths, the
+//! following log targets allow raising the verbosity for a single concern:
+//!
+//! - `routing_connect`: establishing connections to other nodes
+//! - `routing_relay`: acting as a proxy or tunnel for other peers
+//! - `routing_cache`: answering requests from the response cache
+//! - `routing_churn`: changes to the routing table, splits and merges
+//! - `routing_stats`: periodic message and traffic statistics
+//! - `routing_trace`: reports about traced user messages
+//!
+//!
 //! # Sequence diagrams
 //!
 //! - [Bootstrapping](bootstrap.png)
@@ -170,37 +183,60 @@ mod macros;
 
 mod ack_manager;
 mod action;
+#[cfg(all(feature = "async-api", not(feature = "use-mock-crust")))]
+mod async_client;
 mod cache;
 mod client;
+mod client_buffer;
 mod client_error;
+mod close_group_snapshot;
 mod common_types;
 mod config_handler;
 mod cumulative_own_section_merge;
 mod data;
+mod direct_sequencer;
+mod endpoint_acl;
 mod error;
 mod event;
+mod event_channel;
 mod event_stream;
+mod filter_snapshot;
 mod id;
+mod link_keys;
 mod message_filter;
 mod messages;
 mod node;
 mod outbox;
 mod peer_manager;
+mod periodic_tasks;
+mod proxy_certificate;
 mod rate_limiter;
+mod relay_accounting;
+mod request_journal;
+mod request_validator;
 mod resource_prover;
+mod rng;
 mod routing_message_filter;
 mod routing_table;
 mod section_list_cache;
+mod send_queue;
 mod signature_accumulator;
 mod state_machine;
 mod states;
 mod stats;
 mod timer;
+mod transport;
 mod tunnels;
 mod types;
 mod utils;
+mod verifier;
 mod xor_name;
 
+pub mod capture;
+#[cfg(all(feature = "ffi", not(feature = "use-mock-crust")))]
+pub mod ffi;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 #[cfg(feature = "use-mock-crypto")]
 pub mod mock_crypto;
 
@@ -236,7 +272,9 @@ pub const MIN_SECTION_SIZE: usize = 8;
 /// Key of an account data in the account packet
 pub const ACC_LOGIN_ENTRY_KEY: &[u8] = b"Login";
 
-pub use crate::cache::{Cache, NullCache};
+#[cfg(all(feature = "async-api", not(feature = "use-mock-crust")))]
+pub use crate::async_client::{AsyncClient, Events, ResponseFuture};
+pub use crate::cache::{Cache, CacheObserver, CacheStats, NullCache};
 pub use crate::client::Client;
 pub use crate::client_error::{ClientError, EntryError};
 pub use crate::common_types::AccountPacket;
@@ -246,22 +284,31 @@ pub use crate::data::{
     MAX_IMMUTABLE_DATA_SIZE_IN_BYTES, MAX_MUTABLE_DATA_ENTRIES, MAX_MUTABLE_DATA_SIZE_IN_BYTES,
     NO_OWNER_PUB_KEY,
 };
-pub use crate::error::{InterfaceError, RoutingError};
-pub use crate::event::Event;
+pub use crate::endpoint_acl::IpRange;
+pub use crate::error::{ErrorCategory, ErrorContext, InterfaceError, RoutingError};
+pub use crate::event::{DropStage, Event, StateKind};
+pub use crate::event_channel::EventSender;
 pub use crate::event_stream::EventStream;
 pub use crate::id::{FullId, PublicId};
-pub use crate::messages::{AccountInfo, Request, Response};
+pub use crate::messages::{AccountInfo, NodeLoad, Quorum, RelocationDenial, Request, Response};
 #[cfg(feature = "use-mock-crust")]
 pub use crate::mock_crust::crust;
+#[cfg(not(feature = "use-mock-crust"))]
+pub use crate::node::NodeHandle;
 pub use crate::node::{Node, NodeBuilder};
 #[cfg(feature = "use-mock-crust")]
 pub use crate::peer_manager::test_consts;
+pub use crate::peer_manager::PeerInfo;
 #[cfg(feature = "use-mock-crust")]
 pub use crate::rate_limiter::rate_limiter_consts;
+pub use crate::relay_accounting::RelayedTraffic;
+pub use crate::request_journal::{unanswered_requests, JournalEntry};
+pub use crate::request_validator::RequestValidator;
 #[cfg(any(test, feature = "use-mock-crust"))]
 pub use crate::routing_table::verify_network_invariant;
 pub use crate::routing_table::Error as RoutingTableError;
-pub use crate::routing_table::{Authority, Prefix, RoutingTable, Xorable};
+pub use crate::routing_table::{Authority, AuthorityExplanation, Prefix, RoutingTable, Xorable};
+pub use crate::signature_accumulator::RefreshPolicy;
 pub use crate::types::MessageId;
 pub use crate::xor_name::{XorName, XorNameFromHexError, XOR_NAME_BITS, XOR_NAME_LEN};
 
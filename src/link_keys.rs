@@ -0,0 +1,192 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use crate::id::{FullId, PublicId};
+use crate::messages::Message;
+use crate::rust_sodium::crypto::{box_, sign};
+use maidsafe_utilities::serialisation;
+use std::collections::HashMap;
+
+/// The session keys encrypting our connections to other nodes.
+///
+/// Once connected, each side generates a key pair for the link and sends the public key in a
+/// `DirectMessage::LinkEncryption`, signed with its long-term signing key. As soon as we have the
+/// peer's key, everything we send over the link is sealed with the two session keys, so recorded
+/// traffic stays secret even if long-term keys leak later. Once the peer sealed a message to us,
+/// we reject plaintext from it, so nobody on the path can downgrade the link again.
+pub struct LinkKeys {
+    links: HashMap<PublicId, Link>,
+}
+
+struct Link {
+    our_secret_key: box_::SecretKey,
+    their_public_key: Option<box_::PublicKey>,
+    /// Whether the peer sealed a message to us already.
+    sealed_by_peer: bool,
+}
+
+impl LinkKeys {
+    pub fn new() -> Self {
+        LinkKeys {
+            links: HashMap::new(),
+        }
+    }
+
+    /// Generates our key pair for the link to `pub_id`. Returns the public key, signed by
+    /// `our_id`, to be sent to the peer, or `None` if we already did so.
+    pub fn start(
+        &mut self,
+        our_id: &FullId,
+        pub_id: PublicId,
+    ) -> Option<Result<(box_::PublicKey, sign::Signature), RoutingError>> {
+        if self.links.contains_key(&pub_id) {
+            return None;
+        }
+        let (our_public_key, our_secret_key) = box_::gen_keypair();
+        let _ = self.links.insert(
+            pub_id,
+            Link {
+                our_secret_key,
+                their_public_key: None,
+                sealed_by_peer: false,
+            },
+        );
+        Some(signed_bytes(&our_public_key, &pub_id).map(|bytes| {
+            (
+                our_public_key,
+                sign::sign_detached(&bytes, our_id.signing_private_key()),
+            )
+        }))
+    }
+
+    /// Accepts the session key `their_key` which `pub_id` sent to `our_id`, if the signature is
+    /// valid. From then on, our messages to `pub_id` are sealed. Call `start` first, so that our
+    /// own key is sent before that.
+    pub fn receive(
+        &mut self,
+        our_id: &PublicId,
+        pub_id: &PublicId,
+        their_key: box_::PublicKey,
+        signature: &sign::Signature,
+    ) -> Result<(), RoutingError> {
+        let bytes = signed_bytes(&their_key, our_id)?;
+        if !sign::verify_detached(signature, &bytes, pub_id.signing_public_key()) {
+            return Err(RoutingError::FailedSignature);
+        }
+        let link = match self.links.get_mut(pub_id) {
+            Some(link) => link,
+            None => return Err(RoutingError::InvalidStateForOperation),
+        };
+        // Changing keys mid-session would let a replayed message break the link.
+        if link.their_public_key.is_some() {
+            return Err(RoutingError::InvalidStateForOperation);
+        }
+        link.their_public_key = Some(their_key);
+        Ok(())
+    }
+
+    /// Returns whether we seal our messages to `pub_id`.
+    pub fn is_sealed(&self, pub_id: &PublicId) -> bool {
+        self.links
+            .get(pub_id)
+            .map_or(false, |link| link.their_public_key.is_some())
+    }
+
+    /// Returns whether `pub_id` sealed a message to us, so that we don't accept plaintext from it
+    /// anymore.
+    pub fn requires_sealed(&self, pub_id: &PublicId) -> bool {
+        self.links
+            .get(pub_id)
+            .map_or(false, |link| link.sealed_by_peer)
+    }
+
+    /// Returns the serialised message `bytes` sealed for `pub_id`, or unchanged if we haven't
+    /// agreed on session keys yet.
+    pub fn seal(&self, pub_id: &PublicId, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        match self.links.get(pub_id) {
+            Some(&Link {
+                ref our_secret_key,
+                their_public_key: Some(ref their_public_key),
+                ..
+            }) => Message::seal(&bytes, their_public_key, our_secret_key),
+            _ => Ok(bytes),
+        }
+    }
+
+    /// Opens the `Message::Sealed` with the given `nonce` and `content` from `pub_id`.
+    pub fn open(
+        &mut self,
+        pub_id: &PublicId,
+        nonce: [u8; box_::NONCEBYTES],
+        content: &[u8],
+    ) -> Result<Vec<u8>, RoutingError> {
+        let link = match self.links.get_mut(pub_id) {
+            Some(link) => link,
+            None => return Err(RoutingError::AsymmetricDecryptionFailure),
+        };
+        let bytes = match link.their_public_key {
+            Some(ref their_public_key) => {
+                Message::open(nonce, content, their_public_key, &link.our_secret_key)?
+            }
+            None => return Err(RoutingError::AsymmetricDecryptionFailure),
+        };
+        link.sealed_by_peer = true;
+        Ok(bytes)
+    }
+
+    /// Forgets the session keys for `pub_id`, whose connection was lost.
+    pub fn remove(&mut self, pub_id: &PublicId) {
+        let _ = self.links.remove(pub_id);
+    }
+}
+
+// The bytes a session key is signed as, which bind the key to its recipient.
+fn signed_bytes(key: &box_::PublicKey, recipient: &PublicId) -> Result<Vec<u8>, RoutingError> {
+    Ok(serialisation::serialise(&(key, recipient))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_after_key_exchange() {
+        let full_id_a = FullId::new();
+        let full_id_b = FullId::new();
+        let (a, b) = (*full_id_a.public_id(), *full_id_b.public_id());
+        let mut keys_a = LinkKeys::new();
+        let mut keys_b = LinkKeys::new();
+
+        let (key_a, sig_a) = unwrap!(unwrap!(keys_a.start(&full_id_a, b)));
+        assert!(keys_a.start(&full_id_a, b).is_none());
+        let (key_b, sig_b) = unwrap!(unwrap!(keys_b.start(&full_id_b, a)));
+
+        // A key signed for someone else is rejected.
+        assert!(keys_b.receive(&b, &a, key_a, &sig_b).is_err());
+        unwrap!(keys_b.receive(&b, &a, key_a, &sig_a));
+        assert!(keys_b.receive(&b, &a, key_a, &sig_a).is_err());
+        assert!(keys_b.is_sealed(&a));
+        assert!(!keys_a.is_sealed(&b));
+        assert_eq!(unwrap!(keys_a.seal(&b, vec![1, 2, 3])), vec![1, 2, 3]);
+
+        unwrap!(keys_a.receive(&a, &b, key_b, &sig_b));
+        let sealed = unwrap!(keys_a.seal(&b, vec![1, 2, 3]));
+        let (nonce, content) = match unwrap!(serialisation::deserialise(&sealed)) {
+            Message::Sealed { nonce, content } => (nonce, content),
+            message => panic!("Unexpected message {:?}", message),
+        };
+        assert!(!keys_b.requires_sealed(&a));
+        assert_eq!(unwrap!(keys_b.open(&a, nonce, &content)), vec![1, 2, 3]);
+        assert!(keys_b.requires_sealed(&a));
+
+        keys_b.remove(&a);
+        assert!(keys_b.open(&a, nonce, &content).is_err());
+        assert!(!keys_b.requires_sealed(&a));
+    }
+}
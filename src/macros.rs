@@ -7,14 +7,18 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 /// This macro will panic with the given message if compiled with "use-mock-crust", otherwise it
-/// will simply log the message at the requested level.
+/// will simply log the message at the requested level. With "fuzzing", it only logs, so that
+/// malformed input doesn't stop the fuzzer.
 ///
 /// Example usage:
 /// `log_or_panic!(Level::Warn, "{:?} Bad value: {}", self, value);`
 #[macro_export]
 macro_rules! log_or_panic {
     ($log_level:expr, $($arg:tt)*) => {
-        if cfg!(feature = "use-mock-crust") && !::std::thread::panicking() {
+        if cfg!(feature = "use-mock-crust")
+            && !cfg!(feature = "fuzzing")
+            && !::std::thread::panicking()
+        {
             panic!($($arg)*);
         } else {
             log!($log_level, $($arg)*);
@@ -12,11 +12,13 @@ use std::collections::hash_map::{DefaultHasher, Entry};
 use std::collections::{HashMap, VecDeque};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem;
 use std::time::Duration;
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
 
-fn hash<T: Hash>(t: &T) -> u64 {
+/// The hash a `MessageFilter` stores for `t`.
+pub fn hash<T: Hash>(t: &T) -> u64 {
     let mut hasher = DefaultHasher::new();
     t.hash(&mut hasher);
     hasher.finish()
@@ -88,6 +90,24 @@ impl<Message: Hash> MessageFilter<Message> {
         let _old_val = self.count.remove(&hash(message));
     }
 
+    /// Removes any expired messages and releases capacity no longer needed by the filter.
+    pub fn compact(&mut self) {
+        self.remove_expired();
+        self.count.shrink_to_fit();
+        self.timeout_queue.shrink_to_fit();
+    }
+
+    /// Returns the hashes of the messages in the filter.
+    pub fn hashes<'a>(&'a self) -> impl Iterator<Item = u64> + 'a {
+        self.count.keys().cloned()
+    }
+
+    /// Returns the approximate number of bytes allocated by the filter.
+    pub fn footprint(&self) -> usize {
+        self.count.capacity() * mem::size_of::<(u64, (usize, Instant))>()
+            + self.timeout_queue.capacity() * mem::size_of::<(u64, Instant)>()
+    }
+
     fn remove_expired(&mut self) {
         let now = Instant::now();
         while self
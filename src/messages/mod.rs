@@ -8,6 +8,8 @@
 
 mod request;
 mod response;
+#[cfg(all(test, not(feature = "use-mock-crypto")))]
+mod test_vectors;
 
 pub use self::request::Request;
 pub use self::response::{AccountInfo, Response};
@@ -18,6 +20,7 @@ use crate::error::{BootstrapResponseError, RoutingError};
 use crate::event::Event;
 use crate::id::{FullId, PublicId};
 use crate::peer_manager::SectionMap;
+use crate::proxy_certificate::ProxyCertificate;
 use crate::routing_table::Authority;
 use crate::routing_table::{Prefix, VersionedPrefix, Xorable};
 use crate::rust_sodium::crypto::{box_, sign};
@@ -28,15 +31,26 @@ use crate::xor_name::XorName;
 use itertools::Itertools;
 use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation::{deserialise, serialise};
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use serde::de::{self, Deserialize, Deserializer};
+use serde::ser::{Serialize, Serializer};
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashSet};
 use std::fmt::{self, Debug, Formatter};
 use std::iter;
+use std::net::SocketAddr;
 use std::time::Duration;
 use tiny_keccak::sha3_256;
 
 /// The maximal length of a user message part, in bytes.
 pub const MAX_PART_LEN: usize = 20 * 1024;
 pub const MAX_PARTS: u32 = ((MAX_IMMUTABLE_DATA_SIZE_IN_BYTES / MAX_PART_LEN as u64) + 1) as u32;
+/// The default maximal size of a serialised message we decode, in bytes.
+pub const MAX_MESSAGE_SIZE: usize = 256 * 1024;
+/// The maximal number of members of a single section listed in a message.
+const MAX_MEMBER_LIST_LEN: usize = 256;
+/// The maximal number of sections listed in a message.
+const MAX_SECTION_LIST_LEN: usize = 512;
+/// The maximal length of encrypted connection info, which contains the peer's endpoints.
+const MAX_CONN_INFO_LEN: usize = 16 * 1024;
 
 /// Get and refresh messages from nodes have a high priority: They relocate data under churn and are
 /// critical to prevent data loss.
@@ -46,6 +60,9 @@ pub const DEFAULT_PRIORITY: u8 = 2;
 /// `Get` requests from clients have the lowest priority: If bandwidth is insufficient, the network
 /// needs to prioritise maintaining its structure, data and consensus.
 pub const CLIENT_GET_PRIORITY: u8 = 3;
+/// The lowest priority Crust sends messages with. Of the messages queued for a peer, Crust sends the
+/// ones with this priority last.
+const LOWEST_PRIORITY: u8 = std::u8::MAX;
 
 /// Wrapper of all messages.
 ///
@@ -55,13 +72,20 @@ pub const CLIENT_GET_PRIORITY: u8 = 3;
 #[cfg_attr(feature = "cargo-clippy", allow(large_enum_variant))]
 pub enum Message {
     /// A message sent between two nodes directly
-    Direct(DirectMessage),
+    Direct {
+        /// The wrapped message
+        content: DirectMessage,
+        /// The sender's sequence number of its direct messages to the receiver
+        seq: u32,
+    },
     /// A message sent across the network (in transit)
     Hop(HopMessage),
     /// A direct message sent via a tunnel because the nodes could not connect directly
     TunnelDirect {
         /// The wrapped message
         content: DirectMessage,
+        /// The sender's sequence number of its direct messages to the receiver
+        seq: u32,
         /// The sender
         src: PublicId,
         /// The receiver
@@ -76,19 +100,70 @@ pub enum Message {
         /// The receiver
         dst: PublicId,
     },
+    /// Any of the above, serialised and encrypted for the peer at the other end of the connection.
+    Sealed {
+        /// The nonce used for encryption.
+        nonce: [u8; box_::NONCEBYTES],
+        /// The encrypted message.
+        content: Vec<u8>,
+    },
 }
 
 impl Message {
     pub fn priority(&self) -> u8 {
         match *self {
-            Message::Direct(ref content) | Message::TunnelDirect { ref content, .. } => {
+            Message::Direct { ref content, .. } | Message::TunnelDirect { ref content, .. } => {
                 content.priority()
             }
             Message::Hop(ref content) | Message::TunnelHop { ref content, .. } => {
-                content.content.content.priority()
+                content.content.priority()
             }
+            // Messages are only sealed once they have been queued with their own priority.
+            Message::Sealed { .. } => DEFAULT_PRIORITY,
         }
     }
+
+    /// Returns `MessageTooLarge` if any list or payload in the message is longer than an honest
+    /// peer would ever send.
+    pub fn check_field_sizes(&self) -> Result<(), RoutingError> {
+        match *self {
+            Message::Direct { ref content, .. } | Message::TunnelDirect { ref content, .. } => {
+                content.check_field_sizes()
+            }
+            Message::Hop(ref content) | Message::TunnelHop { ref content, .. } => {
+                content.content.check_field_sizes()
+            }
+            // The sealed message is checked once it has been opened.
+            Message::Sealed { .. } => Ok(()),
+        }
+    }
+
+    /// Encrypts the serialised message `bytes` with the session keys of a link and returns the
+    /// serialised `Message::Sealed`.
+    pub fn seal(
+        bytes: &[u8],
+        their_key: &box_::PublicKey,
+        our_key: &box_::SecretKey,
+    ) -> Result<Vec<u8>, RoutingError> {
+        let nonce = box_::gen_nonce();
+        let content = box_::seal(bytes, &nonce, their_key, our_key);
+        Ok(serialise(&Message::Sealed {
+            nonce: nonce.0,
+            content,
+        })?)
+    }
+
+    /// Decrypts the `content` of a `Message::Sealed` with the session keys of a link, returning
+    /// the serialised message it contains.
+    pub fn open(
+        nonce: [u8; box_::NONCEBYTES],
+        content: &[u8],
+        their_key: &box_::PublicKey,
+        our_key: &box_::SecretKey,
+    ) -> Result<Vec<u8>, RoutingError> {
+        box_::open(content, &box_::Nonce(nonce), their_key, our_key)
+            .map_err(|()| RoutingError::AsymmetricDecryptionFailure)
+    }
 }
 
 /// Messages sent via a direct connection.
@@ -124,6 +199,9 @@ pub enum DirectMessage {
         /// Client authority from after relocation.
         new_client_auth: Authority<XorName>,
     },
+    /// Sent in response to `CandidateInfo` if a node with the candidate's new name already
+    /// exists in the section, so that the candidate restarts with a fresh key pair.
+    CandidateNameTaken,
     /// Sent from a node that needs a tunnel to be able to connect to the given peer.
     TunnelRequest(PublicId),
     /// Sent as a response to `TunnelRequest` if the node can act as a tunnel.
@@ -163,6 +241,40 @@ pub enum DirectMessage {
     ResourceProofResponseReceipt,
     /// Sent from a proxy node to its client to indicate that the client exceeded its rate limit.
     ProxyRateLimitExceeded { ack: Ack },
+    /// Sent to a newly connected node with our session key for the link, signed together with the
+    /// recipient's `PublicId`. Once both sides have the other's key, everything they send over the
+    /// link is a `Message::Sealed`.
+    LinkEncryption {
+        /// Our public session key for the link.
+        key: box_::PublicKey,
+        /// The signature of the key and the recipient's `PublicId`.
+        signature: sign::Signature,
+    },
+    /// Sent periodically to the other members of our section, with a hash of the names we consider
+    /// to be in our section and, optionally, our current load.
+    CloseGroupDigest(Digest256, Option<NodeLoad>),
+    /// Sent in response to a `CloseGroupDigest` which doesn't match ours, with the members we
+    /// consider to be in our section.
+    CloseGroup(BTreeSet<PublicId>),
+    /// Sent to a node which joined our section, with our signature of its public ID. The node
+    /// collects these into its `ProxyCertificate`.
+    ProxyCertificateSignature(sign::Signature),
+    /// Sent from the bootstrap node to a client after a successful `BootstrapResponse`, so that
+    /// the client can check that the node's section vouches for it.
+    ProxyCertificate(ProxyCertificate),
+    /// Sent to a peer we dropped from our routing table although it is still alive, right before
+    /// we disconnect from it, so that it doesn't try to reconnect.
+    Disconnecting,
+    /// Sent from a proxy node to its clients when it shuts down, with the endpoints of other
+    /// members of its section, so that the clients can bootstrap off one of them right away.
+    ProxyClosing(Vec<SocketAddr>),
+    /// Sent from a node to its proxy once it doesn't need it anymore, i.e. once its section is
+    /// complete and the proxy isn't in its routing table.
+    ProxyRetirement,
+    /// Sent from a proxy node in response to `ProxyRetirement`, once it has sent all messages it
+    /// held for the node, so that the node can disconnect without losing any of them. It is sent
+    /// with the lowest priority, so that Crust can't send it ahead of any of those messages.
+    ProxyRetired,
 }
 
 impl DirectMessage {
@@ -170,9 +282,31 @@ impl DirectMessage {
     pub fn priority(&self) -> u8 {
         match *self {
             DirectMessage::ResourceProofResponse { .. } => 9,
+            DirectMessage::ProxyRetired => LOWEST_PRIORITY,
             _ => 0,
         }
     }
+
+    fn check_field_sizes(&self) -> Result<(), RoutingError> {
+        match *self {
+            DirectMessage::SectionListSignature(ref section_list, _) => {
+                check_len(section_list.pub_ids.len(), MAX_MEMBER_LIST_LEN)
+            }
+            DirectMessage::ResourceProof { ref seed, .. } => check_len(seed.len(), MAX_PART_LEN),
+            DirectMessage::ResourceProofResponse { ref proof, .. } => {
+                check_len(proof.len(), MAX_PART_LEN)
+            }
+            DirectMessage::CloseGroup(ref members) => check_len(members.len(), MAX_MEMBER_LIST_LEN),
+            DirectMessage::ProxyCertificate(ref cert) => {
+                check_len(cert.len(), MAX_MEMBER_LIST_LEN)?;
+                check_len(cert.member_count(), MAX_MEMBER_LIST_LEN)
+            }
+            DirectMessage::ProxyClosing(ref endpoints) => {
+                check_len(endpoints.len(), MAX_MEMBER_LIST_LEN)
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 /// An individual hop message that represents a part of the route of a message in transit.
@@ -189,6 +323,9 @@ pub struct HopMessage {
     pub route: u8,
     /// Every node this has already been sent to.
     pub sent_to: BTreeSet<XorName>,
+    /// Whether a client's proxy, unable to route the message itself, relayed it via a second
+    /// proxy. Such a message is never relayed again.
+    pub relayed: bool,
     /// Signature to be validated against the neighbouring sender's public key.
     signature: sign::Signature,
 }
@@ -207,6 +344,7 @@ impl HopMessage {
             content,
             route,
             sent_to,
+            relayed: false,
             signature: sign::sign_detached(&bytes_to_sign, signing_key),
         })
     }
@@ -245,17 +383,91 @@ impl SectionList {
     }
 }
 
+/// The load a node reports about itself to the other members of its section.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct NodeLoad {
+    /// The number of clients the node acts as a proxy for.
+    pub relayed_clients: u64,
+    /// The number of responses in the node's cache.
+    pub cache_entries: u64,
+    /// The number of bytes the node sent since its previous report.
+    pub bytes_sent: u64,
+}
+
+impl NodeLoad {
+    /// Adds `other` to this load, saturating at the numeric bounds.
+    pub fn add(&mut self, other: &NodeLoad) {
+        self.relayed_clients = self.relayed_clients.saturating_add(other.relayed_clients);
+        self.cache_entries = self.cache_entries.saturating_add(other.cache_entries);
+        self.bytes_sent = self.bytes_sent.saturating_add(other.bytes_sent);
+    }
+}
+
+/// Why the network refused to relocate a joining node, as reported by `Event::JoinDenied`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum RelocationDenial {
+    /// The `Relocate` request wasn't addressed to the section of the joining node's own name.
+    MalformedId,
+    /// The section the request reached isn't the one responsible for the joining node's name.
+    WrongSection,
+    /// The target section kept deferring the relocation because too many nodes joined it recently.
+    Throttled,
+}
+
+/// The share of a set of voters needed to agree: a quorum is reached if strictly more than
+/// `numerator / denominator` of them do.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub struct Quorum {
+    /// The numerator of the required share.
+    pub numerator: usize,
+    /// The denominator of the required share.
+    pub denominator: usize,
+}
+
+impl Quorum {
+    /// Returns whether `votes` out of `voters` reach this quorum.
+    pub fn is_reached(&self, votes: usize, voters: usize) -> bool {
+        votes * self.denominator > voters * self.numerator
+    }
+
+    /// Returns whether the quorum can be reached at all, and requires at least half of the votes.
+    /// A lower quorum would let two disjoint minorities both accept conflicting messages.
+    pub fn is_valid(&self) -> bool {
+        self.denominator > 0
+            && self.numerator < self.denominator
+            && self.numerator * 2 >= self.denominator
+    }
+}
+
+impl Default for Quorum {
+    fn default() -> Quorum {
+        Quorum {
+            numerator: QUORUM_NUMERATOR,
+            denominator: QUORUM_DENOMINATOR,
+        }
+    }
+}
+
 /// Wrapper around a routing message, signed by the originator of the message.
-#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Serialize, Deserialize)]
+///
+/// On the wire, the message's `RoutingHeader` is sent in front of the serialised message, see
+/// `SignedMessageEnvelope`.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash)]
 pub struct SignedMessage {
     /// A request or response type message.
     content: RoutingMessage,
+    /// The serialised `content`, which the signatures cover. It is kept so that each hop can
+    /// verify and forward the message without serialising it again.
+    signed_bytes: Vec<u8>,
     /// Nodes sending the message (those expected to sign it)
     src_sections: Vec<SectionList>,
     /// The lists of the sections involved in routing this message, in chronological order.
     // TODO: implement (MAID-1677): sec_lists: Vec<SectionList>,
     /// The IDs and signatures of the source authority's members.
     signatures: BTreeMap<PublicId, sign::Signature>,
+    /// Hash of the destination section's members as seen by the sender, if the destination is a
+    /// section or group authority. This is not covered by the signatures.
+    dst_section_hash: Option<Digest256>,
 }
 
 impl SignedMessage {
@@ -269,32 +481,79 @@ impl SignedMessage {
         mut src_sections: Vec<SectionList>,
     ) -> Result<SignedMessage, RoutingError> {
         src_sections.sort_by_key(|list| list.prefix);
-        let sig = sign::sign_detached(&serialise(&content)?, full_id.signing_private_key());
+        let signed_bytes = serialise(&content)?;
+        let sig = sign::sign_detached(&signed_bytes, full_id.signing_private_key());
         Ok(SignedMessage {
             content,
+            signed_bytes,
             src_sections,
             signatures: iter::once((*full_id.public_id(), sig)).collect(),
+            dst_section_hash: None,
         })
     }
 
-    /// Confirms the signatures.
+    /// Sets the hash of the destination section's members as seen by the sender.
+    pub fn set_dst_section_hash(&mut self, hash: Option<Digest256>) {
+        self.dst_section_hash = hash;
+    }
+
+    /// Returns the hash of the destination section's members as seen by the sender, if any.
+    pub fn dst_section_hash(&self) -> Option<&Digest256> {
+        self.dst_section_hash.as_ref()
+    }
+
+    /// Confirms the signatures, and that there are enough of them to reach `quorum`.
     // TODO (MAID-1677): verify the sending SectionLists via each hop's signed lists
-    pub fn check_integrity(&self, min_section_size: usize) -> Result<(), RoutingError> {
-        let signed_bytes = serialise(&self.content)?;
-        if !self.find_invalid_sigs(signed_bytes).is_empty() {
+    pub fn check_integrity(
+        &self,
+        min_section_size: usize,
+        quorum: Quorum,
+    ) -> Result<(), RoutingError> {
+        if !self.find_invalid_sigs(&self.signed_bytes).is_empty() {
             return Err(RoutingError::FailedSignature);
         }
-        if !self.has_enough_sigs(min_section_size) {
+        if !self.has_enough_sigs(min_section_size, quorum) {
             return Err(RoutingError::NotEnoughSignatures);
         }
         Ok(())
     }
 
+    fn check_field_sizes(&self) -> Result<(), RoutingError> {
+        check_len(self.src_sections.len(), MAX_SECTION_LIST_LEN)?;
+        for section_list in &self.src_sections {
+            check_len(section_list.pub_ids.len(), MAX_MEMBER_LIST_LEN)?;
+        }
+        check_len(
+            self.signatures.len(),
+            MAX_SECTION_LIST_LEN * MAX_MEMBER_LIST_LEN,
+        )?;
+        self.content.content.check_field_sizes()
+    }
+
+    /// Returns the public IDs whose signatures the message carries.
+    pub fn signers(&self) -> btree_map::Keys<PublicId, sign::Signature> {
+        self.signatures.keys()
+    }
+
     /// Returns whether the message is signed by the given public ID.
     pub fn signed_by(&self, pub_id: &PublicId) -> bool {
         self.signatures.contains_key(pub_id)
     }
 
+    /// Returns whether the message comes from the client with the given public ID and carries its
+    /// valid signature.
+    pub fn signed_by_client(&self, pub_id: &PublicId) -> bool {
+        match self.content.src {
+            Authority::Client { ref client_id, .. } if client_id == pub_id => (),
+            _ => return false,
+        }
+        let sig = match self.signatures.get(pub_id) {
+            Some(sig) => sig,
+            None => return false,
+        };
+        sign::verify_detached(sig, &self.signed_bytes, pub_id.signing_public_key())
+    }
+
     /// Returns the number of nodes in the source authority.
     pub fn src_size(&self) -> usize {
         self.src_sections.iter().map(|sl| sl.pub_ids.len()).sum()
@@ -331,9 +590,14 @@ impl SignedMessage {
         self.content.priority()
     }
 
-    /// Returns whether there are enough signatures from the sender.
-    pub fn check_fully_signed(&mut self, min_section_size: usize) -> bool {
-        if !self.has_enough_sigs(min_section_size) {
+    /// The part of the routing message needed to route it.
+    pub fn header(&self) -> RoutingHeader {
+        self.content.header()
+    }
+
+    /// Returns whether there are enough signatures from the sender to reach `quorum`.
+    pub fn check_fully_signed(&mut self, min_section_size: usize, quorum: Quorum) -> bool {
+        if !self.has_enough_sigs(min_section_size, quorum) {
             return false;
         }
 
@@ -345,18 +609,26 @@ impl SignedMessage {
         // section(s) at some point in recent history; i.e. that it was valid; but we shouldn't
         // force it to match our own because our routing table may have changed since.
 
-        let signed_bytes = match serialise(&self.content) {
-            Ok(serialised) => serialised,
-            Err(error) => {
-                warn!("Failed to serialise {:?}: {:?}", self, error);
-                return false;
-            }
-        };
-        for invalid_signature in &self.find_invalid_sigs(signed_bytes) {
+        for invalid_signature in &self.find_invalid_sigs(&self.signed_bytes) {
             let _ = self.signatures.remove(invalid_signature);
         }
 
-        self.has_enough_sigs(min_section_size)
+        self.has_enough_sigs(min_section_size, quorum)
+    }
+
+    /// Returns whether the signatures of the signers `is_verifiable` accepts reach `quorum`, e.g.
+    /// of those whose key matches the one we know for their name. Unlike `check_integrity`, this
+    /// doesn't verify the signatures themselves.
+    pub fn has_enough_verifiable_sigs<F>(
+        &self,
+        min_section_size: usize,
+        quorum: Quorum,
+        is_verifiable: F,
+    ) -> bool
+    where
+        F: Fn(&PublicId) -> bool,
+    {
+        self.has_enough_sigs_from(min_section_size, quorum, is_verifiable)
     }
 
     // Returns true iff `pub_id` is in self.section_lists
@@ -368,7 +640,7 @@ impl SignedMessage {
 
     // Returns a list of all invalid signatures (not from an expected key or not cryptographically
     // valid).
-    fn find_invalid_sigs(&self, signed_bytes: Vec<u8>) -> Vec<PublicId> {
+    fn find_invalid_sigs(&self, signed_bytes: &[u8]) -> Vec<PublicId> {
         let invalid = self
             .signatures
             .iter()
@@ -376,10 +648,10 @@ impl SignedMessage {
                 // Remove if not in sending nodes or signature is invalid:
                 let is_valid = if let Authority::Client { ref client_id, .. } = self.content.src {
                     client_id == pub_id
-                        && sign::verify_detached(sig, &signed_bytes, client_id.signing_public_key())
+                        && sign::verify_detached(sig, signed_bytes, client_id.signing_public_key())
                 } else {
                     self.is_sender(pub_id)
-                        && sign::verify_detached(sig, &signed_bytes, pub_id.signing_public_key())
+                        && sign::verify_detached(sig, signed_bytes, pub_id.signing_public_key())
                 };
                 if is_valid {
                     None
@@ -396,8 +668,19 @@ impl SignedMessage {
 
     // Returns true if there are enough signatures (note that this method does not verify the
     // signatures, it only counts them; it also does not verify `self.src_sections`).
-    fn has_enough_sigs(&self, min_section_size: usize) -> bool {
+    fn has_enough_sigs(&self, min_section_size: usize, quorum: Quorum) -> bool {
+        self.has_enough_sigs_from(min_section_size, quorum, |_| true)
+    }
+
+    // Like `has_enough_sigs`, but only counts the signers `counts` accepts. Signers sharing a name
+    // are counted once, so that a single node can't vote with several keys.
+    fn has_enough_sigs_from<F>(&self, min_section_size: usize, quorum: Quorum, counts: F) -> bool
+    where
+        F: Fn(&PublicId) -> bool,
+    {
         use crate::Authority::*;
+        let counts = &counts;
+        let signers = || self.signatures.keys().filter(move |pub_id| counts(pub_id));
         match self.content.src {
             ClientManager(_) | NaeManager(_) | NodeManager(_) => {
                 // Note: there should be exactly one source section, but we use safe code:
@@ -409,16 +692,16 @@ impl SignedMessage {
                     .into_iter()
                     .take(min_section_size)
                     .collect();
-                let valid_sigs = self
-                    .signatures
-                    .keys()
-                    .filter(|pub_id| valid_names.contains(pub_id.name()))
+                let valid_sigs = signers()
+                    .map(PublicId::name)
+                    .filter(|name| valid_names.contains(name))
+                    .unique()
                     .count();
                 // TODO: we should consider replacing valid_names.len() with
                 // cmp::min(routing_table.len(), min_section_size)
                 // (or just min_section_size, but in that case we will not be able to handle user
                 // messages during boot-up).
-                valid_sigs * QUORUM_DENOMINATOR > valid_names.len() * QUORUM_NUMERATOR
+                quorum.is_reached(valid_sigs, valid_names.len())
             }
             Section(_) => {
                 // Note: there should be exactly one source section, but we use safe code:
@@ -426,18 +709,18 @@ impl SignedMessage {
                     .src_sections
                     .iter()
                     .fold(0, |count, list| count + list.pub_ids.len());
-                let valid_sigs = self.signatures.len();
-                valid_sigs * QUORUM_DENOMINATOR > num_sending * QUORUM_NUMERATOR
+                let valid_sigs = signers().map(PublicId::name).unique().count();
+                quorum.is_reached(valid_sigs, num_sending)
             }
             PrefixSection(_) => {
                 // Each section must have enough signatures:
                 self.src_sections.iter().all(|list| {
-                    let valid_sigs = self
-                        .signatures
-                        .keys()
+                    let valid_sigs = signers()
                         .filter(|pub_id| list.pub_ids.contains(pub_id))
+                        .map(PublicId::name)
+                        .unique()
                         .count();
-                    valid_sigs * QUORUM_DENOMINATOR > list.pub_ids.len() * QUORUM_NUMERATOR
+                    quorum.is_reached(valid_sigs, list.pub_ids.len())
                 })
             }
             ManagedNode(_) | Client { .. } => self.signatures.len() == 1,
@@ -445,6 +728,77 @@ impl SignedMessage {
     }
 }
 
+/// The wire format of a `SignedMessage`: a small header with everything a relay needs, followed by
+/// the serialised `RoutingMessage` as an opaque payload. The payload is exactly what the
+/// signatures cover, so relays verify it and pass it on as received, and the header is rejected
+/// unless it matches the decoded payload.
+#[derive(Serialize)]
+struct SignedMessageEnvelopeRef<'a> {
+    header: RoutingHeader,
+    payload: &'a [u8],
+    src_sections: &'a [SectionList],
+    signatures: &'a BTreeMap<PublicId, sign::Signature>,
+    dst_section_hash: &'a Option<Digest256>,
+}
+
+/// See `SignedMessageEnvelopeRef`.
+#[derive(Deserialize)]
+struct SignedMessageEnvelope {
+    header: RoutingHeader,
+    payload: Vec<u8>,
+    src_sections: Vec<SectionList>,
+    signatures: BTreeMap<PublicId, sign::Signature>,
+    dst_section_hash: Option<Digest256>,
+}
+
+impl Serialize for SignedMessage {
+    fn serialize<S: Serializer>(&self, serialiser: S) -> Result<S::Ok, S::Error> {
+        SignedMessageEnvelopeRef {
+            header: self.content.header(),
+            payload: &self.signed_bytes,
+            src_sections: &self.src_sections,
+            signatures: &self.signatures,
+            dst_section_hash: &self.dst_section_hash,
+        }
+        .serialize(serialiser)
+    }
+}
+
+impl<'de> Deserialize<'de> for SignedMessage {
+    fn deserialize<D: Deserializer<'de>>(deserialiser: D) -> Result<Self, D::Error> {
+        let envelope = SignedMessageEnvelope::deserialize(deserialiser)?;
+        let routing_msg: RoutingMessage =
+            deserialise(&envelope.payload).map_err(de::Error::custom)?;
+        if routing_msg.header() != envelope.header {
+            return Err(de::Error::custom(
+                "routing header doesn't match the payload",
+            ));
+        }
+        Ok(SignedMessage {
+            content: routing_msg,
+            signed_bytes: envelope.payload,
+            src_sections: envelope.src_sections,
+            signatures: envelope.signatures,
+            dst_section_hash: envelope.dst_section_hash,
+        })
+    }
+}
+
+/// The part of a `RoutingMessage` which relays need to route it, i.e. everything but the content.
+#[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct RoutingHeader {
+    /// Source authority
+    pub src: Authority<XorName>,
+    /// Destination authority
+    pub dst: Authority<XorName>,
+    /// The ID of the message, if its content has one.
+    pub message_id: Option<MessageId>,
+    /// The priority Crust should send the message with.
+    pub priority: u8,
+    /// Whether every node handling the message should send a `TraceReport` back to `src`.
+    pub trace: bool,
+}
+
 /// A routing message with source and destination authorities.
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Hash, Debug, Serialize, Deserialize)]
 pub struct RoutingMessage {
@@ -454,6 +808,8 @@ pub struct RoutingMessage {
     pub dst: Authority<XorName>,
     /// The message content
     pub content: MessageContent,
+    /// Whether every node handling this message should send a `TraceReport` back to `src`.
+    pub trace: bool,
 }
 
 impl RoutingMessage {
@@ -463,6 +819,7 @@ impl RoutingMessage {
             src,
             dst: msg.src,
             content: MessageContent::Ack(Ack::compute(msg)?, msg.priority()),
+            trace: false,
         })
     }
 
@@ -471,6 +828,17 @@ impl RoutingMessage {
         self.content.priority()
     }
 
+    /// Returns the header relays need to route this message.
+    pub fn header(&self) -> RoutingHeader {
+        RoutingHeader {
+            src: self.src,
+            dst: self.dst,
+            message_id: self.content.message_id(),
+            priority: self.priority(),
+            trace: self.trace,
+        }
+    }
+
     /// Returns a `DirectMessage::MessageSignature` for this message.
     pub fn to_signature(
         &self,
@@ -596,6 +964,28 @@ pub enum MessageContent {
         /// The message's unique identifier.
         message_id: MessageId,
     },
+    /// Tells a joining node that the section it is being relocated to has admitted too many nodes
+    /// recently, and that it should send a new `Relocate` request later.
+    ///
+    /// Sent from the `Section` the node was to be relocated to, to the joining node's `Client`
+    /// authority.
+    RelocateDeferred {
+        /// The number of seconds after which the joining node may try again.
+        retry_after_secs: u64,
+        /// The unique identifier of the `Relocate` request.
+        message_id: MessageId,
+    },
+    /// Tells a joining node that its `Relocate` request was refused, so it doesn't need to wait
+    /// for a response that will never come.
+    ///
+    /// Sent from the `Section` the `Relocate` request was addressed to, to the joining node's
+    /// `Client` authority.
+    RelocateDenied {
+        /// Why the request was refused.
+        reason: RelocationDenial,
+        /// The unique identifier of the `Relocate` request.
+        message_id: MessageId,
+    },
     /// Sent to notify neighbours and own members when our section's member list changed (for now,
     /// only when new nodes join).
     SectionUpdate {
@@ -605,6 +995,35 @@ pub enum MessageContent {
         /// Members of the section
         members: BTreeSet<PublicId>,
     },
+    /// Asks a section to send us a `SectionUpdate`, because we know fewer of its members than it
+    /// should have.
+    ///
+    /// Sent from a `ManagedNode` to the `PrefixSection` it's missing peers from. The field is the
+    /// requester's own prefix, to which the section update should be sent.
+    SectionUpdateRequest(Prefix<XorName>),
+    /// Asks the section responsible for `name` which nodes it consists of.
+    ///
+    /// Sent from a `ManagedNode` to the `Section` of `name`.
+    GetCloseGroup {
+        /// The name whose close group is requested.
+        name: XorName,
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
+    /// Reply to a `GetCloseGroup` request.
+    ///
+    /// Sent from the `Section` of the requested name to the requesting `ManagedNode`.
+    GetCloseGroupResponse {
+        /// The members of the section responsible for the requested name.
+        close_group: BTreeSet<PublicId>,
+        /// The message's unique identifier, as used in the request.
+        message_id: MessageId,
+    },
+    /// Reports that the sender lost its connection to the given member of its section.
+    ///
+    /// Sent from the `Section` of the unreachable node to itself. Only once a quorum of the
+    /// section agrees is the node dropped by all of its members.
+    ReportUnreachable(XorName),
     /// Sent to all connected peers when our own section splits
     SectionSplit(VersionedPrefix<XorName>, XorName),
     /// Sent amongst members of a newly-merged section to allow synchronisation of their routing
@@ -634,7 +1053,8 @@ pub enum MessageContent {
         part_index: u32,
         /// The message priority.
         priority: u8,
-        /// Is the message cacheable?
+        /// Is the message relevant to caching nodes, i.e. can it be cached or answered from a
+        /// cache, or does it invalidate cached data?
         cacheable: bool,
         /// The `part_index`-th part of the serialised user message.
         payload: Vec<u8>,
@@ -652,6 +1072,16 @@ pub enum MessageContent {
         /// The message's unique identifier.
         message_id: MessageId,
     },
+    /// Shares the candidate our section accepted with a node which joined the section since, so
+    /// that it can validate the candidate's `CandidateInfo` too.
+    ///
+    /// Sent from the `Section` of the new member to the new `ManagedNode`.
+    ShareCandidate {
+        /// The joining node's current public ID.
+        old_public_id: PublicId,
+        /// The interval into which the joining node should join.
+        target_interval: (XorName, XorName),
+    },
     /// Sent among Group Y to vote to accept a joining node.
     CandidateApproval {
         /// The joining node's current public ID.
@@ -669,6 +1099,52 @@ pub enum MessageContent {
         /// contacts.
         sections: SectionMap,
     },
+    /// Reports how a node handled a message which had tracing enabled.
+    ///
+    /// Sent from the reporting `ManagedNode` to the source authority of the traced message.
+    TraceReport {
+        /// Identifies the traced message.
+        ack: Ack,
+        /// What the reporting node did with the message.
+        decision: TraceDecision,
+        /// Time at which the message was handled, in seconds since the Unix epoch.
+        timestamp: u64,
+    },
+    /// Reports that a user message didn't collect a quorum of signatures in time, and so was
+    /// dropped.
+    ///
+    /// Sent from the `ManagedNode` which accumulated the signatures to the source authority of the
+    /// failed message.
+    AccumulationFailed {
+        /// The unique identifier of the failed message.
+        msg_id: MessageId,
+        /// The destination authority of the failed message.
+        dst: Authority<XorName>,
+    },
+    /// Asks a member of our section for the cached `ImmutableData` chunks in our range, so that a
+    /// node which just joined can answer requests from its cache right away. The chunks are sent
+    /// back as `GetIData` responses with the same message ID.
+    ///
+    /// Sent from a `ManagedNode` to a `ManagedNode` of its own section.
+    CachePrimingRequest {
+        /// The prefix the chunks' names have to match.
+        prefix: Prefix<XorName>,
+        /// The maximum total size of the chunks' contents, in bytes.
+        max_bytes: u64,
+        /// The message's unique identifier.
+        message_id: MessageId,
+    },
+}
+
+/// The action a node took on a traced message.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
+pub enum TraceDecision {
+    /// The message was passed on towards its destination.
+    Forwarded,
+    /// The message was addressed to us, or answered from our cache.
+    Handled,
+    /// The message was discarded, e.g. as a duplicate or because it couldn't be sent on.
+    Dropped,
 }
 
 impl MessageContent {
@@ -681,6 +1157,100 @@ impl MessageContent {
             _ => 0,
         }
     }
+
+    fn check_field_sizes(&self) -> Result<(), RoutingError> {
+        use self::MessageContent::*;
+        match *self {
+            ConnectionInfoRequest {
+                ref encrypted_conn_info,
+                ..
+            }
+            | ConnectionInfoResponse {
+                ref encrypted_conn_info,
+                ..
+            } => check_len(encrypted_conn_info.len(), MAX_CONN_INFO_LEN),
+            RelocateResponse {
+                section: (_, ref members),
+                ..
+            }
+            | SectionUpdate { ref members, .. }
+            | GetCloseGroupResponse {
+                close_group: ref members,
+                ..
+            }
+            | OtherSectionMerge(ref members, _) => check_len(members.len(), MAX_MEMBER_LIST_LEN),
+            OwnSectionMerge(ref sections)
+            | CandidateApproval { ref sections, .. }
+            | NodeApproval { ref sections } => {
+                check_len(sections.len(), MAX_SECTION_LIST_LEN)?;
+                for members in sections.values() {
+                    check_len(members.len(), MAX_MEMBER_LIST_LEN)?;
+                }
+                Ok(())
+            }
+            UserMessagePart {
+                part_count,
+                ref payload,
+                ..
+            } => {
+                check_len(part_count as usize, MAX_PARTS as usize)?;
+                check_len(payload.len(), MAX_PART_LEN)
+            }
+            Relocate { .. }
+            | ExpectCandidate { .. }
+            | RelocateDeferred { .. }
+            | RelocateDenied { .. }
+            | SectionUpdateRequest(..)
+            | GetCloseGroup { .. }
+            | ReportUnreachable(..)
+            | SectionSplit(..)
+            | Ack(..)
+            | AcceptAsCandidate { .. }
+            | ShareCandidate { .. }
+            | TraceReport { .. }
+            | AccumulationFailed { .. }
+            | CachePrimingRequest { .. } => Ok(()),
+        }
+    }
+
+    /// The message's unique identifier, if it has one.
+    pub fn message_id(&self) -> Option<MessageId> {
+        use self::MessageContent::*;
+        match *self {
+            Relocate { message_id }
+            | ExpectCandidate { message_id, .. }
+            | RelocateResponse { message_id, .. }
+            | RelocateDeferred { message_id, .. }
+            | RelocateDenied { message_id, .. }
+            | AcceptAsCandidate { message_id, .. }
+            | GetCloseGroup { message_id, .. }
+            | GetCloseGroupResponse { message_id, .. }
+            | CachePrimingRequest { message_id, .. }
+            | ConnectionInfoRequest {
+                msg_id: message_id, ..
+            }
+            | ConnectionInfoResponse {
+                msg_id: message_id, ..
+            }
+            | UserMessagePart {
+                msg_id: message_id, ..
+            }
+            | AccumulationFailed {
+                msg_id: message_id, ..
+            } => Some(message_id),
+            SectionUpdate { .. }
+            | SectionUpdateRequest(..)
+            | ReportUnreachable(..)
+            | SectionSplit(..)
+            | OwnSectionMerge(..)
+            | OtherSectionMerge(..)
+            | Ack(..)
+            | ShareCandidate { .. }
+            | CandidateApproval { .. }
+            | NodeApproval { .. }
+            | TraceReport { .. } => None,
+        }
+    }
 }
 
 impl Debug for DirectMessage {
@@ -698,6 +1268,7 @@ impl Debug for DirectMessage {
             BootstrapRequest(_) => write!(formatter, "BootstrapRequest"),
             BootstrapResponse(ref result) => write!(formatter, "BootstrapResponse({:?})", result),
             CandidateInfo { .. } => write!(formatter, "CandidateInfo {{ .. }}"),
+            CandidateNameTaken => write!(formatter, "CandidateNameTaken"),
             TunnelRequest(pub_id) => write!(formatter, "TunnelRequest({:?})", pub_id),
             TunnelSuccess(pub_id) => write!(formatter, "TunnelSuccess({:?})", pub_id),
             TunnelSelect(pub_id) => write!(formatter, "TunnelSelect({:?})", pub_id),
@@ -730,6 +1301,22 @@ impl Debug for DirectMessage {
             ProxyRateLimitExceeded { ref ack } => {
                 write!(formatter, "ProxyRateLimitExceeded({:?})", ack)
             }
+            LinkEncryption { .. } => write!(formatter, "LinkEncryption"),
+            Disconnecting => write!(formatter, "Disconnecting"),
+            CloseGroupDigest(ref digest, ref load) => write!(
+                formatter,
+                "CloseGroupDigest({}, {:?})",
+                utils::format_binary_array(&digest),
+                load
+            ),
+            CloseGroup(ref members) => write!(formatter, "CloseGroup({:?})", members),
+            ProxyCertificateSignature(_) => write!(formatter, "ProxyCertificateSignature"),
+            ProxyCertificate(ref cert) => {
+                write!(formatter, "ProxyCertificate({} signatures)", cert.len())
+            }
+            ProxyClosing(ref endpoints) => write!(formatter, "ProxyClosing({:?})", endpoints),
+            ProxyRetirement => write!(formatter, "ProxyRetirement"),
+            ProxyRetired => write!(formatter, "ProxyRetired"),
         }
     }
 }
@@ -738,8 +1325,8 @@ impl Debug for HopMessage {
     fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
         write!(
             formatter,
-            "HopMessage {{ content: {:?}, route: {}, sent_to: .., signature: .. }}",
-            self.content, self.route
+            "HopMessage {{ content: {:?}, route: {}, sent_to: .., relayed: {}, signature: .. }}",
+            self.content, self.route, self.relayed
         )
     }
 }
@@ -797,6 +1384,22 @@ impl Debug for MessageContent {
                 "RelocateResponse {{ {:?}, {:?}, {:?} }}",
                 target_interval, section, message_id
             ),
+            RelocateDeferred {
+                retry_after_secs,
+                ref message_id,
+            } => write!(
+                formatter,
+                "RelocateDeferred {{ {}s, {:?} }}",
+                retry_after_secs, message_id
+            ),
+            RelocateDenied {
+                reason,
+                ref message_id,
+            } => write!(
+                formatter,
+                "RelocateDenied {{ {:?}, {:?} }}",
+                reason, message_id
+            ),
             SectionUpdate {
                 ref versioned_prefix,
                 ref members,
@@ -805,6 +1408,26 @@ impl Debug for MessageContent {
                 "SectionUpdate {{ {:?}, {:?} }}",
                 versioned_prefix, members
             ),
+            SectionUpdateRequest(ref prefix) => {
+                write!(formatter, "SectionUpdateRequest({:?})", prefix)
+            }
+            GetCloseGroup {
+                ref name,
+                ref message_id,
+            } => write!(
+                formatter,
+                "GetCloseGroup {{ {:?}, {:?} }}",
+                name, message_id
+            ),
+            GetCloseGroupResponse {
+                ref close_group,
+                ref message_id,
+            } => write!(
+                formatter,
+                "GetCloseGroupResponse {{ {:?}, {:?} }}",
+                close_group, message_id
+            ),
+            ReportUnreachable(ref name) => write!(formatter, "ReportUnreachable({:?})", name),
             SectionSplit(ref ver_pfx, ref joining_node) => {
                 write!(formatter, "SectionSplit({:?}, {:?})", ver_pfx, joining_node)
             }
@@ -842,6 +1465,14 @@ impl Debug for MessageContent {
                 "AcceptAsCandidate {{ {:?}, {:?}, {:?}, {:?} }}",
                 old_public_id, old_client_auth, target_interval, message_id
             ),
+            ShareCandidate {
+                ref old_public_id,
+                ref target_interval,
+            } => write!(
+                formatter,
+                "ShareCandidate {{ {:?}, {:?} }}",
+                old_public_id, target_interval
+            ),
             CandidateApproval {
                 ref new_public_id,
                 ref new_client_auth,
@@ -852,17 +1483,52 @@ impl Debug for MessageContent {
                 new_public_id, new_client_auth, sections
             ),
             NodeApproval { ref sections } => write!(formatter, "NodeApproval {{ {:?} }}", sections),
+            TraceReport {
+                ref ack,
+                ref decision,
+                timestamp,
+            } => write!(
+                formatter,
+                "TraceReport {{ {:?}, {:?}, {} }}",
+                ack, decision, timestamp
+            ),
+            AccumulationFailed {
+                ref msg_id,
+                ref dst,
+            } => write!(
+                formatter,
+                "AccumulationFailed {{ {:?}, {:?} }}",
+                msg_id, dst
+            ),
+            CachePrimingRequest {
+                ref prefix,
+                max_bytes,
+                ref message_id,
+            } => write!(
+                formatter,
+                "CachePrimingRequest {{ {:?}, {}, {:?} }}",
+                prefix, max_bytes, message_id
+            ),
         }
     }
 }
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Debug, Hash, Serialize, Deserialize)]
-/// A user-visible message: a `Request` or `Response`.
+/// A user-visible message: a `Request`, a `Response` or a message of a user-defined kind.
 pub enum UserMessage {
     /// A user-visible request message.
     Request(Request),
     /// A user-visible response message.
     Response(Response),
+    /// A message of a kind defined by the user, identified by `tag`.
+    Tagged {
+        /// The user-defined kind of the message.
+        tag: u64,
+        /// The message content.
+        payload: Vec<u8>,
+        /// The message's unique identifier.
+        msg_id: MessageId,
+    },
 }
 
 impl UserMessage {
@@ -907,11 +1573,32 @@ impl UserMessage {
     }
 
     /// Returns an event indicating that this message was received with the given source and
-    /// destination authorities.
-    pub fn into_event(self, src: Authority<XorName>, dst: Authority<XorName>) -> Event {
+    /// destination authorities. `signers` are the members of `src` who signed it.
+    pub fn into_event(
+        self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        signers: BTreeSet<PublicId>,
+    ) -> Event {
         match self {
-            UserMessage::Request(request) => Event::Request { request, src, dst },
-            UserMessage::Response(response) => Event::Response { response, src, dst },
+            UserMessage::Request(request) => Event::Request {
+                request,
+                src,
+                dst,
+                signers,
+            },
+            UserMessage::Response(response) => Event::Response {
+                response,
+                src,
+                dst,
+                signers,
+            },
+            UserMessage::Tagged { tag, payload, .. } => Event::UserMessage {
+                tag,
+                payload,
+                src,
+                dst,
+            },
         }
     }
 
@@ -920,21 +1607,31 @@ impl UserMessage {
         match *self {
             UserMessage::Request(ref request) => request.message_id(),
             UserMessage::Response(ref response) => response.message_id(),
+            UserMessage::Tagged { ref msg_id, .. } => msg_id,
         }
     }
 
+    // Whether caching nodes need to reassemble this message: either to answer or cache it, or to
+    // invalidate the cached data it modifies.
     fn is_cacheable(&self) -> bool {
         match *self {
-            UserMessage::Request(ref request) => request.is_cacheable(),
-            UserMessage::Response(ref response) => response.is_cacheable(),
+            UserMessage::Request(ref request) => {
+                request.is_cacheable() || request.mutated_data().is_some()
+            }
+            UserMessage::Response(ref response) => {
+                response.is_cacheable() || response.mutation_result().is_some()
+            }
+            UserMessage::Tagged { .. } => false,
         }
     }
 }
 
 /// This assembles `UserMessage`s from `UserMessagePart`s.
 /// It maps `(hash, part_count)` of an incoming `UserMessage` to the map containing
-/// all `UserMessagePart`s that have already arrived, by `part_index`.
-pub struct UserMessageCache(LruCache<(Digest256, u32), BTreeMap<u32, Vec<u8>>>);
+/// all `UserMessagePart`s that have already arrived, by `part_index`, each with its signers.
+pub struct UserMessageCache(
+    LruCache<(Digest256, u32), BTreeMap<u32, (Vec<u8>, BTreeSet<PublicId>)>>,
+);
 
 impl UserMessageCache {
     pub fn with_expiry_duration(duration: Duration) -> Self {
@@ -950,12 +1647,26 @@ impl UserMessageCache {
         part_index: u32,
         payload: Vec<u8>,
     ) -> Option<UserMessage> {
+        self.add_signed(hash, part_count, part_index, payload, BTreeSet::new())
+            .map(|(user_msg, _)| user_msg)
+    }
+
+    /// Like `add`, for a part signed by `signers`. The completed `UserMessage` is returned together
+    /// with the signers of all of its parts.
+    pub fn add_signed(
+        &mut self,
+        hash: Digest256,
+        part_count: u32,
+        part_index: u32,
+        payload: Vec<u8>,
+        signers: BTreeSet<PublicId>,
+    ) -> Option<(UserMessage, BTreeSet<PublicId>)> {
         {
             let entry = self
                 .0
                 .entry((hash, part_count))
                 .or_insert_with(BTreeMap::new);
-            if entry.insert(part_index, payload).is_some() {
+            if entry.insert(part_index, (payload, signers)).is_some() {
                 debug!(
                     "Duplicate UserMessagePart {}/{} with hash {:02x}{:02x}{:02x}.. \
                      added to cache.",
@@ -972,27 +1683,69 @@ impl UserMessageCache {
             }
         }
 
-        self.0
-            .remove(&(hash, part_count))
-            .and_then(|part_map| UserMessage::from_parts(hash, part_map.values()).ok())
+        let part_map = self.0.remove(&(hash, part_count))?;
+        let payloads = part_map.values().map(|&(ref payload, _)| payload);
+        let user_msg = UserMessage::from_parts(hash, payloads).ok()?;
+        let mut signer_sets = part_map.into_iter().map(|(_, (_, signers))| signers);
+        let first = signer_sets.next().unwrap_or_default();
+        let signers = signer_sets.fold(first, |all, signers| {
+            all.intersection(&signers).cloned().collect()
+        });
+        Some((user_msg, signers))
+    }
+}
+
+fn check_len(len: usize, max_len: usize) -> Result<(), RoutingError> {
+    if len > max_len {
+        Err(RoutingError::MessageTooLarge)
+    } else {
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::ImmutableData;
+    use crate::data::{ImmutableData, PermissionSet, User};
     use crate::id::FullId;
     use crate::routing_table::{Authority, Prefix};
     use crate::rust_sodium::crypto::sign;
     use crate::types::MessageId;
     use crate::xor_name::XorName;
-    use maidsafe_utilities::serialisation::serialise;
+    use maidsafe_utilities::serialisation::{deserialise, serialise};
     use rand;
     use std::collections::BTreeSet;
     use std::iter;
     use tiny_keccak::sha3_256;
 
+    #[test]
+    fn quorum() {
+        let default = Quorum::default();
+        assert!(default.is_valid());
+        assert!(!default.is_reached(4, 8));
+        assert!(default.is_reached(5, 8));
+
+        let half = Quorum {
+            numerator: 1,
+            denominator: 2,
+        };
+        assert!(half.is_valid());
+        assert!(half.is_reached(5, 8));
+        assert!(!half.is_reached(4, 8));
+
+        let minority = Quorum {
+            numerator: 1,
+            denominator: 3,
+        };
+        assert!(!minority.is_valid());
+
+        let unreachable = Quorum {
+            numerator: 3,
+            denominator: 3,
+        };
+        assert!(!unreachable.is_valid());
+    }
+
     #[test]
     fn signed_message_check_integrity() {
         let min_section_size = 1000;
@@ -1005,6 +1758,7 @@ mod tests {
             },
             dst: Authority::ClientManager(name),
             content: MessageContent::SectionSplit(Prefix::new(0, name).with_version(0), name),
+            trace: false,
         };
         let senders = iter::empty().collect();
         let signed_message_result = SignedMessage::new(routing_message.clone(), &full_id, senders);
@@ -1018,18 +1772,59 @@ mod tests {
             signed_message.signatures.keys().next()
         );
 
-        unwrap!(signed_message.check_integrity(min_section_size));
+        unwrap!(signed_message.check_integrity(min_section_size, Quorum::default()));
+        assert!(signed_message.signed_by_client(full_id.public_id()));
 
         let full_id = FullId::new();
+        assert!(!signed_message.signed_by_client(full_id.public_id()));
         let bytes_to_sign = unwrap!(serialise(&(&routing_message, full_id.public_id())));
         let signature = sign::sign_detached(&bytes_to_sign, full_id.signing_private_key());
 
         signed_message.signatures = iter::once((*full_id.public_id(), signature)).collect();
 
         // Invalid because it's not signed by the sender:
-        assert!(signed_message.check_integrity(min_section_size).is_err());
+        assert!(signed_message
+            .check_integrity(min_section_size, Quorum::default())
+            .is_err());
+        assert!(!signed_message.signed_by_client(full_id.public_id()));
         // However, the signature itself should be valid:
-        assert!(signed_message.has_enough_sigs(min_section_size));
+        assert!(signed_message.has_enough_sigs(min_section_size, Quorum::default()));
+    }
+
+    #[test]
+    fn signed_message_envelope() {
+        let name: XorName = rand::random();
+        let full_id = FullId::new();
+        let routing_message = RoutingMessage {
+            src: Authority::ManagedNode(*full_id.public_id().name()),
+            dst: Authority::NaeManager(name),
+            content: MessageContent::GetCloseGroup {
+                name,
+                message_id: MessageId::new(),
+            },
+            trace: false,
+        };
+        let signed_message = unwrap!(SignedMessage::new(
+            routing_message.clone(),
+            &full_id,
+            iter::empty().collect()
+        ));
+        let bytes = unwrap!(serialise(&signed_message));
+        assert_eq!(signed_message, unwrap!(deserialise(&bytes)));
+
+        // An envelope whose header doesn't match its payload is rejected.
+        let payload = unwrap!(serialise(&routing_message));
+        let mut header = routing_message.header();
+        header.dst = Authority::NaeManager(rand::random());
+        let envelope = SignedMessageEnvelopeRef {
+            header,
+            payload: &payload,
+            src_sections: &signed_message.src_sections,
+            signatures: &signed_message.signatures,
+            dst_section_hash: &None,
+        };
+        let bytes = unwrap!(serialise(&envelope));
+        assert!(deserialise::<SignedMessage>(&bytes).is_err());
     }
 
     #[test]
@@ -1055,6 +1850,7 @@ mod tests {
             src: Authority::ClientManager(name),
             dst: Authority::ClientManager(name),
             content: part,
+            trace: false,
         };
 
         let src_sections = vec![SectionList::from(
@@ -1087,7 +1883,7 @@ mod tests {
         assert!(!signed_msg
             .signatures
             .contains_key(irrelevant_full_id.public_id(),));
-        assert!(!signed_msg.check_fully_signed(min_section_size));
+        assert!(!signed_msg.check_fully_signed(min_section_size, Quorum::default()));
 
         // Add a valid signature for ID 1 and an invalid one for ID 2
         match unwrap!(signed_msg
@@ -1104,7 +1900,7 @@ mod tests {
         let bad_sig = sign::Signature([0; sign::SIGNATUREBYTES]);
         signed_msg.add_signature(*full_id_2.public_id(), bad_sig);
         assert_eq!(signed_msg.signatures.len(), 3);
-        assert!(signed_msg.check_fully_signed(min_section_size));
+        assert!(signed_msg.check_fully_signed(min_section_size, Quorum::default()));
 
         // Check the bad signature got removed (by check_fully_signed) properly.
         assert_eq!(signed_msg.signatures.len(), 2);
@@ -1116,6 +1912,16 @@ mod tests {
         assert!(!signed_msg
             .signatures
             .contains_key(irrelevant_full_id.public_id(),));
+
+        // Signatures from signers we can't verify don't count.
+        assert!(
+            signed_msg.has_enough_verifiable_sigs(min_section_size, Quorum::default(), |_| true)
+        );
+        assert!(!signed_msg.has_enough_verifiable_sigs(
+            min_section_size,
+            Quorum::default(),
+            |pub_id| pub_id != full_id_1.public_id()
+        ));
     }
 
     #[test]
@@ -1125,6 +1931,7 @@ mod tests {
             src: Authority::ClientManager(name),
             dst: Authority::ClientManager(name),
             content: MessageContent::SectionSplit(Prefix::new(0, name).with_version(1), name),
+            trace: false,
         };
         let full_id = FullId::new();
         let senders = iter::empty().collect();
@@ -1149,6 +1956,80 @@ mod tests {
         assert!(hop_message.verify(&public_signing_key).is_err());
     }
 
+    #[test]
+    fn sealed_message() {
+        let (our_public_key, our_secret_key) = box_::gen_keypair();
+        let (their_public_key, their_secret_key) = box_::gen_keypair();
+        let bytes = unwrap!(serialise(&Message::Direct {
+            content: DirectMessage::Disconnecting,
+            seq: 0,
+        }));
+        let sealed_bytes = unwrap!(Message::seal(&bytes, &their_public_key, &our_secret_key));
+
+        let (nonce, content) = match unwrap!(deserialise(&sealed_bytes)) {
+            Message::Sealed { nonce, content } => (nonce, content),
+            message => panic!("Unexpected message {:?}", message),
+        };
+        let opened_bytes = unwrap!(Message::open(
+            nonce,
+            &content,
+            &our_public_key,
+            &their_secret_key
+        ));
+        assert_eq!(bytes, opened_bytes);
+
+        let (_, other_secret_key) = box_::gen_keypair();
+        assert!(Message::open(nonce, &content, &our_public_key, &other_secret_key).is_err());
+    }
+
+    #[test]
+    fn message_field_sizes() {
+        let full_id = FullId::new();
+        let name: XorName = rand::random();
+        let hop_message = |payload_len: usize| {
+            let routing_message = RoutingMessage {
+                src: Authority::ClientManager(name),
+                dst: Authority::ClientManager(name),
+                content: MessageContent::UserMessagePart {
+                    hash: sha3_256(&[]),
+                    msg_id: MessageId::new(),
+                    part_count: 1,
+                    part_index: 0,
+                    priority: DEFAULT_PRIORITY,
+                    cacheable: false,
+                    payload: vec![0; payload_len],
+                },
+                trace: false,
+            };
+            let signed_message = unwrap!(SignedMessage::new(
+                routing_message,
+                &full_id,
+                iter::empty().collect()
+            ));
+            let hop_message = unwrap!(HopMessage::new(
+                signed_message,
+                0,
+                BTreeSet::new(),
+                full_id.signing_private_key(),
+            ));
+            Message::Hop(hop_message)
+        };
+        assert!(hop_message(MAX_PART_LEN).check_field_sizes().is_ok());
+        match hop_message(MAX_PART_LEN + 1).check_field_sizes() {
+            Err(RoutingError::MessageTooLarge) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let members = (0..MAX_MEMBER_LIST_LEN + 1)
+            .map(|_| *FullId::new().public_id())
+            .collect::<BTreeSet<_>>();
+        let message = Message::Direct {
+            content: DirectMessage::CloseGroup(members),
+            seq: 0,
+        };
+        assert!(message.check_field_sizes().is_err());
+    }
+
     #[test]
     fn user_message_parts() {
         let data_bytes: Vec<u8> = (0..(MAX_PART_LEN * 2)).map(|i| i as u8).collect();
@@ -1187,4 +2068,127 @@ mod tests {
         let deserialised_user_msg = unwrap!(UserMessage::from_parts(msg_hash, payloads.iter()));
         assert_eq!(user_msg, deserialised_user_msg);
     }
+
+    #[test]
+    fn user_message_cache_keeps_common_signers() {
+        let data_bytes: Vec<u8> = (0..(MAX_PART_LEN * 2)).map(|i| i as u8).collect();
+        let user_msg = UserMessage::Response(Response::GetIData {
+            res: Ok(ImmutableData::new(data_bytes)),
+            msg_id: MessageId::new(),
+        });
+        let ids: Vec<PublicId> = (0..3).map(|_| *FullId::new().public_id()).collect();
+        let signer_sets: Vec<BTreeSet<_>> = vec![
+            ids.iter().cloned().collect(),
+            ids[..2].iter().cloned().collect(),
+            ids[1..].iter().cloned().collect(),
+        ];
+        let mut cache = UserMessageCache::with_expiry_duration(Duration::from_secs(60));
+        let mut result = None;
+        for (part, signers) in unwrap!(user_msg.to_parts(0)).into_iter().zip(signer_sets) {
+            match part {
+                MessageContent::UserMessagePart {
+                    hash,
+                    part_count,
+                    part_index,
+                    payload,
+                    ..
+                } => {
+                    assert!(result.is_none());
+                    result = cache.add_signed(hash, part_count, part_index, payload, signers);
+                }
+                msg => panic!("Unexpected message {:?}", msg),
+            }
+        }
+        let (cached_user_msg, signers) = unwrap!(result);
+        assert_eq!(cached_user_msg, user_msg);
+        assert_eq!(signers, iter::once(ids[1]).collect::<BTreeSet<_>>());
+    }
+
+    #[test]
+    fn mutation_parts_are_cacheable() {
+        fn is_cacheable(user_msg: UserMessage) -> bool {
+            unwrap!(user_msg.to_parts(0))
+                .into_iter()
+                .all(|part| match part {
+                    MessageContent::UserMessagePart { cacheable, .. } => cacheable,
+                    msg => panic!("Unexpected message {:?}", msg),
+                })
+        }
+
+        let name: XorName = rand::random();
+        let tag = 10_000;
+        let msg_id = MessageId::new();
+        let requester = sign::gen_keypair().0;
+        let mutations = vec![
+            Request::MutateMDataEntries {
+                name,
+                tag,
+                actions: Default::default(),
+                msg_id,
+                requester,
+            },
+            Request::SetMDataUserPermissions {
+                name,
+                tag,
+                user: User::Anyone,
+                permissions: PermissionSet::new(),
+                version: 1,
+                msg_id,
+                requester,
+            },
+            Request::DelMDataUserPermissions {
+                name,
+                tag,
+                user: User::Anyone,
+                version: 1,
+                msg_id,
+                requester,
+            },
+            Request::ChangeMDataOwner {
+                name,
+                tag,
+                new_owners: iter::once(requester).collect(),
+                version: 1,
+                msg_id,
+            },
+        ];
+        for request in mutations {
+            assert_eq!(Some((name, tag)), request.mutated_data());
+            assert!(!request.is_cacheable());
+            assert!(is_cacheable(UserMessage::Request(request)));
+        }
+
+        let responses = vec![
+            Response::MutateMDataEntries {
+                res: Ok(()),
+                msg_id,
+            },
+            Response::SetMDataUserPermissions {
+                res: Ok(()),
+                msg_id,
+            },
+            Response::DelMDataUserPermissions {
+                res: Ok(()),
+                msg_id,
+            },
+            Response::ChangeMDataOwner {
+                res: Ok(()),
+                msg_id,
+            },
+        ];
+        for response in responses {
+            assert_eq!(Some(&Ok(())), response.mutation_result());
+            assert!(is_cacheable(UserMessage::Response(response)));
+        }
+
+        let get = Request::GetMData { name, tag, msg_id };
+        assert_eq!(None, get.mutated_data());
+        assert!(!is_cacheable(UserMessage::Request(get)));
+        let put = Response::PutMData {
+            res: Ok(()),
+            msg_id,
+        };
+        assert_eq!(None, put.mutation_result());
+        assert!(!is_cacheable(UserMessage::Response(put)));
+    }
 }
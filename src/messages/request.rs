@@ -6,6 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use super::Response;
+use crate::client_error::ClientError;
 use crate::data::{EntryAction, ImmutableData, MutableData, PermissionSet, User};
 use crate::rust_sodium::crypto::sign;
 use crate::types::MessageId as MsgId;
@@ -256,12 +258,114 @@ impl Request {
         }
     }
 
+    /// Returns the response to this request carrying the given error, or `None` for a refresh,
+    /// which isn't responded to.
+    pub fn error_response(&self, error: ClientError) -> Option<Response> {
+        use crate::Request::*;
+        Some(match *self {
+            Refresh(..) => return None,
+            GetAccountInfo(msg_id) => Response::GetAccountInfo {
+                res: Err(error),
+                msg_id,
+            },
+            PutIData { msg_id, .. } => Response::PutIData {
+                res: Err(error),
+                msg_id,
+            },
+            GetIData { msg_id, .. } => Response::GetIData {
+                res: Err(error),
+                msg_id,
+            },
+            GetMData { msg_id, .. } => Response::GetMData {
+                res: Err(error),
+                msg_id,
+            },
+            PutMData { msg_id, .. } => Response::PutMData {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataVersion { msg_id, .. } => Response::GetMDataVersion {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataShell { msg_id, .. } => Response::GetMDataShell {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataEntries { msg_id, .. } => Response::ListMDataEntries {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataKeys { msg_id, .. } => Response::ListMDataKeys {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataValues { msg_id, .. } => Response::ListMDataValues {
+                res: Err(error),
+                msg_id,
+            },
+            GetMDataValue { msg_id, .. } => Response::GetMDataValue {
+                res: Err(error),
+                msg_id,
+            },
+            MutateMDataEntries { msg_id, .. } => Response::MutateMDataEntries {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataPermissions { msg_id, .. } => Response::ListMDataPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            ListMDataUserPermissions { msg_id, .. } => Response::ListMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            SetMDataUserPermissions { msg_id, .. } => Response::SetMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            DelMDataUserPermissions { msg_id, .. } => Response::DelMDataUserPermissions {
+                res: Err(error),
+                msg_id,
+            },
+            ChangeMDataOwner { msg_id, .. } => Response::ChangeMDataOwner {
+                res: Err(error),
+                msg_id,
+            },
+            ListAuthKeysAndVersion(msg_id) => Response::ListAuthKeysAndVersion {
+                res: Err(error),
+                msg_id,
+            },
+            InsAuthKey { msg_id, .. } => Response::InsAuthKey {
+                res: Err(error),
+                msg_id,
+            },
+            DelAuthKey { msg_id, .. } => Response::DelAuthKey {
+                res: Err(error),
+                msg_id,
+            },
+        })
+    }
+
     /// Is the response corresponding to this request cacheable?
     pub fn is_cacheable(&self) -> bool {
-        if let Request::GetIData { .. } = *self {
-            true
-        } else {
-            false
+        match *self {
+            Request::GetIData { .. } | Request::GetMData { .. } | Request::GetMDataShell { .. } => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the name and type tag of the `MutableData` this request modifies, if it modifies
+    /// existing data. Responses to such requests may have been cached and need to be invalidated.
+    pub fn mutated_data(&self) -> Option<(XorName, u64)> {
+        match *self {
+            Request::MutateMDataEntries { name, tag, .. }
+            | Request::SetMDataUserPermissions { name, tag, .. }
+            | Request::DelMDataUserPermissions { name, tag, .. }
+            | Request::ChangeMDataOwner { name, tag, .. } => Some((name, tag)),
+            _ => None,
         }
     }
 }
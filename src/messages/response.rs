@@ -10,6 +10,7 @@ use crate::client_error::ClientError;
 use crate::data::{ImmutableData, MutableData, PermissionSet, User, Value};
 use crate::rust_sodium::crypto::sign;
 use crate::types::MessageId as MsgId;
+use crate::xor_name::XorName;
 use std::collections::{BTreeMap, BTreeSet};
 
 /// Response message types
@@ -216,10 +217,45 @@ impl Response {
 
     /// Is this response cacheable?
     pub fn is_cacheable(&self) -> bool {
-        if let Response::GetIData { .. } = *self {
-            true
-        } else {
-            false
+        match *self {
+            Response::GetIData { .. }
+            | Response::GetMData { .. }
+            | Response::GetMDataShell { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Is this a response to a request for `MutableData`?
+    pub fn is_mdata(&self) -> bool {
+        match *self {
+            Response::GetMData { .. } | Response::GetMDataShell { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Returns the name, type tag and version of the `MutableData` this response carries, if any.
+    /// A cache should never replace a response with one carrying an older version.
+    pub fn mdata_version(&self) -> Option<(XorName, u64, u64)> {
+        match *self {
+            Response::GetMData {
+                res: Ok(ref data), ..
+            }
+            | Response::GetMDataShell {
+                res: Ok(ref data), ..
+            } => Some((*data.name(), data.tag(), data.version())),
+            _ => None,
+        }
+    }
+
+    /// Returns the result of modifying existing `MutableData`, or `None` if this is not a response
+    /// to such a request.
+    pub fn mutation_result(&self) -> Option<&Result<(), ClientError>> {
+        match *self {
+            Response::MutateMDataEntries { ref res, .. }
+            | Response::SetMDataUserPermissions { ref res, .. }
+            | Response::DelMDataUserPermissions { ref res, .. }
+            | Response::ChangeMDataOwner { ref res, .. } => Some(res),
+            _ => None,
         }
     }
 }
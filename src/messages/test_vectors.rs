@@ -0,0 +1,144 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Golden test vectors pinning the wire format of our messages.
+//!
+//! Each test serialises a message built from fixed keys, names and nonces, and compares the bytes
+//! with the hex-encoded vector in `tests/vectors`. A mismatch means the wire format changed, which
+//! breaks compatibility with nodes running other versions or implementations. A missing vector is
+//! a failure too. If the change is intended, rerun the tests with `ROUTING_RECORD_VECTORS` set to
+//! write the vectors anew, and commit them together with the change.
+//!
+//! The vectors rely on real signatures, so the module is skipped with `use-mock-crypto`. CI runs it
+//! in a separate step without the mock features.
+
+use super::{DirectMessage, MessageContent, RoutingMessage, SectionList, SignedMessage};
+use crate::id::{FullId, PublicId};
+use crate::routing_table::{Authority, Prefix};
+use crate::rust_sodium::crypto::{box_, sign};
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use hex;
+use maidsafe_utilities::serialisation::serialise;
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::iter;
+use std::path::PathBuf;
+
+fn full_id(seed: u8) -> FullId {
+    let sign_keys = sign::keypair_from_seed(&sign::Seed([seed; sign::SEEDBYTES]));
+    let encrypt_keys = (
+        box_::PublicKey([seed; box_::PUBLICKEYBYTES]),
+        box_::SecretKey([seed; box_::SECRETKEYBYTES]),
+    );
+    FullId::with_keys(encrypt_keys, sign_keys)
+}
+
+fn message_id(seed: u8) -> MessageId {
+    MessageId::from_added_node(XorName([seed; 32]))
+}
+
+fn connection_info_request() -> RoutingMessage {
+    let sender = full_id(1);
+    RoutingMessage {
+        src: Authority::ManagedNode(*sender.public_id().name()),
+        dst: Authority::ManagedNode(*full_id(2).public_id().name()),
+        content: MessageContent::ConnectionInfoRequest {
+            encrypted_conn_info: vec![3; 16],
+            nonce: [4; box_::NONCEBYTES],
+            pub_id: *sender.public_id(),
+            msg_id: message_id(5),
+        },
+        trace: false,
+    }
+}
+
+fn connection_info_response() -> RoutingMessage {
+    let sender = full_id(2);
+    RoutingMessage {
+        src: Authority::ManagedNode(*sender.public_id().name()),
+        dst: Authority::ManagedNode(*full_id(1).public_id().name()),
+        content: MessageContent::ConnectionInfoResponse {
+            encrypted_conn_info: vec![6; 16],
+            nonce: [7; box_::NONCEBYTES],
+            pub_id: *sender.public_id(),
+            msg_id: message_id(5),
+        },
+        trace: false,
+    }
+}
+
+// The message a section sends its members and neighbours on churn.
+fn section_update() -> RoutingMessage {
+    let members: Vec<PublicId> = (1..4).map(|seed| *full_id(seed).public_id()).collect();
+    let prefix = Prefix::new(1, XorName([0; 32]));
+    RoutingMessage {
+        src: Authority::PrefixSection(prefix),
+        dst: Authority::PrefixSection(prefix),
+        content: MessageContent::SectionUpdate {
+            versioned_prefix: prefix.with_version(8),
+            members: members.into_iter().collect(),
+        },
+        trace: false,
+    }
+}
+
+// Compares the serialised `value` with the vector `name`, or records it if `ROUTING_RECORD_VECTORS`
+// is set.
+fn check_vector<T: Serialize>(name: &str, value: &T) {
+    let bytes = unwrap!(serialise(value));
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests");
+    path.push("vectors");
+    path.push(format!("{}.hex", name));
+
+    if env::var_os("ROUTING_RECORD_VECTORS").is_some() {
+        if let Some(dir) = path.parent() {
+            unwrap!(fs::create_dir_all(dir));
+        }
+        unwrap!(fs::write(&path, hex::encode(&bytes) + "\n"));
+        return;
+    }
+
+    let vector = unwrap!(
+        fs::read_to_string(&path),
+        "The vector {} is missing. Rerun the tests with ROUTING_RECORD_VECTORS set to record it.",
+        path.display()
+    );
+    let expected = unwrap!(hex::decode(vector.trim()));
+    assert!(
+        bytes == expected,
+        "The wire format of {} changed. If this is intended, rerun the tests with \
+         ROUTING_RECORD_VECTORS set to record the new vector.",
+        name
+    );
+}
+
+#[test]
+fn routing_messages() {
+    check_vector("connection_info_request", &connection_info_request());
+    check_vector("connection_info_response", &connection_info_response());
+    check_vector("section_update", &section_update());
+}
+
+#[test]
+fn signed_message() {
+    let sender = full_id(1);
+    let prefix = Prefix::new(0, XorName([0; 32]));
+    let section = SectionList::from(prefix, iter::once(*sender.public_id()));
+    let signed_msg = unwrap!(SignedMessage::new(section_update(), &sender, vec![section]));
+    check_vector("signed_message", &signed_msg);
+}
+
+#[test]
+fn direct_message() {
+    let signature = unwrap!(section_update().to_signature(full_id(2).signing_private_key()));
+    check_vector("message_signature", &signature);
+    check_vector("candidate_name_taken", &DirectMessage::CandidateNameTaken);
+}
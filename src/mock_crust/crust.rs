@@ -71,6 +71,17 @@ impl<UID: Uid> Service<UID> {
         self.lock().restart(event_sender, uid)
     }
 
+    /// Like `restart`, but also replaces the configuration, e.g. to bootstrap off other contacts.
+    pub fn restart_with_config(
+        &self,
+        event_sender: CrustEventSender<UID>,
+        config: Config,
+        uid: UID,
+    ) {
+        self.lock().set_config(config);
+        self.restart(event_sender, uid)
+    }
+
     /// Start the bootstrapping procedure.
     pub fn start_bootstrap(
         &mut self,
@@ -15,5 +15,6 @@ mod support;
 mod tests;
 
 pub use self::support::{
-    make_current, take_current, to_socket_addr, with_current, Endpoint, Network, ServiceHandle,
+    make_current, take_current, to_endpoint, to_socket_addr, with_current, Endpoint, Network,
+    ServiceHandle,
 };
@@ -16,6 +16,7 @@ use crate::CrustEvent;
 use maidsafe_utilities::SeededRng;
 use rand::Rng;
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::btree_map::Entry;
 use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::net::{IpAddr, SocketAddr};
@@ -31,6 +32,7 @@ pub struct NetworkImpl<UID: Uid> {
     queue: BTreeMap<(Endpoint, Endpoint), VecDeque<Packet<UID>>>,
     blocked_connections: HashSet<(Endpoint, Endpoint)>,
     delayed_connections: HashSet<(Endpoint, Endpoint)>,
+    reordered_connections: HashSet<(Endpoint, Endpoint)>,
     rng: SeededRng,
     message_sent: bool,
 }
@@ -50,6 +52,7 @@ impl<UID: Uid> Network<UID> {
             queue: BTreeMap::new(),
             blocked_connections: HashSet::new(),
             delayed_connections: HashSet::new(),
+            reordered_connections: HashSet::new(),
             // Use `SeededRng::new()` here rather than passing in `rng`
             // so that a fresh one is used in every test, i.e. it will
             // not have been affected by initialising rust_sodium.
@@ -145,6 +148,13 @@ impl<UID: Uid> Network<UID> {
         let _ = imp.delayed_connections.insert((sender, receiver));
     }
 
+    /// Deliver the messages from `sender` to `receiver` in random order rather than the order they
+    /// were sent in. Messages are still delivered after the connection events sent before them.
+    pub fn reorder_connection(&self, sender: Endpoint, receiver: Endpoint) {
+        let mut imp = self.0.borrow_mut();
+        let _ = imp.reordered_connections.insert((sender, receiver));
+    }
+
     /// Simulates the loss of a connection.
     pub fn lost_connection(&self, node_1: Endpoint, node_2: Endpoint) {
         let service_1 = unwrap!(
@@ -174,6 +184,40 @@ impl<UID: Uid> Network<UID> {
             .send_event(CrustEvent::LostPeer(unwrap!(service_1.borrow().uid)));
     }
 
+    /// Simulates the loss of a connection which hasn't been reported to `node_1` yet: it can't send
+    /// to `node_2` anymore, but still has it in its routing table.
+    pub fn unreported_lost_connection(&self, node_1: Endpoint, node_2: Endpoint) {
+        let service_1 = unwrap!(
+            self.find_service(node_1),
+            "Cannot fetch service of {:?}.",
+            node_1
+        );
+        let _ = service_1.borrow_mut().remove_connection_by_endpoint(node_2);
+    }
+
+    /// Simulates a direct connection between two nodes succeeding late, e.g. after they already
+    /// found a tunnel to each other.
+    pub fn late_connection(&self, node_1: Endpoint, node_2: Endpoint) {
+        let service_1 = unwrap!(
+            self.find_service(node_1),
+            "Cannot fetch service of {:?}.",
+            node_1
+        );
+        let service_2 = unwrap!(
+            self.find_service(node_2),
+            "Cannot fetch service of {:?}.",
+            node_2
+        );
+        let uid_1 = unwrap!(service_1.borrow().uid);
+        let uid_2 = unwrap!(service_2.borrow().uid);
+        service_1
+            .borrow_mut()
+            .add_rendezvous_connection(uid_2, node_2);
+        service_2
+            .borrow_mut()
+            .add_rendezvous_connection(uid_1, node_1);
+    }
+
     /// Simulates a crust event being sent to the node.
     pub fn send_crust_event(&self, node: Endpoint, crust_event: CrustEvent<UID>) {
         let service = unwrap!(
@@ -243,10 +287,26 @@ impl<UID: Uid> Network<UID> {
         } else {
             return None;
         };
+        let index = if network_impl
+            .reordered_connections
+            .contains(&(sender, receiver))
+        {
+            let count = network_impl.queue[&(sender, receiver)]
+                .iter()
+                .take_while(|packet| packet.is_message())
+                .count();
+            network_impl.rng.gen_range(0, cmp::max(count, 1))
+        } else {
+            0
+        };
         let result = network_impl
             .queue
             .get_mut(&(sender, receiver))
-            .and_then(|packets| packets.pop_front().map(|packet| (sender, receiver, packet)));
+            .and_then(|packets| {
+                packets
+                    .remove(index)
+                    .map(|packet| (sender, receiver, packet))
+            });
         if result.is_some() {
             if let Entry::Occupied(entry) = network_impl.queue.entry((sender, receiver)) {
                 if entry.get().is_empty() {
@@ -350,6 +410,10 @@ impl<UID: Uid> ServiceImpl<UID> {
         self.event_sender = Some(event_sender);
     }
 
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
     pub fn restart(&mut self, event_sender: CrustEventSender<UID>, uid: UID) {
         trace!("{:?} restart", self.endpoint);
 
@@ -633,6 +697,18 @@ pub fn to_socket_addr(endpoint: Endpoint) -> SocketAddr {
     )
 }
 
+/// Returns the endpoint whose address is `addr`, ignoring the port, which is the inverse of
+/// `to_socket_addr` for the addresses returned by `get_peer_ip_addr`.
+pub fn to_endpoint(addr: SocketAddr) -> Endpoint {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            Endpoint(((octets[2] as usize) << 8) | octets[3] as usize)
+        }
+        IpAddr::V6(_) => panic!("Unexpected IPv6 address {}", addr),
+    }
+}
+
 /// Simulated crust config file.
 #[derive(Clone)]
 pub struct Config {
@@ -680,6 +756,13 @@ enum Packet<UID: Uid> {
 }
 
 impl<UID: Uid> Packet<UID> {
+    fn is_message(&self) -> bool {
+        match *self {
+            Packet::Message(_) => true,
+            _ => false,
+        }
+    }
+
     // Given a request packet, returns the corresponding failure packet.
     fn to_failure(&self) -> Option<Packet<UID>> {
         match *self {
@@ -7,36 +7,56 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::action::Action;
-use crate::cache::{Cache, NullCache};
+use crate::cache::{self, Cache, CacheObserver, CacheStats, NullCache, ObservedCache};
 use crate::client_error::ClientError;
+use crate::close_group_snapshot;
 use crate::config_handler::{self, Config};
 use crate::data::{EntryAction, ImmutableData, MutableData, PermissionSet, User, Value};
+use crate::endpoint_acl::IpRange;
 use crate::error::{InterfaceError, RoutingError};
 use crate::event::Event;
+#[cfg(not(feature = "use-mock-crust"))]
+use crate::event_channel::EventSender;
 use crate::event_stream::{EventStepper, EventStream};
 use crate::id::{FullId, PublicId};
+#[cfg(feature = "use-mock-crust")]
+use crate::messages::RelocationDenial;
 use crate::messages::{
     AccountInfo, Request, Response, UserMessage, CLIENT_GET_PRIORITY, DEFAULT_PRIORITY,
     RELOCATE_PRIORITY,
 };
 use crate::outbox::{EventBox, EventBuf};
+use crate::peer_manager::PeerInfo;
+use crate::request_validator::RequestValidator;
+use crate::rng;
 #[cfg(feature = "use-mock-crust")]
 use crate::routing_table::Prefix;
-use crate::routing_table::{Authority, RoutingTable};
-use crate::rust_sodium::crypto::sign;
+use crate::routing_table::{Authority, AuthorityExplanation, RoutingTable};
+use crate::rust_sodium::{self, crypto::sign};
+use crate::signature_accumulator::RefreshPolicy;
 use crate::state_machine::{State, StateMachine};
 use crate::states::{self, Bootstrapping, BootstrappingTargetState};
 use crate::types::{MessageId, RoutingActionSender};
 use crate::xor_name::XorName;
 use crate::MIN_SECTION_SIZE;
 #[cfg(not(feature = "use-mock-crust"))]
-use rust_sodium;
+use maidsafe_utilities::thread::{self, Joiner};
+use maidsafe_utilities::SeededRng;
 use std::collections::{BTreeMap, BTreeSet};
 #[cfg(feature = "use-mock-crust")]
 use std::fmt::{self, Debug, Formatter};
+use std::io;
 #[cfg(feature = "use-mock-crust")]
 use std::net::IpAddr;
+use std::path::PathBuf;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::mpsc::{channel, Receiver, RecvError, Sender, TryRecvError};
+use std::time::Duration;
+
+/// How long a `NodeHandle` waits for the node's thread to answer a call.
+#[cfg(not(feature = "use-mock-crust"))]
+const HANDLE_TIMEOUT: Duration = Duration::from_secs(10);
 
 // Helper macro to implement request sending methods.
 macro_rules! impl_request {
@@ -83,8 +103,12 @@ macro_rules! impl_response {
 /// A builder to configure and create a new `Node`.
 pub struct NodeBuilder {
     cache: Box<Cache>,
+    cache_observer: Option<Box<CacheObserver>>,
+    request_validator: Option<Box<RequestValidator>>,
     first: bool,
     config: Option<Config>,
+    rng_seed: Option<[u32; 4]>,
+    keys: Option<FullId>,
 }
 
 impl NodeBuilder {
@@ -93,6 +117,24 @@ impl NodeBuilder {
         NodeBuilder { cache, ..self }
     }
 
+    /// Configures the node to notify `observer` whenever it puts responses into or invalidates
+    /// responses in its request cache.
+    pub fn cache_observer(self, observer: Box<CacheObserver>) -> NodeBuilder {
+        NodeBuilder {
+            cache_observer: Some(observer),
+            ..self
+        }
+    }
+
+    /// Configures the node to check each request it receives with `validator` before raising it.
+    /// Rejected requests are answered with the validator's error.
+    pub fn request_validator(self, validator: Box<RequestValidator>) -> NodeBuilder {
+        NodeBuilder {
+            request_validator: Some(validator),
+            ..self
+        }
+    }
+
     /// Configures the node to start a new network instead of joining an existing one.
     pub fn first(self, first: bool) -> NodeBuilder {
         NodeBuilder { first, ..self }
@@ -106,6 +148,29 @@ impl NodeBuilder {
         }
     }
 
+    /// Configures the node to start with the given keys rather than newly generated ones. A node
+    /// restarted with the same keys reconnects to the section members it saved if
+    /// `persist_close_group` is enabled.
+    pub fn keys(self, keys: FullId) -> NodeBuilder {
+        NodeBuilder {
+            keys: Some(keys),
+            ..self
+        }
+    }
+
+    /// Configures the node to seed its random number generators with `seed`, so that its keys,
+    /// connection tokens, message IDs and timing jitter are reproducible, e.g. in simulations.
+    ///
+    /// This must not be used in production: the keys are only as secret as the seed. The crypto
+    /// library can only be seeded before it is first used, so the keys are only reproducible for
+    /// the first node or client created in the process.
+    pub fn rng_seed(self, seed: [u32; 4]) -> NodeBuilder {
+        NodeBuilder {
+            rng_seed: Some(seed),
+            ..self
+        }
+    }
+
     /// Creates new `Node`.
     ///
     /// It will automatically connect to the network in the same way a client does, but then
@@ -113,41 +178,145 @@ impl NodeBuilder {
     ///
     /// The initial `Node` object will have newly generated keys.
     pub fn create(self) -> Result<Node, RoutingError> {
+        self.init_sodium()?;
+        Ok(self.build().1)
+    }
+
+    /// Creates a new `Node` which runs on its own thread and passes its events to `event_sender`.
+    ///
+    /// The node is driven by that thread, so unlike with `create`, the events don't need to be
+    /// polled for. The returned handle is used to send messages and to shut the node down.
+    ///
+    /// `event_sender` is either a `Sender` or a `SyncSender`. With the latter, the channel's
+    /// capacity bounds the backlog of events, as described in `EventSender`.
+    #[cfg(not(feature = "use-mock-crust"))]
+    pub fn spawn<S: Into<EventSender>>(self, event_sender: S) -> Result<NodeHandle, RoutingError> {
+        self.init_sodium()?;
+
+        let mut event_sender = event_sender.into();
+
+        let (get_action_sender_tx, get_action_sender_rx) = channel();
+        let joiner = thread::named("Node thread", move || {
+            let (action_sender, mut node) = self.build();
+            if get_action_sender_tx.send(action_sender).is_err() {
+                return;
+            }
+            // Stop once the node has terminated or nobody is listening to its events anymore.
+            while let Ok(event) = node.next_ev() {
+                if !event_sender.send(event) {
+                    return;
+                }
+            }
+        });
+
+        let action_sender = get_action_sender_rx
+            .recv()
+            .map_err(|_| RoutingError::NotBootstrapped)?;
+
+        Ok(NodeHandle {
+            action_sender,
+            _joiner: joiner,
+        })
+    }
+
+    // Initialises `rust_sodium`, which generates our keys. It has to be seeded before it is
+    // initialised, as that fixes its generator for the rest of the process.
+    fn init_sodium(&self) -> Result<(), RoutingError> {
+        if let Some(seed) = self.rng_seed {
+            rust_sodium::init_with_rng(&mut SeededRng::from_seed(seed)).map_err(|error| {
+                error!("Failed to seed rust_sodium: {}", error);
+                RoutingError::InitialisationFailed
+            })?;
+        }
         // If we're not in a test environment where we might want to manually seed the crypto RNG
         // then seed randomly.
         #[cfg(not(feature = "use-mock-crust"))]
         let _ = rust_sodium::init();
+        Ok(())
+    }
 
+    fn build(self) -> (RoutingActionSender, Node) {
         let mut ev_buffer = EventBuf::new();
 
         // start the handler for routing without a restriction to become a full node
-        let (_, machine) = self.make_state_machine(&mut ev_buffer);
+        let (action_sender, machine) = self.make_state_machine(&mut ev_buffer);
         let (tx, rx) = channel();
 
-        Ok(Node {
+        let node = Node {
             interface_result_tx: tx,
             interface_result_rx: rx,
             machine,
             event_buffer: ev_buffer,
-        })
+            last_churn_event: None,
+        };
+        (action_sender, node)
     }
 
     fn make_state_machine(self, outbox: &mut EventBox) -> (RoutingActionSender, StateMachine) {
-        let full_id = FullId::new();
+        // This runs on the thread which drives the node, so its generator is the one we seed.
+        if let Some(seed) = self.rng_seed {
+            rng::seed(seed);
+        }
+        let full_id = self.keys.unwrap_or_else(FullId::new);
         let pub_id = *full_id.public_id();
         let config = self.config.unwrap_or_else(config_handler::get_config);
-        let dev_config = config.dev.unwrap_or_default();
+        let mut dev_config = config.dev.unwrap_or_default();
         let min_section_size = dev_config.min_section_size.unwrap_or(MIN_SECTION_SIZE);
+        let first = self.first;
+        // Our previous neighbours are likely to still be close to where we rejoin, so we bootstrap
+        // off them and treat them as static contacts. If none of them are reachable, we simply
+        // discover our section as usual.
+        let mut bootstrap_config = None;
+        if dev_config.persist_close_group && !first {
+            match close_group_snapshot::restore(&full_id) {
+                Ok(members) => {
+                    debug!(
+                        target: "routing_connect",
+                        "Restored {} previous section members.",
+                        members.len()
+                    );
+                    bootstrap_config = close_group_snapshot::bootstrap_config(
+                        members.iter().filter_map(|&(_, addr)| addr).collect(),
+                    );
+                    dev_config
+                        .static_contacts
+                        .extend(members.into_iter().map(|(pub_id, _)| pub_id));
+                }
+                Err(error) => debug!(
+                    target: "routing_connect",
+                    "Failed to restore previous section members: {:?}",
+                    error
+                ),
+            }
+        }
+        let request_validator = self.request_validator;
+        let cache: Box<Cache> = match self.cache_observer {
+            Some(observer) => Box::new(ObservedCache::new(self.cache, observer)),
+            None => self.cache,
+        };
+        if dev_config.persist_cache {
+            match cache::restore(&*cache) {
+                Ok(count) => debug!(target: "routing_cache", "Restored {} cached chunks.", count),
+                Err(error) => debug!(
+                    target: "routing_cache",
+                    "Failed to restore cached chunks: {:?}",
+                    error
+                ),
+            }
+        }
 
+        let lan_discovery = dev_config.lan_discovery_enabled();
         StateMachine::new(
             move |action_sender, crust_service, timer, outbox2| {
-                if self.first {
+                if first {
                     if let Some(state) = states::Node::first(
                         action_sender,
-                        self.cache,
+                        cache,
+                        request_validator,
                         crust_service,
                         full_id,
                         min_section_size,
+                        dev_config,
                         timer,
                     ) {
                         State::Node(state)
@@ -163,18 +332,21 @@ impl NodeBuilder {
                 } else {
                     Bootstrapping::new(
                         action_sender,
-                        self.cache,
+                        cache,
+                        request_validator,
                         BootstrappingTargetState::JoiningNode,
                         crust_service,
                         full_id,
                         min_section_size,
+                        dev_config,
                         timer,
                     )
                     .map_or(State::Terminated, State::Bootstrapping)
                 }
             },
             pub_id,
-            None,
+            bootstrap_config,
+            lan_discovery,
             outbox,
         )
     }
@@ -192,6 +364,8 @@ pub struct Node {
     interface_result_rx: Receiver<Result<(), InterfaceError>>,
     machine: StateMachine,
     event_buffer: EventBuf,
+    /// The node named in the latest `NodeAdded` or `NodeLost` event we returned.
+    last_churn_event: Option<XorName>,
 }
 
 impl Node {
@@ -199,8 +373,12 @@ impl Node {
     pub fn builder() -> NodeBuilder {
         NodeBuilder {
             cache: Box::new(NullCache),
+            cache_observer: None,
+            request_validator: None,
             first: false,
             config: None,
+            rng_seed: None,
+            keys: None,
         }
     }
 
@@ -318,6 +496,59 @@ impl Node {
                       msg_id: MessageId,
                   }, DEFAULT_PRIORITY);
 
+    /// Sends a message of a user-defined kind, identified by `tag`, from `src` to `dst`. It is
+    /// delivered as `Event::UserMessage`. If `src` is a group authority, the message is only
+    /// delivered once a quorum of the group sent it with the same `tag`, `payload` and `msg_id`.
+    pub fn send_user_message(
+        &mut self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        tag: u64,
+        payload: Vec<u8>,
+        msg_id: MessageId,
+    ) -> Result<(), InterfaceError> {
+        let msg = UserMessage::Tagged {
+            tag,
+            payload,
+            msg_id,
+        };
+        self.send_action(src, dst, msg, DEFAULT_PRIORITY)
+    }
+
+    /// Sends `request` from us to the group closest to `name`. Unlike the methods taking explicit
+    /// authorities, this uses our current name as the `ManagedNode` source.
+    pub fn send_request_to_group(
+        &mut self,
+        name: XorName,
+        request: Request,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        let action = Action::SendToGroup {
+            name,
+            content: UserMessage::Request(request),
+            priority,
+            result_tx: self.interface_result_tx.clone(),
+        };
+        self.send_interface_action(action)
+    }
+
+    /// Sends `request` from us to the node called `name`, using our current name as the
+    /// `ManagedNode` source.
+    pub fn send_request_to_node(
+        &mut self,
+        name: XorName,
+        request: Request,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        let action = Action::SendToNode {
+            name,
+            content: UserMessage::Request(request),
+            priority,
+            result_tx: self.interface_result_tx.clone(),
+        };
+        self.send_interface_action(action)
+    }
+
     /// Send a `Refresh` request from `src` to `dst` to trigger churn.
     pub fn send_refresh_request(
         &mut self,
@@ -330,6 +561,52 @@ impl Node {
         self.send_action(src, dst, msg, RELOCATE_PRIORITY)
     }
 
+    /// Send a `Refresh` request from the group authority `src` to itself. Its content is the
+    /// serialised tuple of `type_tag` and `payload`, and its message ID is derived via
+    /// `MessageId::from_refresh_cause` from `cause`, the name of the node whose churn triggered the
+    /// refresh. If `cause` is `None`, the node named in the latest `Event::NodeAdded` or
+    /// `Event::NodeLost` returned by this node is used: the other members of `src` raise the same
+    /// event for the same churn, so they derive the same message ID from it.
+    pub fn send_refresh(
+        &mut self,
+        src: Authority<XorName>,
+        type_tag: u64,
+        payload: Vec<u8>,
+        cause: Option<XorName>,
+    ) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding crust events.
+        let _ = self.poll();
+
+        let action = Action::SendRefresh {
+            src,
+            type_tag,
+            payload,
+            cause: cause.or(self.last_churn_event),
+            result_tx: self.interface_result_tx.clone(),
+        };
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(action, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        self.interface_result_rx.recv()?
+    }
+
+    /// Sets how refreshes with the given type tag, as sent by `send_refresh`, are accumulated and
+    /// handled, or restores the defaults if `policy` is `None`. All members of a group need to use
+    /// the same policies.
+    pub fn set_refresh_policy(&mut self, type_tag: u64, policy: Option<RefreshPolicy>) {
+        let _ = self.poll();
+
+        let transition = self.machine.current_mut().handle_action(
+            Action::SetRefreshPolicy { type_tag, policy },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
     /// Respond to a `GetAccountInfo` request.
     impl_response!(
         send_get_account_info_response,
@@ -502,6 +779,163 @@ impl Node {
         self.machine.close_group(name, count)
     }
 
+    /// Returns the names of the `min_section_size` nodes closest to `name`, including our own, or
+    /// `None` if we are not among them.
+    pub fn close_group_to(&self, name: XorName) -> Option<Vec<XorName>> {
+        self.close_group(name, self.min_section_size())
+    }
+
+    /// Returns whether we are among the `min_section_size` nodes closest to `name`.
+    pub fn is_closest(&self, name: &XorName) -> bool {
+        self.close_group_to(*name).is_some()
+    }
+
+    /// Connects to the given nodes as soon as they fit into our routing table, rather than waiting
+    /// to discover them. If we are not connected to the network yet, this happens once we are.
+    pub fn add_contacts(&mut self, contacts: Vec<PublicId>) {
+        // Make sure the state machine has processed any outstanding crust events.
+        let _ = self.poll();
+
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::AddContacts(contacts), &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
+    /// Replaces the IP ranges we accept new connections from, which are initially read from the
+    /// config file. If `allowed` is empty, all addresses not in `denied` are accepted. Existing
+    /// connections are not affected.
+    pub fn set_ip_ranges(&mut self, allowed: Vec<IpRange>, denied: Vec<IpRange>) {
+        let _ = self.poll();
+
+        let transition = self.machine.current_mut().handle_action(
+            Action::SetIpRanges { allowed, denied },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
+    /// Raises `Event::InternalState`, describing our current name, state, routing table and proxy.
+    pub fn request_internal_state(&mut self) {
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::GetInternalState, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
+    /// Returns our estimate of the number of nodes in the network, computed from the density of our
+    /// routing table, and whether it is exact because our routing table covers the whole network.
+    /// Returns `None` if we don't have a routing table yet.
+    pub fn network_size_estimate(&mut self) -> Result<Option<(u64, bool)>, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self.machine.current_mut().handle_action(
+            Action::GetNetworkSizeEstimate { result_tx },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        Ok(result_rx.recv()?)
+    }
+
+    /// Resets the per-client counters of relayed traffic reported by `Event::RelayAccounting`,
+    /// e.g. once the embedder has settled the reported traffic.
+    pub fn reset_relay_accounting(&mut self) {
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::ResetRelayAccounting, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
+    /// Starts appending the messages we send and receive to the capture file at `path`, in the
+    /// format described in the `capture` module. A capture in progress is stopped first. Returns an
+    /// error if the file can't be opened or written to, in which case nothing is captured.
+    pub fn start_capture(&mut self, path: PathBuf) -> io::Result<()> {
+        let (result_tx, result_rx) = channel();
+        let transition = self.machine.current_mut().handle_action(
+            Action::StartCapture { path, result_tx },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        result_rx
+            .recv()
+            .unwrap_or_else(|error| Err(io::Error::new(io::ErrorKind::Other, error)))
+    }
+
+    /// Stops the message capture, if one is in progress.
+    pub fn stop_capture(&mut self) {
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::StopCapture, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+    }
+
+    /// Returns the port our listener accepts connections on, if it has been started. Other nodes
+    /// need to be able to reach us on this port, e.g. for firewall and port forwarding rules.
+    pub fn listener_port(&mut self) -> Result<Option<u16>, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::ListenerPort { result_tx }, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        Ok(result_rx.recv()?)
+    }
+
+    /// Returns information about the peers we are connected to, such as their addresses and since
+    /// when we are connected. This is meant for debugging and monitoring. Empty unless we are a
+    /// routing node.
+    pub fn peer_info(&mut self) -> Result<Vec<PeerInfo>, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self
+            .machine
+            .current_mut()
+            .handle_action(Action::GetPeerInfo { result_tx }, &mut self.event_buffer);
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        Ok(result_rx.recv()?)
+    }
+
+    /// Returns whether this node is a part of `auth`, and why. This is meant for debugging
+    /// messages which are unexpectedly dropped, e.g. with `RoutingError::BadAuthority`.
+    pub fn explain_authority(
+        &mut self,
+        auth: Authority<XorName>,
+    ) -> Result<AuthorityExplanation, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self.machine.current_mut().handle_action(
+            Action::ExplainAuthority { auth, result_tx },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        Ok(result_rx.recv()?)
+    }
+
+    /// Returns whether this node is a member of the `NaeManager` authority for the data `name`,
+    /// i.e. whether it is among the nodes responsible for storing that data. Returns
+    /// `InterfaceError::InvalidState` if we are not an approved routing node yet.
+    pub fn is_nae_manager_for(&mut self, name: XorName) -> Result<bool, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        let transition = self.machine.current_mut().handle_action(
+            Action::IsNaeManagerFor { name, result_tx },
+            &mut self.event_buffer,
+        );
+        self.machine
+            .apply_transition(transition, &mut self.event_buffer);
+        result_rx.recv()?
+    }
+
     /// Returns the `PublicId` of this node.
     pub fn id(&self) -> Result<PublicId, RoutingError> {
         self.machine.id().ok_or(RoutingError::Terminated)
@@ -512,20 +946,47 @@ impl Node {
         self.machine.routing_table().ok_or(RoutingError::Terminated)
     }
 
+    /// Returns whether our request cache holds a response to `request`, i.e. whether we would
+    /// answer it from the cache. Always `false` if we are not a routing node yet.
+    pub fn cache_contains(&self, request: &Request) -> bool {
+        self.machine.cache_contains(request)
+    }
+
+    /// Returns statistics about our use of the request cache, or `None` if we are not a routing
+    /// node yet.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.machine.cache_stats()
+    }
+
+    /// Returns the peers we recently failed to connect to, with the number of consecutive failures
+    /// and the time left until we try to connect to them again, or `None` if we are not a routing
+    /// node yet. The retry delay doubles with every failure, but is shorter right after we started
+    /// and during churn storms.
+    pub fn connect_backoffs(&self) -> Option<BTreeMap<XorName, (u32, Duration)>> {
+        self.machine.connect_backoffs()
+    }
+
     /// Returns the minimum section size this vault is using.
     pub fn min_section_size(&self) -> usize {
         self.machine.min_section_size()
     }
 
-    fn send_action(
+    /// Processes all pending network events and timeouts without blocking, and returns the events
+    /// this raised. This allows embedding the node in a single-threaded event loop, e.g. in a
+    /// simulation.
+    pub fn step(&mut self) -> Vec<Event> {
+        let _ = self.poll();
+        self.event_buffer.take_all()
+    }
+
+    pub(crate) fn send_action(
         &mut self,
         src: Authority<XorName>,
         dst: Authority<XorName>,
         user_msg: UserMessage,
         priority: u8,
     ) -> Result<(), InterfaceError> {
-        // Make sure the state machine has processed any outstanding crust events.
-        let _ = self.poll();
+        check_authorities(&src, &dst, &user_msg)?;
 
         let action = Action::NodeSendMessage {
             src,
@@ -534,6 +995,13 @@ impl Node {
             priority,
             result_tx: self.interface_result_tx.clone(),
         };
+        self.send_interface_action(action)
+    }
+
+    // Handles an action which replies via `interface_result_tx`.
+    fn send_interface_action(&mut self, action: Action) -> Result<(), InterfaceError> {
+        // Make sure the state machine has processed any outstanding crust events.
+        let _ = self.poll();
 
         let transition = self
             .machine
@@ -557,7 +1025,11 @@ impl EventStepper for Node {
     }
 
     fn pop_item(&mut self) -> Option<Event> {
-        self.event_buffer.take_first()
+        let event = self.event_buffer.take_first();
+        if let Some(Event::NodeAdded(name, _)) | Some(Event::NodeLost(name, _)) = &event {
+            self.last_churn_event = Some(*name);
+        }
+        event
     }
 }
 
@@ -568,6 +1040,16 @@ impl Node {
         self.machine.current_mut().purge_invalid_rt_entry()
     }
 
+    /// Feeds `bytes` to the node as if it received them from the peer `src`, running them through
+    /// the same decoding and dispatch as messages from the network. Events raised by the handlers
+    /// are queued as usual.
+    #[cfg(feature = "fuzzing")]
+    pub fn fuzz_dispatch(&mut self, src: PublicId, bytes: Vec<u8>) -> Result<(), RoutingError> {
+        self.machine
+            .current_mut()
+            .fuzz_dispatch(src, bytes, &mut self.event_buffer)
+    }
+
     /// Check whether this node acts as a tunnel node between `client_1` and `client_2`.
     pub fn has_tunnel_clients(&self, client_1: PublicId, client_2: PublicId) -> bool {
         self.machine
@@ -589,6 +1071,17 @@ impl Node {
         self.machine.current().get_banned_client_ips()
     }
 
+    /// Returns whether this node ignores the message signatures of `pub_id`, a new routing table
+    /// entry in quarantine.
+    pub fn is_quarantined(&self, pub_id: &PublicId) -> bool {
+        self.machine.current().is_quarantined(pub_id)
+    }
+
+    /// Returns whether this node holds back direct messages because earlier ones are missing.
+    pub fn has_held_direct_messages(&self) -> bool {
+        self.machine.current().has_held_direct_messages()
+    }
+
     /// Returns whether the current state is `Node`.
     pub fn is_node(&self) -> bool {
         if let State::Node(..) = *self.machine.current() {
@@ -612,6 +1105,23 @@ impl Node {
             .set_next_relocation_interval(interval)
     }
 
+    /// Makes this node deny the next node relocation request it receives with the given reason.
+    pub fn deny_next_relocation(&mut self, reason: RelocationDenial) {
+        self.machine.current_mut().deny_next_relocation(reason)
+    }
+
+    /// Makes this node report to `src` that the message `msg_id` to `dst` failed to accumulate.
+    pub fn report_accumulation_failed(
+        &mut self,
+        src: Authority<XorName>,
+        msg_id: MessageId,
+        dst: Authority<XorName>,
+    ) {
+        self.machine
+            .current_mut()
+            .report_accumulation_failed(src, msg_id, dst)
+    }
+
     /// Clears the name to be used when the next node relocation request is received by this node so
     /// the normal process is followed to calculate the relocated name.
     pub fn clear_next_relocation_dst(&mut self) {
@@ -657,6 +1167,164 @@ impl Drop for Node {
     }
 }
 
+/// A handle to a `Node` running on its own thread, created by `NodeBuilder::spawn`.
+///
+/// Dropping the handle terminates the node and waits for its thread to finish.
+///
+/// The methods returning a result wait for the node's thread to produce it. That thread may itself
+/// be waiting for the receiver of its events to make room in a bounded channel, so if the receiver
+/// calls these methods while handling events, they fail with `InterfaceError::Timeout` after
+/// `HANDLE_TIMEOUT` rather than blocking both threads forever.
+#[cfg(not(feature = "use-mock-crust"))]
+pub struct NodeHandle {
+    action_sender: RoutingActionSender,
+    _joiner: Joiner,
+}
+
+#[cfg(not(feature = "use-mock-crust"))]
+impl NodeHandle {
+    /// Sends a request from `src` to `dst`. See `Node` for the roles `src` can take.
+    pub fn send_request(
+        &self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        request: Request,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send(src, dst, UserMessage::Request(request), priority)
+    }
+
+    /// Sends a response from `src` to `dst`. See `Node` for the roles `src` can take.
+    pub fn send_response(
+        &self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        response: Response,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        self.send(src, dst, UserMessage::Response(response), priority)
+    }
+
+    /// Sends a message of a user-defined kind from `src` to `dst`. See `Node::send_user_message`.
+    pub fn send_user_message(
+        &self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        tag: u64,
+        payload: Vec<u8>,
+        msg_id: MessageId,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        let msg = UserMessage::Tagged {
+            tag,
+            payload,
+            msg_id,
+        };
+        self.send(src, dst, msg, priority)
+    }
+
+    /// Asks the node to raise `Event::InternalState`, which is passed to the event sender like
+    /// all other events.
+    pub fn request_internal_state(&self) -> Result<(), InterfaceError> {
+        Ok(self.action_sender.send(Action::GetInternalState)?)
+    }
+
+    /// Returns whether the node is a member of the `NaeManager` authority for the data `name`. See
+    /// `Node::is_nae_manager_for`.
+    pub fn is_nae_manager_for(&self, name: XorName) -> Result<bool, InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        self.action_sender
+            .send(Action::IsNaeManagerFor { name, result_tx })?;
+        recv_result(&result_rx)
+    }
+
+    /// Starts capturing the node's messages to `path`. See `Node::start_capture`.
+    pub fn start_capture(&self, path: PathBuf) -> Result<(), InterfaceError> {
+        let (result_tx, result_rx) = channel();
+        self.action_sender
+            .send(Action::StartCapture { path, result_tx })?;
+        recv_result(&result_rx)
+    }
+
+    /// Stops the message capture, if one is in progress.
+    pub fn stop_capture(&self) -> Result<(), InterfaceError> {
+        Ok(self.action_sender.send(Action::StopCapture)?)
+    }
+
+    /// Terminates the node and waits for its thread to finish. This is the same as dropping the
+    /// handle, but more explicit.
+    pub fn shutdown(self) {}
+
+    fn send(
+        &self,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        user_msg: UserMessage,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        check_authorities(&src, &dst, &user_msg)?;
+        // A fresh channel for each call, so that the result of one which timed out can't be
+        // mistaken for the result of the next one.
+        let (result_tx, result_rx) = channel();
+        let action = Action::NodeSendMessage {
+            src,
+            dst,
+            content: user_msg,
+            priority,
+            result_tx,
+        };
+        self.action_sender.send(action)?;
+        recv_result(&result_rx)
+    }
+}
+
+// Waits up to `HANDLE_TIMEOUT` for the result of an action sent to a node's thread.
+#[cfg(not(feature = "use-mock-crust"))]
+fn recv_result<T, E>(result_rx: &Receiver<Result<T, E>>) -> Result<T, InterfaceError>
+where
+    InterfaceError: From<E>,
+{
+    match result_rx.recv_timeout(HANDLE_TIMEOUT) {
+        Ok(result) => Ok(result?),
+        Err(RecvTimeoutError::Timeout) => Err(InterfaceError::Timeout),
+        Err(RecvTimeoutError::Disconnected) => Err(InterfaceError::ChannelRxError(RecvError)),
+    }
+}
+
+#[cfg(not(feature = "use-mock-crust"))]
+impl Drop for NodeHandle {
+    fn drop(&mut self) {
+        if let Err(err) = self.action_sender.send(Action::Terminate) {
+            debug!("Error {:?} sending event to Core", err);
+        }
+    }
+}
+
+// Checks that a node is able to send `user_msg` from `src` to `dst`. Otherwise the message would
+// be built and then silently dropped by the state machine, or rejected by its recipients.
+fn check_authorities(
+    src: &Authority<XorName>,
+    dst: &Authority<XorName>,
+    user_msg: &UserMessage,
+) -> Result<(), InterfaceError> {
+    let reason = match *user_msg {
+        _ if src.is_client() => "a node can't send messages as a client",
+        UserMessage::Request(Request::Refresh(..)) if !src.is_multiple() => {
+            "a refresh must be sent by a group authority"
+        }
+        UserMessage::Request(Request::Refresh(..)) if src != dst => {
+            "a refresh must be sent to the group authority which sends it"
+        }
+        UserMessage::Request(_) if dst.is_client() => "a request can't be sent to a client",
+        _ => return Ok(()),
+    };
+    Err(InterfaceError::BadAuthority {
+        src: *src,
+        dst: *dst,
+        reason,
+    })
+}
+
 // Priority of messages that might be used during relocation/churn, depending
 // on the destination.
 fn relocate_priority(dst: &Authority<XorName>) -> u8 {
@@ -11,10 +11,12 @@ use crate::error::RoutingError;
 use crate::id::PublicId;
 use crate::messages::MessageContent;
 use crate::resource_prover::RESOURCE_PROOF_DURATION_SECS;
+use crate::rng;
 use crate::routing_table::Error as RoutingTableError;
 use crate::routing_table::{
     Authority, OwnMergeState, Prefix, RemovalDetails, RoutingTable, VersionedPrefix,
 };
+use crate::rust_sodium::crypto::sign;
 use crate::signature_accumulator::ACCUMULATION_TIMEOUT_SECS;
 use crate::types::MessageId;
 use crate::xor_name::XorName;
@@ -23,17 +25,19 @@ use crate::{PrivConnectionInfo, PubConnectionInfo};
 use fake_clock::FakeClock as Instant;
 use itertools::Itertools;
 use log::Level;
-use rand;
 use resource_proof::ResourceProof;
 use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::net::IpAddr;
-use std::time::Duration;
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
+use std::time::{Duration, SystemTime};
 use std::{error, fmt, iter, mem};
 
 /// Time (in seconds) after which a joining node will get dropped from the map of joining nodes.
 const JOINING_NODE_TIMEOUT_SECS: u64 = 900;
+/// Time (in seconds) after which we stop relaying for a joining node which hasn't sent us any
+/// messages. This exceeds the time a joining node waits for its `RelocateResponse`.
+const JOINING_NODE_SILENCE_TIMEOUT_SECS: u64 = 120 + RESOURCE_PROOF_DURATION_SECS;
 /// Time (in seconds) after which the connection to a peer is considered failed.
 const CONNECTING_PEER_TIMEOUT_SECS: u64 = 90;
 /// Time (in seconds) the node waits for a peer to either become valid once connected to it or to
@@ -41,6 +45,10 @@ const CONNECTING_PEER_TIMEOUT_SECS: u64 = 90;
 const CONNECTED_PEER_TIMEOUT_SECS: u64 = 60;
 /// Time (in seconds) after which a `VotedFor` candidate will be removed.
 const CANDIDATE_ACCEPT_TIMEOUT_SECS: u64 = 60;
+/// Default maximum number of clients we act as a proxy for.
+const MAX_CLIENTS: usize = 100;
+/// Default maximum number of joining nodes we act as a proxy for at the same time.
+const MAX_JOINING_NODES: usize = 10;
 
 #[cfg(feature = "use-mock-crust")]
 #[doc(hidden)]
@@ -53,6 +61,10 @@ pub mod test_consts {
     pub const CONNECTED_PEER_TIMEOUT_SECS: u64 = super::CONNECTED_PEER_TIMEOUT_SECS;
     pub const JOINING_NODE_TIMEOUT_SECS: u64 = super::JOINING_NODE_TIMEOUT_SECS;
     pub const RATE_EXCEED_RETRY_MS: u64 = crate::states::RATE_EXCEED_RETRY_MS;
+    pub const CACHE_RESPONSE_MAX_DELAY_MS: u64 = crate::states::CACHE_RESPONSE_MAX_DELAY_MS;
+    pub const TICK_TIMEOUT_SECS: u64 = crate::states::TICK_TIMEOUT_SECS;
+    pub const MAX_REJECTED_REQUESTS: usize = crate::states::MAX_REJECTED_REQUESTS;
+    pub const STANDBY_TUNNEL_SECS: u64 = crate::tunnels::STANDBY_TUNNEL_SECS;
 }
 
 pub type SectionMap = BTreeMap<VersionedPrefix<XorName>, BTreeSet<PublicId>>;
@@ -221,12 +233,33 @@ pub enum ReconnectingPeer {
     False,
 }
 
+/// Information about a peer we are connected to, as returned by `Node::peer_info`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PeerInfo {
+    /// The peer's public ID.
+    pub pub_id: PublicId,
+    /// The IP address of the peer, unless the connection goes via a tunnel node.
+    pub ip: Option<IpAddr>,
+    /// When the current connection to the peer was established.
+    pub connected_since: SystemTime,
+    /// Whether the connection goes via a tunnel node.
+    pub tunnelled: bool,
+    /// Whether we encrypt the messages we send to the peer, i.e. whether we have its session key.
+    pub sealed: bool,
+    /// Whether the peer is in our routing table.
+    pub routing: bool,
+    /// Whether we act as the peer's proxy, i.e. it is a client or a node which is still joining.
+    pub relayed: bool,
+}
+
 /// Represents peer we are connected or attempting connection to.
 #[derive(Debug)]
 pub struct Peer {
     pub_id: PublicId,
     state: PeerState,
     timestamp: Instant,
+    /// When the current connection to the peer was established, if we are connected.
+    connected_at: Option<Instant>,
     valid: bool,
     reconnecting: ReconnectingPeer,
 }
@@ -238,13 +271,18 @@ impl Peer {
         valid: bool,
         reconnecting: ReconnectingPeer,
     ) -> Self {
-        Self {
+        let mut peer = Self {
             pub_id,
             state,
             timestamp: Instant::now(),
+            connected_at: None,
             valid,
             reconnecting,
+        };
+        if peer.is_connected().is_some() {
+            peer.connected_at = Some(peer.timestamp);
         }
+        peer
     }
 
     pub fn pub_id(&self) -> &PublicId {
@@ -267,6 +305,21 @@ impl Peer {
         self.reconnecting == ReconnectingPeer::True
     }
 
+    /// Returns the information about the peer reported by `Node::peer_info`, if we are connected.
+    pub fn info(&self, ip: Option<IpAddr>, sealed: bool) -> Option<PeerInfo> {
+        let tunnelled = self.is_connected()?;
+        let connected_at = self.connected_at?;
+        Some(PeerInfo {
+            pub_id: self.pub_id,
+            ip: if tunnelled { None } else { ip },
+            connected_since: SystemTime::now() - connected_at.elapsed(),
+            tunnelled,
+            sealed,
+            routing: self.is_routing(),
+            relayed: self.is_client() || self.is_joining_node(),
+        })
+    }
+
     /// Returns connected status of `Peer`
     /// `None` for not connected. `Some(true)` for tunnels and `Some(false)` for direct connections
     fn is_connected(&self) -> Option<bool> {
@@ -292,7 +345,8 @@ impl Peer {
             | PeerState::ConnectionInfoReady(_)
             | PeerState::CrustConnecting
             | PeerState::SearchingForTunnel => CONNECTING_PEER_TIMEOUT_SECS,
-            PeerState::JoiningNode | PeerState::Proxy => JOINING_NODE_TIMEOUT_SECS,
+            PeerState::JoiningNode => JOINING_NODE_SILENCE_TIMEOUT_SECS,
+            PeerState::Proxy => JOINING_NODE_TIMEOUT_SECS,
             PeerState::Bootstrapper { .. } | PeerState::Connected(_) => CONNECTED_PEER_TIMEOUT_SECS,
             PeerState::Candidate(_) | PeerState::Client { .. } | PeerState::Routing(_) => {
                 return false;
@@ -413,12 +467,16 @@ struct ResourceProofChallenge {
 /// This keeps track of which nodes we know of, which ones we have tried to connect to, which IDs
 /// we have verified, whom we are directly connected to or via a tunnel.
 pub struct PeerManager {
-    connection_token_map: HashMap<u32, PublicId>,
+    /// The peer each pending `prepare_connection_info` call is for, and when the attempt started.
+    connection_token_map: HashMap<u32, (PublicId, Instant)>,
     peers: HashMap<PublicId, Peer>,
     routing_table: RoutingTable<XorName>,
     our_public_id: PublicId,
     candidate: Candidate,
     disable_client_rate_limiter: bool,
+    max_clients: usize,
+    reserved_client_keys: BTreeSet<sign::PublicKey>,
+    max_joining_nodes: usize,
 }
 
 impl PeerManager {
@@ -435,9 +493,29 @@ impl PeerManager {
             our_public_id,
             candidate: Candidate::None,
             disable_client_rate_limiter,
+            max_clients: MAX_CLIENTS,
+            reserved_client_keys: BTreeSet::new(),
+            max_joining_nodes: MAX_JOINING_NODES,
         }
     }
 
+    /// Sets the maximum number of clients we accept, or restores the default if `None`. Slots for
+    /// the clients with the `reserved_client_keys` are set aside from that number.
+    pub fn set_client_limits(
+        &mut self,
+        max_clients: Option<usize>,
+        reserved_client_keys: BTreeSet<sign::PublicKey>,
+    ) {
+        self.max_clients = max_clients.unwrap_or(MAX_CLIENTS);
+        self.reserved_client_keys = reserved_client_keys;
+    }
+
+    /// Sets the maximum number of joining nodes we relay for at the same time, or restores the
+    /// default if `None`.
+    pub fn set_joining_node_limit(&mut self, max_joining_nodes: Option<usize>) {
+        self.max_joining_nodes = max_joining_nodes.unwrap_or(MAX_JOINING_NODES);
+    }
+
     /// Add prefixes into routing table.
     pub fn add_prefixes(
         &mut self,
@@ -506,6 +584,33 @@ impl PeerManager {
         )
     }
 
+    /// Returns the old public ID and target interval of the candidate our section accepted, if it
+    /// hasn't sent its `CandidateInfo` yet.
+    pub fn accepted_candidate(&self) -> Option<(PublicId, (XorName, XorName))> {
+        match self.candidate {
+            Candidate::AcceptedForResourceProof {
+                old_pub_id,
+                target_interval,
+                ..
+            } => Some((old_pub_id, target_interval)),
+            _ => None,
+        }
+    }
+
+    /// Accepts a candidate which our section accepted before we joined it, unless we are already
+    /// handling a candidate. Returns whether the candidate was adopted.
+    pub fn adopt_candidate(
+        &mut self,
+        old_pub_id: PublicId,
+        target_interval: (XorName, XorName),
+    ) -> bool {
+        if self.candidate != Candidate::None {
+            return false;
+        }
+        let _ = self.accept_as_candidate(old_pub_id, target_interval);
+        true
+    }
+
     /// Verifies proof of resource.  If the response is not the current candidate, or if it fails
     /// validation, returns `Err`.  Otherwise returns the target size, difficulty and the time
     /// elapsed since the candidate was inserted.
@@ -965,6 +1070,28 @@ impl PeerManager {
             .map(Peer::name)
     }
 
+    /// Returns our proxy node if it isn't in our routing table, i.e. if we only use it to relay our
+    /// messages while we join.
+    pub fn unneeded_proxy(&self) -> Option<PublicId> {
+        self.peers
+            .values()
+            .find(|peer| match peer.state {
+                PeerState::Proxy => true,
+                _ => false,
+            })
+            .map(|peer| peer.pub_id)
+    }
+
+    /// Removes our proxy node with the given ID, unless it was added to our routing table in the
+    /// meantime. Returns whether it was removed.
+    pub fn remove_unneeded_proxy(&mut self, pub_id: &PublicId) -> bool {
+        if self.unneeded_proxy() != Some(*pub_id) {
+            return false;
+        }
+        let _ = self.peers.remove(pub_id);
+        true
+    }
+
     pub fn remove_expired_peers(&mut self) -> Vec<PublicId> {
         let remove_candidate = if self.candidate.is_expired() {
             match self.candidate {
@@ -1005,6 +1132,7 @@ impl PeerManager {
         for id in &expired_peers {
             let _ = self.remove_peer(id);
         }
+        self.remove_expired_connection_tokens();
 
         expired_peers
     }
@@ -1015,6 +1143,100 @@ impl PeerManager {
         self.peers.values().filter(|peer| peer.is_client()).count()
     }
 
+    /// Returns the peers we are connected to.
+    pub fn connected_peers(&self) -> impl Iterator<Item = &Peer> {
+        self.peers
+            .values()
+            .filter(|peer| peer.is_connected().is_some())
+    }
+
+    /// Returns the public IDs of the clients for which we act as a proxy.
+    pub fn clients(&self) -> Vec<PublicId> {
+        self.peers
+            .values()
+            .filter(|peer| peer.is_client())
+            .map(Peer::pub_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number of joining nodes we are relaying for, i.e. those which haven't been
+    /// relocated yet.
+    pub fn joining_node_num(&self) -> usize {
+        self.peers
+            .values()
+            .filter(|peer| match *peer.state() {
+                PeerState::JoiningNode => true,
+                _ => false,
+            })
+            .count()
+    }
+
+    /// Returns whether we can relay for another joining node. Joining nodes have a limit of their
+    /// own, separate from the clients'.
+    pub fn can_accept_joining_node(&self) -> bool {
+        self.joining_node_num() < self.max_joining_nodes
+    }
+
+    /// Resets the expiry of the given joining node, as it is still active.
+    pub fn refresh_joining_node(&mut self, pub_id: &PublicId) {
+        if let Some(peer) = self.peers.get_mut(pub_id) {
+            if let PeerState::JoiningNode = peer.state {
+                peer.timestamp = Instant::now();
+            }
+        }
+    }
+
+    /// Returns the peers we are connected to but which no section has vouched for yet.
+    ///
+    /// A peer that connects to us in response to its own connection info request is held here
+    /// until a section or group message (e.g. a `SectionUpdate` or `CandidateApproval`) names it,
+    /// which marks it valid and adds it to the routing table. If that doesn't happen within
+    /// `CONNECTED_PEER_TIMEOUT_SECS`, `remove_expired_peers` drops it.
+    pub fn unvouched_peers(&self) -> Vec<PublicId> {
+        self.peers
+            .values()
+            .filter(|peer| !peer.valid())
+            .filter(|peer| match peer.state {
+                PeerState::Connected(_) => true,
+                _ => false,
+            })
+            .map(Peer::pub_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the peers we are trying to connect to, but have neither a direct connection nor a
+    /// tunnel to after `CONNECTING_PEER_TIMEOUT_SECS`. `remove_expired_peers` drops them, together
+    /// with their connection tokens.
+    pub fn stalled_connects(&self) -> Vec<PublicId> {
+        self.peers
+            .values()
+            .filter(|peer| match peer.state {
+                PeerState::ConnectionInfoPreparing { .. }
+                | PeerState::ConnectionInfoReady(_)
+                | PeerState::CrustConnecting
+                | PeerState::SearchingForTunnel => peer.is_expired(),
+                _ => false,
+            })
+            .map(Peer::pub_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number of peers we are currently trying to connect to.
+    pub fn connecting_num(&self) -> usize {
+        self.peers
+            .values()
+            .filter(|peer| match *peer.state() {
+                PeerState::ConnectionInfoPreparing { .. }
+                | PeerState::ConnectionInfoReady(_)
+                | PeerState::CrustConnecting => true,
+                _ => false,
+            })
+            .count()
+    }
+
     /// Updates the given clients total traffic amount.
     pub fn add_client_traffic(&mut self, pub_id: &PublicId, added_bytes: u64) {
         let self_pfx = format!("{:?}", self);
@@ -1039,13 +1261,33 @@ impl PeerManager {
         });
     }
 
-    /// Checks whether we can accept more clients.
-    pub fn can_accept_client(&self, client_ip: IpAddr) -> bool {
-        self.disable_client_rate_limiter
-            || !self.peers.values().any(|peer| match *peer.state() {
-                PeerState::Client { ip, .. } => client_ip == ip,
-                _ => false,
-            })
+    /// Checks whether we can accept the given client connecting from `client_ip`. Clients with a
+    /// reserved key are always accepted. Others are only accepted while unreserved slots are left
+    /// and, unless the rate limiter is disabled, no other client is connected from the same IP.
+    pub fn can_accept_client(&self, pub_id: &PublicId, client_ip: IpAddr) -> bool {
+        if self.is_reserved_client(pub_id) {
+            return true;
+        }
+        let mut unreserved_count = 0;
+        for peer in self.peers.values() {
+            if let PeerState::Client { ip, .. } = *peer.state() {
+                if !self.disable_client_rate_limiter && client_ip == ip {
+                    return false;
+                }
+                if !self.is_reserved_client(peer.pub_id()) {
+                    unreserved_count += 1;
+                }
+            }
+        }
+        unreserved_count
+            < self
+                .max_clients
+                .saturating_sub(self.reserved_client_keys.len())
+    }
+
+    fn is_reserved_client(&self, pub_id: &PublicId) -> bool {
+        self.reserved_client_keys
+            .contains(pub_id.signing_public_key())
     }
 
     /// Marks the given peer as direct-connected.
@@ -1062,6 +1304,7 @@ impl PeerManager {
                     peer.state = PeerState::Connected(false);
                 }
             }
+            peer.connected_at = Some(Instant::now());
             peer.reconnecting = ReconnectingPeer::False;
             return;
         }
@@ -1087,6 +1330,7 @@ impl PeerManager {
         let found = if let Some(peer) = self.peers.get_mut(pub_id) {
             peer.timestamp = Instant::now();
             peer.state = PeerState::Connected(true);
+            peer.connected_at = Some(peer.timestamp);
             peer.reconnecting = ReconnectingPeer::False;
             true
         } else {
@@ -1319,10 +1563,10 @@ impl PeerManager {
         token: u32,
         our_info: PrivConnectionInfo,
     ) -> Result<ConnectionInfoPreparedResult, Error> {
-        let pub_id = self
-            .connection_token_map
-            .remove(&token)
-            .ok_or(Error::PeerNotFound)?;
+        let pub_id = match self.connection_token_map.remove(&token) {
+            Some((pub_id, start)) if !is_connection_token_expired(start) => pub_id,
+            _ => return Err(Error::PeerNotFound),
+        };
         let (us_as_src, them_as_dst, opt_their_info, valid, reconnecting) =
             match self.peers.remove(&pub_id) {
                 Some(Peer {
@@ -1490,8 +1734,7 @@ impl PeerManager {
                     their_info: Some((peer_info, msg_id)),
                 };
                 self.insert_peer(Peer::new(pub_id, state, valid, reconnecting));
-                let token = rand::random();
-                let _ = self.connection_token_map.insert(token, pub_id);
+                let token = self.insert_connection_token(pub_id, Instant::now());
                 Ok(ConnectionInfoReceivedResult::Prepare(token))
             }
         }
@@ -1513,8 +1756,7 @@ impl PeerManager {
             },
             None => reconnecting_in,
         };
-        let token = rand::random();
-        let _ = self.connection_token_map.insert(token, pub_id);
+        let token = self.insert_connection_token(pub_id, Instant::now());
         self.insert_peer(Peer::new(
             pub_id,
             PeerState::ConnectionInfoPreparing {
@@ -1529,14 +1771,48 @@ impl PeerManager {
     }
 
     /// If preparing connection info failed with the given token, prepares and returns a new token.
+    /// The new token keeps the original attempt's start time, so retries don't extend its deadline.
     pub fn get_new_connection_info_token(&mut self, token: u32) -> Result<u32, Error> {
-        let pub_id = self
+        let (pub_id, start) = self
             .connection_token_map
             .remove(&token)
             .ok_or(Error::PeerNotFound)?;
-        let new_token = rand::random();
-        let _ = self.connection_token_map.insert(new_token, pub_id);
-        Ok(new_token)
+        if is_connection_token_expired(start) {
+            return Err(Error::PeerNotFound);
+        }
+        Ok(self.insert_connection_token(pub_id, start))
+    }
+
+    fn insert_connection_token(&mut self, pub_id: PublicId, start: Instant) -> u32 {
+        let token = rng::random();
+        let _ = self.connection_token_map.insert(token, (pub_id, start));
+        token
+    }
+
+    /// Removes connection tokens whose attempt timed out, or whose peer is no longer preparing
+    /// connection info.
+    fn remove_expired_connection_tokens(&mut self) {
+        let peers = &self.peers;
+        self.connection_token_map
+            .retain(|_, &mut (ref pub_id, start)| {
+                let preparing = match peers.get(pub_id).map(Peer::state) {
+                    Some(&PeerState::ConnectionInfoPreparing { .. }) => true,
+                    _ => false,
+                };
+                preparing && !is_connection_token_expired(start)
+            });
+    }
+
+    /// Removes connection tokens referring to peers we no longer know about and releases capacity
+    /// no longer needed by the peer map. Returns the number of tokens removed.
+    pub fn prune_connection_tokens(&mut self) -> usize {
+        let before = self.connection_token_map.len();
+        let peers = &self.peers;
+        self.connection_token_map
+            .retain(|_, &mut (ref pub_id, _)| peers.contains_key(pub_id));
+        self.connection_token_map.shrink_to_fit();
+        self.peers.shrink_to_fit();
+        before - self.connection_token_map.len()
     }
 
     /// Returns all peers we are looking for a tunnel to.
@@ -1710,6 +1986,10 @@ impl PeerManager {
     }
 }
 
+fn is_connection_token_expired(start: Instant) -> bool {
+    start.elapsed() >= Duration::from_secs(CONNECTING_PEER_TIMEOUT_SECS)
+}
+
 impl fmt::Debug for PeerManager {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -1730,6 +2010,7 @@ mod tests {
     use crate::routing_table::Authority;
     use crate::types::MessageId;
     use crate::xor_name::{XorName, XOR_NAME_LEN};
+    use fake_clock::FakeClock;
 
     fn node_auth(byte: u8) -> Authority<XorName> {
         Authority::ManagedNode(XorName([byte; XOR_NAME_LEN]))
@@ -1841,4 +2122,168 @@ mod tests {
             state => panic!("Unexpected state: {:?}", state),
         }
     }
+
+    #[test]
+    pub fn unvouched_peer_expiry() {
+        let min_section_size = 8;
+        let our_pub_id = *FullId::new().public_id();
+        let their_pub_id = *FullId::new().public_id();
+        let mut peer_mgr = PeerManager::new(min_section_size, our_pub_id, false);
+        let their_connection_info = PubConnectionInfo {
+            id: their_pub_id,
+            endpoint: Endpoint(1),
+        };
+        // The peer asked us to connect, but no section has told us about it.
+        let _ = unwrap!(peer_mgr.connection_info_received(
+            node_auth(0),
+            node_auth(1),
+            their_connection_info,
+            MessageId::new(),
+            true,
+        ));
+        peer_mgr.connected_to(&their_pub_id);
+        assert_eq!(peer_mgr.unvouched_peers(), vec![their_pub_id]);
+
+        // Once vouched for, it is no longer held back.
+        peer_mgr.set_peer_valid(&their_pub_id, true);
+        assert!(peer_mgr.unvouched_peers().is_empty());
+
+        // Without a voucher, it is dropped after the deadline.
+        peer_mgr.set_peer_valid(&their_pub_id, false);
+        FakeClock::advance_time(CONNECTED_PEER_TIMEOUT_SECS * 1000 + 1);
+        assert_eq!(peer_mgr.remove_expired_peers(), vec![their_pub_id]);
+        assert!(peer_mgr.unvouched_peers().is_empty());
+    }
+
+    #[test]
+    pub fn client_limit() {
+        let min_section_size = 8;
+        let our_pub_id = *FullId::new().public_id();
+        let mut peer_mgr = PeerManager::new(min_section_size, our_pub_id, true);
+        let reserved_pub_id = *FullId::new().public_id();
+        let reserved_keys = iter::once(*reserved_pub_id.signing_public_key()).collect();
+        peer_mgr.set_client_limits(Some(3), reserved_keys);
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        for _ in 0..2 {
+            let pub_id = *FullId::new().public_id();
+            assert!(peer_mgr.can_accept_client(&pub_id, ip));
+            let state = PeerState::Client { ip, traffic: 0 };
+            peer_mgr.insert_peer(Peer::new(pub_id, state, false, ReconnectingPeer::False));
+        }
+
+        // All unreserved slots are taken, but the reserved one is still available.
+        assert!(!peer_mgr.can_accept_client(FullId::new().public_id(), ip));
+        assert!(peer_mgr.can_accept_client(&reserved_pub_id, ip));
+    }
+
+    #[test]
+    pub fn joining_node_limit() {
+        let min_section_size = 8;
+        let our_pub_id = *FullId::new().public_id();
+        let mut peer_mgr = PeerManager::new(min_section_size, our_pub_id, false);
+        peer_mgr.set_joining_node_limit(Some(2));
+
+        let ip = IpAddr::from([127, 0, 0, 1]);
+        let client_state = PeerState::Client { ip, traffic: 0 };
+        let client = Peer::new(
+            *FullId::new().public_id(),
+            client_state,
+            false,
+            ReconnectingPeer::False,
+        );
+        peer_mgr.insert_peer(client);
+
+        // Clients don't count against the joining nodes' limit.
+        let mut joining_pub_ids = Vec::new();
+        for _ in 0..2 {
+            assert!(peer_mgr.can_accept_joining_node());
+            let pub_id = *FullId::new().public_id();
+            let state = PeerState::JoiningNode;
+            peer_mgr.insert_peer(Peer::new(pub_id, state, false, ReconnectingPeer::False));
+            joining_pub_ids.push(pub_id);
+        }
+        assert!(!peer_mgr.can_accept_joining_node());
+
+        // A joining node which goes silent is dropped, freeing its slot.
+        FakeClock::advance_time(JOINING_NODE_SILENCE_TIMEOUT_SECS * 1000 - 1);
+        peer_mgr.refresh_joining_node(&joining_pub_ids[0]);
+        FakeClock::advance_time(2);
+        assert_eq!(peer_mgr.remove_expired_peers(), vec![joining_pub_ids[1]]);
+        assert!(peer_mgr.can_accept_joining_node());
+    }
+
+    #[test]
+    pub fn unneeded_proxy_removal() {
+        let min_section_size = 8;
+        let our_pub_id = *FullId::new().public_id();
+        let proxy_pub_id = *FullId::new().public_id();
+        let mut peer_mgr = PeerManager::new(min_section_size, our_pub_id, false);
+        peer_mgr.insert_peer(Peer::new(
+            proxy_pub_id,
+            PeerState::Proxy,
+            false,
+            ReconnectingPeer::False,
+        ));
+        let joining_pub_id = *FullId::new().public_id();
+        peer_mgr.insert_peer(Peer::new(
+            joining_pub_id,
+            PeerState::JoiningNode,
+            false,
+            ReconnectingPeer::False,
+        ));
+
+        assert_eq!(peer_mgr.unneeded_proxy(), Some(proxy_pub_id));
+        assert!(!peer_mgr.remove_unneeded_proxy(&joining_pub_id));
+        assert!(peer_mgr.remove_unneeded_proxy(&proxy_pub_id));
+        assert_eq!(peer_mgr.unneeded_proxy(), None);
+        assert!(peer_mgr.get_proxy_name().is_none());
+
+        // The proxy's acknowledgement can arrive after we gave up waiting for it and removed the
+        // proxy already.
+        assert!(!peer_mgr.remove_unneeded_proxy(&proxy_pub_id));
+        assert!(peer_mgr.get_peer(&proxy_pub_id).is_none());
+    }
+
+    #[test]
+    pub fn connection_token_expiry() {
+        let min_section_size = 8;
+        let our_pub_id = *FullId::new().public_id();
+        let their_pub_id = *FullId::new().public_id();
+        let mut peer_mgr = PeerManager::new(min_section_size, our_pub_id, false);
+        let our_connection_info = PrivConnectionInfo {
+            id: our_pub_id,
+            endpoint: Endpoint(0),
+        };
+
+        let token = unwrap!(peer_mgr.get_connection_token(
+            node_auth(0),
+            node_auth(1),
+            their_pub_id,
+            ReconnectingPeer::False,
+        ));
+        // Preparing the connection info failed, so we retry with a new token.
+        let token = unwrap!(peer_mgr.get_new_connection_info_token(token));
+
+        // Crust only reports back after the connection attempt timed out.
+        FakeClock::advance_time(CONNECTING_PEER_TIMEOUT_SECS * 1000 + 1);
+        match peer_mgr.connection_info_prepared(token, our_connection_info) {
+            Err(Error::PeerNotFound) => (),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+
+        // Expired peers take their tokens with them.
+        let _ = unwrap!(peer_mgr.get_connection_token(
+            node_auth(0),
+            node_auth(2),
+            *FullId::new().public_id(),
+            ReconnectingPeer::False,
+        ));
+        FakeClock::advance_time(CONNECTING_PEER_TIMEOUT_SECS * 1000 + 1);
+        // Neither peer got past preparing the connection info.
+        assert_eq!(peer_mgr.stalled_connects().len(), 2);
+        let _ = peer_mgr.remove_expired_peers();
+        assert!(peer_mgr.connection_token_map.is_empty());
+        assert!(peer_mgr.stalled_connects().is_empty());
+    }
 }
@@ -0,0 +1,100 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::outbox::EventBox;
+use std::time::Duration;
+
+/// A function doing periodic work on `T`.
+pub type Task<T> = fn(&mut T, &mut EventBox);
+
+struct Entry<T> {
+    name: &'static str,
+    /// The number of ticks between two runs.
+    period: u64,
+    run: Task<T>,
+}
+
+/// Periodic work driven by a regular tick.
+///
+/// Tasks are registered with an interval, which is rounded up to a whole number of ticks. Each
+/// call to `tick` returns the tasks due at that tick, in the order they were registered. Since
+/// the tick is driven by the `Timer`, the tasks follow the mock clock in tests.
+pub struct PeriodicTasks<T> {
+    tick_period: Duration,
+    ticks: u64,
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> PeriodicTasks<T> {
+    /// Creates an empty schedule, for a tick every `tick_period`.
+    pub fn new(tick_period: Duration) -> Self {
+        PeriodicTasks {
+            tick_period,
+            ticks: 0,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Registers `run` to be called every `interval`, starting one interval from now.
+    pub fn register(&mut self, name: &'static str, interval: Duration, run: Task<T>) {
+        let tick_ms = duration_to_ms(self.tick_period).max(1);
+        let period = (duration_to_ms(interval) + tick_ms - 1) / tick_ms;
+        self.entries.push(Entry {
+            name,
+            period: period.max(1),
+            run,
+        });
+    }
+
+    /// Advances the schedule by one tick and returns the names and functions of the due tasks.
+    pub fn tick(&mut self) -> Vec<(&'static str, Task<T>)> {
+        self.ticks += 1;
+        let ticks = self.ticks;
+        self.entries
+            .iter()
+            .filter(|entry| ticks % entry.period == 0)
+            .map(|entry| (entry.name, entry.run))
+            .collect()
+    }
+}
+
+fn duration_to_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outbox::EventBuf;
+
+    fn run_ticks(tasks: &mut PeriodicTasks<Vec<&'static str>>, count: usize) -> Vec<&'static str> {
+        let mut log = Vec::new();
+        let mut outbox = EventBuf::new();
+        for _ in 0..count {
+            for (_, run) in tasks.tick() {
+                run(&mut log, &mut outbox);
+            }
+        }
+        log
+    }
+
+    #[test]
+    fn tasks_run_at_their_interval() {
+        let mut tasks: PeriodicTasks<Vec<_>> = PeriodicTasks::new(Duration::from_secs(60));
+        tasks.register("fast", Duration::from_secs(60), |log, _| log.push("fast"));
+        // Rounded up to three ticks.
+        tasks.register("slow", Duration::from_secs(150), |log, _| log.push("slow"));
+
+        assert_eq!(run_ticks(&mut tasks, 2), vec!["fast", "fast"]);
+        assert_eq!(run_ticks(&mut tasks, 1), vec!["fast", "slow"]);
+        assert_eq!(
+            run_ticks(&mut tasks, 3),
+            vec!["fast", "fast", "fast", "slow"]
+        );
+    }
+}
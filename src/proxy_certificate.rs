@@ -0,0 +1,233 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use crate::id::{FullId, PublicId};
+use crate::messages::Quorum;
+use crate::routing_table::Prefix;
+use crate::rust_sodium::crypto::sign::{self, Signature};
+use crate::xor_name::XorName;
+use maidsafe_utilities::serialisation;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A statement of a node's public ID, signed by other members of its section.
+///
+/// Members of a section sign each other's IDs as they join, so a node can present the signatures
+/// it collected to the clients bootstrapping off it, together with its section's prefix and
+/// members. Only signatures by listed members count, and all members must lie in the prefix. A
+/// client can't tell whether the signers are genuine nodes, but it can at least detect a proxy
+/// that no group vouches for.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProxyCertificate {
+    prefix: Prefix<XorName>,
+    members: BTreeSet<PublicId>,
+    signatures: BTreeMap<PublicId, Signature>,
+}
+
+impl ProxyCertificate {
+    /// Returns our signature for the given member of our section.
+    pub fn sign(full_id: &FullId, subject: &PublicId) -> Result<Signature, RoutingError> {
+        let ser_subject = serialisation::serialise(subject)?;
+        Ok(sign::sign_detached(
+            &ser_subject,
+            full_id.signing_private_key(),
+        ))
+    }
+
+    /// Adds `signer`'s signature for `subject`. Returns `false` if the signature is invalid.
+    pub fn add_signature(
+        &mut self,
+        subject: &PublicId,
+        signer: PublicId,
+        signature: Signature,
+    ) -> Result<bool, RoutingError> {
+        if signer == *subject || !verify(subject, &signer, &signature)? {
+            return Ok(false);
+        }
+        let _ = self.signatures.insert(signer, signature);
+        Ok(true)
+    }
+
+    /// Removes the signature of the node with the given name, if we have one.
+    pub fn remove_signer(&mut self, name: &XorName) {
+        let signer = self
+            .signatures
+            .keys()
+            .find(|pub_id| pub_id.name() == name)
+            .cloned();
+        if let Some(signer) = signer {
+            let _ = self.signatures.remove(&signer);
+        }
+    }
+
+    /// Returns a copy of the certificate for the section with the given prefix and members, with
+    /// the signatures of the members only.
+    pub fn for_section(&self, prefix: Prefix<XorName>, members: BTreeSet<PublicId>) -> Self {
+        let signatures = self
+            .signatures
+            .iter()
+            .filter(|&(signer, _)| members.contains(signer))
+            .map(|(signer, signature)| (*signer, *signature))
+            .collect();
+        ProxyCertificate {
+            prefix,
+            members,
+            signatures,
+        }
+    }
+
+    /// Returns the number of signatures.
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    /// Returns the number of listed section members.
+    pub fn member_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns whether a quorum of the listed section signed `subject`. The section must have at
+    /// least `min_section_size` members, all matching its prefix, and include `subject`.
+    pub fn is_valid_for(&self, subject: &PublicId, min_section_size: usize) -> bool {
+        if self.members.len() < min_section_size
+            || !self.members.contains(subject)
+            || self
+                .members
+                .iter()
+                .any(|member| !self.prefix.matches(member.name()))
+        {
+            return false;
+        }
+        let valid_sigs = self
+            .signatures
+            .iter()
+            .filter(|&(signer, signature)| {
+                signer != subject
+                    && self.members.contains(signer)
+                    && verify(subject, signer, signature).unwrap_or(false)
+            })
+            .count();
+        Quorum::default().is_reached(valid_sigs, self.members.len())
+    }
+}
+
+fn verify(
+    subject: &PublicId,
+    signer: &PublicId,
+    signature: &Signature,
+) -> Result<bool, RoutingError> {
+    let ser_subject = serialisation::serialise(subject)?;
+    Ok(sign::verify_detached(
+        signature,
+        &ser_subject,
+        signer.signing_public_key(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing_table::Xorable;
+
+    fn sign_all(cert: &mut ProxyCertificate, subject: &PublicId, signers: &[FullId]) {
+        for signer in signers {
+            let sig = unwrap!(ProxyCertificate::sign(signer, subject));
+            assert!(unwrap!(cert.add_signature(
+                subject,
+                *signer.public_id(),
+                sig
+            )));
+        }
+    }
+
+    #[test]
+    fn needs_quorum_of_valid_signatures() {
+        let min_section_size = 4;
+        let proxy = *FullId::new().public_id();
+        let members: Vec<_> = (0..3).map(|_| FullId::new()).collect();
+        let section: BTreeSet<_> = members
+            .iter()
+            .map(|member| *member.public_id())
+            .chain(Some(proxy))
+            .collect();
+        let mut cert = ProxyCertificate::default();
+
+        // A signature for a different ID is rejected.
+        let other = *FullId::new().public_id();
+        let wrong_sig = unwrap!(ProxyCertificate::sign(&members[0], &other));
+        assert!(!unwrap!(cert.add_signature(
+            &proxy,
+            *members[0].public_id(),
+            wrong_sig
+        )));
+        assert_eq!(cert.len(), 0);
+
+        sign_all(&mut cert, &proxy, &members[..2]);
+        let sent_cert = cert.for_section(Prefix::default(), section.clone());
+        assert!(!sent_cert.is_valid_for(&proxy, min_section_size));
+
+        sign_all(&mut cert, &proxy, &members[2..]);
+        let sent_cert = cert.for_section(Prefix::default(), section.clone());
+        assert!(sent_cert.is_valid_for(&proxy, min_section_size));
+        assert!(!sent_cert.is_valid_for(&other, min_section_size));
+
+        cert.remove_signer(members[2].public_id().name());
+        let sent_cert = cert.for_section(Prefix::default(), section);
+        assert!(!sent_cert.is_valid_for(&proxy, min_section_size));
+    }
+
+    #[test]
+    fn needs_signers_from_the_listed_section() {
+        let min_section_size = 4;
+        let proxy = *FullId::new().public_id();
+        let members: Vec<_> = (0..3).map(|_| FullId::new()).collect();
+        let outsiders: Vec<_> = (0..3).map(|_| FullId::new()).collect();
+        let section: BTreeSet<_> = members
+            .iter()
+            .map(|member| *member.public_id())
+            .chain(Some(proxy))
+            .collect();
+        let mut cert = ProxyCertificate::default();
+        sign_all(&mut cert, &proxy, &outsiders);
+        sign_all(&mut cert, &proxy, &members[..1]);
+
+        // Signatures by keys outside the section are dropped, and don't count.
+        let sent_cert = cert.for_section(Prefix::default(), section.clone());
+        assert_eq!(sent_cert.len(), 1);
+        assert!(!sent_cert.is_valid_for(&proxy, min_section_size));
+        let forged_cert = ProxyCertificate {
+            signatures: cert.signatures.clone(),
+            ..sent_cert
+        };
+        assert!(!forged_cert.is_valid_for(&proxy, min_section_size));
+
+        // With all members signing, the certificate is only valid if the prefix covers them all.
+        sign_all(&mut cert, &proxy, &members[1..]);
+        let proxy_only = Prefix::new(XorName::bit_len(), *proxy.name());
+        let sent_cert = cert.for_section(proxy_only, section.clone());
+        assert!(!sent_cert.is_valid_for(&proxy, min_section_size));
+        let sent_cert = cert.for_section(Prefix::default(), section);
+        assert!(sent_cert.is_valid_for(&proxy, min_section_size));
+
+        // A section smaller than `min_section_size`, or one without the proxy, isn't accepted.
+        let small_section: BTreeSet<_> = members[..2]
+            .iter()
+            .map(|member| *member.public_id())
+            .chain(Some(proxy))
+            .collect();
+        let small_cert = cert.for_section(Prefix::default(), small_section);
+        assert!(!small_cert.is_valid_for(&proxy, min_section_size));
+        let other_section: BTreeSet<_> = members
+            .iter()
+            .chain(&outsiders)
+            .map(|member| *member.public_id())
+            .collect();
+        let other_cert = cert.for_section(Prefix::default(), other_section);
+        assert!(!other_cert.is_valid_for(&proxy, min_section_size));
+    }
+}
@@ -129,7 +129,8 @@ impl RateLimiter {
                         Refresh(..) => return Err(RoutingError::InvalidMessage),
                     }
                 }
-                Ok(Response(_)) => return Err(RoutingError::InvalidMessage),
+                // Only nodes can send responses and user-defined messages.
+                Ok(Response(_)) | Ok(Tagged { .. }) => return Err(RoutingError::InvalidMessage),
                 Err(SerialisationError::DeserialiseExtraBytes) => {
                     return Err(RoutingError::InvalidMessage);
                 }
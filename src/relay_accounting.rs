@@ -0,0 +1,164 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::id::PublicId;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
+use std::collections::BTreeMap;
+use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
+
+/// The counters of a client we didn't relay any traffic for in this long are dropped. They were
+/// included in every `Event::RelayAccounting` raised in the meantime.
+const IDLE_EXPIRY_SECS: u64 = 60 * 60;
+/// The maximum number of clients we keep counters for. Beyond it, the counters of the client idle
+/// for the longest time are dropped.
+const MAX_CLIENTS: usize = 1000;
+
+/// The traffic a node relayed for one of its clients.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RelayedTraffic {
+    /// The number of messages received from the client and passed on to the network.
+    pub msgs_from_client: u64,
+    /// The number of bytes received from the client and passed on to the network.
+    pub bytes_from_client: u64,
+    /// The number of messages passed on from the network to the client.
+    pub msgs_to_client: u64,
+    /// The number of bytes passed on from the network to the client.
+    pub bytes_to_client: u64,
+}
+
+/// Counts the traffic relayed for each client since the counters were last reset. Counters are
+/// kept after a client disconnects, so that no traffic goes unaccounted, until the client was
+/// idle for `IDLE_EXPIRY_SECS` or `MAX_CLIENTS` more recently active clients need counters.
+#[derive(Default)]
+pub struct RelayAccounting {
+    counters: BTreeMap<PublicId, (Instant, RelayedTraffic)>,
+}
+
+impl RelayAccounting {
+    /// Records a message of `len` bytes relayed from the client `pub_id`.
+    pub fn add_from_client(&mut self, pub_id: PublicId, len: usize) {
+        let traffic = self.counters_for(pub_id);
+        traffic.msgs_from_client = traffic.msgs_from_client.saturating_add(1);
+        traffic.bytes_from_client = traffic.bytes_from_client.saturating_add(len as u64);
+    }
+
+    /// Records a message of `len` bytes relayed to the client `pub_id`.
+    pub fn add_to_client(&mut self, pub_id: PublicId, len: usize) {
+        let traffic = self.counters_for(pub_id);
+        traffic.msgs_to_client = traffic.msgs_to_client.saturating_add(1);
+        traffic.bytes_to_client = traffic.bytes_to_client.saturating_add(len as u64);
+    }
+
+    /// Returns a copy of the current counters.
+    pub fn snapshot(&mut self) -> BTreeMap<PublicId, RelayedTraffic> {
+        self.remove_expired();
+        self.counters
+            .iter()
+            .map(|(pub_id, &(_, traffic))| (*pub_id, traffic))
+            .collect()
+    }
+
+    /// Resets all counters.
+    pub fn reset(&mut self) {
+        self.counters.clear();
+    }
+
+    // Returns the counters for `pub_id`, marked as active now, making room for them if needed.
+    fn counters_for(&mut self, pub_id: PublicId) -> &mut RelayedTraffic {
+        self.remove_expired();
+        if self.counters.len() >= MAX_CLIENTS && !self.counters.contains_key(&pub_id) {
+            let idlest = self
+                .counters
+                .iter()
+                .min_by_key(|&(_, &(last_active, _))| last_active)
+                .map(|(pub_id, _)| *pub_id);
+            if let Some(idlest) = idlest {
+                let _ = self.counters.remove(&idlest);
+            }
+        }
+        let entry = self
+            .counters
+            .entry(pub_id)
+            .or_insert_with(|| (Instant::now(), RelayedTraffic::default()));
+        entry.0 = Instant::now();
+        &mut entry.1
+    }
+
+    fn remove_expired(&mut self) {
+        let expiry = Duration::from_secs(IDLE_EXPIRY_SECS);
+        self.counters
+            .retain(|_, &mut (last_active, _)| last_active.elapsed() < expiry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+
+    #[test]
+    fn counts_per_client_until_reset() {
+        let client_0 = *FullId::new().public_id();
+        let client_1 = *FullId::new().public_id();
+        let mut accounting = RelayAccounting::default();
+        assert!(accounting.snapshot().is_empty());
+
+        accounting.add_from_client(client_0, 100);
+        accounting.add_from_client(client_0, 50);
+        accounting.add_to_client(client_0, 1000);
+        accounting.add_to_client(client_1, 10);
+
+        let snapshot = accounting.snapshot();
+        assert_eq!(
+            snapshot[&client_0],
+            RelayedTraffic {
+                msgs_from_client: 2,
+                bytes_from_client: 150,
+                msgs_to_client: 1,
+                bytes_to_client: 1000,
+            }
+        );
+        assert_eq!(snapshot[&client_1].msgs_from_client, 0);
+        assert_eq!(snapshot[&client_1].bytes_to_client, 10);
+
+        accounting.reset();
+        assert!(accounting.snapshot().is_empty());
+    }
+
+    #[cfg(feature = "use-mock-crust")]
+    #[test]
+    fn drops_idle_clients() {
+        use fake_clock::FakeClock;
+
+        let client_0 = *FullId::new().public_id();
+        let client_1 = *FullId::new().public_id();
+        let mut accounting = RelayAccounting::default();
+        accounting.add_from_client(client_0, 100);
+        FakeClock::advance_time(IDLE_EXPIRY_SECS * 1000 / 2);
+        accounting.add_to_client(client_1, 10);
+        FakeClock::advance_time(IDLE_EXPIRY_SECS * 1000 / 2);
+
+        let snapshot = accounting.snapshot();
+        assert!(!snapshot.contains_key(&client_0));
+        assert!(snapshot.contains_key(&client_1));
+
+        // Beyond the limit, the client idle for the longest time makes room for a new one.
+        for _ in 1..MAX_CLIENTS {
+            accounting.add_from_client(*FullId::new().public_id(), 1);
+        }
+        assert_eq!(accounting.snapshot().len(), MAX_CLIENTS);
+        accounting.add_from_client(client_0, 1);
+        let snapshot = accounting.snapshot();
+        assert_eq!(snapshot.len(), MAX_CLIENTS);
+        assert!(snapshot.contains_key(&client_0));
+        assert!(!snapshot.contains_key(&client_1));
+    }
+}
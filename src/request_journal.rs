@@ -0,0 +1,182 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::RoutingError;
+use crate::messages::Request;
+use crate::routing_table::Authority;
+use crate::sha3::Digest256;
+use crate::types::MessageId;
+use crate::xor_name::XorName;
+use config_file_handler::{self, FileHandler};
+use maidsafe_utilities::serialisation;
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tiny_keccak::sha3_256;
+
+/// A client request which was sent, but neither answered nor expired yet.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// The request's unique identifier.
+    pub message_id: MessageId,
+    /// The SHA3-256 hash of the serialised request.
+    pub content_hash: Digest256,
+    /// The destination authority of the request.
+    pub dst: Authority<XorName>,
+    /// When the request expires, in seconds since the Unix epoch.
+    pub deadline: u64,
+}
+
+/// A write-ahead journal of a client's outgoing requests, kept on disk so that unanswered requests
+/// can be reconciled after a crash.
+///
+/// A request is written to the journal before it is sent, and removed once its response arrives
+/// or it expires. Entries a previous run left behind are kept until their deadline. Each client ID
+/// has a journal file of its own, so that several clients in one process don't overwrite each
+/// other's entries.
+pub struct RequestJournal {
+    client_name: XorName,
+    entries: BTreeMap<MessageId, JournalEntry>,
+}
+
+impl RequestJournal {
+    /// Opens the journal of the client named `client_name`, keeping the unexpired entries written
+    /// by a previous run.
+    pub fn open(client_name: &XorName) -> Self {
+        let now = unix_secs();
+        let entries = match read(client_name) {
+            Ok(entries) => entries
+                .into_iter()
+                .filter(|entry| entry.deadline > now)
+                .map(|entry| (entry.message_id, entry))
+                .collect(),
+            Err(error) => {
+                debug!("No request journal restored: {:?}", error);
+                BTreeMap::new()
+            }
+        };
+        RequestJournal {
+            client_name: *client_name,
+            entries,
+        }
+    }
+
+    /// Records `request` to `dst`, which expires after `expires_in`, and writes the journal.
+    pub fn record(&mut self, request: &Request, dst: Authority<XorName>, expires_in: Duration) {
+        let content_hash = match serialisation::serialise(request) {
+            Ok(bytes) => sha3_256(&bytes),
+            Err(error) => {
+                debug!("Failed to journal request: {:?}", error);
+                return;
+            }
+        };
+        let entry = JournalEntry {
+            message_id: *request.message_id(),
+            content_hash,
+            dst,
+            deadline: unix_secs() + expires_in.as_secs(),
+        };
+        let _ = self.entries.insert(entry.message_id, entry);
+        self.write();
+    }
+
+    /// Removes the request with the given ID, if any, and writes the journal.
+    pub fn complete(&mut self, message_id: &MessageId) {
+        if self.entries.remove(message_id).is_some() {
+            self.write();
+        }
+    }
+
+    fn write(&self) {
+        let entries: Vec<_> = self.entries.values().cloned().collect();
+        let result =
+            file_handler(&self.client_name).and_then(|handler| Ok(handler.write_file(&entries)?));
+        if let Err(error) = result {
+            warn!("Failed to write request journal: {:?}", error);
+        }
+    }
+}
+
+/// Returns the requests the client named `client_name`, with `journal_client_requests` enabled,
+/// sent but saw neither a response to nor the expiry of, including those left behind by a previous
+/// run.
+pub fn unanswered_requests(client_name: &XorName) -> Result<Vec<JournalEntry>, RoutingError> {
+    let now = unix_secs();
+    Ok(read(client_name)?
+        .into_iter()
+        .filter(|entry| entry.deadline > now)
+        .collect())
+}
+
+fn read(client_name: &XorName) -> Result<Vec<JournalEntry>, RoutingError> {
+    Ok(file_handler(client_name)?.read_file()?)
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn file_handler(client_name: &XorName) -> Result<FileHandler<Vec<JournalEntry>>, RoutingError> {
+    let mut name = config_file_handler::exe_file_stem()?;
+    name.push(format!(".{}.routing.journal", client_name.to_hex()));
+    Ok(FileHandler::new(&name, true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+
+    fn request() -> Request {
+        Request::GetIData {
+            name: rand::random(),
+            msg_id: MessageId::new(),
+        }
+    }
+
+    fn unanswered_ids(client_name: &XorName) -> Vec<MessageId> {
+        unwrap!(unanswered_requests(client_name))
+            .into_iter()
+            .map(|entry| entry.message_id)
+            .collect()
+    }
+
+    #[test]
+    fn journals_are_kept_per_client() {
+        let names: [XorName; 2] = [rand::random(), rand::random()];
+        let requests = [request(), request()];
+        let dst = Authority::NaeManager(rand::random());
+        let expires_in = Duration::from_secs(60);
+        for (name, request) in names.iter().zip(&requests) {
+            RequestJournal::open(name).record(request, dst, expires_in);
+        }
+        for (name, request) in names.iter().zip(&requests) {
+            assert_eq!(unanswered_ids(name), vec![*request.message_id()]);
+        }
+
+        // A later run of a client restores its own entries only.
+        for (name, request) in names.iter().zip(&requests) {
+            let mut journal = RequestJournal::open(name);
+            assert_eq!(journal.entries.len(), 1);
+            journal.complete(request.message_id());
+            assert!(unanswered_ids(name).is_empty());
+        }
+    }
+
+    #[test]
+    fn expired_entries_are_not_returned() {
+        let name: XorName = rand::random();
+        let dst = Authority::NaeManager(rand::random());
+        let mut journal = RequestJournal::open(&name);
+        journal.record(&request(), dst, Duration::from_secs(0));
+        assert!(unanswered_ids(&name).is_empty());
+        assert!(RequestJournal::open(&name).entries.is_empty());
+    }
+}
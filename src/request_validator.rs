@@ -0,0 +1,26 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::client_error::ClientError;
+use crate::messages::Request;
+use crate::routing_table::Authority;
+use crate::xor_name::XorName;
+
+/// Checks the requests a node receives before they are raised as `Event::Request`. Should be
+/// implemented by layers above routing, so that invalid requests, e.g. for data types they don't
+/// store or with oversized payloads, are rejected as early as possible.
+pub trait RequestValidator: Send {
+    /// Returns an error if `request` from `src` to `dst` is invalid. Routing then responds to
+    /// `src` with that error instead of raising the request.
+    fn validate(
+        &self,
+        src: &Authority<XorName>,
+        dst: &Authority<XorName>,
+        request: &Request,
+    ) -> Result<(), ClientError>;
+}
@@ -0,0 +1,104 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! The source of randomness for connection tokens, message IDs and timing jitter.
+//!
+//! Each thread uses the OS-seeded `rand::thread_rng` unless `seed` is called, in which case it
+//! switches to a `SeededRng`. Together with seeding `rust_sodium`, which generates our keys, this
+//! makes a node's behaviour reproducible, e.g. in simulations. In tests, the unseeded generator is
+//! `SeededRng::thread_rng`, so that a failing test can be rerun with the seed it printed.
+
+use maidsafe_utilities::SeededRng;
+#[cfg(not(any(test, feature = "use-mock-crust")))]
+use rand::ThreadRng;
+use rand::{Rand, Rng};
+use std::cell::RefCell;
+
+thread_local! {
+    static RNG: RefCell<NodeRng> = RefCell::new(NodeRng::unseeded());
+}
+
+/// The generator of a thread: either seeded by the user, or the default one.
+pub enum NodeRng {
+    Seeded(SeededRng),
+    #[cfg(not(any(test, feature = "use-mock-crust")))]
+    Unseeded(ThreadRng),
+    #[cfg(any(test, feature = "use-mock-crust"))]
+    Unseeded(SeededRng),
+}
+
+impl NodeRng {
+    #[cfg(not(any(test, feature = "use-mock-crust")))]
+    fn unseeded() -> Self {
+        NodeRng::Unseeded(rand::thread_rng())
+    }
+
+    #[cfg(any(test, feature = "use-mock-crust"))]
+    fn unseeded() -> Self {
+        NodeRng::Unseeded(SeededRng::thread_rng())
+    }
+}
+
+impl Rng for NodeRng {
+    fn next_u32(&mut self) -> u32 {
+        match *self {
+            NodeRng::Seeded(ref mut rng) => rng.next_u32(),
+            NodeRng::Unseeded(ref mut rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match *self {
+            NodeRng::Seeded(ref mut rng) => rng.next_u64(),
+            NodeRng::Unseeded(ref mut rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match *self {
+            NodeRng::Seeded(ref mut rng) => rng.fill_bytes(dest),
+            NodeRng::Unseeded(ref mut rng) => rng.fill_bytes(dest),
+        }
+    }
+}
+
+/// Seeds the current thread's generator, replacing any previous one.
+pub fn seed(seed: [u32; 4]) {
+    RNG.with(|rng| *rng.borrow_mut() = NodeRng::Seeded(SeededRng::from_seed(seed)));
+}
+
+/// Calls `f` with the current thread's generator.
+pub fn with_rng<F, R>(f: F) -> R
+where
+    F: FnOnce(&mut NodeRng) -> R,
+{
+    RNG.with(|rng| f(&mut *rng.borrow_mut()))
+}
+
+/// Returns a random value from the current thread's generator.
+pub fn random<T: Rand>() -> T {
+    with_rng(|rng| rng.gen())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_sequences_repeat() {
+        seed([1, 2, 3, 4]);
+        let first: Vec<u64> = (0..10).map(|_| random()).collect();
+        seed([1, 2, 3, 4]);
+        let second: Vec<u64> = (0..10).map(|_| random()).collect();
+        assert_eq!(first, second);
+
+        seed([4, 3, 2, 1]);
+        let third: Vec<u64> = (0..10).map(|_| random()).collect();
+        assert_ne!(first, third);
+    }
+}
@@ -6,16 +6,21 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::filter_snapshot::FilterSnapshot;
 use crate::id::PublicId;
-use crate::message_filter::MessageFilter;
+use crate::message_filter::{self, MessageFilter};
 use crate::messages::RoutingMessage;
 use crate::sha3;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
 use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation::serialise;
 use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
 use tiny_keccak::sha3_256;
 
-const INCOMING_EXPIRY_DURATION_SECS: u64 = 60 * 20;
+pub const INCOMING_EXPIRY_DURATION_SECS: u64 = 60 * 20;
 const OUTGOING_EXPIRY_DURATION_SECS: u64 = 60 * 10;
 
 /// An enum representing a result of message filtering
@@ -34,6 +39,8 @@ pub struct RoutingMessageFilter {
     incoming: MessageFilter<RoutingMessage>,
     incoming_route: MessageFilter<(RoutingMessage, u8)>,
     outgoing: LruCache<(sha3::Digest256, PublicId, u8), ()>,
+    /// The messages we handled before a restart, and when that information expires.
+    restored: Option<(FilterSnapshot, Instant)>,
 }
 
 impl RoutingMessageFilter {
@@ -45,14 +52,29 @@ impl RoutingMessageFilter {
             incoming: MessageFilter::with_expiry_duration(incoming_duration),
             incoming_route: MessageFilter::with_expiry_duration(incoming_duration),
             outgoing: LruCache::with_expiry_duration(outgoing_duration),
+            restored: None,
         }
     }
 
+    // Returns a snapshot of the incoming messages we have seen, to be restored after a restart.
+    pub fn snapshot(&self) -> FilterSnapshot {
+        let mut snapshot = FilterSnapshot::new();
+        for hash in self.incoming.hashes() {
+            snapshot.insert(hash);
+        }
+        snapshot
+    }
+
+    // Treats the messages in `snapshot` as already seen, until `expires_in` has passed.
+    pub fn restore(&mut self, snapshot: FilterSnapshot, expires_in: Duration) {
+        self.restored = Some((snapshot, Instant::now() + expires_in));
+    }
+
     // Filter incoming `RoutingMessage`. Return the number of times this specific message has been
     // seen, including this time.
     // TODO - refactor to avoid cloning `msg` as `MessageFilter` only holds the hash of the tuple.
     pub fn filter_incoming(&mut self, msg: &RoutingMessage, route: u8) -> FilteringResult {
-        let known_msg = self.incoming.insert(msg) > 1;
+        let known_msg = self.incoming.insert(msg) > 1 || self.seen_before_restart(msg);
         let known_msg_rt = self.incoming_route.insert(&(msg.clone(), route)) > 1;
         match (known_msg, known_msg_rt) {
             (false, false) => FilteringResult::NewMessage,
@@ -61,6 +83,20 @@ impl RoutingMessageFilter {
         }
     }
 
+    fn seen_before_restart(&mut self, msg: &RoutingMessage) -> bool {
+        let expired = match self.restored {
+            Some((_, expires_at)) => expires_at <= Instant::now(),
+            None => return false,
+        };
+        if expired {
+            self.restored = None;
+            return false;
+        }
+        self.restored.as_ref().map_or(false, |&(ref snapshot, _)| {
+            snapshot.contains(message_filter::hash(msg))
+        })
+    }
+
     // Filter outgoing `RoutingMessage`. Return whether this specific message has been seen recently
     // (and thus should not be sent, due to deduplication).
     //
@@ -75,6 +111,19 @@ impl RoutingMessageFilter {
         }
     }
 
+    // Purges expired entries from the incoming filters and shrinks them. Returns the approximate
+    // number of bytes allocated before and after compaction.
+    pub fn compact(&mut self) -> (usize, usize) {
+        let before = self.footprint();
+        self.incoming.compact();
+        self.incoming_route.compact();
+        (before, self.footprint())
+    }
+
+    fn footprint(&self) -> usize {
+        self.incoming.footprint() + self.incoming_route.footprint()
+    }
+
     // Removes the given message from the outgoing filter if it exists.
     pub fn remove_from_outgoing_filter(
         &mut self,
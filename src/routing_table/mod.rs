@@ -174,6 +174,16 @@ pub enum OwnMergeState<T: Binary + Clone + Copy + Default + Hash + Xorable> {
     AlreadyMerged,
 }
 
+/// Whether a node is a part of an authority, together with a human-readable reason, as returned
+/// by `RoutingTable::explain_authority`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorityExplanation {
+    /// Whether the node is a part of the authority.
+    pub in_authority: bool,
+    /// Why the node is or isn't a part of the authority.
+    pub reason: String,
+}
+
 /// A routing table to manage contacts for a node.
 ///
 /// It maintains a list of sections (identified by a `Prefix<T>`), each with a
@@ -914,6 +924,51 @@ impl<T: Binary + Clone + Copy + Debug + Default + Hash + Xorable> RoutingTable<T
         }
     }
 
+    /// Returns the same result as `in_authority`, together with the reason for it. This is meant
+    /// for debugging, e.g. to find out why a message was dropped with `BadAuthority`.
+    pub fn explain_authority(&self, auth: &Authority<T>) -> AuthorityExplanation {
+        let reason = match *auth {
+            Authority::Client { .. } => "clients have no routing tables".to_string(),
+            Authority::ManagedNode(ref name) => format!(
+                "the node's name is {:?}, and ours is {:?}",
+                name, self.our_name
+            ),
+            Authority::ClientManager(ref name)
+            | Authority::NaeManager(ref name)
+            | Authority::NodeManager(ref name) => {
+                let closest = self.closest_known_names(name, self.min_section_size);
+                format!(
+                    "the {} known names closest to {:?} are {:?}, and ours is {:?}",
+                    self.min_section_size, name, closest, self.our_name
+                )
+            }
+            Authority::Section(ref name) => format!(
+                "our prefix {:?} {} {:?}",
+                self.our_prefix,
+                if self.our_prefix.matches(name) {
+                    "matches"
+                } else {
+                    "doesn't match"
+                },
+                name
+            ),
+            Authority::PrefixSection(ref prefix) => format!(
+                "our prefix {:?} is {}compatible with {:?}",
+                self.our_prefix,
+                if self.our_prefix.is_compatible(prefix) {
+                    ""
+                } else {
+                    "not "
+                },
+                prefix
+            ),
+        };
+        AuthorityExplanation {
+            in_authority: self.in_authority(auth),
+            reason,
+        }
+    }
+
     /// Returns the section matching the given `name`, if present.
     /// Includes our own name in the case that our prefix matches `name`.
     pub fn get_section(&self, name: &T) -> Option<&BTreeSet<T>> {
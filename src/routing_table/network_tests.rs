@@ -427,3 +427,53 @@ fn merging_sections() {
             true
         },));
 }
+
+#[test]
+fn authority_explanations() {
+    let mut network = Network::new(8, None);
+    for _ in 0..100 {
+        network.add_node();
+    }
+    verify_invariant(&network);
+
+    let members = |network: &Network, auth: &Authority<u64>| -> Vec<u64> {
+        network
+            .nodes
+            .values()
+            .filter(|table| {
+                let explanation = table.explain_authority(auth);
+                assert_eq!(
+                    explanation.in_authority,
+                    table.in_authority(auth),
+                    "{}",
+                    explanation.reason
+                );
+                explanation.in_authority
+            })
+            .map(|table| *table.our_name())
+            .collect()
+    };
+
+    for _ in 0..100 {
+        let name: u64 = network.rng.gen();
+        let closest = *unwrap!(network
+            .nodes
+            .keys()
+            .min_by(|lhs, rhs| name.cmp_distance(lhs, rhs)));
+
+        // The node closest to a name always considers itself part of the name's group.
+        assert!(members(&network, &Authority::NaeManager(name)).contains(&closest));
+
+        // Exactly the nodes of one section are part of a section authority.
+        let section = members(&network, &Authority::Section(name));
+        let prefix = *network.nodes[&closest].our_prefix();
+        assert!(prefix.matches(&name));
+        assert_eq!(section, network.nodes_covered_by_prefixes(&[prefix]));
+
+        // Only the node itself is part of its managed node authority.
+        assert_eq!(
+            members(&network, &Authority::ManagedNode(closest)),
+            vec![closest]
+        );
+    }
+}
@@ -53,6 +53,18 @@ impl SectionListCache {
         }
     }
 
+    /// Returns the names of all nodes we currently hold section list signatures from.
+    pub fn signer_names(&self) -> Vec<XorName> {
+        self.signed_by.keys().map(|pub_id| *pub_id.name()).collect()
+    }
+
+    /// Releases capacity no longer needed by the cache.
+    pub fn shrink_to_fit(&mut self) {
+        self.signatures.shrink_to_fit();
+        self.signed_by.shrink_to_fit();
+        self.lists_cache.shrink_to_fit();
+    }
+
     /// Adds a new signature for a section list
     pub fn add_signature(
         &mut self,
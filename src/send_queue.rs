@@ -0,0 +1,269 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::id::PublicId;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
+use std::cmp;
+use std::collections::VecDeque;
+use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
+
+/// The number of bytes a traffic class may send per turn, for each unit of its weight.
+const QUANTUM_BYTES: u64 = 64 * 1024;
+/// Default weights of own messages, routing forwards and client relays.
+pub const DEFAULT_SEND_WEIGHTS: [u32; 3] = [4, 4, 1];
+/// Default number of bytes we send per `SEND_INTERVAL_MS`.
+pub const DEFAULT_SEND_BUDGET: u64 = 4 * 1024 * 1024;
+/// The interval in which the send budget is replenished, in milliseconds.
+pub const SEND_INTERVAL_MS: u64 = 100;
+/// The maximum number of bytes waiting in the queue of a single traffic class. Beyond that, the
+/// messages with the lowest priority are dropped.
+const MAX_QUEUED_BYTES: u64 = 32 * 1024 * 1024;
+
+/// The kinds of outgoing traffic which share our bandwidth.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TrafficClass {
+    /// Messages sent by us or by a section we belong to.
+    Own,
+    /// Routing messages we pass on for other nodes, including tunnelled ones.
+    Forward,
+    /// Messages from or to clients we act as a proxy for.
+    ClientRelay,
+}
+
+impl TrafficClass {
+    fn index(self) -> usize {
+        match self {
+            TrafficClass::Own => 0,
+            TrafficClass::Forward => 1,
+            TrafficClass::ClientRelay => 2,
+        }
+    }
+}
+
+/// A message waiting to be handed to Crust.
+pub struct QueuedMessage {
+    pub pub_id: PublicId,
+    pub bytes: Vec<u8>,
+    pub priority: u8,
+}
+
+/// Shares the outgoing bandwidth between the traffic classes using deficit round robin: in each
+/// turn, a class may send up to its weight times `QUANTUM_BYTES`, and unused allowance carries
+/// over as long as the class has messages waiting. Messages are held back once `budget` bytes have
+/// been sent within the current `SEND_INTERVAL_MS`, so that a busy class can't starve the others.
+pub struct SendQueue {
+    queues: [VecDeque<QueuedMessage>; 3],
+    /// The number of bytes waiting in each queue.
+    queued_bytes: [u64; 3],
+    max_queued_bytes: u64,
+    weights: [u32; 3],
+    deficits: [u64; 3],
+    /// The index of the class whose turn it is.
+    current: usize,
+    budget: u64,
+    spent: u64,
+    interval_start: Instant,
+}
+
+impl SendQueue {
+    pub fn new(weights: [u32; 3], budget: u64) -> Self {
+        // A class with zero weight would never get a turn.
+        let weights = [
+            cmp::max(weights[0], 1),
+            cmp::max(weights[1], 1),
+            cmp::max(weights[2], 1),
+        ];
+        SendQueue {
+            queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            queued_bytes: [0; 3],
+            max_queued_bytes: MAX_QUEUED_BYTES,
+            weights,
+            deficits: [QUANTUM_BYTES * u64::from(weights[0]), 0, 0],
+            current: 0,
+            budget,
+            spent: 0,
+            interval_start: Instant::now(),
+        }
+    }
+
+    /// Appends the message to the queue of the given class. If that queue then holds more than
+    /// `MAX_QUEUED_BYTES`, its messages with the lowest priority are dropped, the most recent ones
+    /// first, which may include the new one. Returns the number of messages dropped.
+    pub fn push(
+        &mut self,
+        class: TrafficClass,
+        pub_id: PublicId,
+        bytes: Vec<u8>,
+        priority: u8,
+    ) -> usize {
+        let index = class.index();
+        self.queued_bytes[index] += bytes.len() as u64;
+        self.queues[index].push_back(QueuedMessage {
+            pub_id,
+            bytes,
+            priority,
+        });
+
+        let mut dropped = 0;
+        while self.queued_bytes[index] > self.max_queued_bytes {
+            // A higher value means a lower priority. Of equals, `max_by_key` picks the last one.
+            let lowest = self.queues[index]
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, msg)| msg.priority)
+                .map(|(position, _)| position);
+            match lowest.and_then(|position| self.queues[index].remove(position)) {
+                Some(msg) => {
+                    self.queued_bytes[index] -= msg.bytes.len() as u64;
+                    dropped += 1;
+                }
+                None => break,
+            }
+        }
+        dropped
+    }
+
+    /// Returns the next message to send, or `None` if there is none or the send budget for the
+    /// current interval is used up.
+    pub fn pop(&mut self) -> Option<QueuedMessage> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.interval_start.elapsed() >= Duration::from_millis(SEND_INTERVAL_MS) {
+            self.interval_start = Instant::now();
+            self.spent = 0;
+        }
+        if self.spent >= self.budget {
+            return None;
+        }
+        loop {
+            let len = match self.queues[self.current].front() {
+                Some(msg) => msg.bytes.len() as u64,
+                None => {
+                    self.deficits[self.current] = 0;
+                    self.next_turn();
+                    continue;
+                }
+            };
+            if len <= self.deficits[self.current] {
+                self.deficits[self.current] -= len;
+                self.queued_bytes[self.current] -= len;
+                self.spent = self.spent.saturating_add(len);
+                return self.queues[self.current].pop_front();
+            }
+            self.next_turn();
+        }
+    }
+
+    /// Returns whether no messages are waiting.
+    pub fn is_empty(&self) -> bool {
+        self.queues.iter().all(VecDeque::is_empty)
+    }
+
+    /// Returns whether any messages to `pub_id` are waiting.
+    pub fn has_messages_for(&self, pub_id: &PublicId) -> bool {
+        self.queues
+            .iter()
+            .any(|queue| queue.iter().any(|msg| msg.pub_id == *pub_id))
+    }
+
+    /// Returns the number of waiting messages of own messages, routing forwards and client
+    /// relays.
+    pub fn depths(&self) -> [usize; 3] {
+        [
+            self.queues[0].len(),
+            self.queues[1].len(),
+            self.queues[2].len(),
+        ]
+    }
+
+    fn next_turn(&mut self) {
+        self.current = (self.current + 1) % self.queues.len();
+        self.deficits[self.current] += QUANTUM_BYTES * u64::from(self.weights[self.current]);
+    }
+}
+
+#[cfg(all(test, feature = "use-mock-crust"))]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use fake_clock::FakeClock;
+
+    fn push_n(queue: &mut SendQueue, class: TrafficClass, count: usize, len: usize) {
+        let pub_id = *FullId::new().public_id();
+        for _ in 0..count {
+            assert_eq!(queue.push(class, pub_id, vec![0; len], 0), 0);
+        }
+    }
+
+    #[test]
+    fn classes_share_by_weight() {
+        let len = QUANTUM_BYTES as usize;
+        let mut queue = SendQueue::new([1, 1, 2], std::u64::MAX);
+        push_n(&mut queue, TrafficClass::ClientRelay, 40, len);
+        push_n(&mut queue, TrafficClass::Forward, 10, len);
+        push_n(&mut queue, TrafficClass::Own, 10, len);
+        assert_eq!(queue.depths(), [10, 10, 40]);
+
+        // Of the first 40 messages sent, half are client relays, despite them queueing first.
+        for _ in 0..40 {
+            assert!(queue.pop().is_some());
+        }
+        assert_eq!(queue.depths(), [0, 0, 20]);
+
+        // Once the other classes are idle, client relays get all the bandwidth.
+        for _ in 0..20 {
+            assert!(queue.pop().is_some());
+        }
+        assert!(queue.pop().is_none());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn budget_is_replenished_every_interval() {
+        let mut queue = SendQueue::new(DEFAULT_SEND_WEIGHTS, 100);
+        push_n(&mut queue, TrafficClass::Own, 3, 60);
+        assert!(queue.pop().is_some());
+        assert!(queue.pop().is_some());
+        assert!(queue.pop().is_none());
+        assert_eq!(queue.depths(), [1, 0, 0]);
+
+        FakeClock::advance_time(SEND_INTERVAL_MS);
+        assert!(queue.pop().is_some());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_queues_drop_lowest_priority_messages() {
+        let mut queue = SendQueue::new(DEFAULT_SEND_WEIGHTS, 0);
+        queue.max_queued_bytes = 300;
+        let pub_id = *FullId::new().public_id();
+        let relay = TrafficClass::ClientRelay;
+        assert_eq!(queue.push(relay, pub_id, vec![0; 100], 3), 0);
+        assert_eq!(queue.push(relay, pub_id, vec![1; 100], 2), 0);
+        assert_eq!(queue.push(relay, pub_id, vec![2; 100], 3), 0);
+
+        // The newest of the messages with the lowest priority makes room.
+        assert_eq!(queue.push(relay, pub_id, vec![3; 100], 1), 1);
+        assert_eq!(queue.depths(), [0, 0, 3]);
+        // Other classes have queues of their own.
+        assert_eq!(queue.push(TrafficClass::Own, pub_id, vec![4; 300], 3), 0);
+        // A message with the lowest priority itself is dropped right away.
+        assert_eq!(queue.push(relay, pub_id, vec![5; 100], 4), 1);
+
+        queue.budget = std::u64::MAX;
+        let mut sent = Vec::new();
+        while let Some(msg) = queue.pop() {
+            sent.push(msg.bytes[0]);
+        }
+        assert_eq!(sent, vec![4, 0, 1, 3]);
+    }
+}
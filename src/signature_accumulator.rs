@@ -7,15 +7,19 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::id::PublicId;
-use crate::messages::SignedMessage;
+use crate::messages::{MessageContent, Quorum, RoutingMessage, SignedMessage};
+use crate::routing_table::Prefix;
 use crate::rust_sodium::crypto::sign;
 use crate::sha3::Digest256;
+use crate::types::MessageId;
+use crate::xor_name::XorName;
 #[cfg(feature = "use-mock-crust")]
 use fake_clock::FakeClock as Instant;
 use itertools::Itertools;
 use maidsafe_utilities::serialisation;
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::mem;
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
 use tiny_keccak::sha3_256;
@@ -24,13 +28,51 @@ use tiny_keccak::sha3_256;
 /// accumulate.
 pub const ACCUMULATION_TIMEOUT_SECS: u64 = 30;
 
+/// How the refreshes with a given type tag are accumulated and handled, registered with
+/// `Node::set_refresh_policy`. Fields which are `None` keep the defaults for all refreshes.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct RefreshPolicy {
+    /// The quorum of signatures a refresh needs to be accepted, instead of the refresh quorum.
+    pub quorum: Option<Quorum>,
+    /// The time within which a refresh and a quorum of signatures need to arrive, in seconds,
+    /// instead of `ACCUMULATION_TIMEOUT_SECS`. Signatures which arrive before their refresh still
+    /// expire after the default time.
+    pub expiry_secs: Option<u64>,
+    /// The maximum size of a refresh's payload, in bytes. Larger refreshes are neither sent nor
+    /// handled.
+    pub max_payload_len: Option<usize>,
+}
+
 #[derive(Default)]
 pub struct SignatureAccumulator {
     sigs: HashMap<Digest256, (Vec<(PublicId, sign::Signature)>, Instant)>,
     msgs: HashMap<Digest256, (SignedMessage, u8, Instant)>,
+    /// Messages which expired before reaching quorum, and haven't been taken yet.
+    failed: Vec<RoutingMessage>,
+    /// The quorum a message needs to accumulate.
+    quorum: Quorum,
+    /// The quorum of user messages a group sends to itself, i.e. refreshes.
+    refresh_quorum: Quorum,
+    /// The policies for refreshes of particular type tags.
+    refresh_policies: HashMap<u64, RefreshPolicy>,
 }
 
 impl SignatureAccumulator {
+    /// Creates an accumulator which requires `refresh_quorum` for refreshes, i.e. user messages a
+    /// group sends to itself, and `quorum` for all other messages.
+    pub fn new(quorum: Quorum, refresh_quorum: Quorum) -> Self {
+        SignatureAccumulator {
+            quorum,
+            refresh_quorum,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the quorum non-refresh messages need to accumulate.
+    pub fn quorum(&self) -> Quorum {
+        self.quorum
+    }
+
     /// Adds the given signature to the list of pending signatures or to the appropriate
     /// `SignedMessage`. Returns the message, if it has enough signatures now.
     pub fn add_signature(
@@ -44,13 +86,14 @@ impl SignatureAccumulator {
         if let Some(&mut (ref mut msg, _, _)) = self.msgs.get_mut(&hash) {
             msg.add_signature(pub_id, sig);
         } else {
-            // FIXME: rustc stable requires this to be non-mutable?
-            #[allow(unused)]
-            let mut sigs_vec = self
+            let sigs = &mut self
                 .sigs
                 .entry(hash)
-                .or_insert_with(|| (vec![], Instant::now()));
-            sigs_vec.0.push((pub_id, sig));
+                .or_insert_with(|| (vec![], Instant::now()))
+                .0;
+            // A sender resending its signature, e.g. on retries, is only counted once.
+            sigs.retain(|&(ref sender, _)| *sender != pub_id);
+            sigs.push((pub_id, sig));
             return None;
         }
         self.remove_if_complete(min_section_size, &hash)
@@ -88,6 +131,92 @@ impl SignatureAccumulator {
         self.remove_if_complete(min_section_size, &hash)
     }
 
+    /// Returns the quorum `msg` needs to accumulate.
+    pub fn quorum_for(&self, msg: &RoutingMessage) -> Quorum {
+        match refresh_id(msg) {
+            Some(msg_id) => self
+                .refresh_policy(msg_id.refresh_type_tag())
+                .quorum
+                .filter(Quorum::is_valid)
+                .unwrap_or(self.refresh_quorum),
+            None => self.quorum,
+        }
+    }
+
+    /// Sets the policy for refreshes with the given type tag, or restores the defaults if `None`.
+    pub fn set_refresh_policy(&mut self, type_tag: u64, policy: Option<RefreshPolicy>) {
+        match policy {
+            Some(policy) => {
+                let _ = self.refresh_policies.insert(type_tag, policy);
+            }
+            None => {
+                let _ = self.refresh_policies.remove(&type_tag);
+            }
+        }
+    }
+
+    /// Returns the policy for refreshes with the given type tag.
+    pub fn refresh_policy(&self, type_tag: u64) -> RefreshPolicy {
+        self.refresh_policies
+            .get(&type_tag)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    // Returns the time within which `msg` needs to accumulate, in seconds.
+    fn expiry_secs(&self, msg: &RoutingMessage) -> u64 {
+        refresh_id(msg)
+            .and_then(|msg_id| self.refresh_policy(msg_id.refresh_type_tag()).expiry_secs)
+            .unwrap_or(ACCUMULATION_TIMEOUT_SECS)
+    }
+
+    /// Restarts the accumulation timeout of pending messages from section or group authorities
+    /// within `prefix`, giving their senders more time to reach quorum while the membership is
+    /// changing. Signatures whose message hasn't arrived yet can't be attributed to a source, so
+    /// their timeout is restarted too.
+    pub fn reset_expiry(&mut self, prefix: &Prefix<XorName>) {
+        self.remove_expired();
+        let now = Instant::now();
+        for &mut (ref msg, _, ref mut time) in self.msgs.values_mut() {
+            let src = &msg.routing_message().src;
+            if src.is_multiple() && prefix.matches(&src.name()) {
+                *time = now;
+            }
+        }
+        for &mut (_, ref mut time) in self.sigs.values_mut() {
+            *time = now;
+        }
+    }
+
+    /// Removes expired entries and returns the messages which failed to reach quorum since the
+    /// last call.
+    pub fn take_failed(&mut self) -> Vec<RoutingMessage> {
+        self.remove_expired();
+        mem::replace(&mut self.failed, Vec::new())
+    }
+
+    /// Removes expired entries and releases capacity no longer needed. Returns the approximate
+    /// number of bytes allocated before and after compaction.
+    pub fn compact(&mut self) -> (usize, usize) {
+        let before = self.footprint();
+        self.remove_expired();
+        self.sigs.shrink_to_fit();
+        self.msgs.shrink_to_fit();
+        (before, self.footprint())
+    }
+
+    fn footprint(&self) -> usize {
+        let sig_size = mem::size_of::<(PublicId, sign::Signature)>();
+        let pending_sigs: usize = self
+            .sigs
+            .values()
+            .map(|&(ref sigs, _)| sigs.capacity() * sig_size)
+            .sum();
+        pending_sigs
+            + self.sigs.capacity() * mem::size_of::<(Digest256, (Vec<u8>, Instant))>()
+            + self.msgs.capacity() * mem::size_of::<(Digest256, (SignedMessage, u8, Instant))>()
+    }
+
     fn remove_expired(&mut self) {
         let expired_sigs = self
             .sigs
@@ -101,11 +230,15 @@ impl SignatureAccumulator {
         let expired_msgs = self
             .msgs
             .iter()
-            .filter(|&(_, &(_, _, ref time))| time.elapsed().as_secs() > ACCUMULATION_TIMEOUT_SECS)
+            .filter(|&(_, &(ref msg, _, ref time))| {
+                time.elapsed().as_secs() > self.expiry_secs(msg.routing_message())
+            })
             .map(|(hash, _)| *hash)
             .collect_vec();
         for hash in expired_msgs {
-            let _ = self.msgs.remove(&hash);
+            if let Some((msg, _, _)) = self.msgs.remove(&hash) {
+                self.failed.push(msg.routing_message().clone());
+            }
         }
     }
 
@@ -114,18 +247,44 @@ impl SignatureAccumulator {
         min_section_size: usize,
         hash: &Digest256,
     ) -> Option<(SignedMessage, u8)> {
+        let quorum = match self.msgs.get(hash) {
+            None => return None,
+            Some(&(ref msg, _, _)) => self.quorum_for(msg.routing_message()),
+        };
         match self.msgs.get_mut(hash) {
             None => return None,
             Some(&mut (ref mut msg, _, _)) => {
-                if !msg.check_fully_signed(min_section_size) {
+                if !msg.check_fully_signed(min_section_size, quorum) {
                     return None;
                 }
+                if refresh_id(msg.routing_message()).is_some() {
+                    debug!(
+                        "Refresh {:?} from {:?} accumulated with signatures from {:?}.",
+                        msg.routing_message().content.message_id(),
+                        msg.routing_message().src,
+                        msg.signers().map(PublicId::name).collect_vec()
+                    );
+                }
             }
         }
         self.msgs.remove(hash).map(|(msg, route, _)| (msg, route))
     }
 }
 
+// Refreshes are the user messages a group authority sends to itself. Their parts can't be told
+// apart from other user messages otherwise, as the request is only reassembled after accumulation.
+// Returns the refresh's message ID if `msg` is a part of one.
+fn refresh_id(msg: &RoutingMessage) -> Option<MessageId> {
+    match msg.content {
+        MessageContent::UserMessagePart { msg_id, .. }
+            if msg.src.is_multiple() && msg.src == msg.dst =>
+        {
+            Some(msg_id)
+        }
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -160,6 +319,7 @@ mod tests {
                     Prefix::new(0, rand::random()).with_version(0),
                     rand::random(),
                 ),
+                trace: false,
             };
             let prefix = Prefix::new(0, *unwrap!(all_ids.iter().next()).name());
             let lists = vec![SectionList::new(prefix, all_ids)];
@@ -263,8 +423,8 @@ mod tests {
             assert!(sig_accumulator.msgs.is_empty());
             assert_eq!(route, returned_route);
             assert_eq!(signed_msg.routing_message(), returned_msg.routing_message());
-            unwrap!(returned_msg.check_integrity(1000));
-            assert!(returned_msg.check_fully_signed(env.num_nodes()));
+            unwrap!(returned_msg.check_integrity(1000, Quorum::default()));
+            assert!(returned_msg.check_fully_signed(env.num_nodes(), Quorum::default()));
             env.senders
                 .iter()
                 .foreach(|pub_id| assert!(returned_msg.signed_by(pub_id)));
@@ -315,10 +475,208 @@ mod tests {
                                 msg_and_sigs.signed_msg.routing_message(),
                                 returned_msg.routing_message()
                             );
-                            unwrap!(returned_msg.check_integrity(1000));
-                            assert!(returned_msg.check_fully_signed(env.num_nodes()));
+                            unwrap!(returned_msg.check_integrity(1000, Quorum::default()));
+                            assert!(
+                                returned_msg.check_fully_signed(env.num_nodes(), Quorum::default())
+                            );
                         }
                     });
             });
     }
+
+    #[test]
+    fn resent_signatures_are_counted_once() {
+        let mut sig_accumulator = SignatureAccumulator::default();
+        let env = Env::new();
+        let msg_and_sigs = &env.msgs_and_sigs[0];
+        let (signature_msg, full_id) = (&msg_and_sigs.signature_msgs[0], &env.other_ids[0]);
+        let (hash, sig) = match *signature_msg {
+            DirectMessage::MessageSignature(hash, sig) => (hash, sig),
+            ref unexpected_msg => panic!("Unexpected message: {:?}", unexpected_msg),
+        };
+
+        for _ in 0..3 {
+            let result =
+                sig_accumulator.add_signature(env.num_nodes(), hash, sig, *full_id.public_id());
+            assert!(result.is_none());
+        }
+        assert_eq!(sig_accumulator.sigs[&hash].0.len(), 1);
+    }
+
+    #[test]
+    fn refresh_policy_overrides_quorum() {
+        let quorum = Quorum {
+            numerator: 1,
+            denominator: 2,
+        };
+        let refresh_quorum = Quorum {
+            numerator: 2,
+            denominator: 3,
+        };
+        let mut sig_accumulator = SignatureAccumulator::new(quorum, refresh_quorum);
+        let src = Authority::NaeManager(rand::random());
+        let refresh = |type_tag| RoutingMessage {
+            src,
+            dst: src,
+            content: MessageContent::UserMessagePart {
+                hash: [0; 32],
+                msg_id: MessageId::from_refresh_cause(&rand::random(), type_tag),
+                part_count: 1,
+                part_index: 0,
+                priority: 0,
+                cacheable: false,
+                payload: vec![],
+            },
+            trace: false,
+        };
+        assert_eq!(sig_accumulator.quorum_for(&refresh(7)), refresh_quorum);
+
+        let full_quorum = Quorum {
+            numerator: 99,
+            denominator: 100,
+        };
+        let policy = RefreshPolicy {
+            quorum: Some(full_quorum),
+            expiry_secs: Some(60),
+            max_payload_len: None,
+        };
+        sig_accumulator.set_refresh_policy(7, Some(policy));
+        assert_eq!(sig_accumulator.quorum_for(&refresh(7)), full_quorum);
+        assert_eq!(sig_accumulator.expiry_secs(&refresh(7)), 60);
+        assert_eq!(sig_accumulator.quorum_for(&refresh(8)), refresh_quorum);
+        assert_eq!(
+            sig_accumulator.expiry_secs(&refresh(8)),
+            ACCUMULATION_TIMEOUT_SECS
+        );
+
+        // A policy quorum below one half is ignored.
+        let minority_quorum = Quorum {
+            numerator: 1,
+            denominator: 3,
+        };
+        let policy = RefreshPolicy {
+            quorum: Some(minority_quorum),
+            ..policy
+        };
+        sig_accumulator.set_refresh_policy(7, Some(policy));
+        assert_eq!(sig_accumulator.quorum_for(&refresh(7)), refresh_quorum);
+
+        sig_accumulator.set_refresh_policy(7, None);
+        assert_eq!(sig_accumulator.quorum_for(&refresh(7)), refresh_quorum);
+    }
+
+    #[cfg(feature = "use-mock-crust")]
+    #[test]
+    fn expired_entries_are_removed() {
+        use fake_clock::FakeClock;
+
+        let mut sig_accumulator = SignatureAccumulator::default();
+        let env = Env::new();
+
+        // Add the first message without signatures, and only signatures for the second one.
+        let (first, second) = (&env.msgs_and_sigs[0], &env.msgs_and_sigs[1]);
+        assert!(sig_accumulator
+            .add_message(first.signed_msg.clone(), env.num_nodes(), 0)
+            .is_none());
+        second
+            .signature_msgs
+            .iter()
+            .zip(env.other_ids.iter())
+            .foreach(|(signature_msg, full_id)| match *signature_msg {
+                DirectMessage::MessageSignature(hash, sig) => {
+                    let result = sig_accumulator.add_signature(
+                        env.num_nodes(),
+                        hash,
+                        sig,
+                        *full_id.public_id(),
+                    );
+                    assert!(result.is_none());
+                }
+                ref unexpected_msg => panic!("Unexpected message: {:?}", unexpected_msg),
+            });
+        assert_eq!(sig_accumulator.msgs.len(), 1);
+        assert_eq!(sig_accumulator.sigs.len(), 1);
+
+        // Nothing expires before the accumulation timeout.
+        FakeClock::advance_time(ACCUMULATION_TIMEOUT_SECS * 1000);
+        let _ = sig_accumulator.compact();
+        assert_eq!(sig_accumulator.msgs.len(), 1);
+        assert_eq!(sig_accumulator.sigs.len(), 1);
+
+        // Both entries are dropped once the timeout has passed.
+        FakeClock::advance_time(1000);
+        let (before, after) = sig_accumulator.compact();
+        assert!(sig_accumulator.msgs.is_empty());
+        assert!(sig_accumulator.sigs.is_empty());
+        assert!(after <= before);
+    }
+
+    #[cfg(feature = "use-mock-crust")]
+    #[test]
+    fn reset_expiry_postpones_removal() {
+        use fake_clock::FakeClock;
+
+        let mut sig_accumulator = SignatureAccumulator::default();
+        let env = Env::new();
+        let signed_msg = env.msgs_and_sigs[0].signed_msg.clone();
+        let src_name = signed_msg.routing_message().src.name();
+        assert!(sig_accumulator
+            .add_message(signed_msg, env.num_nodes(), 0)
+            .is_none());
+
+        // Resetting the expiry for a prefix not covering the source doesn't keep it.
+        FakeClock::advance_time(ACCUMULATION_TIMEOUT_SECS * 1000);
+        let other_prefix = Prefix::new(1, !src_name);
+        sig_accumulator.reset_expiry(&other_prefix);
+        FakeClock::advance_time(1000);
+        let _ = sig_accumulator.compact();
+        assert!(sig_accumulator.msgs.is_empty());
+
+        // Resetting it for the source's prefix does.
+        assert!(sig_accumulator
+            .add_message(env.msgs_and_sigs[0].signed_msg.clone(), env.num_nodes(), 0)
+            .is_none());
+        FakeClock::advance_time(ACCUMULATION_TIMEOUT_SECS * 1000);
+        sig_accumulator.reset_expiry(&Prefix::new(1, src_name));
+        FakeClock::advance_time(1000);
+        let _ = sig_accumulator.compact();
+        assert_eq!(sig_accumulator.msgs.len(), 1);
+
+        FakeClock::advance_time(ACCUMULATION_TIMEOUT_SECS * 1000);
+        let _ = sig_accumulator.compact();
+        assert!(sig_accumulator.msgs.is_empty());
+    }
+
+    #[cfg(feature = "use-mock-crust")]
+    #[test]
+    fn expired_messages_are_reported() {
+        use fake_clock::FakeClock;
+
+        let mut sig_accumulator = SignatureAccumulator::default();
+        let env = Env::new();
+        let signed_msg = env.msgs_and_sigs[0].signed_msg.clone();
+        let routing_msg = signed_msg.routing_message().clone();
+        assert!(sig_accumulator
+            .add_message(signed_msg, env.num_nodes(), 0)
+            .is_none());
+        assert!(sig_accumulator.take_failed().is_empty());
+
+        FakeClock::advance_time((ACCUMULATION_TIMEOUT_SECS + 1) * 1000);
+        assert_eq!(sig_accumulator.take_failed(), vec![routing_msg]);
+        assert!(sig_accumulator.take_failed().is_empty());
+
+        // Messages which reach quorum aren't reported.
+        let msg_and_sigs = &env.msgs_and_sigs[1];
+        assert!(sig_accumulator
+            .add_message(msg_and_sigs.signed_msg.clone(), env.num_nodes(), 0)
+            .is_none());
+        for (signature_msg, full_id) in msg_and_sigs.signature_msgs.iter().zip(&env.other_ids) {
+            if let DirectMessage::MessageSignature(hash, sig) = *signature_msg {
+                let _ =
+                    sig_accumulator.add_signature(env.num_nodes(), hash, sig, *full_id.public_id());
+            }
+        }
+        FakeClock::advance_time((ACCUMULATION_TIMEOUT_SECS + 1) * 1000);
+        assert!(sig_accumulator.take_failed().is_empty());
+    }
 }
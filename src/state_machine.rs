@@ -7,30 +7,42 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::action::Action;
+use crate::cache::CacheStats;
+use crate::capture::Direction;
+use crate::error::RoutingError;
+use crate::event::{Event, StateKind};
 use crate::id::{FullId, PublicId};
 #[cfg(feature = "use-mock-crust")]
+use crate::messages::RelocationDenial;
+use crate::messages::Request;
+#[cfg(feature = "use-mock-crust")]
 use crate::mock_crust;
 use crate::outbox::EventBox;
+#[cfg(feature = "use-mock-crust")]
+use crate::routing_table::Authority;
 use crate::routing_table::{Prefix, RoutingTable};
 #[cfg(feature = "use-mock-crust")]
 use crate::rust_sodium::crypto::sign;
 use crate::states::common::Base;
 use crate::states::{Bootstrapping, Client, JoiningNode, Node};
 use crate::timer::Timer;
+use crate::transport::TransportEvent;
+#[cfg(feature = "use-mock-crust")]
+use crate::types::MessageId;
 use crate::types::RoutingActionSender;
 use crate::xor_name::XorName;
 use crate::BootstrapConfig;
 use crate::{CrustEvent, CrustEventSender, Service, MIN_SECTION_SIZE};
 use log::Level;
 use maidsafe_utilities::event_sender::MaidSafeEventCategory;
-#[cfg(feature = "use-mock-crust")]
-use std::collections::BTreeMap;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use std::io;
 use std::mem;
 #[cfg(feature = "use-mock-crust")]
 use std::net::IpAddr;
 use std::sync::mpsc::{self, Receiver, RecvError, Sender, TryRecvError};
+use std::time::Duration;
 
 /// Holds the current state and handles state transitions.
 pub struct StateMachine {
@@ -57,7 +69,7 @@ pub enum State {
 
 #[cfg(feature = "use-mock-crust")]
 enum EventType {
-    CrustEvent(CrustEvent<PublicId>),
+    Transport(TransportEvent),
     Action(Box<Action>),
 }
 
@@ -77,25 +89,63 @@ impl EventType {
 
 impl State {
     pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
+        match action {
+            Action::GetInternalState => {
+                if let Some(event) = self.internal_state() {
+                    outbox.send_event(event);
+                }
+                return Transition::Stay;
+            }
+            Action::GetNetworkSizeEstimate { result_tx } => {
+                let estimate = self
+                    .routing_table()
+                    .map(RoutingTable::network_size_estimate);
+                let _ = result_tx.send(estimate);
+                return Transition::Stay;
+            }
+            Action::StartCapture {
+                ref path,
+                ref result_tx,
+            } => {
+                let result = match self.base_state_mut() {
+                    Some(state) => state.capture().start(path),
+                    None => Err(io::Error::new(io::ErrorKind::Other, "Node has terminated")),
+                };
+                let _ = result_tx.send(result);
+                return Transition::Stay;
+            }
+            Action::StopCapture => {
+                if let Some(state) = self.base_state_mut() {
+                    state.capture().stop();
+                }
+                return Transition::Stay;
+            }
+            _ => (),
+        }
         match *self {
             State::Bootstrapping(ref mut state) => state.handle_action(action),
-            State::Client(ref mut state) => state.handle_action(action),
+            State::Client(ref mut state) => state.handle_action(action, outbox),
             State::JoiningNode(ref mut state) => state.handle_action(action, outbox),
             State::Node(ref mut state) => state.handle_action(action, outbox),
             State::Terminated => Transition::Terminate,
         }
     }
 
-    fn handle_crust_event(
+    fn handle_transport_event(
         &mut self,
-        event: CrustEvent<PublicId>,
+        event: TransportEvent,
         outbox: &mut EventBox,
     ) -> Transition {
+        if let TransportEvent::NewMessage(ref pub_id, _, ref bytes) = event {
+            if let Some(state) = self.base_state_mut() {
+                state.capture().record(Direction::Received, pub_id, bytes);
+            }
+        }
         match *self {
-            State::Bootstrapping(ref mut state) => state.handle_crust_event(event, outbox),
-            State::Client(ref mut state) => state.handle_crust_event(event, outbox),
-            State::JoiningNode(ref mut state) => state.handle_crust_event(event, outbox),
-            State::Node(ref mut state) => state.handle_crust_event(event, outbox),
+            State::Bootstrapping(ref mut state) => state.handle_transport_event(event, outbox),
+            State::Client(ref mut state) => state.handle_transport_event(event, outbox),
+            State::JoiningNode(ref mut state) => state.handle_transport_event(event, outbox),
+            State::Node(ref mut state) => state.handle_transport_event(event, outbox),
             State::Terminated => Transition::Terminate,
         }
     }
@@ -104,6 +154,36 @@ impl State {
         self.base_state().map(|state| *state.id())
     }
 
+    // Returns the kind of our state, or `None` if we have terminated.
+    fn kind(&self) -> Option<StateKind> {
+        match *self {
+            State::Bootstrapping(_) => Some(StateKind::Bootstrapping),
+            State::Client(_) => Some(StateKind::Client),
+            State::JoiningNode(_) => Some(StateKind::JoiningNode),
+            State::Node(_) => Some(StateKind::Node),
+            State::Terminated => None,
+        }
+    }
+
+    // Returns an `Event::InternalState` describing us, unless we have terminated.
+    fn internal_state(&self) -> Option<Event> {
+        let state = self.kind()?;
+        let base = self.base_state()?;
+        let name = *base.name();
+        Some(Event::InternalState {
+            name,
+            state,
+            table_size: self.routing_table().map_or(0, RoutingTable::len),
+            network_size: self
+                .routing_table()
+                .map(|table| table.network_size_estimate().0),
+            close_group: base
+                .close_group(name, base.min_section_size())
+                .unwrap_or_default(),
+            proxy: base.proxy_name(),
+        })
+    }
+
     fn routing_table(&self) -> Option<&RoutingTable<XorName>> {
         match *self {
             State::Node(ref state) => Some(state.routing_table()),
@@ -111,6 +191,27 @@ impl State {
         }
     }
 
+    fn cache_contains(&self, request: &Request) -> bool {
+        match *self {
+            State::Node(ref state) => state.cache_contains(request),
+            _ => false,
+        }
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        match *self {
+            State::Node(ref state) => Some(state.cache_stats()),
+            _ => None,
+        }
+    }
+
+    fn connect_backoffs(&self) -> Option<BTreeMap<XorName, (u32, Duration)>> {
+        match *self {
+            State::Node(ref state) => Some(state.connect_backoffs()),
+            _ => None,
+        }
+    }
+
     fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
         self.base_state()
             .and_then(|state| state.close_group(name, count))
@@ -135,6 +236,16 @@ impl State {
             State::Terminated => None,
         }
     }
+
+    fn base_state_mut(&mut self) -> Option<&mut Base> {
+        match *self {
+            State::Bootstrapping(ref mut bootstrapping) => Some(bootstrapping),
+            State::Client(ref mut client) => Some(client),
+            State::JoiningNode(ref mut joining_node) => Some(joining_node),
+            State::Node(ref mut node) => Some(node),
+            State::Terminated => None,
+        }
+    }
 }
 
 impl Debug for State {
@@ -157,6 +268,12 @@ impl State {
         }
     }
 
+    pub fn set_claim_relayed(&mut self, claim_relayed: bool) {
+        if let State::Client(ref mut state) = *self {
+            state.set_claim_relayed(claim_relayed);
+        }
+    }
+
     pub fn has_tunnel_clients(&self, client_1: PublicId, client_2: PublicId) -> bool {
         match *self {
             State::Node(ref state) => state.has_tunnel_clients(client_1, client_2),
@@ -164,6 +281,19 @@ impl State {
         }
     }
 
+    #[cfg(feature = "fuzzing")]
+    pub fn fuzz_dispatch(
+        &mut self,
+        pub_id: PublicId,
+        bytes: Vec<u8>,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        match *self {
+            State::Node(ref mut state) => state.fuzz_dispatch(pub_id, bytes, outbox),
+            _ => Err(RoutingError::InvalidStateForOperation),
+        }
+    }
+
     pub fn section_list_signatures(
         &self,
         prefix: Prefix<XorName>,
@@ -181,6 +311,20 @@ impl State {
         }
     }
 
+    pub fn is_quarantined(&self, pub_id: &PublicId) -> bool {
+        match *self {
+            State::Node(ref state) => state.is_quarantined(pub_id),
+            _ => false,
+        }
+    }
+
+    pub fn has_held_direct_messages(&self) -> bool {
+        match *self {
+            State::Node(ref state) => state.has_held_direct_messages(),
+            _ => false,
+        }
+    }
+
     pub fn set_next_relocation_dst(&mut self, dst: Option<XorName>) {
         if let State::Node(ref mut node) = *self {
             node.set_next_relocation_dst(dst);
@@ -193,6 +337,23 @@ impl State {
         }
     }
 
+    pub fn deny_next_relocation(&mut self, reason: RelocationDenial) {
+        if let State::Node(ref mut node) = *self {
+            node.deny_next_relocation(reason);
+        }
+    }
+
+    pub fn report_accumulation_failed(
+        &mut self,
+        src: Authority<XorName>,
+        msg_id: MessageId,
+        dst: Authority<XorName>,
+    ) {
+        if let State::Node(ref mut node) = *self {
+            node.report_accumulation_failed(src, msg_id, dst);
+        }
+    }
+
     pub fn get_timed_out_tokens(&mut self) -> Vec<u64> {
         match *self {
             State::Node(ref mut state) => state.get_timed_out_tokens(),
@@ -239,9 +400,53 @@ pub enum Transition {
         new_id: FullId,
         our_section: (Prefix<XorName>, BTreeSet<PublicId>),
     },
+    // `Client` state transitioning back to `Bootstrapping` after losing its proxy.
+    Rebootstrap,
+    Terminate,
+}
+
+/// The kinds of `Transition`, without their data.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransitionKind {
+    Stay,
+    IntoBootstrapped,
+    IntoBootstrapping,
+    Rebootstrap,
     Terminate,
 }
 
+impl Transition {
+    pub fn kind(&self) -> TransitionKind {
+        match *self {
+            Transition::Stay => TransitionKind::Stay,
+            Transition::IntoBootstrapped { .. } => TransitionKind::IntoBootstrapped,
+            Transition::IntoBootstrapping { .. } => TransitionKind::IntoBootstrapping,
+            Transition::Rebootstrap => TransitionKind::Rebootstrap,
+            Transition::Terminate => TransitionKind::Terminate,
+        }
+    }
+}
+
+/// The transition table: returns `Ok` if a state of kind `from`, or the terminated state if
+/// `from` is `None`, can make a transition of the given kind.
+///
+/// Staying and terminating are always possible. Only `Bootstrapping` can become bootstrapped, into
+/// `Client`, `JoiningNode` or `Node` depending on its target state. `JoiningNode` returns to
+/// `Bootstrapping` to be relocated, and `Client` to find a new proxy.
+pub fn check_transition(
+    from: Option<StateKind>,
+    transition: TransitionKind,
+) -> Result<(), RoutingError> {
+    match (from, transition) {
+        (_, TransitionKind::Stay)
+        | (_, TransitionKind::Terminate)
+        | (Some(StateKind::Bootstrapping), TransitionKind::IntoBootstrapped)
+        | (Some(StateKind::JoiningNode), TransitionKind::IntoBootstrapping)
+        | (Some(StateKind::Client), TransitionKind::Rebootstrap) => Ok(()),
+        _ => Err(RoutingError::InvalidTransition),
+    }
+}
+
 impl StateMachine {
     // Construct a new StateMachine by passing a function returning the initial state.
     #[allow(clippy::new_ret_no_self)]
@@ -249,6 +454,7 @@ impl StateMachine {
         init_state: F,
         pub_id: PublicId,
         bootstrap_config: Option<BootstrapConfig>,
+        lan_discovery: bool,
         outbox: &mut EventBox,
     ) -> (RoutingActionSender, Self)
     where
@@ -283,7 +489,9 @@ impl StateMachine {
 
         let mut crust_service = unwrap!(res, "Unable to start crust::Service");
 
-        crust_service.start_service_discovery();
+        if lan_discovery {
+            crust_service.start_service_discovery();
+        }
 
         let timer = Timer::new(action_sender.clone());
 
@@ -327,7 +535,9 @@ impl StateMachine {
                 }
             }
             MaidSafeEventCategory::Crust => match self.crust_rx.try_recv() {
-                Ok(crust_event) => self.state.handle_crust_event(crust_event, outbox),
+                Ok(crust_event) => self
+                    .state
+                    .handle_transport_event(TransportEvent::from(crust_event), outbox),
                 Err(TryRecvError::Empty) => {
                     debug!(
                         "Crust receiver temporarily empty, probably due to node \
@@ -352,9 +562,7 @@ impl StateMachine {
         let event = self.events.remove(0);
         let transition = match event {
             EventType::Action(action) => self.state.handle_action(*action, outbox),
-            EventType::CrustEvent(crust_event) => {
-                self.state.handle_crust_event(crust_event, outbox)
-            }
+            EventType::Transport(event) => self.state.handle_transport_event(event, outbox),
         };
 
         self.apply_transition(transition, outbox)
@@ -362,6 +570,17 @@ impl StateMachine {
 
     pub fn apply_transition(&mut self, transition: Transition, outbox: &mut EventBox) {
         use self::Transition::*;
+        if let Err(error) = check_transition(self.state.kind(), transition.kind()) {
+            log_or_panic!(
+                Level::Error,
+                "{:?} Can't make transition {:?}: {:?}",
+                self,
+                transition.kind(),
+                error
+            );
+            self.terminate();
+            return;
+        }
         match transition {
             Stay => (),
             IntoBootstrapped { proxy_public_id } => {
@@ -396,6 +615,20 @@ impl StateMachine {
                 };
                 self.state = new_state;
             }
+            Rebootstrap => {
+                let new_state = match mem::replace(&mut self.state, State::Terminated) {
+                    State::Client(client) => {
+                        let crust_sender = CrustEventSender::new(
+                            self.crust_tx.clone(),
+                            MaidSafeEventCategory::Crust,
+                            self.category_tx.clone(),
+                        );
+                        client.into_bootstrapping(&mut self.crust_rx, crust_sender, outbox)
+                    }
+                    _ => unreachable!(),
+                };
+                self.state = new_state;
+            }
             Terminate => self.terminate(),
         }
     }
@@ -454,7 +687,9 @@ impl StateMachine {
                     }
                 }
                 MaidSafeEventCategory::Crust => match self.crust_rx.try_recv() {
-                    Ok(crust_event) => events.push(EventType::CrustEvent(crust_event)),
+                    Ok(crust_event) => {
+                        events.push(EventType::Transport(TransportEvent::from(crust_event)))
+                    }
                     Err(TryRecvError::Empty) => {}
                     Err(TryRecvError::Disconnected) => {
                         self.apply_transition(Transition::Terminate, outbox);
@@ -512,6 +747,18 @@ impl StateMachine {
         self.state.close_group(name, count)
     }
 
+    pub fn cache_contains(&self, request: &Request) -> bool {
+        self.state.cache_contains(request)
+    }
+
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.state.cache_stats()
+    }
+
+    pub fn connect_backoffs(&self) -> Option<BTreeMap<XorName, (u32, Duration)>> {
+        self.state.connect_backoffs()
+    }
+
     pub fn min_section_size(&self) -> usize {
         self.state.min_section_size()
     }
@@ -533,3 +780,42 @@ impl Debug for StateMachine {
         self.state.fmt(formatter)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_table() {
+        let transitions = [
+            TransitionKind::Stay,
+            TransitionKind::IntoBootstrapped,
+            TransitionKind::IntoBootstrapping,
+            TransitionKind::Rebootstrap,
+            TransitionKind::Terminate,
+        ];
+        // Whether each state can make each of the `transitions` above, in order.
+        const T: bool = true;
+        const F: bool = false;
+        let table = [
+            (Some(StateKind::Bootstrapping), [T, T, F, F, T]),
+            (Some(StateKind::Client), [T, F, F, T, T]),
+            (Some(StateKind::JoiningNode), [T, F, T, F, T]),
+            (Some(StateKind::Node), [T, F, F, F, T]),
+            (None, [T, F, F, F, T]),
+        ];
+        for &(from, allowed) in &table {
+            for (&transition, &expected) in transitions.iter().zip(&allowed) {
+                let result = check_transition(from, transition);
+                assert_eq!(
+                    result.is_ok(),
+                    expected,
+                    "{:?} -> {:?}: {:?}",
+                    from,
+                    transition,
+                    result
+                );
+            }
+        }
+    }
+}
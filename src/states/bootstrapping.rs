@@ -10,28 +10,48 @@ use super::common::Base;
 use super::{Client, JoiningNode, Node};
 use crate::action::Action;
 use crate::cache::Cache;
+use crate::capture::Capture;
+use crate::config_handler::DevConfig;
 use crate::crust::CrustUser;
-use crate::error::RoutingError;
+use crate::error::{InterfaceError, RoutingError};
 use crate::event::Event;
 use crate::id::{FullId, PublicId};
-use crate::messages::{DirectMessage, Message};
+use crate::messages::{DirectMessage, Message, UserMessage};
 use crate::outbox::EventBox;
+use crate::request_validator::RequestValidator;
 use crate::routing_table::{Authority, Prefix};
 use crate::rust_sodium::crypto::sign;
 use crate::state_machine::{State, Transition};
 use crate::stats::Stats;
 use crate::timer::Timer;
+use crate::transport::TransportEvent;
 use crate::types::RoutingActionSender;
 use crate::xor_name::XorName;
-use crate::{CrustEvent, Service};
+use crate::Service;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
 use maidsafe_utilities::serialisation;
-use std::collections::{BTreeSet, HashSet};
+use std::cmp;
+use std::collections::{BTreeSet, HashSet, VecDeque};
 use std::fmt::{self, Debug, Formatter};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::mpsc::{self, Sender};
 use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
 
 // Time (in seconds) after which bootstrap is cancelled (and possibly retried).
 const BOOTSTRAP_TIMEOUT_SECS: u64 = 20;
+// Maximum number of send actions held until we are bootstrapped.
+const MAX_PENDING_ACTIONS: usize = 100;
+// Time (in seconds) after which a held send action is dropped instead of being sent.
+const PENDING_ACTION_EXPIRY_SECS: u64 = 60;
+// Default number of times we retry to bootstrap after Crust failed to.
+const BOOTSTRAP_RETRY_ROUNDS: usize = 3;
+// Delay (in seconds) before the first bootstrap retry. It doubles with every further round.
+const BOOTSTRAP_RETRY_DELAY_SECS: u64 = 10;
+// Maximum delay (in seconds) before a bootstrap retry.
+const BOOTSTRAP_RETRY_MAX_DELAY_SECS: u64 = 60 * 60;
 
 // State to transition into after bootstrap process is complete.
 // FIXME - See https://maidsafe.atlassian.net/browse/MAID-2026 for info on removing this exclusion.
@@ -52,24 +72,47 @@ pub struct Bootstrapping {
     action_sender: RoutingActionSender,
     bootstrap_blacklist: HashSet<SocketAddr>,
     bootstrap_connection: Option<(PublicId, u64)>,
+    /// The sequence number of the next direct message our proxy sends us, for the `Node` state.
+    proxy_next_seq: u32,
+    /// The number of times bootstrapping failed so far.
+    failed_rounds: usize,
+    /// The number of times we retry to bootstrap before giving up.
+    max_retry_rounds: usize,
+    /// The timer token for the next bootstrap retry.
+    retry_timer_token: Option<u64>,
+    /// Whether to raise `Event::LanPeerFound` when we bootstrap off a peer on our LAN.
+    lan_discovery: bool,
     cache: Box<Cache>,
+    request_validator: Option<Box<RequestValidator>>,
     target_state: TargetState,
     crust_service: Service,
     full_id: FullId,
+    /// The port our listener was started on, if we are going to be a node.
+    listener_port: Option<u16>,
     min_section_size: usize,
+    /// Only held here to be passed to the target state.
+    dev_config: DevConfig,
+    /// Send actions received before we were bootstrapped, to be handled by the target state.
+    pending_actions: VecDeque<(Instant, Action)>,
+    /// The result sender for pending actions. Their callers have already been told the actions
+    /// were queued, so the results of handling them are discarded.
+    pending_result_tx: Sender<Result<(), InterfaceError>>,
     stats: Stats,
+    capture: Capture,
     timer: Timer,
 }
 
 impl Bootstrapping {
-    #[allow(clippy::new_ret_no_self)]
+    #[allow(clippy::new_ret_no_self, clippy::too_many_arguments)]
     pub fn new(
         action_sender: RoutingActionSender,
         cache: Box<Cache>,
+        request_validator: Option<Box<RequestValidator>>,
         target_state: TargetState,
         mut crust_service: Service,
         full_id: FullId,
         min_section_size: usize,
+        dev_config: DevConfig,
         timer: Timer,
     ) -> Option<Self> {
         match target_state {
@@ -83,36 +126,157 @@ impl Bootstrapping {
                 }
             }
         }
+        let max_retry_rounds = dev_config
+            .bootstrap_retry_rounds
+            .unwrap_or(BOOTSTRAP_RETRY_ROUNDS);
         Some(Bootstrapping {
             action_sender,
             bootstrap_blacklist: HashSet::new(),
             bootstrap_connection: None,
+            proxy_next_seq: 0,
+            failed_rounds: 0,
+            max_retry_rounds,
+            retry_timer_token: None,
+            lan_discovery: dev_config.lan_discovery_enabled(),
             cache,
+            request_validator,
             target_state,
             crust_service,
             full_id,
+            listener_port: None,
             min_section_size,
+            dev_config,
+            pending_actions: VecDeque::new(),
+            pending_result_tx: mpsc::channel().0,
             stats: Stats::new(),
+            capture: Capture::default(),
             timer,
         })
     }
 
+    /// Continues the message capture of a previous state.
+    pub fn with_capture(mut self, capture: Capture) -> Self {
+        self.capture = capture;
+        self
+    }
+
+    /// Continues counting the messages of a previous state.
+    pub fn with_stats(mut self, stats: Stats) -> Self {
+        self.stats = stats;
+        self
+    }
+
     pub fn handle_action(&mut self, action: Action) -> Transition {
         match action {
-            Action::ClientSendRequest { ref result_tx, .. }
-            | Action::NodeSendMessage { ref result_tx, .. } => {
-                warn!("{:?} Cannot handle {:?} - not bootstrapped.", self, action);
-                // TODO: return Err here eventually. Returning Ok for now to
-                // preserve the pre-refactor behaviour.
-                let _ = result_tx.send(Ok(()));
+            Action::ClientSendRequest {
+                content,
+                dst,
+                priority,
+                expires_at,
+                result_tx,
+            } => {
+                let result = self.queue_action(Action::ClientSendRequest {
+                    content,
+                    dst,
+                    priority,
+                    expires_at,
+                    result_tx: self.pending_result_tx.clone(),
+                });
+                let _ = result_tx.send(result);
+            }
+            Action::NodeSendMessage {
+                src,
+                dst,
+                content,
+                priority,
+                result_tx,
+            } => {
+                let result = self.queue_action(Action::NodeSendMessage {
+                    src,
+                    dst,
+                    content,
+                    priority,
+                    result_tx: self.pending_result_tx.clone(),
+                });
+                let _ = result_tx.send(result);
+            }
+            Action::SendToGroup {
+                name,
+                content,
+                priority,
+                result_tx,
+            } => {
+                let result = self.queue_action(Action::SendToGroup {
+                    name,
+                    content,
+                    priority,
+                    result_tx: self.pending_result_tx.clone(),
+                });
+                let _ = result_tx.send(result);
+            }
+            Action::SendToNode {
+                name,
+                content,
+                priority,
+                result_tx,
+            } => {
+                let result = self.queue_action(Action::SendToNode {
+                    name,
+                    content,
+                    priority,
+                    result_tx: self.pending_result_tx.clone(),
+                });
+                let _ = result_tx.send(result);
             }
             Action::Id { result_tx } => {
                 let _ = result_tx.send(*self.id());
             }
+            Action::ProxyName { result_tx } => {
+                let _ = result_tx.send(None);
+            }
+            Action::ListenerPort { result_tx } => {
+                let _ = result_tx.send(self.listener_port);
+            }
+            Action::GetPeerInfo { result_tx } => {
+                let _ = result_tx.send(Vec::new());
+            }
+            Action::ExplainAuthority { auth, result_tx } => {
+                let _ = result_tx.send(self.explain_authority(&auth));
+            }
+            Action::IsNaeManagerFor { result_tx, .. } => {
+                let _ = result_tx.send(Err(InterfaceError::InvalidState));
+            }
+            Action::SendRefresh { result_tx, .. } => {
+                warn!("{:?} Cannot send a refresh - not bootstrapped.", self);
+                let _ = result_tx.send(Err(InterfaceError::InvalidState));
+            }
             Action::Timeout(token) => self.handle_timeout(token),
-            Action::ResourceProofResult(..) => {
+            Action::AddContacts(contacts) => {
+                if let Err(error) = self.queue_action(Action::AddContacts(contacts)) {
+                    warn!("{:?} Failed to queue contacts: {:?}", self, error);
+                }
+            }
+            Action::SetIpRanges { allowed, denied } => {
+                if let Err(error) = self.queue_action(Action::SetIpRanges { allowed, denied }) {
+                    warn!("{:?} Failed to queue IP ranges: {:?}", self, error);
+                }
+            }
+            Action::SetRefreshPolicy { type_tag, policy } => {
+                let action = Action::SetRefreshPolicy { type_tag, policy };
+                if let Err(error) = self.queue_action(action) {
+                    warn!("{:?} Failed to queue refresh policy: {:?}", self, error);
+                }
+            }
+            Action::ResourceProofResult(..) | Action::HopMessageVerified { .. } => {
                 warn!("{:?} Cannot handle {:?} - not bootstrapped.", self, action);
             }
+            // We haven't relayed anything yet.
+            Action::ResetRelayAccounting => (),
+            // Answered by `State::handle_action` for all states.
+            Action::GetInternalState
+            | Action::GetNetworkSizeEstimate { .. }
+            | Action::StartCapture { .. }
+            | Action::StopCapture => (),
             Action::Terminate => {
                 return Transition::Terminate;
             }
@@ -120,22 +284,25 @@ impl Bootstrapping {
         Transition::Stay
     }
 
-    pub fn handle_crust_event(
+    pub fn handle_transport_event(
         &mut self,
-        crust_event: CrustEvent<PublicId>,
+        event: TransportEvent,
         outbox: &mut EventBox,
     ) -> Transition {
-        match crust_event {
-            CrustEvent::BootstrapConnect(pub_id, socket_addr) => {
+        match event {
+            TransportEvent::BootstrapConnect(pub_id, socket_addr) => {
+                if self.lan_discovery && is_lan_ip(&socket_addr.ip()) {
+                    outbox.send_event(Event::LanPeerFound(socket_addr));
+                }
                 self.handle_bootstrap_connect(pub_id, socket_addr)
             }
-            CrustEvent::BootstrapFailed => self.handle_bootstrap_failed(outbox),
-            CrustEvent::LostPeer(pub_id) => {
+            TransportEvent::BootstrapFailed => self.handle_bootstrap_failed(outbox),
+            TransportEvent::LostPeer(pub_id) => {
                 info!("{:?} Lost connection to proxy {:?}.", self, pub_id);
                 self.rebootstrap();
                 Transition::Stay
             }
-            CrustEvent::NewMessage(pub_id, _, bytes) => {
+            TransportEvent::NewMessage(pub_id, _, bytes) => {
                 match self.handle_new_message(pub_id, bytes) {
                     Ok(transition) => transition,
                     Err(error) => {
@@ -144,19 +311,20 @@ impl Bootstrapping {
                     }
                 }
             }
-            CrustEvent::ListenerStarted(port) => {
+            TransportEvent::ListenerStarted(port) => {
                 if self.client_restriction() {
                     error!("{:?} A client must not run a crust listener.", self);
                     outbox.send_event(Event::Terminate);
                     return Transition::Terminate;
                 }
                 trace!("{:?} Listener started on port {}.", self, port);
+                self.listener_port = Some(port);
                 let _ = self
                     .crust_service
                     .start_bootstrap(HashSet::new(), CrustUser::Node);
                 Transition::Stay
             }
-            CrustEvent::ListenerFailed => {
+            TransportEvent::ListenerFailed => {
                 if self.client_restriction() {
                     error!("{:?} A client must not run a crust listener.", self);
                 } else {
@@ -166,20 +334,25 @@ impl Bootstrapping {
                 Transition::Terminate
             }
             _ => {
-                debug!("{:?} Unhandled crust event {:?}", self, crust_event);
+                debug!("{:?} Unhandled transport event {:?}", self, event);
                 Transition::Stay
             }
         }
     }
 
-    pub fn into_target_state(self, proxy_public_id: PublicId, outbox: &mut EventBox) -> State {
-        match self.target_state {
+    pub fn into_target_state(mut self, proxy_public_id: PublicId, outbox: &mut EventBox) -> State {
+        self.remove_expired_actions();
+        let pending_actions = self.pending_actions;
+        let mut state = match self.target_state {
             TargetState::Client { msg_expiry_dur } => State::Client(Client::from_bootstrapping(
+                self.action_sender,
                 self.crust_service,
                 self.full_id,
                 self.min_section_size,
+                self.dev_config,
                 proxy_public_id,
                 self.stats,
+                self.capture,
                 self.timer,
                 msg_expiry_dur,
                 outbox,
@@ -188,11 +361,15 @@ impl Bootstrapping {
                 if let Some(joining_node) = JoiningNode::from_bootstrapping(
                     self.action_sender,
                     self.cache,
+                    self.request_validator,
                     self.crust_service,
                     self.full_id,
+                    self.listener_port,
                     self.min_section_size,
+                    self.dev_config,
                     proxy_public_id,
                     self.stats,
+                    self.capture,
                     self.timer,
                 ) {
                     State::JoiningNode(joining_node)
@@ -209,14 +386,91 @@ impl Bootstrapping {
                 our_section,
                 self.action_sender,
                 self.cache,
+                self.request_validator,
                 self.crust_service,
                 old_full_id,
                 self.full_id,
+                self.listener_port,
                 self.min_section_size,
+                self.dev_config,
                 proxy_public_id,
+                self.proxy_next_seq,
                 self.stats,
+                self.capture,
                 self.timer,
             )),
+        };
+
+        if !pending_actions.is_empty() {
+            debug!(
+                "Handling {} actions received while bootstrapping.",
+                pending_actions.len()
+            );
+        }
+        for (_, action) in pending_actions {
+            let _ = state.handle_action(action, outbox);
+        }
+        state
+    }
+
+    // Holds a send action until we are bootstrapped, so that it isn't lost. Actions which the state
+    // we are bootstrapping for would reject fail right away instead.
+    fn queue_action(&mut self, action: Action) -> Result<(), InterfaceError> {
+        if !self.target_accepts(&action) {
+            warn!("{:?} Cannot handle {:?} - wrong state.", self, action);
+            return Err(InterfaceError::InvalidState);
+        }
+        self.remove_expired_actions();
+        if self.pending_actions.len() >= MAX_PENDING_ACTIONS {
+            warn!("{:?} Cannot handle {:?} - not bootstrapped.", self, action);
+            return Err(InterfaceError::NotConnected);
+        }
+        trace!("{:?} Holding {:?} until bootstrapped.", self, action);
+        self.pending_actions.push_back((Instant::now(), action));
+        Ok(())
+    }
+
+    // Returns whether the state we are bootstrapping for handles `action` rather than failing it.
+    // A joining node handles none of them: it only sends its relocation request.
+    fn target_accepts(&self, action: &Action) -> bool {
+        match (&self.target_state, action) {
+            (&TargetState::Client { .. }, &Action::ClientSendRequest { .. })
+            | (
+                &TargetState::Client { .. },
+                &Action::SendToGroup {
+                    content: UserMessage::Request(_),
+                    ..
+                },
+            )
+            | (
+                &TargetState::Client { .. },
+                &Action::SendToNode {
+                    content: UserMessage::Request(_),
+                    ..
+                },
+            )
+            | (&TargetState::Node { .. }, &Action::NodeSendMessage { .. })
+            | (&TargetState::Node { .. }, &Action::SendToGroup { .. })
+            | (&TargetState::Node { .. }, &Action::SendToNode { .. })
+            | (&TargetState::Node { .. }, &Action::AddContacts(..))
+            | (&TargetState::Node { .. }, &Action::SetIpRanges { .. })
+            | (&TargetState::Node { .. }, &Action::SetRefreshPolicy { .. }) => true,
+            _ => false,
+        }
+    }
+
+    fn remove_expired_actions(&mut self) {
+        let expiry = Duration::from_secs(PENDING_ACTION_EXPIRY_SECS);
+        while let Some(&(ref queued_at, _)) = self.pending_actions.front() {
+            if queued_at.elapsed() < expiry {
+                break;
+            }
+            if let Some((_, action)) = self.pending_actions.pop_front() {
+                debug!(
+                    "{:?} Dropping {:?} - not bootstrapped in time.",
+                    self, action
+                );
+            }
         }
     }
 
@@ -227,7 +481,25 @@ impl Bootstrapping {
         }
     }
 
+    fn crust_user(&self) -> CrustUser {
+        if self.client_restriction() {
+            CrustUser::Client
+        } else {
+            CrustUser::Node
+        }
+    }
+
     fn handle_timeout(&mut self, token: u64) {
+        if self.retry_timer_token == Some(token) {
+            self.retry_timer_token = None;
+            // The contacts which failed before may be reachable by now, so try all of them again.
+            self.bootstrap_blacklist.clear();
+            let crust_user = self.crust_user();
+            let _ = self
+                .crust_service
+                .start_bootstrap(HashSet::new(), crust_user);
+            return;
+        }
         if let Some((bootstrap_id, bootstrap_token)) = self.bootstrap_connection {
             if bootstrap_token == token {
                 debug!(
@@ -266,8 +538,33 @@ impl Bootstrapping {
         Transition::Stay
     }
 
+    // Schedules another attempt to bootstrap via Crust, which again tries its cached contacts, the
+    // contacts from its config file and service discovery, or gives up once we have retried
+    // `max_retry_rounds` times.
     fn handle_bootstrap_failed(&mut self, outbox: &mut EventBox) -> Transition {
+        if self.failed_rounds < self.max_retry_rounds {
+            let delay = Duration::from_secs(retry_delay_secs(self.failed_rounds));
+            self.failed_rounds += 1;
+            info!(
+                "{:?} Failed to bootstrap. Retrying in {} seconds ({}/{}).",
+                self,
+                delay.as_secs(),
+                self.failed_rounds,
+                self.max_retry_rounds
+            );
+            self.retry_timer_token = Some(self.timer.schedule(delay));
+            return Transition::Stay;
+        }
+        if let Some(port) = self.listener_port {
+            // Crust only lets a node bootstrap if its bootstrap peer can connect back to it.
+            warn!(
+                "{:?} Failed to bootstrap. Check that port {} is reachable from the internet \
+                 and not blocked by a firewall or NAT.",
+                self, port
+            );
+        }
         info!("{:?} Failed to bootstrap. Terminating.", self);
+        outbox.send_event(Event::BootstrapFailed);
         outbox.send_event(Event::Terminate);
         Transition::Terminate
     }
@@ -278,7 +575,9 @@ impl Bootstrapping {
         bytes: Vec<u8>,
     ) -> Result<Transition, RoutingError> {
         match serialisation::deserialise(&bytes) {
-            Ok(Message::Direct(direct_msg)) => Ok(self.handle_direct_message(direct_msg, pub_id)),
+            Ok(Message::Direct { content, seq }) => {
+                Ok(self.handle_direct_message(content, seq, pub_id))
+            }
             Ok(message) => {
                 debug!("{:?} Unhandled new message: {:?}", self, message);
                 Ok(Transition::Stay)
@@ -290,13 +589,17 @@ impl Bootstrapping {
     fn handle_direct_message(
         &mut self,
         direct_message: DirectMessage,
+        seq: u32,
         pub_id: PublicId,
     ) -> Transition {
         use self::DirectMessage::*;
         match direct_message {
-            BootstrapResponse(Ok(())) => Transition::IntoBootstrapped {
-                proxy_public_id: pub_id,
-            },
+            BootstrapResponse(Ok(())) => {
+                self.proxy_next_seq = seq.wrapping_add(1);
+                Transition::IntoBootstrapped {
+                    proxy_public_id: pub_id,
+                }
+            }
             BootstrapResponse(Err(error)) => {
                 info!("{:?} Connection failed: {}", self, error);
                 self.rebootstrap();
@@ -332,7 +635,12 @@ impl Bootstrapping {
         let direct_message = DirectMessage::BootstrapRequest(signature);
 
         self.stats().count_direct_message(&direct_message);
-        self.send_message(&pub_id, Message::Direct(direct_message));
+        // This is the only direct message we send the peer, so it is the first in sequence.
+        let message = Message::Direct {
+            content: direct_message,
+            seq: 0,
+        };
+        self.send_message(&pub_id, message);
     }
 
     fn disconnect_peer(&mut self, pub_id: &PublicId) {
@@ -350,11 +658,7 @@ impl Bootstrapping {
                 self, bootstrap_id
             );
             let _ = self.crust_service.disconnect(&bootstrap_id);
-            let crust_user = if self.client_restriction() {
-                CrustUser::Client
-            } else {
-                CrustUser::Node
-            };
+            let crust_user = self.crust_user();
             let _ = self
                 .crust_service
                 .start_bootstrap(self.bootstrap_blacklist.clone(), crust_user);
@@ -362,6 +666,29 @@ impl Bootstrapping {
     }
 }
 
+// Returns whether the given address belongs to a private, link-local or loopback network.
+fn is_lan_ip(ip: &IpAddr) -> bool {
+    match *ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local() || ip.is_loopback(),
+        // Unique local addresses are `fc00::/7`, link-local ones `fe80::/10`.
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.segments()[0] & 0xfe00 == 0xfc00
+                || ip.segments()[0] & 0xffc0 == 0xfe80
+        }
+    }
+}
+
+// Returns the delay before retrying to bootstrap after `failed_rounds` failed retries, doubling
+// with each round up to `BOOTSTRAP_RETRY_MAX_DELAY_SECS`.
+fn retry_delay_secs(failed_rounds: usize) -> u64 {
+    1u64.checked_shl(failed_rounds as u32)
+        .and_then(|factor| BOOTSTRAP_RETRY_DELAY_SECS.checked_mul(factor))
+        .map_or(BOOTSTRAP_RETRY_MAX_DELAY_SECS, |secs| {
+            cmp::min(secs, BOOTSTRAP_RETRY_MAX_DELAY_SECS)
+        })
+}
+
 impl Base for Bootstrapping {
     fn crust_service(&self) -> &Service {
         &self.crust_service
@@ -375,6 +702,10 @@ impl Base for Bootstrapping {
         &mut self.stats
     }
 
+    fn capture(&mut self) -> &mut Capture {
+        &mut self.capture
+    }
+
     fn in_authority(&self, _: &Authority<XorName>) -> bool {
         false
     }
@@ -395,12 +726,15 @@ mod tests {
     use super::*;
     use crate::cache::NullCache;
     use crate::id::FullId;
+    use crate::messages::Request;
     use crate::mock_crust::crust::{Config, Service};
     use crate::mock_crust::{self, Network};
     use crate::outbox::EventBuf;
     use crate::state_machine::StateMachine;
+    use crate::types::MessageId;
     use crate::CrustEvent;
     use maidsafe_utilities::event_sender::{MaidSafeEventCategory, MaidSafeObserver};
+    use rand;
     use std::sync::mpsc;
 
     #[test]
@@ -435,6 +769,13 @@ mod tests {
         // off the Crust service above.
         let handle1 = network.new_service_handle(Some(config.clone()), None);
         let mut outbox = EventBuf::new();
+        // Terminate once bootstrapping failed, instead of retrying. Mock Crust doesn't implement
+        // service discovery.
+        let dev_config = DevConfig {
+            bootstrap_retry_rounds: Some(0),
+            lan_discovery: Some(false),
+            ..DevConfig::default()
+        };
         let mut state_machine = mock_crust::make_current(&handle1, || {
             let full_id = FullId::new();
             let pub_id = *full_id.public_id();
@@ -443,18 +784,21 @@ mod tests {
                     Bootstrapping::new(
                         action_sender,
                         Box::new(NullCache),
+                        None,
                         TargetState::Client {
                             msg_expiry_dur: Duration::from_secs(60),
                         },
                         crust_service,
                         full_id,
                         min_section_size,
+                        dev_config,
                         timer,
                     )
                     .map_or(State::Terminated, State::Bootstrapping)
                 },
                 pub_id,
                 Some(config),
+                false,
                 &mut outbox,
             )
             .1
@@ -488,7 +832,10 @@ mod tests {
         network.deliver_messages();
         if let CrustEvent::NewMessage::<_>(_, _, serialised_msg) = unwrap!(event_rx.try_recv()) {
             match unwrap!(serialisation::deserialise(&serialised_msg)) {
-                Message::Direct(DirectMessage::BootstrapRequest(_)) => (),
+                Message::Direct {
+                    content: DirectMessage::BootstrapRequest(_),
+                    ..
+                } => (),
                 _ => panic!("Should have received a `BootstrapRequest`."),
             }
         } else {
@@ -497,15 +844,105 @@ mod tests {
         drop(crust_service);
         network.deliver_messages();
 
-        // Check the state machine received the `LostPeer` and sent `Terminate` via the `outbox`
-        // since it can't re-bootstrap (there are no more bootstrap contacts).
+        // Check the state machine received the `LostPeer` and sent `BootstrapFailed` and
+        // `Terminate` via the `outbox` since it can't re-bootstrap (there are no more bootstrap
+        // contacts).
         unwrap!(state_machine.step(&mut outbox));
         assert!(outbox.take_all().is_empty());
         network.deliver_messages();
 
         unwrap!(state_machine.step(&mut outbox));
         let events = outbox.take_all();
-        assert_eq!(events.len(), 1);
-        assert_eq!(events[0], Event::Terminate);
+        assert_eq!(events, vec![Event::BootstrapFailed, Event::Terminate]);
+    }
+
+    #[test]
+    fn fails_actions_the_target_state_rejects() {
+        let min_section_size = 8;
+        let network = Network::new(min_section_size, None);
+        let new_state = |target_state| {
+            let handle = network.new_service_handle(None, None);
+            let (action_tx, _) = mpsc::channel();
+            let (crust_tx, _) = mpsc::channel();
+            let (category_tx, _) = mpsc::channel();
+            let action_sender = RoutingActionSender::new(
+                action_tx,
+                MaidSafeEventCategory::Routing,
+                category_tx.clone(),
+            );
+            let crust_sender =
+                MaidSafeObserver::new(crust_tx, MaidSafeEventCategory::Crust, category_tx);
+            let full_id = FullId::new();
+            let crust_service = unwrap!(Service::with_handle(
+                &handle,
+                crust_sender,
+                *full_id.public_id(),
+            ));
+            unwrap!(Bootstrapping::new(
+                action_sender.clone(),
+                Box::new(NullCache),
+                None,
+                target_state,
+                crust_service,
+                full_id,
+                min_section_size,
+                DevConfig::default(),
+                Timer::new(action_sender),
+            ))
+        };
+        let send_to_group = |result_tx| Action::SendToGroup {
+            name: rand::random(),
+            content: UserMessage::Request(Request::GetIData {
+                name: rand::random(),
+                msg_id: MessageId::new(),
+            }),
+            priority: 0,
+            result_tx,
+        };
+
+        // A client sends the request once it is bootstrapped.
+        let mut client = new_state(TargetState::Client {
+            msg_expiry_dur: Duration::from_secs(60),
+        });
+        let (result_tx, result_rx) = mpsc::channel();
+        let _ = client.handle_action(send_to_group(result_tx));
+        assert!(unwrap!(result_rx.try_recv()).is_ok());
+        assert_eq!(client.pending_actions.len(), 1);
+
+        // A joining node can't send it at all, so it isn't reported as queued.
+        let mut joining_node = new_state(TargetState::JoiningNode);
+        let (result_tx, result_rx) = mpsc::channel();
+        let _ = joining_node.handle_action(send_to_group(result_tx));
+        match unwrap!(result_rx.try_recv()) {
+            Err(InterfaceError::InvalidState) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+        assert!(joining_node.pending_actions.is_empty());
+    }
+
+    #[test]
+    fn lan_ips() {
+        let is_lan = |ip: &str| is_lan_ip(&unwrap!(ip.parse()));
+        for ip in &["10.0.0.1", "192.168.1.20", "169.254.0.3", "127.0.0.1"] {
+            assert!(is_lan(ip), "{}", ip);
+        }
+        for ip in &["fd00::1", "fe80::1", "::1"] {
+            assert!(is_lan(ip), "{}", ip);
+        }
+        for ip in &["8.8.8.8", "172.32.0.1", "2001:db8::1"] {
+            assert!(!is_lan(ip), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay_secs(0), BOOTSTRAP_RETRY_DELAY_SECS);
+        assert_eq!(retry_delay_secs(2), 4 * BOOTSTRAP_RETRY_DELAY_SECS);
+        for &failed_rounds in &[20, 60, 64, 1000] {
+            assert_eq!(
+                retry_delay_secs(failed_rounds),
+                BOOTSTRAP_RETRY_MAX_DELAY_SECS
+            );
+        }
     }
 }
@@ -7,78 +7,143 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::common::{Base, Bootstrapped, USER_MSG_CACHE_EXPIRY_DURATION_SECS};
+use super::{Bootstrapping, BootstrappingTargetState};
 use crate::ack_manager::{Ack, AckManager, UnacknowledgedMessage};
 use crate::action::Action;
+use crate::cache::NullCache;
+use crate::capture::Capture;
+use crate::config_handler::DevConfig;
 use crate::error::{InterfaceError, RoutingError};
-use crate::event::Event;
+use crate::event::{DropStage, Event};
 use crate::id::{FullId, PublicId};
 use crate::messages::{
-    DirectMessage, HopMessage, Message, MessageContent, RoutingMessage, SignedMessage, UserMessage,
-    UserMessageCache,
+    DirectMessage, HopMessage, Message, MessageContent, Quorum, RoutingMessage, SignedMessage,
+    UserMessage, UserMessageCache,
 };
+#[cfg(feature = "use-mock-crust")]
+use crate::mock_crust::to_endpoint;
 use crate::outbox::EventBox;
+use crate::proxy_certificate::ProxyCertificate;
+use crate::request_journal::RequestJournal;
 use crate::routing_message_filter::{FilteringResult, RoutingMessageFilter};
 use crate::routing_table::Authority;
-use crate::state_machine::Transition;
+use crate::state_machine::{State, Transition};
 use crate::stats::Stats;
 use crate::timer::Timer;
+use crate::transport::TransportEvent;
+use crate::types::{MessageId, RoutingActionSender};
 use crate::xor_name::XorName;
-use crate::{CrustEvent, Service};
+use crate::{BootstrapConfig, CrustEvent, CrustEventSender, Service};
 #[cfg(feature = "use-mock-crust")]
 use fake_clock::FakeClock as Instant;
+use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::{self, Debug, Formatter};
+use std::mem;
+use std::net::SocketAddr;
+use std::sync::mpsc::Receiver;
 use std::time::Duration;
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
 
 /// Duration to wait before sending rate limit exceeded messages.
 pub const RATE_EXCEED_RETRY_MS: u64 = 800;
+/// Time to wait for our proxy's `ProxyCertificate` before reporting it as unrecognised.
+const PROXY_CERTIFICATE_TIMEOUT_SECS: u64 = 30;
 
 /// A node connecting a user to the network, as opposed to a routing / data storage node.
 ///
 /// Each client has a _proxy_: a node through which all requests are routed.
 pub struct Client {
     ack_mgr: AckManager,
+    /// Only held here to be passed to the `Bootstrapping` state if we lose our proxy.
+    action_sender: RoutingActionSender,
     crust_service: Service,
     full_id: FullId,
     min_section_size: usize,
+    /// Only held here to be passed to the `Bootstrapping` state if we lose our proxy.
+    dev_config: DevConfig,
     proxy_pub_id: PublicId,
+    /// Fires if our proxy didn't present a valid `ProxyCertificate` in time.
+    proxy_cert_timer_token: Option<u64>,
     routing_msg_filter: RoutingMessageFilter,
     stats: Stats,
+    capture: Capture,
     timer: Timer,
     user_msg_cache: UserMessageCache,
     resend_buf: BTreeMap<u64, UnacknowledgedMessage>,
     msg_expiry_dur: Duration,
+    /// Requests we recently raised `Event::RequestExpired` for.
+    expired_requests: LruCache<MessageId, ()>,
+    /// Whether the user messages we send request a `TraceReport` from every node handling them.
+    trace_user_messages: bool,
+    /// Whether to raise `Event::MessageDropped` when we discard a message.
+    report_dropped_messages: bool,
+    /// Our unanswered requests, if `journal_client_requests` is enabled.
+    journal: Option<RequestJournal>,
+    /// Whether to bootstrap again instead of terminating if we lose our proxy.
+    rebootstrap: bool,
+    /// Requests made after we lost our proxy, to be sent once we bootstrapped again.
+    held_actions: Vec<Action>,
+    /// The alternative contacts our shutting down proxy sent us, to bootstrap off instead.
+    proxy_contacts: Vec<SocketAddr>,
+    /// Whether our hop messages claim to have been relayed by a second proxy.
+    #[cfg(feature = "use-mock-crust")]
+    claim_relayed: bool,
 }
 
 impl Client {
     #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn from_bootstrapping(
+        action_sender: RoutingActionSender,
         crust_service: Service,
         full_id: FullId,
         min_section_size: usize,
+        dev_config: DevConfig,
         proxy_pub_id: PublicId,
         stats: Stats,
+        capture: Capture,
         timer: Timer,
         msg_expiry_dur: Duration,
         outbox: &mut EventBox,
     ) -> Self {
+        let proxy_cert_timer_token =
+            timer.schedule(Duration::from_secs(PROXY_CERTIFICATE_TIMEOUT_SECS));
+        let journal = if dev_config.journal_client_requests {
+            Some(RequestJournal::open(full_id.public_id().name()))
+        } else {
+            None
+        };
         let client = Client {
             ack_mgr: AckManager::new(),
+            action_sender,
             crust_service,
             full_id,
             min_section_size,
             proxy_pub_id,
+            proxy_cert_timer_token: Some(proxy_cert_timer_token),
             routing_msg_filter: RoutingMessageFilter::new(),
             stats,
+            capture,
             timer,
             user_msg_cache: UserMessageCache::with_expiry_duration(Duration::from_secs(
                 USER_MSG_CACHE_EXPIRY_DURATION_SECS,
             )),
             resend_buf: Default::default(),
             msg_expiry_dur,
+            expired_requests: LruCache::with_expiry_duration(Duration::from_secs(
+                USER_MSG_CACHE_EXPIRY_DURATION_SECS,
+            )),
+            trace_user_messages: dev_config.trace_user_messages,
+            report_dropped_messages: dev_config.report_dropped_messages,
+            journal,
+            rebootstrap: dev_config.client_rebootstrap_enabled(),
+            held_actions: Vec::new(),
+            proxy_contacts: Vec::new(),
+            dev_config,
+            #[cfg(feature = "use-mock-crust")]
+            claim_relayed: false,
         };
 
         debug!("{:?} State changed to client.", client);
@@ -87,37 +152,135 @@ impl Client {
         client
     }
 
-    pub fn handle_action(&mut self, action: Action) -> Transition {
+    pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
         match action {
             Action::ClientSendRequest {
                 content,
                 dst,
                 priority,
+                expires_at,
                 result_tx,
             } => {
+                if expires_at.map_or(false, |expires_at| expires_at < Instant::now()) {
+                    // The request was held back while bootstrapping until it expired.
+                    self.report_expired(*content.message_id(), dst, outbox);
+                    let _ = result_tx.send(Ok(()));
+                    return Transition::Stay;
+                }
+
+                if self.rebootstrap && !self.crust_service.is_connected(&self.proxy_pub_id) {
+                    // Send the request once we have a new proxy.
+                    self.held_actions.push(Action::ClientSendRequest {
+                        content,
+                        dst,
+                        priority,
+                        expires_at,
+                        result_tx,
+                    });
+                    return self.handle_lost_proxy(outbox);
+                }
+
                 let src = Authority::Client {
                     client_id: *self.full_id.public_id(),
                     proxy_node_name: *self.proxy_pub_id.name(),
                 };
 
+                if let Some(ref mut journal) = self.journal {
+                    let now = Instant::now();
+                    let expires_in = expires_at.map_or(self.msg_expiry_dur, |expires_at| {
+                        if expires_at > now {
+                            expires_at.duration_since(now)
+                        } else {
+                            Duration::from_secs(0)
+                        }
+                    });
+                    journal.record(&content, dst, expires_in);
+                }
+
                 let user_msg = UserMessage::Request(content);
-                let result = match self.send_user_message(src, dst, user_msg, priority) {
+                let result = match self.send_user_message(src, dst, user_msg, priority, expires_at)
+                {
                     Err(RoutingError::Interface(err)) => Err(err),
                     Err(_) | Ok(_) => Ok(()),
                 };
 
                 let _ = result_tx.send(result);
             }
-            Action::NodeSendMessage { result_tx, .. } => {
+            Action::SendToGroup {
+                name,
+                content: UserMessage::Request(content),
+                priority,
+                result_tx,
+            } => {
+                return self.handle_action(
+                    Action::ClientSendRequest {
+                        content,
+                        dst: Authority::NaeManager(name),
+                        priority,
+                        expires_at: None,
+                        result_tx,
+                    },
+                    outbox,
+                );
+            }
+            Action::SendToNode {
+                name,
+                content: UserMessage::Request(content),
+                priority,
+                result_tx,
+            } => {
+                return self.handle_action(
+                    Action::ClientSendRequest {
+                        content,
+                        dst: Authority::ManagedNode(name),
+                        priority,
+                        expires_at: None,
+                        result_tx,
+                    },
+                    outbox,
+                );
+            }
+            // Clients only send requests.
+            Action::NodeSendMessage { result_tx, .. }
+            | Action::SendToGroup { result_tx, .. }
+            | Action::SendToNode { result_tx, .. }
+            | Action::SendRefresh { result_tx, .. } => {
                 let _ = result_tx.send(Err(InterfaceError::InvalidState));
             }
             Action::Id { result_tx } => {
                 let _ = result_tx.send(*self.id());
             }
-            Action::Timeout(token) => self.handle_timeout(token),
+            Action::ProxyName { result_tx } => {
+                let _ = result_tx.send(self.proxy_name());
+            }
+            Action::ListenerPort { result_tx } => {
+                let _ = result_tx.send(None);
+            }
+            Action::GetPeerInfo { result_tx } => {
+                let _ = result_tx.send(Vec::new());
+            }
+            Action::ExplainAuthority { auth, result_tx } => {
+                let _ = result_tx.send(self.explain_authority(&auth));
+            }
+            Action::IsNaeManagerFor { result_tx, .. } => {
+                let _ = result_tx.send(Err(InterfaceError::InvalidState));
+            }
+            Action::Timeout(token) => self.handle_timeout(token, outbox),
             Action::ResourceProofResult(..) => {
                 error!("Action::ResourceProofResult received by Client state");
             }
+            Action::AddContacts(..)
+            | Action::HopMessageVerified { .. }
+            | Action::SetIpRanges { .. }
+            | Action::SetRefreshPolicy { .. }
+            | Action::ResetRelayAccounting => {
+                warn!("{:?} Cannot handle {:?} - not a node.", self, action);
+            }
+            // Answered by `State::handle_action` for all states.
+            Action::GetInternalState
+            | Action::GetNetworkSizeEstimate { .. }
+            | Action::StartCapture { .. }
+            | Action::StopCapture => (),
             Action::Terminate => {
                 return Transition::Terminate;
             }
@@ -126,34 +289,159 @@ impl Client {
         Transition::Stay
     }
 
-    pub fn handle_crust_event(
+    pub fn handle_transport_event(
         &mut self,
-        crust_event: CrustEvent<PublicId>,
+        event: TransportEvent,
         outbox: &mut EventBox,
     ) -> Transition {
-        match crust_event {
-            CrustEvent::LostPeer(pub_id) => self.handle_lost_peer(pub_id, outbox),
-            CrustEvent::NewMessage(pub_id, _, bytes) => {
+        match event {
+            TransportEvent::LostPeer(pub_id) => self.handle_lost_peer(pub_id, outbox),
+            TransportEvent::NewMessage(pub_id, _, bytes) => {
                 self.handle_new_message(pub_id, bytes, outbox)
             }
             _ => {
-                debug!("{:?} Unhandled crust event {:?}", self, crust_event);
+                debug!("{:?} Unhandled transport event {:?}", self, event);
                 Transition::Stay
             }
         }
     }
 
+    pub fn into_bootstrapping(
+        self,
+        crust_rx: &mut Receiver<CrustEvent<PublicId>>,
+        crust_sender: CrustEventSender,
+        outbox: &mut EventBox,
+    ) -> State {
+        let target_state = BootstrappingTargetState::Client {
+            msg_expiry_dur: self.msg_expiry_dur,
+        };
+        let capture = self.capture;
+        let stats = self.stats;
+        let service = if self.proxy_contacts.is_empty() {
+            self.crust_service
+        } else {
+            Self::start_new_crust_service(
+                self.crust_service,
+                *self.full_id.public_id(),
+                crust_rx,
+                crust_sender,
+                &self.proxy_contacts,
+                self.dev_config.lan_discovery_enabled(),
+            )
+        };
+        if let Some(bootstrapping) = Bootstrapping::new(
+            self.action_sender,
+            Box::new(NullCache),
+            None,
+            target_state,
+            service,
+            self.full_id,
+            self.min_section_size,
+            self.dev_config,
+            self.timer,
+        ) {
+            let mut bootstrapping = bootstrapping.with_capture(capture).with_stats(stats);
+            for action in self.held_actions {
+                let _ = bootstrapping.handle_action(action);
+            }
+            State::Bootstrapping(bootstrapping)
+        } else {
+            outbox.send_event(Event::Terminate);
+            State::Terminated
+        }
+    }
+
+    // Replaces the Crust service by one which bootstraps off `contacts`.
+    #[cfg(not(feature = "use-mock-crust"))]
+    fn start_new_crust_service(
+        old_crust_service: Service,
+        pub_id: PublicId,
+        crust_rx: &mut Receiver<CrustEvent<PublicId>>,
+        crust_sender: CrustEventSender,
+        contacts: &[SocketAddr],
+        lan_discovery: bool,
+    ) -> Service {
+        // Drop the current Crust service and flush the receiver
+        drop(old_crust_service);
+        while let Ok(_crust_event) = crust_rx.try_recv() {}
+
+        let config = BootstrapConfig {
+            hard_coded_contacts: contacts.to_vec(),
+            ..BootstrapConfig::default()
+        };
+        let mut crust_service = match Service::with_config(crust_sender, config, pub_id) {
+            Ok(service) => service,
+            Err(error) => panic!("Unable to start crust::Service {:?}", error),
+        };
+        if lan_discovery {
+            crust_service.start_service_discovery();
+        }
+        crust_service
+    }
+
+    #[cfg(feature = "use-mock-crust")]
+    fn start_new_crust_service(
+        old_crust_service: Service,
+        pub_id: PublicId,
+        _crust_rx: &mut Receiver<CrustEvent<PublicId>>,
+        crust_sender: CrustEventSender,
+        contacts: &[SocketAddr],
+        _lan_discovery: bool,
+    ) -> Service {
+        let contacts: Vec<_> = contacts.iter().map(|addr| to_endpoint(*addr)).collect();
+        let config = BootstrapConfig::with_contacts(&contacts);
+        old_crust_service.restart_with_config(crust_sender, config, pub_id);
+        old_crust_service
+    }
+
+    // Reports the requests still waiting for an ack as failed, and bootstraps again.
+    fn handle_lost_proxy(&mut self, outbox: &mut EventBox) -> Transition {
+        let resend_buf = mem::replace(&mut self.resend_buf, BTreeMap::new());
+        let failed: BTreeMap<_, _> = self
+            .ack_mgr
+            .take_pending()
+            .into_iter()
+            .chain(resend_buf.into_iter().map(|(_, unacked_msg)| unacked_msg))
+            .filter_map(|unacked_msg| match unacked_msg.routing_msg.content {
+                MessageContent::UserMessagePart { msg_id, .. } => {
+                    Some((msg_id, unacked_msg.routing_msg.dst))
+                }
+                _ => None,
+            })
+            .collect();
+        for (message_id, dst) in failed {
+            if let Some(ref mut journal) = self.journal {
+                journal.complete(&message_id);
+            }
+            debug!("{:?} Request {:?} to {:?} failed.", self, message_id, dst);
+            outbox.send_event(Event::RequestFailed { message_id, dst });
+        }
+        debug!("{:?} Bootstrapping again.", self);
+        Transition::Rebootstrap
+    }
+
     fn handle_ack_response(&mut self, ack: Ack) -> Transition {
         self.ack_mgr.receive(ack);
         Transition::Stay
     }
 
-    fn handle_timeout(&mut self, token: u64) {
+    fn handle_timeout(&mut self, token: u64, outbox: &mut EventBox) {
         let proxy_pub_id = self.proxy_pub_id;
 
+        if self.proxy_cert_timer_token == Some(token) {
+            self.proxy_cert_timer_token = None;
+            warn!(
+                "{:?} Proxy {} didn't present a certificate from its section.",
+                self, proxy_pub_id
+            );
+            outbox.send_event(Event::ProxyUnrecognised(*proxy_pub_id.name()));
+            return;
+        }
+
         // Check if token corresponds to a rate limit exceeded msg.
         if let Some(unacked_msg) = self.resend_buf.remove(&token) {
             if unacked_msg.expires_at.map_or(false, |i| i < Instant::now()) {
+                self.report_expired_msg(&unacked_msg.routing_msg, outbox);
                 return;
             }
 
@@ -175,7 +463,59 @@ impl Client {
         }
 
         // Check if token corresponds to an unacknowledged msg.
-        self.resend_unacknowledged_timed_out_msgs(token)
+        self.resend_or_expire(token, outbox)
+    }
+
+    fn resend_or_expire(&mut self, token: u64, outbox: &mut EventBox) {
+        if let Some((unacked_msg, ack)) = self.ack_mgr.find_timed_out(token) {
+            trace!(
+                "{:?} Timed out waiting for {:?}: {:?}",
+                self,
+                ack,
+                unacked_msg
+            );
+
+            let msg_expired = unacked_msg.expires_at.map_or(false, |i| i < Instant::now());
+            if msg_expired || unacked_msg.route as usize == self.min_section_size {
+                debug!(
+                    "{:?} Message unable to be acknowledged - giving up. {:?}",
+                    self, unacked_msg
+                );
+                self.stats.count_unacked();
+                if msg_expired {
+                    self.report_expired_msg(&unacked_msg.routing_msg, outbox);
+                }
+            } else if let Err(error) = self.send_routing_message_via_route(
+                unacked_msg.routing_msg,
+                unacked_msg.route,
+                unacked_msg.expires_at,
+            ) {
+                debug!("{:?} Failed to send message: {:?}", self, error);
+            }
+            // Resend a msg part on ack time out doesn't count in stats.
+        }
+    }
+
+    fn report_expired_msg(&mut self, routing_msg: &RoutingMessage, outbox: &mut EventBox) {
+        if let MessageContent::UserMessagePart { msg_id, .. } = routing_msg.content {
+            self.report_expired(msg_id, routing_msg.dst, outbox);
+        }
+    }
+
+    // Raises `Event::RequestExpired`, only once per request even if several of its parts expire.
+    fn report_expired(
+        &mut self,
+        message_id: MessageId,
+        dst: Authority<XorName>,
+        outbox: &mut EventBox,
+    ) {
+        if let Some(ref mut journal) = self.journal {
+            journal.complete(&message_id);
+        }
+        if self.expired_requests.insert(message_id, ()).is_none() {
+            debug!("{:?} Request {:?} to {:?} expired.", self, message_id, dst);
+            outbox.send_event(Event::RequestExpired { message_id, dst });
+        }
     }
 
     fn handle_new_message(
@@ -184,9 +524,15 @@ impl Client {
         bytes: Vec<u8>,
         outbox: &mut EventBox,
     ) -> Transition {
+        let mut message_id = None;
         let transition = match serialisation::deserialise(&bytes) {
-            Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, pub_id, outbox),
-            Ok(Message::Direct(direct_msg)) => self.handle_direct_message(direct_msg),
+            Ok(Message::Hop(hop_msg)) => {
+                message_id = hop_msg.content.routing_message().content.message_id();
+                self.handle_hop_message(hop_msg, pub_id, outbox)
+            }
+            // None of our proxy's direct messages depend on the ones it sent before, so we don't
+            // need to restore their order.
+            Ok(Message::Direct { content, .. }) => self.handle_direct_message(content, outbox),
             Ok(message) => {
                 debug!("{:?} Unhandled new message: {:?}", self, message);
                 Ok(Transition::Stay)
@@ -196,9 +542,18 @@ impl Client {
 
         match transition {
             Ok(transition) => transition,
-            Err(RoutingError::FilterCheckFailed) => Transition::Stay,
             Err(error) => {
-                debug!("{:?} {:?}", self, error);
+                match error {
+                    RoutingError::FilterCheckFailed => (),
+                    ref error => debug!("{:?} {:?}", self, error),
+                }
+                if self.report_dropped_messages {
+                    outbox.send_event(Event::MessageDropped {
+                        message_id,
+                        reason: format!("{:?}", error),
+                        stage: DropStage::Receiving,
+                    });
+                }
                 Transition::Stay
             }
         }
@@ -217,8 +572,9 @@ impl Client {
         }
 
         let signed_msg = hop_msg.content;
-        signed_msg.check_integrity(self.min_section_size())?;
+        signed_msg.check_integrity(self.min_section_size(), Quorum::default())?;
 
+        let signers = signed_msg.signers().cloned().collect();
         let routing_msg = signed_msg.into_routing_message();
         let in_authority = self.in_authority(&routing_msg.dst);
 
@@ -237,14 +593,29 @@ impl Client {
             return Ok(Transition::Stay);
         }
 
-        Ok(self.dispatch_routing_message(routing_msg, outbox))
+        Ok(self.dispatch_routing_message(routing_msg, signers, outbox))
     }
 
     fn handle_direct_message(
         &mut self,
         direct_msg: DirectMessage,
+        outbox: &mut EventBox,
     ) -> Result<Transition, RoutingError> {
-        if let DirectMessage::ProxyRateLimitExceeded { ack } = direct_msg {
+        if let DirectMessage::ProxyCertificate(cert) = direct_msg {
+            self.handle_proxy_certificate(&cert, outbox);
+        } else if let DirectMessage::ProxyClosing(endpoints) = direct_msg {
+            debug!(
+                "{:?} Proxy {} is shutting down. Alternative contacts: {:?}",
+                self, self.proxy_pub_id, endpoints
+            );
+            outbox.send_event(Event::ProxyClosing(endpoints.clone()));
+            if self.rebootstrap {
+                self.proxy_contacts = endpoints;
+                return Ok(self.handle_lost_proxy(outbox));
+            }
+            outbox.send_event(Event::Terminate);
+            return Ok(Transition::Terminate);
+        } else if let DirectMessage::ProxyRateLimitExceeded { ack } = direct_msg {
             if let Some(unack_msg) = self.ack_mgr.remove(&ack) {
                 let token = self
                     .timer()
@@ -262,9 +633,34 @@ impl Client {
         Ok(Transition::Stay)
     }
 
+    /// Checks that a quorum of our proxy's section signed its public ID.
+    fn handle_proxy_certificate(&mut self, cert: &ProxyCertificate, outbox: &mut EventBox) {
+        if self.proxy_cert_timer_token.take().is_none() {
+            debug!("{:?} Ignoring duplicate proxy certificate.", self);
+            return;
+        }
+        if cert.is_valid_for(&self.proxy_pub_id, self.min_section_size) {
+            debug!(
+                "{:?} Proxy {} is vouched for by {} members of its section.",
+                self,
+                self.proxy_pub_id,
+                cert.len()
+            );
+        } else {
+            warn!(
+                "{:?} Proxy {} presented an insufficient certificate ({} signatures).",
+                self,
+                self.proxy_pub_id,
+                cert.len()
+            );
+            outbox.send_event(Event::ProxyUnrecognised(*self.proxy_pub_id.name()));
+        }
+    }
+
     fn dispatch_routing_message(
         &mut self,
         routing_msg: RoutingMessage,
+        signers: BTreeSet<PublicId>,
         outbox: &mut EventBox,
     ) -> Transition {
         match routing_msg.content {
@@ -288,15 +684,37 @@ impl Client {
                     routing_msg.dst
                 );
                 self.stats.increase_user_msg_part();
-                if let Some(msg) = self
+                if let Some((msg, signers)) = self
                     .user_msg_cache
-                    .add(hash, part_count, part_index, payload)
+                    .add_signed(hash, part_count, part_index, payload, signers)
                 {
                     self.stats().count_user_message(&msg);
-                    outbox.send_event(msg.into_event(routing_msg.src, routing_msg.dst));
+                    if let (&UserMessage::Response(ref response), Some(journal)) =
+                        (&msg, self.journal.as_mut())
+                    {
+                        journal.complete(response.message_id());
+                    }
+                    outbox.send_event(msg.into_event(routing_msg.src, routing_msg.dst, signers));
                 }
                 Transition::Stay
             }
+            MessageContent::TraceReport {
+                ack,
+                decision,
+                timestamp,
+            } => {
+                info!(
+                    target: "routing_trace",
+                    "{:?} Trace of {:?} to {:?}: {} {:?} it at {}.",
+                    self,
+                    ack,
+                    routing_msg.dst,
+                    routing_msg.src.name(),
+                    decision,
+                    timestamp
+                );
+                Transition::Stay
+            }
             content => {
                 debug!(
                     "{:?} Unhandled routing message: {:?} from {:?} to {:?}",
@@ -314,17 +732,19 @@ impl Client {
         dst: Authority<XorName>,
         user_msg: UserMessage,
         priority: u8,
+        expires_at: Option<Instant>,
     ) -> Result<(), RoutingError> {
         self.stats.count_user_message(&user_msg);
         let parts = user_msg.to_parts(priority)?;
-        let msg_expiry_dur = self.msg_expiry_dur;
-        for part in parts {
-            self.send_routing_message_with_expiry(
+        let expires_at = expires_at.unwrap_or_else(|| Instant::now() + self.msg_expiry_dur);
+        for content in parts {
+            let routing_msg = RoutingMessage {
                 src,
                 dst,
-                part,
-                Some(Instant::now() + msg_expiry_dur),
-            )?;
+                content,
+                trace: self.trace_user_messages,
+            };
+            self.send_routing_message_via_route(routing_msg, 0, Some(expires_at))?;
             self.stats.increase_user_msg_part();
         }
         Ok(())
@@ -349,11 +769,18 @@ impl Base for Client {
         }
     }
 
+    fn proxy_name(&self) -> Option<XorName> {
+        Some(*self.proxy_pub_id.name())
+    }
+
     fn handle_lost_peer(&mut self, pub_id: PublicId, outbox: &mut EventBox) -> Transition {
         debug!("{:?} Received LostPeer - {:?}", self, pub_id);
 
         if self.proxy_pub_id == pub_id {
             debug!("{:?} Lost bootstrap connection to {}.", self, pub_id);
+            if self.rebootstrap {
+                return self.handle_lost_proxy(outbox);
+            }
             outbox.send_event(Event::Terminate);
             Transition::Terminate
         } else {
@@ -365,6 +792,10 @@ impl Base for Client {
         &mut self.stats
     }
 
+    fn capture(&mut self) -> &mut Capture {
+        &mut self.capture
+    }
+
     fn min_section_size(&self) -> usize {
         self.min_section_size
     }
@@ -379,33 +810,6 @@ impl Bootstrapped for Client {
         &mut self.ack_mgr
     }
 
-    fn resend_unacknowledged_timed_out_msgs(&mut self, token: u64) {
-        if let Some((unacked_msg, ack)) = self.ack_mgr.find_timed_out(token) {
-            trace!(
-                "{:?} Timed out waiting for {:?}: {:?}",
-                self,
-                ack,
-                unacked_msg
-            );
-
-            let msg_expired = unacked_msg.expires_at.map_or(false, |i| i < Instant::now());
-            if msg_expired || unacked_msg.route as usize == self.min_section_size {
-                debug!(
-                    "{:?} Message unable to be acknowledged - giving up. {:?}",
-                    self, unacked_msg
-                );
-                self.stats.count_unacked();
-            } else if let Err(error) = self.send_routing_message_via_route(
-                unacked_msg.routing_msg,
-                unacked_msg.route,
-                unacked_msg.expires_at,
-            ) {
-                debug!("{:?} Failed to send message: {:?}", self, error);
-            }
-            // Resend a msg part on ack time out doesn't count in stats.
-        }
-    }
-
     fn send_routing_message_via_route(
         &mut self,
         routing_msg: RoutingMessage,
@@ -448,6 +852,8 @@ impl Bootstrapped for Client {
             && !self.filter_outgoing_routing_msg(signed_msg.routing_message(), &proxy_pub_id, route)
         {
             let bytes = self.to_hop_bytes(signed_msg.clone(), route, BTreeSet::new())?;
+            #[cfg(feature = "use-mock-crust")]
+            let bytes = self.mark_relayed(bytes)?;
             self.send_or_drop(&proxy_pub_id, bytes, signed_msg.priority());
         }
 
@@ -472,6 +878,24 @@ impl Client {
     pub fn get_user_msg_parts_count(&self) -> u64 {
         self.stats.msg_user_parts
     }
+
+    pub fn set_claim_relayed(&mut self, claim_relayed: bool) {
+        self.claim_relayed = claim_relayed;
+    }
+
+    // Sets the `relayed` flag of the serialised hop message, if `claim_relayed` is set.
+    fn mark_relayed(&self, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        if !self.claim_relayed {
+            return Ok(bytes);
+        }
+        match serialisation::deserialise(&bytes)? {
+            Message::Hop(mut hop_msg) => {
+                hop_msg.relayed = true;
+                Ok(serialisation::serialise(&Message::Hop(hop_msg))?)
+            }
+            _ => Ok(bytes),
+        }
+    }
 }
 
 impl Debug for Client {
@@ -6,10 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::capture::{Capture, Direction};
+use crate::error::RoutingError;
 use crate::id::{FullId, PublicId};
 use crate::messages::Message;
 use crate::outbox::EventBox;
-use crate::routing_table::Authority;
+use crate::routing_table::{Authority, AuthorityExplanation};
 use crate::state_machine::Transition;
 use crate::stats::Stats;
 use crate::xor_name::XorName;
@@ -22,6 +24,7 @@ pub trait Base: Debug {
     fn crust_service(&self) -> &Service;
     fn full_id(&self) -> &FullId;
     fn stats(&mut self) -> &mut Stats;
+    fn capture(&mut self) -> &mut Capture;
     fn in_authority(&self, auth: &Authority<XorName>) -> bool;
     fn min_section_size(&self) -> usize;
 
@@ -41,6 +44,26 @@ pub trait Base: Debug {
         None
     }
 
+    // Returns the name of the proxy node relaying our messages, if we have one.
+    fn proxy_name(&self) -> Option<XorName> {
+        None
+    }
+
+    // Returns `in_authority`, together with the reason for it.
+    fn explain_authority(&self, auth: &Authority<XorName>) -> AuthorityExplanation {
+        AuthorityExplanation {
+            in_authority: self.in_authority(auth),
+            reason: "we have no routing table, so we can only be our own client authority"
+                .to_string(),
+        }
+    }
+
+    // Returns the serialised message `bytes` as it should be sent to `pub_id`: a serialised
+    // `Message::Sealed` if we agreed on session keys for the link, otherwise unchanged.
+    fn seal_message(&self, _pub_id: &PublicId, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        Ok(bytes)
+    }
+
     fn send_message(&mut self, pub_id: &PublicId, message: Message) {
         let priority = message.priority();
 
@@ -63,7 +86,18 @@ pub trait Base: Debug {
     // Sends the given `bytes` to the peer with the given Crust `PublicId`. If that results in an
     // error, it disconnects from the peer.
     fn send_or_drop(&mut self, pub_id: &PublicId, bytes: Vec<u8>, priority: u8) {
+        let bytes = match self.seal_message(pub_id, bytes) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                error!(
+                    "{:?} Failed to seal message to {}: {:?}",
+                    self, pub_id, error
+                );
+                return;
+            }
+        };
         self.stats().count_bytes(bytes.len());
+        self.capture().record(Direction::Sent, pub_id, &bytes);
 
         if let Err(err) = self.crust_service().send(pub_id, bytes, priority) {
             info!("{:?} Connection to {} failed: {:?}", self, pub_id, err);
@@ -129,7 +129,12 @@ pub trait Bootstrapped: Base {
         content: MessageContent,
         expires_at: Option<Instant>,
     ) -> Result<(), RoutingError> {
-        let routing_msg = RoutingMessage { src, dst, content };
+        let routing_msg = RoutingMessage {
+            src,
+            dst,
+            content,
+            trace: false,
+        };
         self.send_routing_message_via_route(routing_msg, 0, expires_at)
     }
 
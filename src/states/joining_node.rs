@@ -11,17 +11,23 @@ use super::{Bootstrapping, BootstrappingTargetState};
 use crate::ack_manager::{Ack, AckManager};
 use crate::action::Action;
 use crate::cache::Cache;
+use crate::capture::Capture;
+use crate::config_handler::DevConfig;
 use crate::error::{InterfaceError, RoutingError};
 use crate::event::Event;
 use crate::id::{FullId, PublicId};
-use crate::messages::{HopMessage, Message, MessageContent, RoutingMessage, SignedMessage};
+use crate::messages::{
+    HopMessage, Message, MessageContent, Quorum, RelocationDenial, RoutingMessage, SignedMessage,
+};
 use crate::outbox::EventBox;
+use crate::request_validator::RequestValidator;
 use crate::resource_prover::RESOURCE_PROOF_DURATION_SECS;
 use crate::routing_message_filter::{FilteringResult, RoutingMessageFilter};
 use crate::routing_table::{Authority, Prefix};
 use crate::state_machine::{State, Transition};
 use crate::stats::Stats;
 use crate::timer::Timer;
+use crate::transport::TransportEvent;
 use crate::types::{MessageId, RoutingActionSender};
 use crate::xor_name::XorName;
 use crate::{CrustEvent, CrustEventSender, Service};
@@ -38,21 +44,33 @@ use std::time::Instant;
 
 /// Total time (in seconds) to wait for `RelocateResponse`.
 const RELOCATE_TIMEOUT_SECS: u64 = 60 + RESOURCE_PROOF_DURATION_SECS;
+/// Number of times we retry a deferred relocation before restarting.
+const MAX_RELOCATE_DEFERRALS: usize = 5;
 
 pub struct JoiningNode {
     action_sender: RoutingActionSender,
     ack_mgr: AckManager,
     crust_service: Service,
     full_id: FullId,
+    /// The port our listener was started on while bootstrapping.
+    listener_port: Option<u16>,
     /// Only held here to be passed eventually to the `Node` state.
     cache: Box<Cache>,
+    /// Only held here to be passed eventually to the `Node` state.
+    request_validator: Option<Box<RequestValidator>>,
     min_section_size: usize,
+    /// Only held here to be passed eventually to the `Node` state.
+    dev_config: DevConfig,
     proxy_pub_id: PublicId,
     /// The queue of routing messages addressed to us. These do not themselves need forwarding,
     /// although they may wrap a message which needs forwarding.
     routing_msg_filter: RoutingMessageFilter,
     stats: Stats,
+    capture: Capture,
     relocation_timer_token: u64,
+    /// The timer for sending a new `Relocate` request after ours was deferred.
+    relocation_retry_token: Option<u64>,
+    relocation_deferrals: usize,
     timer: Timer,
 }
 
@@ -61,11 +79,15 @@ impl JoiningNode {
     pub fn from_bootstrapping(
         action_sender: RoutingActionSender,
         cache: Box<Cache>,
+        request_validator: Option<Box<RequestValidator>>,
         crust_service: Service,
         full_id: FullId,
+        listener_port: Option<u16>,
         min_section_size: usize,
+        dev_config: DevConfig,
         proxy_pub_id: PublicId,
         stats: Stats,
+        capture: Capture,
         timer: Timer,
     ) -> Option<Self> {
         let duration = Duration::from_secs(RELOCATE_TIMEOUT_SECS);
@@ -75,12 +97,18 @@ impl JoiningNode {
             ack_mgr: AckManager::new(),
             crust_service,
             full_id,
+            listener_port,
             cache,
+            request_validator,
             min_section_size,
+            dev_config,
             proxy_pub_id,
             routing_msg_filter: RoutingMessageFilter::new(),
             stats,
+            capture,
             relocation_timer_token,
+            relocation_retry_token: None,
+            relocation_deferrals: 0,
             timer,
         };
         if let Err(error) = joining_node.relocate() {
@@ -95,21 +123,50 @@ impl JoiningNode {
     pub fn handle_action(&mut self, action: Action, outbox: &mut EventBox) -> Transition {
         match action {
             Action::ClientSendRequest { ref result_tx, .. }
-            | Action::NodeSendMessage { ref result_tx, .. } => {
+            | Action::NodeSendMessage { ref result_tx, .. }
+            | Action::SendToGroup { ref result_tx, .. }
+            | Action::SendToNode { ref result_tx, .. }
+            | Action::SendRefresh { ref result_tx, .. } => {
                 warn!("{:?} Cannot handle {:?} - not joined.", self, action);
                 let _ = result_tx.send(Err(InterfaceError::InvalidState));
             }
             Action::Id { result_tx } => {
                 let _ = result_tx.send(*self.id());
             }
+            Action::ProxyName { result_tx } => {
+                let _ = result_tx.send(self.proxy_name());
+            }
+            Action::ListenerPort { result_tx } => {
+                let _ = result_tx.send(self.listener_port);
+            }
+            Action::GetPeerInfo { result_tx } => {
+                let _ = result_tx.send(Vec::new());
+            }
+            Action::ExplainAuthority { auth, result_tx } => {
+                let _ = result_tx.send(self.explain_authority(&auth));
+            }
+            Action::IsNaeManagerFor { result_tx, .. } => {
+                let _ = result_tx.send(Err(InterfaceError::InvalidState));
+            }
             Action::Timeout(token) => {
                 if let Transition::Terminate = self.handle_timeout(token, outbox) {
                     return Transition::Terminate;
                 }
             }
-            Action::ResourceProofResult(..) => {
+            Action::ResourceProofResult(..)
+            | Action::HopMessageVerified { .. }
+            | Action::AddContacts(..)
+            | Action::SetIpRanges { .. }
+            | Action::SetRefreshPolicy { .. } => {
                 warn!("{:?} Cannot handle {:?} - not joined.", self, action);
             }
+            // We haven't relayed anything yet.
+            Action::ResetRelayAccounting => (),
+            // Answered by `State::handle_action` for all states.
+            Action::GetInternalState
+            | Action::GetNetworkSizeEstimate { .. }
+            | Action::StartCapture { .. }
+            | Action::StopCapture => (),
             Action::Terminate => {
                 return Transition::Terminate;
             }
@@ -117,16 +174,18 @@ impl JoiningNode {
         Transition::Stay
     }
 
-    pub fn handle_crust_event(
+    pub fn handle_transport_event(
         &mut self,
-        crust_event: CrustEvent<PublicId>,
+        event: TransportEvent,
         outbox: &mut EventBox,
     ) -> Transition {
-        match crust_event {
-            CrustEvent::LostPeer(pub_id) => self.handle_lost_peer(pub_id, outbox),
-            CrustEvent::NewMessage(pub_id, _, bytes) => self.handle_new_message(pub_id, bytes),
+        match event {
+            TransportEvent::LostPeer(pub_id) => self.handle_lost_peer(pub_id, outbox),
+            TransportEvent::NewMessage(pub_id, _, bytes) => {
+                self.handle_new_message(pub_id, bytes, outbox)
+            }
             _ => {
-                debug!("{:?} - Unhandled crust event: {:?}", self, crust_event);
+                debug!("{:?} - Unhandled transport event: {:?}", self, event);
                 Transition::Stay
             }
         }
@@ -150,16 +209,19 @@ impl JoiningNode {
             old_full_id: self.full_id,
             our_section,
         };
+        let capture = self.capture;
         if let Some(bootstrapping) = Bootstrapping::new(
             self.action_sender,
             self.cache,
+            self.request_validator,
             target_state,
             service,
             new_full_id,
             self.min_section_size,
+            self.dev_config,
             self.timer,
         ) {
-            State::Bootstrapping(bootstrapping)
+            State::Bootstrapping(bootstrapping.with_capture(capture))
         } else {
             outbox.send_event(Event::RestartRequired);
             State::Terminated
@@ -196,9 +258,14 @@ impl JoiningNode {
         old_crust_service
     }
 
-    fn handle_new_message(&mut self, pub_id: PublicId, bytes: Vec<u8>) -> Transition {
+    fn handle_new_message(
+        &mut self,
+        pub_id: PublicId,
+        bytes: Vec<u8>,
+        outbox: &mut EventBox,
+    ) -> Transition {
         let transition = match serialisation::deserialise(&bytes) {
-            Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, pub_id),
+            Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, pub_id, outbox),
             Ok(message) => {
                 debug!("{:?} - Unhandled new message: {:?}", self, message);
                 Ok(Transition::Stay)
@@ -220,6 +287,7 @@ impl JoiningNode {
         &mut self,
         hop_msg: HopMessage,
         pub_id: PublicId,
+        outbox: &mut EventBox,
     ) -> Result<Transition, RoutingError> {
         if self.proxy_pub_id == pub_id {
             hop_msg.verify(self.proxy_pub_id.signing_public_key())?;
@@ -228,7 +296,7 @@ impl JoiningNode {
         }
 
         let signed_msg = hop_msg.content;
-        signed_msg.check_integrity(self.min_section_size())?;
+        signed_msg.check_integrity(self.min_section_size(), Quorum::default())?;
 
         let routing_msg = signed_msg.routing_message();
         let in_authority = self.in_authority(&routing_msg.dst);
@@ -251,10 +319,14 @@ impl JoiningNode {
             return Ok(Transition::Stay);
         }
 
-        Ok(self.dispatch_routing_message(routing_msg.clone()))
+        Ok(self.dispatch_routing_message(routing_msg.clone(), outbox))
     }
 
-    fn dispatch_routing_message(&mut self, routing_msg: RoutingMessage) -> Transition {
+    fn dispatch_routing_message(
+        &mut self,
+        routing_msg: RoutingMessage,
+        outbox: &mut EventBox,
+    ) -> Transition {
         use crate::messages::MessageContent::*;
         match routing_msg.content {
             Relocate { .. }
@@ -262,19 +334,31 @@ impl JoiningNode {
             | ConnectionInfoRequest { .. }
             | ConnectionInfoResponse { .. }
             | SectionUpdate { .. }
+            | SectionUpdateRequest(..)
+            | GetCloseGroup { .. }
+            | GetCloseGroupResponse { .. }
+            | ReportUnreachable(..)
             | SectionSplit(..)
             | OwnSectionMerge(..)
             | OtherSectionMerge(..)
             | UserMessagePart { .. }
             | AcceptAsCandidate { .. }
+            | ShareCandidate { .. }
             | CandidateApproval { .. }
-            | NodeApproval { .. } => {
+            | NodeApproval { .. }
+            | TraceReport { .. }
+            | AccumulationFailed { .. }
+            | CachePrimingRequest { .. } => {
                 warn!(
                     "{:?} Not joined yet. Not handling {:?} from {:?} to {:?}",
                     self, routing_msg.content, routing_msg.src, routing_msg.dst
                 );
             }
             Ack(ack, _) => self.handle_ack_response(ack),
+            RelocateDeferred {
+                retry_after_secs, ..
+            } => self.handle_relocate_deferred(retry_after_secs, outbox),
+            RelocateDenied { reason, .. } => return self.handle_relocate_denied(reason, outbox),
             RelocateResponse {
                 target_interval,
                 section,
@@ -304,12 +388,58 @@ impl JoiningNode {
         self.send_routing_message(src, dst, request_content)
     }
 
+    fn handle_relocate_deferred(&mut self, retry_after_secs: u64, outbox: &mut EventBox) {
+        if self.relocation_retry_token.is_some() {
+            return; // Already waiting to retry.
+        }
+        self.relocation_deferrals += 1;
+        if self.relocation_deferrals > MAX_RELOCATE_DEFERRALS {
+            // Let `relocation_timer_token` expire, which will cause us to restart.
+            info!("{:?} Relocation deferred too many times.", self);
+            if self.relocation_deferrals == MAX_RELOCATE_DEFERRALS + 1 {
+                outbox.send_event(Event::JoinDenied(RelocationDenial::Throttled));
+            }
+            return;
+        }
+        info!(
+            "{:?} Relocation deferred by the network. Retrying in {} seconds.",
+            self, retry_after_secs
+        );
+        let retry_after = Duration::from_secs(retry_after_secs);
+        self.relocation_retry_token = Some(self.timer.schedule(retry_after));
+        self.relocation_timer_token = self
+            .timer
+            .schedule(retry_after + Duration::from_secs(RELOCATE_TIMEOUT_SECS));
+    }
+
+    fn handle_relocate_denied(
+        &mut self,
+        reason: RelocationDenial,
+        outbox: &mut EventBox,
+    ) -> Transition {
+        info!(
+            "{:?} Relocation denied by the network: {:?}. Restarting.",
+            self, reason
+        );
+        outbox.send_event(Event::JoinDenied(reason));
+        outbox.send_event(Event::RestartRequired);
+        Transition::Terminate
+    }
+
     fn handle_relocate_response(
         &mut self,
         target_interval: (XorName, XorName),
         section: (Prefix<XorName>, BTreeSet<PublicId>),
     ) -> Transition {
-        let new_id = FullId::within_range(&target_interval.0, &target_interval.1);
+        // Don't pick the name of a node that is already in the section we are moving to.
+        let mut new_id = FullId::within_range(&target_interval.0, &target_interval.1);
+        while section
+            .1
+            .iter()
+            .any(|pub_id| pub_id.name() == new_id.public_id().name())
+        {
+            new_id = FullId::within_range(&target_interval.0, &target_interval.1);
+        }
         Transition::IntoBootstrapping {
             new_id,
             our_section: section,
@@ -329,6 +459,13 @@ impl JoiningNode {
             outbox.send_event(Event::RestartRequired);
             return Transition::Terminate;
         }
+        if self.relocation_retry_token == Some(token) {
+            self.relocation_retry_token = None;
+            if let Err(error) = self.relocate() {
+                error!("{:?} Failed to retry relocation: {:?}", self, error);
+            }
+            return Transition::Stay;
+        }
         self.resend_unacknowledged_timed_out_msgs(token);
         Transition::Stay
     }
@@ -356,6 +493,10 @@ impl Base for JoiningNode {
         }
     }
 
+    fn proxy_name(&self) -> Option<XorName> {
+        Some(*self.proxy_pub_id.name())
+    }
+
     fn handle_lost_peer(&mut self, pub_id: PublicId, outbox: &mut EventBox) -> Transition {
         debug!("{:?} Received LostPeer - {}", self, pub_id);
 
@@ -372,6 +513,10 @@ impl Base for JoiningNode {
         &mut self.stats
     }
 
+    fn capture(&mut self) -> &mut Capture {
+        &mut self.capture
+    }
+
     fn min_section_size(&self) -> usize {
         self.min_section_size
     }
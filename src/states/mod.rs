@@ -16,4 +16,4 @@ pub use self::bootstrapping::Bootstrapping;
 pub use self::bootstrapping::TargetState as BootstrappingTargetState;
 pub use self::client::{Client, RATE_EXCEED_RETRY_MS};
 pub use self::joining_node::JoiningNode;
-pub use self::node::Node;
+pub use self::node::{Node, CACHE_RESPONSE_MAX_DELAY_MS, MAX_REJECTED_REQUESTS, TICK_TIMEOUT_SECS};
@@ -9,63 +9,81 @@
 use super::common::{Base, Bootstrapped, USER_MSG_CACHE_EXPIRY_DURATION_SECS};
 use crate::ack_manager::{Ack, AckManager};
 use crate::action::Action;
-use crate::cache::Cache;
-use crate::config_handler;
-use crate::crust::{ConnectionInfoResult, CrustError, CrustUser};
+use crate::cache::{self, Cache, CacheStats};
+use crate::capture::Capture;
+use crate::client_buffer::ClientBuffer;
+use crate::close_group_snapshot;
+use crate::config_handler::DevConfig;
+use crate::crust::{CrustError, CrustUser};
 use crate::cumulative_own_section_merge::CumulativeOwnSectionMerge;
-use crate::error::{BootstrapResponseError, InterfaceError, RoutingError};
-use crate::event::Event;
+use crate::direct_sequencer::DirectSequencer;
+use crate::endpoint_acl::EndpointAcl;
+use crate::error::{BootstrapResponseError, ErrorContext, InterfaceError, RoutingError};
+use crate::event::{DropStage, Event};
+use crate::filter_snapshot;
 use crate::id::{FullId, PublicId};
+use crate::link_keys::LinkKeys;
 use crate::messages::{
-    DirectMessage, HopMessage, Message, MessageContent, RoutingMessage, SectionList, SignedMessage,
-    UserMessage, UserMessageCache, DEFAULT_PRIORITY, MAX_PARTS, MAX_PART_LEN,
+    DirectMessage, HopMessage, Message, MessageContent, NodeLoad, RelocationDenial, Request,
+    Response, RoutingMessage, SectionList, SignedMessage, TraceDecision, UserMessage,
+    UserMessageCache, DEFAULT_PRIORITY, MAX_MESSAGE_SIZE, MAX_PARTS, MAX_PART_LEN,
+    RELOCATE_PRIORITY,
 };
 use crate::outbox::{EventBox, EventBuf};
 use crate::peer_manager::{
-    ConnectionInfoPreparedResult, Peer, PeerManager, PeerState, ReconnectingPeer,
+    ConnectionInfoPreparedResult, Peer, PeerInfo, PeerManager, PeerState, ReconnectingPeer,
     RoutingConnection, SectionMap,
 };
+use crate::periodic_tasks::PeriodicTasks;
+use crate::proxy_certificate::ProxyCertificate;
 use crate::rate_limiter::RateLimiter;
+use crate::relay_accounting::RelayAccounting;
+use crate::request_validator::RequestValidator;
 use crate::resource_prover::{ResourceProver, RESOURCE_PROOF_DURATION_SECS};
-use crate::routing_message_filter::{FilteringResult, RoutingMessageFilter};
+use crate::rng;
+use crate::routing_message_filter::{
+    FilteringResult, RoutingMessageFilter, INCOMING_EXPIRY_DURATION_SECS,
+};
 use crate::routing_table::Error as RoutingTableError;
 use crate::routing_table::{
-    Authority, OwnMergeState, Prefix, RemovalDetails, RoutingTable, VersionedPrefix, Xorable,
+    Authority, AuthorityExplanation, OwnMergeState, Prefix, RemovalDetails, RoutingTable,
+    VersionedPrefix, Xorable,
 };
 use crate::rust_sodium::crypto::{box_, sign};
 use crate::section_list_cache::SectionListCache;
+use crate::send_queue::{
+    SendQueue, TrafficClass, DEFAULT_SEND_BUDGET, DEFAULT_SEND_WEIGHTS, SEND_INTERVAL_MS,
+};
 use crate::sha3::Digest256;
-use crate::signature_accumulator::SignatureAccumulator;
+use crate::signature_accumulator::{SignatureAccumulator, ACCUMULATION_TIMEOUT_SECS};
 use crate::state_machine::Transition;
 use crate::stats::Stats;
 use crate::timer::Timer;
+use crate::transport::TransportEvent;
 use crate::tunnels::Tunnels;
 use crate::types::{MessageId, RoutingActionSender};
 use crate::utils::{self, DisplayDuration};
-use crate::xor_name::XorName;
-use crate::{
-    CrustEvent, PrivConnectionInfo, PubConnectionInfo, Service, QUORUM_DENOMINATOR,
-    QUORUM_NUMERATOR,
-};
+use crate::verifier::{Verified, Verifier};
+use crate::xor_name::{XorName, XOR_NAME_BITS};
+use crate::{PrivConnectionInfo, PubConnectionInfo, Service, QUORUM_DENOMINATOR, QUORUM_NUMERATOR};
 #[cfg(feature = "use-mock-crust")]
 use fake_clock::FakeClock as Instant;
 use itertools::Itertools;
 use log::Level;
 use lru_time_cache::LruCache;
 use maidsafe_utilities::serialisation;
-use rand::{self, Rng};
-#[cfg(feature = "use-mock-crust")]
-use std::collections::BTreeMap;
-use std::collections::{BTreeSet, VecDeque};
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt::{Debug, Formatter};
-use std::net::IpAddr;
-use std::time::Duration;
+use std::net::{IpAddr, SocketAddr};
 #[cfg(not(feature = "use-mock-crust"))]
 use std::time::Instant;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{cmp, fmt, iter, mem};
+use tiny_keccak::sha3_256;
 
 /// Time (in seconds) after which a `Tick` event is sent.
-const TICK_TIMEOUT_SECS: u64 = 60;
+pub const TICK_TIMEOUT_SECS: u64 = 60;
 /// The number of required leading zero bits for the resource proof
 const RESOURCE_PROOF_DIFFICULTY: u8 = 0;
 /// The total size of the resource proof data.
@@ -80,8 +98,73 @@ const CANDIDATE_STATUS_INTERVAL_SECS: u64 = 60;
 const MERGE_TIMEOUT_SECS: u64 = 300;
 /// Duration for which all clients on a given IP will be blocked from joining this node, in seconds.
 const CLIENT_BAN_SECS: u64 = 2 * 60 * 60;
+/// Duration for which requests the `RequestValidator` rejected are counted against their client,
+/// in seconds.
+const REJECTED_REQUESTS_PERIOD_SECS: u64 = 10 * 60;
+/// Number of rejected requests within `REJECTED_REQUESTS_PERIOD_SECS` after which further requests
+/// from the same client are dropped.
+pub const MAX_REJECTED_REQUESTS: usize = 10;
 /// Duration for which clients' IDs we disconnected from are retained, in seconds.
 const DROPPED_CLIENT_TIMEOUT_SECS: u64 = 2 * 60 * 60;
+/// Number of routing table entries lost within one compaction period which is treated as a burst
+/// of churn.
+const CHURN_BURST_SIZE: usize = 4;
+/// Delay between detecting a burst of churn and compacting our routing metadata, in seconds.
+const COMPACTION_DELAY_SECS: u64 = 60;
+/// Default maximum number of peers we try to connect to at the same time. Further connection
+/// attempts are queued.
+const MAX_CONCURRENT_CONNECTS: usize = 50;
+/// Delay before retrying to connect to a peer we failed to connect to, in seconds. It doubles with
+/// every further failure.
+const CONNECT_RETRY_DELAY_SECS: u64 = 20;
+/// Maximal delay before retrying to connect to a peer, in seconds.
+const MAX_CONNECT_RETRY_DELAY_SECS: u64 = 640;
+/// Delay before retrying to connect to a peer while we are recovering, i.e. right after we started
+/// or during a churn storm, in seconds. It doubles with every further failure, too.
+const RECOVERY_CONNECT_RETRY_DELAY_SECS: u64 = 5;
+/// Time after we started during which we retry failed connections more eagerly, in seconds.
+const RECOVERY_PERIOD_SECS: u64 = 120;
+/// Time after which we resend a connection info request that wasn't answered, in seconds.
+const CONNECTION_INFO_RETRY_SECS: u64 = 30;
+/// The number of times we send a connection info request, each time via a different route,
+/// before we consider the peer unreachable for now.
+const MAX_CONNECTION_INFO_ATTEMPTS: u8 = 3;
+/// Interval within which our section admits at most `MAX_JOINS_PER_INTERVAL` nodes, in seconds.
+const JOIN_THROTTLE_INTERVAL_SECS: u64 = 300;
+/// Default maximum number of nodes our section admits within `JOIN_THROTTLE_INTERVAL_SECS`.
+const MAX_JOINS_PER_INTERVAL: usize = 4;
+/// Interval within which `CHURN_STORM_CHANGES` routing table changes are treated as a churn storm,
+/// in seconds.
+const CHURN_STORM_INTERVAL_SECS: u64 = 60;
+/// Default number of routing table changes within `CHURN_STORM_INTERVAL_SECS` which is treated as
+/// a churn storm.
+const CHURN_STORM_CHANGES: usize = 8;
+/// Default duration for which signatures of a new routing table entry don't count towards a
+/// quorum, in seconds.
+const QUARANTINE_SECS: u64 = 300;
+/// Duration for which serialised hop messages are kept for reuse, in milliseconds.
+const HOP_BYTES_CACHE_EXPIRY_MS: u64 = 1000;
+/// Maximum number of serialised hop messages kept for reuse.
+const HOP_BYTES_CACHE_CAPACITY: usize = 100;
+/// Maximal delay before we answer a request from our cache, in milliseconds. Nodes closer to the
+/// request's destination respond sooner, so that the nearest cache usually wins.
+pub const CACHE_RESPONSE_MAX_DELAY_MS: u64 = 500;
+/// Maximum total size of the cached chunks a new node asks its section for, in bytes.
+const CACHE_PRIMING_MAX_BYTES: u64 = 4 * 1024 * 1024;
+/// Duration for which we accept the chunks our section sends to prime our cache, in seconds.
+const CACHE_PRIMING_TIMEOUT_SECS: u64 = 120;
+/// Time after which we disconnect from a proxy we asked to retire, even if it didn't acknowledge
+/// it, in seconds.
+const PROXY_RETIREMENT_TIMEOUT_SECS: u64 = 60;
+/// Duration for which the IDs of proxies we retired are retained, in seconds.
+const RETIRED_PROXY_TIMEOUT_SECS: u64 = 10 * 60;
+/// Default duration for which we hold the messages for a client that lost its connection to us,
+/// in seconds.
+const CLIENT_GRACE_PERIOD_SECS: u64 = 30;
+/// Default number of threads which check the signatures of incoming messages.
+const VERIFIER_THREADS: usize = 2;
+/// Time we give a `Disconnecting` message to be sent before we drop the connection, in seconds.
+const DISCONNECT_DELAY_SECS: u64 = 1;
 
 pub struct Node {
     ack_mgr: AckManager,
@@ -90,32 +173,66 @@ pub struct Node {
     /// ID from before relocating.
     old_full_id: FullId,
     full_id: FullId,
+    /// The port our listener was started on.
+    listener_port: Option<u16>,
     is_first_node: bool,
     is_approved: bool,
     /// The queue of routing messages addressed to us. These do not themselves need forwarding,
-    /// although they may wrap a message which needs forwarding.
-    msg_queue: VecDeque<RoutingMessage>,
+    /// although they may wrap a message which needs forwarding. Each is queued with its signers.
+    msg_queue: VecDeque<(RoutingMessage, BTreeSet<PublicId>)>,
     peer_mgr: PeerManager,
     response_cache: Box<Cache>,
+    /// How we used `response_cache` so far.
+    cache_stats: CacheStats,
+    /// Whether to ask our section for cached chunks once it is complete.
+    prime_cache: bool,
+    /// The IDs of our pending requests to prime our cache.
+    cache_priming_ids: LruCache<MessageId, ()>,
+    /// Checks the requests addressed to us before we raise them as events.
+    request_validator: Option<Box<RequestValidator>>,
+    /// The number of requests from each client the `request_validator` recently rejected.
+    rejected_requests: LruCache<PublicId, usize>,
+    /// Data modified by requests we relayed, by message ID, so we can invalidate cached responses
+    /// once the modification succeeds.
+    pending_mutations: LruCache<MessageId, (XorName, u64)>,
     routing_msg_filter: RoutingMessageFilter,
     sig_accumulator: SignatureAccumulator,
     section_list_sigs: SectionListCache,
+    /// The signatures of our public ID by the other members of our section, which we present to
+    /// the clients bootstrapping off us.
+    proxy_certificate: ProxyCertificate,
     stats: Stats,
+    capture: Capture,
     tick_timer_token: u64,
+    /// The work done on each tick while we are approved.
+    periodic_tasks: PeriodicTasks<Node>,
     timer: Timer,
     tunnels: Tunnels,
+    /// Whether to keep a peer's tunnel as a standby route once we connect to it directly.
+    standby_tunnels: bool,
+    /// Whether to disconnect from our proxy once we don't need it anymore.
+    retire_proxy: bool,
+    /// The proxy we asked to retire, and when.
+    retiring_proxy: Option<(PublicId, Instant)>,
+    /// Recently-retired proxies. Messages they sent before we disconnected may still arrive, and
+    /// are ignored.
+    retired_proxies: LruCache<PublicId, ()>,
+    /// The joining nodes which asked us to retire as their proxy, but still have messages queued.
+    retiring_joining_nodes: BTreeSet<PublicId>,
     user_msg_cache: UserMessageCache,
     /// Value which can be set in mock-crust tests to be used as the calculated name for the next
     /// relocation request received by this node.
     next_relocation_dst: Option<XorName>,
     /// Interval used for relocation in mock crust tests.
     next_relocation_interval: Option<(XorName, XorName)>,
+    /// Reason with which mock-crust tests can make us deny the next relocation request.
+    next_relocation_denial: Option<RelocationDenial>,
     /// The current duration between `RoutingTableRequest`s we send. Doubles with every message.
     su_timeout: Duration,
     /// The timer token for sending the next `RoutingTableRequest`.
     su_timer_token: Option<u64>,
     /// `RoutingMessage`s affecting the routing table that arrived before `NodeApproval`.
-    routing_msg_backlog: Vec<RoutingMessage>,
+    routing_msg_backlog: Vec<(RoutingMessage, BTreeSet<PublicId>)>,
     /// Cache of `OwnSectionMerge` messages we have received, by sender section prefix.
     merge_cache: LruCache<Prefix<XorName>, SectionMap>,
     /// Union of our merged section, deduced from multiple `OwnSectionMerge`.
@@ -126,6 +243,12 @@ pub struct Node {
     candidate_status_token: Option<u64>,
     resource_prover: ResourceProver,
     joining_prefix: Prefix<XorName>,
+    /// The members of the section we are joining, as sent in the `RelocateResponse`.
+    joining_members: BTreeSet<PublicId>,
+    /// The members of the section we are joining which told us that our name is already taken.
+    name_taken_reports: BTreeSet<PublicId>,
+    /// Whether a quorum of `joining_members` reported our name as taken, and we asked to restart.
+    restart_required: bool,
     /// Limits the rate at which clients can pass messages through this node when it acts as their
     /// proxy.
     clients_rate_limiter: RateLimiter,
@@ -136,19 +259,115 @@ pub struct Node {
     /// already enqueued in the channel or added before Crust handled the disconnect request).  If a
     /// client then re-connects, its ID is removed from here when we add it to the `PeerManager`.
     dropped_clients: LruCache<PublicId, ()>,
+    /// The old IDs candidates identified with in their `CandidateInfo`, by new ID.
+    candidate_old_ids: LruCache<PublicId, PublicId>,
     /// Proxy client traffic handled
     proxy_load_amount: u64,
     /// Whether resource proof is disabled.
     disable_resource_proof: bool,
+    /// Number of routing table entries dropped since the last compaction.
+    churn_count: usize,
+    /// The timer token for compacting routing metadata after a burst of churn.
+    compaction_timer_token: Option<u64>,
+    /// Whether the user messages we send request a `TraceReport` from every node handling them.
+    trace_user_messages: bool,
+    /// The session keys encrypting our connections to other nodes.
+    link_keys: LinkKeys,
+    /// Numbers our direct messages, and restores the order of those we receive.
+    direct_sequencer: DirectSequencer,
+    /// The timer token for handling the direct messages the `direct_sequencer` held back too long.
+    direct_sequencer_timer_token: Option<u64>,
+    /// Maximum number of peers we try to connect to at the same time.
+    max_concurrent_connects: usize,
+    /// Connection info requests held back because we were already connecting to too many peers.
+    pending_connects: Vec<(
+        PublicId,
+        Authority<XorName>,
+        Authority<XorName>,
+        ReconnectingPeer,
+    )>,
+    /// The time of the last failed connection attempt and the number of consecutive failures, for
+    /// peers we recently failed to connect to.
+    connect_failures: LruCache<XorName, (Instant, u32)>,
+    /// When this state was created, i.e. when we (re)started as a routing node.
+    started_at: Instant,
+    /// Whether to write the cached chunks to disk when we terminate.
+    persist_cache: bool,
+    /// Whether to write our section's members to disk when we terminate.
+    persist_close_group: bool,
+    /// Whether to write a filter of the messages we handled to disk when we terminate.
+    persist_message_filter: bool,
+    /// Responses from our cache which we are waiting to send, by timer token.
+    pending_cache_responses: BTreeMap<u64, (Authority<XorName>, Response)>,
+    /// Connection info requests awaiting a response, by retry timer token, with the route they
+    /// were last sent on.
+    connection_info_retries: BTreeMap<u64, (PublicId, Authority<XorName>, Authority<XorName>, u8)>,
+    /// Peers we sent `Disconnecting` to, by the timer token at which we drop their connection.
+    pending_disconnects: BTreeMap<u64, PublicId>,
+    /// Whether to raise `Event::MessageDropped` when we discard a message.
+    report_dropped_messages: bool,
+    /// Nodes we connect to whenever they fit into our routing table.
+    static_contacts: Vec<PublicId>,
+    /// When we recently accepted relocated nodes as candidates.
+    recent_joins: VecDeque<Instant>,
+    max_joins_per_interval: usize,
+    /// Decides which IP addresses we accept connections from.
+    endpoint_acl: EndpointAcl,
+    /// The maximal size of a serialised message we decode, in bytes.
+    max_message_size: usize,
+    /// Recently serialised `Message::Hop`s, keyed by the hash of the signed message, the route and
+    /// the `sent_to` list, so we don't sign and serialise the same message for every target.
+    hop_bytes_cache: LruCache<(Digest256, u8, BTreeSet<XorName>), Vec<u8>>,
+    /// When our routing table recently changed.
+    recent_table_changes: VecDeque<Instant>,
+    churn_storm_changes: usize,
+    /// Whether we are currently in a churn storm, so we only raise the event once per storm.
+    in_churn_storm: bool,
+    /// Our close group as of our latest routing table change.
+    close_group_names: BTreeSet<XorName>,
+    /// Whether to raise `Event::RefreshNeeded`.
+    refresh_needed_events: bool,
+    /// Messages for clients which recently lost their connection to us.
+    client_buffer: ClientBuffer,
+    /// Checks the signatures of incoming hop messages off the event loop.
+    verifier: Verifier,
+    /// Outgoing messages, shared fairly between own messages, routing forwards and client relays.
+    send_queue: SendQueue,
+    /// The timer token for sending messages held back by the `send_queue`.
+    send_queue_timer_token: Option<u64>,
+    /// For how long new routing table entries are quarantined.
+    quarantine_duration: Duration,
+    /// Recently added routing table entries whose signatures don't count towards a quorum yet.
+    quarantined: LruCache<PublicId, ()>,
+    /// Candidates which passed our resource proof challenge, and so are never quarantined.
+    proven_candidates: LruCache<PublicId, ()>,
+    /// Whether peers on the LAN can find us via Crust's service discovery.
+    lan_discovery: bool,
+    /// The latest load reported by each other member of our section.
+    section_loads: BTreeMap<XorName, NodeLoad>,
+    /// Our total sent bytes when we last reported our load.
+    bytes_sent_at_last_report: u64,
+    /// Whether to raise `Event::SectionLoad`.
+    section_load_events: bool,
+    /// Whether our section has had `min_section_size` members since we were approved, after which
+    /// we stop raising `Event::RoutingTableGrowth`.
+    group_connected: bool,
+    /// The traffic we relayed for each client since the last reset.
+    relay_accounting: RelayAccounting,
+    /// Whether to count relayed traffic and raise `Event::RelayAccounting`.
+    relay_accounting_events: bool,
 }
 
 impl Node {
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn first(
         action_sender: RoutingActionSender,
         cache: Box<Cache>,
+        request_validator: Option<Box<RequestValidator>>,
         crust_service: Service,
         full_id: FullId,
         min_section_size: usize,
+        dev_config: DevConfig,
         timer: Timer,
     ) -> Option<Self> {
         // old_id is useless for first node
@@ -156,12 +375,15 @@ impl Node {
         let mut node = Self::new(
             action_sender,
             cache,
+            request_validator,
             crust_service,
             true,
             old_id,
             full_id,
             min_section_size,
+            dev_config,
             Stats::new(),
+            Capture::default(),
             timer,
             0,
         );
@@ -180,33 +402,45 @@ impl Node {
         our_section: (Prefix<XorName>, BTreeSet<PublicId>),
         action_sender: RoutingActionSender,
         cache: Box<Cache>,
+        request_validator: Option<Box<RequestValidator>>,
         crust_service: Service,
         old_full_id: FullId,
         new_full_id: FullId,
+        listener_port: Option<u16>,
         min_section_size: usize,
+        dev_config: DevConfig,
         proxy_pub_id: PublicId,
+        proxy_next_seq: u32,
         stats: Stats,
+        capture: Capture,
         timer: Timer,
     ) -> Self {
         let mut node = Self::new(
             action_sender,
             cache,
+            request_validator,
             crust_service,
             false,
             old_full_id,
             new_full_id,
             min_section_size,
+            dev_config,
             stats,
+            capture,
             timer,
             our_section.1.len(),
         );
         node.joining_prefix = our_section.0;
+        node.joining_members = our_section.1.clone();
+        node.listener_port = listener_port;
         node.peer_mgr.insert_peer(Peer::new(
             proxy_pub_id,
             PeerState::Proxy,
             false,
             ReconnectingPeer::False,
         ));
+        // The `Bootstrapping` state already handled the proxy's first direct messages.
+        node.direct_sequencer.expect(proxy_pub_id, proxy_next_seq);
         node.join(our_section.1, &proxy_pub_id);
         node
     }
@@ -215,20 +449,59 @@ impl Node {
     fn new(
         action_sender: RoutingActionSender,
         cache: Box<Cache>,
+        request_validator: Option<Box<RequestValidator>>,
         crust_service: Service,
         first_node: bool,
         old_full_id: FullId,
         new_full_id: FullId,
         min_section_size: usize,
+        dev_config: DevConfig,
         stats: Stats,
+        capture: Capture,
         timer: Timer,
         challenger_count: usize,
     ) -> Self {
-        let dev_config = config_handler::get_config().dev.unwrap_or_default();
         let public_id = *new_full_id.public_id();
         let tick_period = Duration::from_secs(TICK_TIMEOUT_SECS);
         let tick_timer_token = timer.schedule(tick_period);
         let user_msg_cache_duration = Duration::from_secs(USER_MSG_CACHE_EXPIRY_DURATION_SECS);
+        let mut peer_mgr = PeerManager::new(
+            min_section_size,
+            public_id,
+            dev_config.disable_client_rate_limiter,
+        );
+        peer_mgr.set_client_limits(
+            dev_config.max_clients,
+            dev_config.reserved_client_keys.iter().cloned().collect(),
+        );
+        peer_mgr.set_joining_node_limit(dev_config.max_joining_nodes);
+        let lan_discovery = dev_config.lan_discovery_enabled();
+        let sig_accumulator = SignatureAccumulator::new(
+            dev_config.accumulation_quorum(),
+            dev_config.refresh_quorum(),
+        );
+        let section_load_events = dev_config.section_load_events_enabled();
+        let relay_accounting_events = dev_config.relay_accounting_events_enabled();
+        let static_contacts = dev_config.static_contacts;
+        // Messages we handled shortly before a restart may still be in flight, so don't act on them
+        // a second time.
+        let mut routing_msg_filter = RoutingMessageFilter::new();
+        if dev_config.persist_message_filter {
+            let max_age = Duration::from_secs(INCOMING_EXPIRY_DURATION_SECS);
+            match filter_snapshot::restore(max_age) {
+                Ok((snapshot, expires_in)) => routing_msg_filter.restore(snapshot, expires_in),
+                Err(error) => debug!("Failed to restore the message filter: {:?}", error),
+            }
+        }
+        let quarantine_duration =
+            Duration::from_secs(dev_config.quarantine_secs.unwrap_or(QUARANTINE_SECS));
+        let client_grace_period = Duration::from_secs(
+            dev_config
+                .client_grace_period_secs
+                .unwrap_or(CLIENT_GRACE_PERIOD_SECS),
+        );
+        let verifier_threads = dev_config.verifier_threads.unwrap_or(VERIFIER_THREADS);
+        let verifier = Verifier::new(action_sender.clone(), verifier_threads);
 
         Node {
             ack_mgr: AckManager::new(),
@@ -238,25 +511,43 @@ impl Node {
             crust_service,
             old_full_id,
             full_id: new_full_id,
+            listener_port: None,
             is_first_node: first_node,
             is_approved: first_node,
             msg_queue: VecDeque::new(),
-            peer_mgr: PeerManager::new(
-                min_section_size,
-                public_id,
-                dev_config.disable_client_rate_limiter,
-            ),
+            peer_mgr,
             response_cache: cache,
-            routing_msg_filter: RoutingMessageFilter::new(),
-            sig_accumulator: Default::default(),
+            cache_stats: Default::default(),
+            prime_cache: dev_config.prime_cache,
+            cache_priming_ids: LruCache::with_expiry_duration(Duration::from_secs(
+                CACHE_PRIMING_TIMEOUT_SECS,
+            )),
+            request_validator,
+            rejected_requests: LruCache::with_expiry_duration(Duration::from_secs(
+                REJECTED_REQUESTS_PERIOD_SECS,
+            )),
+            pending_mutations: LruCache::with_expiry_duration(user_msg_cache_duration),
+            routing_msg_filter,
+            sig_accumulator,
             section_list_sigs: SectionListCache::new(),
+            proxy_certificate: Default::default(),
             stats,
+            capture,
             tick_timer_token,
+            periodic_tasks: Self::periodic_tasks(tick_period),
             timer: timer.clone(),
             tunnels: Default::default(),
+            standby_tunnels: dev_config.standby_tunnels_enabled(),
+            retire_proxy: dev_config.retire_proxy_enabled(),
+            retiring_proxy: None,
+            retired_proxies: LruCache::with_expiry_duration(Duration::from_secs(
+                RETIRED_PROXY_TIMEOUT_SECS,
+            )),
+            retiring_joining_nodes: BTreeSet::new(),
             user_msg_cache: UserMessageCache::with_expiry_duration(user_msg_cache_duration),
             next_relocation_dst: None,
             next_relocation_interval: None,
+            next_relocation_denial: None,
             su_timeout: Duration::from_secs(SU_MIN_TIMEOUT_SECS),
             su_timer_token: None,
             routing_msg_backlog: vec![],
@@ -266,13 +557,80 @@ impl Node {
             candidate_status_token: None,
             resource_prover: ResourceProver::new(action_sender, timer, challenger_count),
             joining_prefix: Default::default(),
+            joining_members: BTreeSet::new(),
+            name_taken_reports: BTreeSet::new(),
+            restart_required: false,
             clients_rate_limiter: RateLimiter::new(dev_config.disable_client_rate_limiter),
             banned_client_ips: LruCache::with_expiry_duration(Duration::from_secs(CLIENT_BAN_SECS)),
             dropped_clients: LruCache::with_expiry_duration(Duration::from_secs(
                 DROPPED_CLIENT_TIMEOUT_SECS,
             )),
+            candidate_old_ids: LruCache::with_expiry_duration(Duration::from_secs(
+                RESOURCE_PROOF_DURATION_SECS + ACCUMULATION_TIMEOUT_SECS,
+            )),
             proxy_load_amount: 0,
             disable_resource_proof: dev_config.disable_resource_proof,
+            churn_count: 0,
+            compaction_timer_token: None,
+            trace_user_messages: dev_config.trace_user_messages,
+            link_keys: LinkKeys::new(),
+            direct_sequencer: DirectSequencer::new(),
+            direct_sequencer_timer_token: None,
+            max_concurrent_connects: dev_config
+                .max_concurrent_connects
+                .unwrap_or(MAX_CONCURRENT_CONNECTS),
+            pending_connects: Vec::new(),
+            connect_failures: LruCache::with_expiry_duration(Duration::from_secs(
+                MAX_CONNECT_RETRY_DELAY_SECS,
+            )),
+            started_at: Instant::now(),
+            persist_cache: dev_config.persist_cache,
+            persist_close_group: dev_config.persist_close_group,
+            persist_message_filter: dev_config.persist_message_filter,
+            pending_cache_responses: BTreeMap::new(),
+            connection_info_retries: BTreeMap::new(),
+            pending_disconnects: BTreeMap::new(),
+            report_dropped_messages: dev_config.report_dropped_messages,
+            static_contacts,
+            recent_joins: VecDeque::new(),
+            max_joins_per_interval: dev_config
+                .max_joins_per_interval
+                .unwrap_or(MAX_JOINS_PER_INTERVAL),
+            endpoint_acl: EndpointAcl::new(
+                dev_config.allowed_ip_ranges,
+                dev_config.denied_ip_ranges,
+            ),
+            max_message_size: dev_config.max_message_size.unwrap_or(MAX_MESSAGE_SIZE),
+            hop_bytes_cache: LruCache::with_expiry_duration_and_capacity(
+                Duration::from_millis(HOP_BYTES_CACHE_EXPIRY_MS),
+                HOP_BYTES_CACHE_CAPACITY,
+            ),
+            recent_table_changes: VecDeque::new(),
+            churn_storm_changes: dev_config
+                .churn_storm_changes
+                .unwrap_or(CHURN_STORM_CHANGES),
+            in_churn_storm: false,
+            close_group_names: BTreeSet::new(),
+            refresh_needed_events: dev_config.refresh_needed_events_enabled(),
+            client_buffer: ClientBuffer::new(client_grace_period),
+            verifier,
+            send_queue: SendQueue::new(
+                dev_config.send_weights.unwrap_or(DEFAULT_SEND_WEIGHTS),
+                dev_config.send_budget.unwrap_or(DEFAULT_SEND_BUDGET),
+            ),
+            send_queue_timer_token: None,
+            quarantine_duration,
+            quarantined: LruCache::with_expiry_duration(quarantine_duration),
+            proven_candidates: LruCache::with_expiry_duration(Duration::from_secs(
+                RESOURCE_PROOF_DURATION_SECS + ACCUMULATION_TIMEOUT_SECS,
+            )),
+            lan_discovery,
+            section_loads: BTreeMap::new(),
+            bytes_sent_at_last_report: 0,
+            section_load_events,
+            group_connected: false,
+            relay_accounting: RelayAccounting::default(),
+            relay_accounting_events,
         }
     }
 
@@ -391,9 +749,51 @@ impl Node {
 
                 let _ = result_tx.send(result);
             }
+            Action::SendToGroup {
+                name,
+                content,
+                priority,
+                result_tx,
+            } => {
+                let dst = Authority::NaeManager(name);
+                let _ = result_tx.send(self.send_as_managed_node(dst, content, priority));
+            }
+            Action::SendToNode {
+                name,
+                content,
+                priority,
+                result_tx,
+            } => {
+                let dst = Authority::ManagedNode(name);
+                let _ = result_tx.send(self.send_as_managed_node(dst, content, priority));
+            }
             Action::Id { result_tx } => {
                 let _ = result_tx.send(*self.id());
             }
+            Action::ProxyName { result_tx } => {
+                let _ = result_tx.send(self.proxy_name());
+            }
+            Action::ListenerPort { result_tx } => {
+                let _ = result_tx.send(self.listener_port);
+            }
+            Action::GetPeerInfo { result_tx } => {
+                let _ = result_tx.send(self.peer_info());
+            }
+            Action::ExplainAuthority { auth, result_tx } => {
+                let _ = result_tx.send(self.explain_authority(&auth));
+            }
+            Action::IsNaeManagerFor { name, result_tx } => {
+                let _ = result_tx.send(self.is_nae_manager_for(&name));
+            }
+            Action::SendRefresh {
+                src,
+                type_tag,
+                payload,
+                cause,
+                result_tx,
+            } => {
+                let _ = result_tx.send(self.send_refresh(src, type_tag, payload, cause));
+            }
             Action::Timeout(token) => {
                 if let Transition::Terminate = self.handle_timeout(token, outbox) {
                     return Transition::Terminate;
@@ -405,7 +805,96 @@ impl Node {
                     .handle_action_res_proof(pub_id, messages);
                 self.send_direct_message(pub_id, msg);
             }
+            Action::HopMessageVerified {
+                id,
+                pub_id,
+                hop_msg,
+                valid,
+            } => {
+                for verified in self.verifier.complete(id, pub_id, hop_msg, valid) {
+                    match verified {
+                        Verified::Hop(hop_msg, true) => {
+                            let _ = self.receive_hop_message(hop_msg, pub_id, true, outbox);
+                        }
+                        Verified::Hop(hop_msg, false) => {
+                            debug!(
+                                "{:?} Invalid signatures in {:?} from {}.",
+                                self, hop_msg, pub_id
+                            );
+                            let message_id = hop_msg.content.routing_message().content.message_id();
+                            let error = RoutingError::FailedSignature.with_context(ErrorContext {
+                                stage: "receiving",
+                                message_id,
+                                peer: Some(pub_id),
+                            });
+                            self.report_dropped_message(
+                                message_id,
+                                &error,
+                                DropStage::Receiving,
+                                outbox,
+                            );
+                        }
+                        Verified::Direct(direct_msg, seq) => {
+                            let _ = self.sequence_direct_message(direct_msg, seq, pub_id, outbox);
+                        }
+                    }
+                }
+            }
+            Action::AddContacts(contacts) => {
+                self.static_contacts.extend(contacts);
+                if self.is_approved {
+                    self.connect_to_static_contacts(outbox);
+                }
+            }
+            Action::SetIpRanges { allowed, denied } => {
+                self.endpoint_acl = EndpointAcl::new(allowed, denied);
+            }
+            Action::SetRefreshPolicy { type_tag, policy } => {
+                self.sig_accumulator.set_refresh_policy(type_tag, policy)
+            }
+            Action::ResetRelayAccounting => self.relay_accounting.reset(),
+            // Answered by `State::handle_action` for all states.
+            Action::GetInternalState
+            | Action::GetNetworkSizeEstimate { .. }
+            | Action::StartCapture { .. }
+            | Action::StopCapture => (),
             Action::Terminate => {
+                if self.persist_cache {
+                    if let Err(error) = cache::persist(&*self.response_cache) {
+                        debug!(
+                            target: "routing_cache",
+                            "{:?} Failed to persist cached chunks: {:?}",
+                            self, error
+                        );
+                    }
+                }
+                if self.persist_close_group && self.is_approved {
+                    let members = self
+                        .peer_mgr
+                        .get_pub_ids(self.routing_table().our_section())
+                        .into_iter()
+                        .filter(|pub_id| pub_id != self.full_id.public_id())
+                        .map(|pub_id| (pub_id, self.peer_listener_addr(&pub_id)))
+                        .collect();
+                    // Our current keys are new with every start, so sign with the initial ones.
+                    if let Err(error) = close_group_snapshot::persist(&self.old_full_id, members) {
+                        debug!(
+                            target: "routing_connect",
+                            "{:?} Failed to persist section members: {:?}",
+                            self, error
+                        );
+                    }
+                }
+                if self.persist_message_filter {
+                    let snapshot = self.routing_msg_filter.snapshot();
+                    if let Err(error) = filter_snapshot::persist(&snapshot) {
+                        debug!(
+                            "{:?} Failed to persist the message filter: {:?}",
+                            self, error
+                        );
+                    }
+                }
+                self.send_proxy_closing();
                 return Transition::Terminate;
             }
         }
@@ -415,37 +904,40 @@ impl Node {
         Transition::Stay
     }
 
-    pub fn handle_crust_event(
+    pub fn handle_transport_event(
         &mut self,
-        crust_event: CrustEvent<PublicId>,
+        event: TransportEvent,
         outbox: &mut EventBox,
     ) -> Transition {
-        match crust_event {
-            CrustEvent::BootstrapAccept(pub_id, peer_kind) => {
+        match event {
+            TransportEvent::BootstrapAccept(pub_id, peer_kind) => {
                 self.handle_bootstrap_accept(pub_id, peer_kind)
             }
-            CrustEvent::BootstrapConnect(pub_id, _) => {
+            TransportEvent::BootstrapConnect(pub_id, _) => {
                 self.handle_bootstrap_connect(pub_id, outbox)
             }
-            CrustEvent::ConnectSuccess(pub_id) => self.handle_connect_success(pub_id, outbox),
-            CrustEvent::ConnectFailure(pub_id) => self.handle_connect_failure(pub_id),
-            CrustEvent::LostPeer(pub_id) => {
+            TransportEvent::ConnectSuccess(pub_id) => self.handle_connect_success(pub_id, outbox),
+            TransportEvent::ConnectFailure(pub_id) => self.handle_connect_failure(pub_id, outbox),
+            TransportEvent::LostPeer(pub_id) => {
                 if let Transition::Terminate = self.handle_lost_peer(pub_id, outbox) {
                     return Transition::Terminate;
                 }
             }
-            CrustEvent::NewMessage(pub_id, _peer_kind, bytes) => {
-                match self.handle_new_message(pub_id, bytes, outbox) {
-                    Err(RoutingError::FilterCheckFailed) | Ok(_) => (),
-                    Err(err) => debug!("{:?} - {:?}", self, err),
+            TransportEvent::NewMessage(pub_id, _peer_kind, bytes) => {
+                if let Err(err) = self.handle_new_message(pub_id, bytes, outbox) {
+                    match *err.root() {
+                        RoutingError::FilterCheckFailed => (),
+                        _ => debug!("{:?} - {:?}", self, err),
+                    }
                 }
             }
-            CrustEvent::ConnectionInfoPrepared(ConnectionInfoResult {
+            TransportEvent::ConnectionInfoPrepared {
                 result_token,
                 result,
-            }) => self.handle_connection_info_prepared(result_token, result),
-            CrustEvent::ListenerStarted(port) => {
+            } => self.handle_connection_info_prepared(result_token, result),
+            TransportEvent::ListenerStarted(port) => {
                 trace!("{:?} Listener started on port {}.", self, port);
+                self.listener_port = Some(port);
                 // If first node, allow other peers to bootstrap via us
                 // else wait until NodeApproval.
                 if self.is_first_node {
@@ -455,16 +947,20 @@ impl Node {
                             self, err
                         );
                     }
-                    self.crust_service.set_service_discovery_listen(true);
+                    if self.lan_discovery {
+                        self.crust_service.set_service_discovery_listen(true);
+                    }
+                    info!("{:?} Seed node is now accepting connections.", self);
+                    outbox.send_event(Event::NetworkStarted);
                 }
                 return Transition::Stay;
             }
-            CrustEvent::ListenerFailed => {
+            TransportEvent::ListenerFailed => {
                 error!("{:?} Failed to start listening.", self);
                 outbox.send_event(Event::Terminate);
                 return Transition::Terminate;
             }
-            CrustEvent::WriteMsgSizeProhibitive(pub_id, msg) => {
+            TransportEvent::WriteMsgSizeProhibitive(pub_id, msg) => {
                 error!(
                     "{:?} Failed to send {}-byte message to {:?}. Message too large.",
                     self,
@@ -473,7 +969,7 @@ impl Node {
                 );
             }
             _ => {
-                debug!("{:?} - Unhandled crust event: {:?}", self, crust_event);
+                debug!("{:?} - Unhandled transport event: {:?}", self, event);
             }
         }
 
@@ -488,15 +984,39 @@ impl Node {
     }
 
     fn handle_routing_messages(&mut self, outbox: &mut EventBox) {
-        while let Some(routing_msg) = self.msg_queue.pop_front() {
+        while let Some((routing_msg, signers)) = self.msg_queue.pop_front() {
             if self.in_authority(&routing_msg.dst) {
-                if let Err(err) = self.dispatch_routing_message(routing_msg, outbox) {
+                let message_id = routing_msg.content.message_id();
+                if let Err(err) = self.dispatch_routing_message(routing_msg, signers, outbox) {
+                    let err = err.with_context(ErrorContext {
+                        stage: "dispatching",
+                        message_id,
+                        peer: None,
+                    });
                     debug!("{:?} Routing message dispatch failed: {:?}", self, err);
+                    self.report_dropped_message(message_id, &err, DropStage::Dispatching, outbox);
                 }
             }
         }
     }
 
+    // Raises `Event::MessageDropped`, if enabled in the config.
+    fn report_dropped_message(
+        &self,
+        message_id: Option<MessageId>,
+        error: &RoutingError,
+        stage: DropStage,
+        outbox: &mut EventBox,
+    ) {
+        if self.report_dropped_messages {
+            outbox.send_event(Event::MessageDropped {
+                message_id,
+                reason: format!("{:?}", error),
+                stage,
+            });
+        }
+    }
+
     fn handle_bootstrap_accept(&mut self, pub_id: PublicId, peer_kind: CrustUser) {
         trace!(
             "{:?} Received BootstrapAccept from {:?} as {:?}.",
@@ -518,6 +1038,19 @@ impl Node {
             return;
         };
 
+        if !self.endpoint_acl.is_allowed(&ip) {
+            debug!(
+                target: "routing_connect",
+                "{:?} Rejecting bootstrapper {:?} on disallowed IP {}.",
+                self, pub_id, ip
+            );
+            self.disconnect_peer(&pub_id, None);
+            if peer_kind == CrustUser::Client {
+                let _ = self.dropped_clients.insert(pub_id, ());
+            }
+            return;
+        }
+
         if peer_kind == CrustUser::Client && self.banned_client_ips.contains_key(&ip) {
             warn!(
                 "{:?} Client {:?} is trying to bootstrap on banned IP {}.",
@@ -540,13 +1073,30 @@ impl Node {
     }
 
     fn handle_connect_success(&mut self, pub_id: PublicId, outbox: &mut EventBox) {
-        // Remove tunnel connection if we have one for this peer already
-        if let Some(tunnel_id) = self.tunnels.remove_tunnel_for(&pub_id) {
-            debug!("{:?} Removing unwanted tunnel for {:?}", self, pub_id);
+        // Remove tunnel connection if we have one for this peer already, unless it's a routing peer
+        // and we keep the tunnel to fail over to.
+        let standby_id = if self.standby_tunnels && self.peer_mgr.is_routing_peer(&pub_id) {
+            self.tunnels.keep_as_standby(pub_id)
+        } else {
+            None
+        };
+        if let Some(tunnel_id) = standby_id {
+            debug!(
+                target: "routing_connect",
+                "{:?} Keeping tunnel for {:?} via {:?} as standby.",
+                self, pub_id, tunnel_id
+            );
+        } else if let Some(tunnel_id) = self.tunnels.remove_tunnel_for(&pub_id) {
+            debug!(
+                target: "routing_connect",
+                "{:?} Removing unwanted tunnel for {:?}",
+                self, pub_id
+            );
             let message = DirectMessage::TunnelDisconnect(pub_id);
             self.send_direct_message(tunnel_id, message);
         } else if self.peer_mgr.is_routing_peer(&pub_id) {
             warn!(
+                target: "routing_connect",
                 "{:?} Received ConnectSuccess from {:?}, but node is already in routing \
                  state in peer_map.",
                 self, pub_id
@@ -554,15 +1104,40 @@ impl Node {
             return;
         }
 
+        if !self.is_endpoint_allowed(&pub_id) {
+            debug!(
+                target: "routing_connect",
+                "{:?} Rejecting connection from {} on disallowed IP.",
+                self, pub_id
+            );
+            let _ = self.crust_service.disconnect(&pub_id);
+            let _ = self.peer_mgr.remove_peer(&pub_id);
+            return;
+        }
+
         self.peer_mgr.connected_to(&pub_id);
-        debug!("{:?} Received ConnectSuccess from {}.", self, pub_id);
+        debug!(target: "routing_connect", "{:?} Received ConnectSuccess from {}.", self, pub_id);
+        let _ = self.connect_failures.remove(pub_id.name());
+        self.send_link_key(pub_id);
         self.process_connection(pub_id, outbox);
+        self.send_pending_connects(outbox);
+    }
+
+    // Returns whether the peer's IP address passes our endpoint ACL. Peers whose address we can't
+    // determine are only allowed if the ACL is empty.
+    fn is_endpoint_allowed(&self, pub_id: &PublicId) -> bool {
+        self.crust_service
+            .get_peer_ip_addr(pub_id)
+            .map_or(self.endpoint_acl.is_empty(), |ip| {
+                self.endpoint_acl.is_allowed(&ip)
+            })
     }
 
-    fn handle_connect_failure(&mut self, pub_id: PublicId) {
+    fn handle_connect_failure(&mut self, pub_id: PublicId, outbox: &mut EventBox) {
         if let Some(&PeerState::CrustConnecting) = self.peer_mgr.get_peer(&pub_id).map(Peer::state)
         {
-            debug!("{:?} Failed to connect to peer {:?}.", self, pub_id);
+            debug!(target: "routing_connect", "{:?} Failed to connect to peer {:?}.", self, pub_id);
+            self.record_connect_failure(*pub_id.name());
             if self.tunnels.tunnel_for(&pub_id).is_none() {
                 let valid = self
                     .peer_mgr
@@ -570,7 +1145,81 @@ impl Node {
                     .map_or(false, |peer| peer.valid());
                 self.find_tunnel_for_peer(&pub_id, valid);
             } else {
-                debug!("{:?} already has tunnel to peer {}.", self, pub_id);
+                debug!(
+                    target: "routing_connect",
+                    "{:?} already has tunnel to peer {}.",
+                    self, pub_id
+                );
+            }
+        }
+        self.send_pending_connects(outbox);
+    }
+
+    fn record_connect_failure(&mut self, name: XorName) {
+        let failures = self
+            .connect_failures
+            .get(&name)
+            .map_or(0, |&(_, failures)| failures);
+        let _ = self
+            .connect_failures
+            .insert(name, (Instant::now(), failures + 1));
+    }
+
+    // Returns whether we failed to connect to the peer recently enough to not try again yet.
+    fn is_connect_backing_off(&mut self, name: &XorName) -> bool {
+        match self.connect_failures.get(name).cloned() {
+            Some((failed_at, failures)) => failed_at.elapsed() < self.connect_retry_delay(failures),
+            None => false,
+        }
+    }
+
+    // Returns the delay before we retry connecting to a peer after `failures` consecutive failures.
+    // While we are recovering from our own restart or from mass churn, many of our peers are
+    // connecting at the same time, so we retry sooner.
+    fn connect_retry_delay(&self, failures: u32) -> Duration {
+        let recovering = self.in_churn_storm
+            || self.started_at.elapsed() < Duration::from_secs(RECOVERY_PERIOD_SECS);
+        let base_delay = if recovering {
+            RECOVERY_CONNECT_RETRY_DELAY_SECS
+        } else {
+            CONNECT_RETRY_DELAY_SECS
+        };
+        let doublings = cmp::min(failures.saturating_sub(1), 7);
+        Duration::from_secs(cmp::min(
+            base_delay << doublings,
+            MAX_CONNECT_RETRY_DELAY_SECS,
+        ))
+    }
+
+    // Sends the queued connection info requests, as far as the limit of concurrent connection
+    // attempts allows. Peers in the sections we know the fewest members of are preferred, since
+    // they fill the emptiest parts of our routing table.
+    fn send_pending_connects(&mut self, outbox: &mut EventBox) {
+        while !self.pending_connects.is_empty()
+            && self.peer_mgr.connecting_num() < self.max_concurrent_connects
+        {
+            let index = {
+                let routing_table = self.routing_table();
+                let known_section_size = |name: &XorName| {
+                    routing_table
+                        .get_section(name)
+                        .map_or(0, |section| section.len())
+                };
+                self.pending_connects
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|&(_, &(ref pub_id, ..))| known_section_size(pub_id.name()))
+                    .map_or(0, |(index, _)| index)
+            };
+            let (pub_id, src, dst, reconnecting) = self.pending_connects.swap_remove(index);
+            if let Err(error) =
+                self.send_connection_info_request(pub_id, src, dst, outbox, reconnecting)
+            {
+                debug!(
+                    target: "routing_connect",
+                    "{:?} - Failed to send connection info to {:?}: {:?}",
+                    self, pub_id, error
+                );
             }
         }
     }
@@ -578,6 +1227,7 @@ impl Node {
     fn find_tunnel_for_peer(&mut self, pub_id: &PublicId, valid: bool) {
         for dst_pub_id in self.peer_mgr.set_searching_for_tunnel(*pub_id, valid) {
             trace!(
+                target: "routing_relay",
                 "{:?} Asking {} to serve as a tunnel for {:?}.",
                 self,
                 dst_pub_id,
@@ -594,15 +1244,113 @@ impl Node {
         bytes: Vec<u8>,
         outbox: &mut EventBox,
     ) -> Result<(), RoutingError> {
-        match serialisation::deserialise(&bytes) {
-            Ok(Message::Hop(hop_msg)) => self.handle_hop_message(hop_msg, pub_id),
-            Ok(Message::Direct(direct_msg)) => {
-                self.handle_direct_message(direct_msg, pub_id, outbox)
+        let message = self.decode_message(&pub_id, &bytes)?;
+        match message {
+            Message::Sealed { .. } => (),
+            _ if self.link_keys.requires_sealed(&pub_id) => {
+                debug!(
+                    "{:?} Received plaintext message from {} on a sealed link.",
+                    self, pub_id
+                );
+                return Err(RoutingError::InvalidMessage);
+            }
+            _ => (),
+        }
+        self.handle_message(pub_id, message, bytes, outbox)
+    }
+
+    // Generates our session key for the link to `pub_id` and sends it, unless we did already.
+    fn send_link_key(&mut self, pub_id: PublicId) {
+        match self.link_keys.start(&self.full_id, pub_id) {
+            Some(Ok((key, signature))) => {
+                self.send_direct_message(pub_id, DirectMessage::LinkEncryption { key, signature })
+            }
+            Some(Err(error)) => {
+                error!(
+                    "{:?} Failed to sign session key for {}: {:?}",
+                    self, pub_id, error
+                );
+            }
+            None => (),
+        }
+    }
+
+    // Accepts the session key `pub_id` sent us, once we sent ours, so that everything we send it
+    // afterwards is sealed.
+    fn handle_link_key(
+        &mut self,
+        pub_id: PublicId,
+        key: box_::PublicKey,
+        signature: &sign::Signature,
+    ) -> Result<(), RoutingError> {
+        self.send_link_key(pub_id);
+        self.link_keys
+            .receive(self.full_id.public_id(), &pub_id, key, signature)?;
+        debug!("{:?} Encrypting all messages to {}.", self, pub_id);
+        Ok(())
+    }
+
+    // Decodes the message `bytes` received from `pub_id`, penalising the peer if they exceed the
+    // size limits.
+    fn decode_message(&mut self, pub_id: &PublicId, bytes: &[u8]) -> Result<Message, RoutingError> {
+        if bytes.len() > self.max_message_size {
+            debug!(
+                "{:?} Received {}-byte message from {}, exceeding the size limit.",
+                self,
+                bytes.len(),
+                pub_id
+            );
+            self.penalise_oversized_message(pub_id);
+            return Err(RoutingError::MessageTooLarge);
+        }
+        let message: Message = serialisation::deserialise(bytes)?;
+        if let Err(error) = message.check_field_sizes() {
+            debug!(
+                "{:?} Received message from {} with an oversized field.",
+                self, pub_id
+            );
+            self.penalise_oversized_message(pub_id);
+            return Err(error);
+        }
+        Ok(message)
+    }
+
+    fn handle_message(
+        &mut self,
+        pub_id: PublicId,
+        message: Message,
+        bytes: Vec<u8>,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        match message {
+            Message::Hop(hop_msg) => {
+                let result = self.receive_hop_message(hop_msg, pub_id, false, outbox);
+                if self.relay_accounting_events
+                    && result.is_ok()
+                    && self.peer_mgr.is_client(&pub_id)
+                {
+                    self.relay_accounting.add_from_client(pub_id, bytes.len());
+                }
+                result
             }
-            Ok(Message::TunnelDirect { content, src, dst }) => {
+            Message::Direct { content, seq } => {
+                // The peer seals everything after its key, so we need the key before any of the
+                // messages the sequencer might hold back.
+                if let DirectMessage::LinkEncryption { key, ref signature } = content {
+                    self.handle_link_key(pub_id, key, signature)?;
+                }
+                self.handle_sequenced_direct_message(content, seq, pub_id, outbox)
+            }
+            Message::TunnelDirect {
+                content,
+                seq,
+                src,
+                dst,
+            } => {
                 if dst == *self.full_id.public_id() {
+                    self.follow_fail_over(&src, &pub_id, outbox);
                     if self.tunnels.tunnel_for(&src) == Some(&pub_id) {
-                        self.handle_direct_message(content, src, outbox)
+                        self.handle_sequenced_direct_message(content, seq, src, outbox)
                     } else {
                         debug!(
                             "{:?} Message recd via unregistered tunnel node {} from src {:?}",
@@ -611,7 +1359,7 @@ impl Node {
                         Err(RoutingError::InvalidDestination)
                     }
                 } else if self.tunnels.has_clients(src, dst) {
-                    self.send_or_drop(&dst, bytes, content.priority());
+                    self.queue_send(TrafficClass::Forward, &dst, bytes, content.priority());
                     Ok(())
                 } else if !self.peer_mgr.can_tunnel_for(&src, &dst) {
                     debug!(
@@ -629,11 +1377,13 @@ impl Node {
                     Err(RoutingError::InvalidDestination)
                 }
             }
-            Ok(Message::TunnelHop { content, src, dst }) => {
+            Message::TunnelHop { content, src, dst } => {
                 if dst == *self.full_id.public_id() {
-                    self.handle_hop_message(content, src)
+                    self.follow_fail_over(&src, &pub_id, outbox);
+                    self.receive_hop_message(content, src, false, outbox)
                 } else if self.tunnels.has_clients(src, dst) {
-                    self.send_or_drop(&dst, bytes, content.content.priority());
+                    let priority = content.content.priority();
+                    self.queue_send(TrafficClass::Forward, &dst, bytes, priority);
                     Ok(())
                 } else {
                     debug!(
@@ -643,7 +1393,68 @@ impl Node {
                     Err(RoutingError::InvalidDestination)
                 }
             }
-            Err(error) => Err(RoutingError::SerialisationError(error)),
+            Message::Sealed { nonce, content } => {
+                let bytes = self.link_keys.open(&pub_id, nonce, &content)?;
+                match self.decode_message(&pub_id, &bytes)? {
+                    Message::Sealed { .. } => {
+                        debug!(
+                            "{:?} Received sealed message from {} nested in another one.",
+                            self, pub_id
+                        );
+                        Err(RoutingError::InvalidMessage)
+                    }
+                    message => self.handle_message(pub_id, message, bytes, outbox),
+                }
+            }
+        }
+    }
+
+    // Handles the direct messages from `pub_id` which `direct_message` completes the sequence of,
+    // in order. Returns the first error any of them caused. If hop messages from `pub_id` are still
+    // being checked by the verifier, they are handled first.
+    fn handle_sequenced_direct_message(
+        &mut self,
+        direct_message: DirectMessage,
+        seq: u32,
+        pub_id: PublicId,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        match self
+            .verifier
+            .hold_direct_message(pub_id, direct_message, seq)
+        {
+            Some((direct_message, seq)) => {
+                self.sequence_direct_message(direct_message, seq, pub_id, outbox)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn sequence_direct_message(
+        &mut self,
+        direct_message: DirectMessage,
+        seq: u32,
+        pub_id: PublicId,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        let ready = self.direct_sequencer.receive(pub_id, seq, direct_message);
+        self.schedule_direct_sequencer_timer();
+        let mut result = Ok(());
+        for direct_msg in ready {
+            let msg_result = self.handle_direct_message(direct_msg, pub_id, outbox);
+            result = result.and(msg_result);
+        }
+        result
+    }
+
+    // Schedules the timer for the direct messages the `direct_sequencer` holds back, if it isn't
+    // already running.
+    fn schedule_direct_sequencer_timer(&mut self) {
+        if self.direct_sequencer_timer_token.is_some() {
+            return;
+        }
+        if let Some(delay) = self.direct_sequencer.time_to_expiry() {
+            self.direct_sequencer_timer_token = Some(self.timer.schedule(delay));
         }
     }
 
@@ -701,6 +1512,7 @@ impl Node {
                     outbox,
                 );
             }
+            CandidateNameTaken => self.handle_candidate_name_taken(pub_id, outbox),
             TunnelRequest(dst_id) => self.handle_tunnel_request(pub_id, dst_id, outbox),
             TunnelSuccess(dst_id) => self.handle_tunnel_success(pub_id, dst_id, outbox),
             TunnelSelect(dst_id) => self.handle_tunnel_select(pub_id, dst_id),
@@ -739,9 +1551,110 @@ impl Node {
                     leading_zero_bytes,
                 );
             }
-            msg @ BootstrapResponse(_) | msg @ ProxyRateLimitExceeded { .. } => {
+            LinkEncryption { .. } => {
+                // Handled in `handle_message` already, as soon as it was received.
+            }
+            Disconnecting => {
+                // Don't reconnect once the peer is gone. If we still need it, its section's
+                // updates will tell us to connect again.
+                debug!(
+                    target: "routing_connect",
+                    "{:?} {} dropped us from its routing table.",
+                    self, pub_id
+                );
+                self.peer_mgr.set_peer_valid(&pub_id, false);
+            }
+            CloseGroupDigest(digest, load) => self.handle_close_group_digest(pub_id, digest, load),
+            CloseGroup(members) => self.handle_close_group(pub_id, members, outbox),
+            ProxyCertificateSignature(sig) => {
+                self.handle_proxy_certificate_signature(pub_id, sig)?
+            }
+            msg @ BootstrapResponse(_)
+            | msg @ ProxyRateLimitExceeded { .. }
+            | msg @ ProxyCertificate(_)
+            | msg @ ProxyClosing(_) => {
                 debug!("{:?} Unhandled direct message: {:?}", self, msg);
             }
+            ProxyRetirement => self.handle_proxy_retirement(pub_id),
+            ProxyRetired => self.handle_proxy_retired(pub_id),
+        }
+        Ok(())
+    }
+
+    /// Vouches for the given member of our section by signing its public ID.
+    fn send_proxy_certificate_signature(&mut self, pub_id: PublicId) {
+        match ProxyCertificate::sign(&self.full_id, &pub_id) {
+            Ok(sig) => {
+                self.send_direct_message(pub_id, DirectMessage::ProxyCertificateSignature(sig))
+            }
+            Err(error) => debug!("{:?} Failed to sign {}: {:?}", self, pub_id, error),
+        }
+    }
+
+    /// Returns information about the peers we are connected to.
+    fn peer_info(&self) -> Vec<PeerInfo> {
+        self.peer_mgr
+            .connected_peers()
+            .filter_map(|peer| {
+                let ip = self.crust_service.get_peer_ip_addr(peer.pub_id()).ok();
+                peer.info(ip, self.link_keys.is_sealed(peer.pub_id()))
+            })
+            .collect()
+    }
+
+    /// Tells our clients that we are shutting down, with the endpoints of the other members of our
+    /// section, so that they can bootstrap off one of them without waiting for a timeout.
+    fn send_proxy_closing(&mut self) {
+        let clients = self.peer_mgr.clients();
+        if clients.is_empty() {
+            return;
+        }
+        let endpoints: Vec<SocketAddr> = self
+            .peer_mgr
+            .get_pub_ids(self.routing_table().our_section())
+            .into_iter()
+            .filter(|pub_id| pub_id != self.full_id.public_id())
+            .filter_map(|pub_id| self.peer_listener_addr(&pub_id))
+            .collect();
+        debug!(
+            "{:?} Shutting down - notifying {} clients.",
+            self,
+            clients.len()
+        );
+        for pub_id in clients {
+            self.send_direct_message(pub_id, DirectMessage::ProxyClosing(endpoints.clone()));
+        }
+    }
+
+    /// Returns the address the peer `pub_id` accepts connections on, if it is connected.
+    ///
+    /// We only know the IP addresses of our peers, so this assumes they listen on the same port as
+    /// we do, as they do when run with the same Crust config.
+    fn peer_listener_addr(&self, pub_id: &PublicId) -> Option<SocketAddr> {
+        let port = self.listener_port?;
+        let ip = self.crust_service.get_peer_ip_addr(pub_id).ok()?;
+        Some(SocketAddr::new(ip, port))
+    }
+
+    /// Adds a signature of our public ID by a member of our section to our `ProxyCertificate`.
+    fn handle_proxy_certificate_signature(
+        &mut self,
+        pub_id: PublicId,
+        sig: sign::Signature,
+    ) -> Result<(), RoutingError> {
+        if !self.routing_table().our_section().contains(pub_id.name()) {
+            debug!(
+                "{:?} Received proxy certificate signature from {}, which is not in our section.",
+                self, pub_id
+            );
+            return Err(RoutingError::InvalidSource);
+        }
+        let our_pub_id = *self.full_id.public_id();
+        if !self
+            .proxy_certificate
+            .add_signature(&our_pub_id, pub_id, sig)?
+        {
+            return Err(RoutingError::FailedSignature);
         }
         Ok(())
     }
@@ -786,6 +1699,14 @@ impl Node {
             return Err(RoutingError::UnknownConnection(pub_id));
         }
 
+        if self.quarantined.contains_key(&pub_id) {
+            debug!(
+                "{:?} Ignoring message signature from quarantined peer {}",
+                self, pub_id
+            );
+            return Ok(());
+        }
+
         let min_section_size = self.min_section_size();
         if let Some((signed_msg, route)) =
             self.sig_accumulator
@@ -913,13 +1834,59 @@ impl Node {
         }
     }
 
+    // Handles `hop_msg` from `pub_id`. Unless it is `verified` already, its signatures are checked
+    // first, by the `verifier` if it is enabled.
+    fn receive_hop_message(
+        &mut self,
+        hop_msg: HopMessage,
+        pub_id: PublicId,
+        verified: bool,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        if !verified && self.verifier.is_enabled() {
+            let quorum = self
+                .sig_accumulator
+                .quorum_for(hop_msg.content.routing_message());
+            let min_section_size = self.min_section_size();
+            let message_id = hop_msg.content.routing_message().content.message_id();
+            if !self
+                .verifier
+                .verify(pub_id, hop_msg, min_section_size, quorum)
+            {
+                debug!(
+                    "{:?} Too many messages awaiting verification; dropping {:?} from {}.",
+                    self, message_id, pub_id
+                );
+            }
+            return Ok(());
+        }
+        let message_id = hop_msg.content.routing_message().content.message_id();
+        let result = self
+            .handle_hop_message(hop_msg, pub_id, verified)
+            .map_err(|error| {
+                error.with_context(ErrorContext {
+                    stage: "receiving",
+                    message_id,
+                    peer: Some(pub_id),
+                })
+            });
+        if let Err(ref error) = result {
+            self.report_dropped_message(message_id, error, DropStage::Receiving, outbox);
+        }
+        result
+    }
+
     fn handle_hop_message(
         &mut self,
         hop_msg: HopMessage,
         pub_id: PublicId,
+        verified: bool,
     ) -> Result<(), RoutingError> {
-        hop_msg.verify(pub_id.signing_public_key())?;
+        if !verified {
+            hop_msg.verify(pub_id.signing_public_key())?;
+        }
         let mut client_ip = None;
+        let mut joining_node = false;
         let mut hop_name_result = match self.peer_mgr.get_peer(&pub_id).map(Peer::state) {
             Some(&PeerState::Bootstrapper { .. }) => {
                 warn!(
@@ -932,7 +1899,10 @@ impl Node {
                 client_ip = Some(ip);
                 Ok(*self.name())
             }
-            Some(&PeerState::JoiningNode) => Ok(*self.name()),
+            Some(&PeerState::JoiningNode) => {
+                joining_node = true;
+                Ok(*self.name())
+            }
             Some(&PeerState::Candidate(_))
             | Some(&PeerState::Proxy)
             | Some(&PeerState::Routing(_)) => Ok(*pub_id.name()),
@@ -948,6 +1918,12 @@ impl Node {
                         self, hop_msg, pub_id
                     );
                     return Ok(());
+                } else if self.retired_proxies.contains_key(&pub_id) {
+                    debug!(
+                        "{:?} Ignoring {:?} from retired proxy {:?}.",
+                        self, hop_msg, pub_id
+                    );
+                    return Ok(());
                 } else {
                     Ok(*self.name())
                     // FIXME - confirm we can return with an error here by running soak tests
@@ -957,7 +1933,41 @@ impl Node {
             }
         };
 
-        if let Some(ip) = client_ip {
+        if hop_name_result.is_ok() && self.is_forged_as_ours(&hop_msg.content) {
+            warn!(
+                "{:?} Received {:?} from {:?} claiming to be sent by us.",
+                self, hop_msg, pub_id
+            );
+            self.stats.count_forged_claimant();
+            if !hop_msg.content.signed_by(&pub_id) {
+                // The peer may just be relaying it, so only drop the message.
+                return Err(RoutingError::InvalidSource);
+            }
+            hop_name_result = Err(RoutingError::InvalidSource);
+        }
+
+        if joining_node {
+            self.peer_mgr.refresh_joining_node(&pub_id);
+        }
+
+        if hop_msg.relayed && (client_ip.is_some() || joining_node) {
+            debug!(
+                target: "routing_relay",
+                "{:?} {:?} from {:?} claims to have been relayed by a proxy.",
+                self, hop_msg, pub_id
+            );
+            hop_name_result = Err(RoutingError::InvalidSource);
+        }
+
+        if client_ip.is_some() && !hop_msg.content.signed_by_client(&pub_id) {
+            debug!(
+                target: "routing_relay",
+                "{:?} Message {:?} from client {:?} isn't signed by it. Refusing to relay.",
+                self, hop_msg, pub_id
+            );
+            self.stats.count_unverified_client_msg();
+            hop_name_result = Err(RoutingError::FailedSignature);
+        } else if let Some(ip) = client_ip {
             match self.check_valid_client_message(&ip, hop_msg.content.routing_message()) {
                 Ok(added_bytes) => {
                     self.proxy_load_amount += added_bytes;
@@ -975,7 +1985,11 @@ impl Node {
                     sent_to,
                     ..
                 } = hop_msg;
-                self.handle_signed_message(content, route, hop_name, &sent_to)
+                if verified {
+                    self.handle_verified_signed_message(content, route, hop_name, &sent_to)
+                } else {
+                    self.handle_signed_message(content, route, hop_name, &sent_to)
+                }
             }
             Err(RoutingError::ExceedsRateLimit(hash)) => {
                 trace!(
@@ -998,6 +2012,29 @@ impl Node {
         }
     }
 
+    // Returns whether the signature of `pub_id` may count towards a quorum. If the signer is in our
+    // routing table, it has to be there with exactly that key. Otherwise, e.g. for a member which
+    // joined its section during churn and isn't known to us yet, we have no better knowledge than
+    // the sender's section lists, which `check_integrity` already checks the signers against.
+    fn is_verifiable_signer(&self, pub_id: &PublicId) -> bool {
+        if pub_id == self.full_id.public_id() {
+            return true;
+        }
+        let name = pub_id.name();
+        !self.routing_table().has(name) || self.peer_mgr.get_pub_id(name) == Some(pub_id)
+    }
+
+    // Returns whether the message claims to come from us as an individual node or client, without
+    // having been signed by us.
+    fn is_forged_as_ours(&self, signed_msg: &SignedMessage) -> bool {
+        let claims_us = match signed_msg.routing_message().src {
+            Authority::ManagedNode(name) => name == *self.name(),
+            Authority::Client { client_id, .. } => client_id == *self.full_id.public_id(),
+            _ => false,
+        };
+        claims_us && !signed_msg.signed_by(self.full_id.public_id())
+    }
+
     // Verify the message, then, if it is for us, handle the enclosed routing message and swarm it
     // to the rest of our section when destination is targeting multiple; if not, forward it.
     fn handle_signed_message(
@@ -1007,7 +2044,35 @@ impl Node {
         hop_name: XorName,
         sent_to: &BTreeSet<XorName>,
     ) -> Result<(), RoutingError> {
-        signed_msg.check_integrity(self.min_section_size())?;
+        let quorum = self
+            .sig_accumulator
+            .quorum_for(signed_msg.routing_message());
+        signed_msg.check_integrity(self.min_section_size(), quorum)?;
+        self.handle_verified_signed_message(signed_msg, route, hop_name, sent_to)
+    }
+
+    // Like `handle_signed_message`, for a message whose signatures were already checked.
+    fn handle_verified_signed_message(
+        &mut self,
+        signed_msg: SignedMessage,
+        route: u8,
+        hop_name: XorName,
+        sent_to: &BTreeSet<XorName>,
+    ) -> Result<(), RoutingError> {
+        let quorum = self
+            .sig_accumulator
+            .quorum_for(signed_msg.routing_message());
+        if signed_msg.routing_message().src.is_multiple()
+            && !signed_msg.has_enough_verifiable_sigs(self.min_section_size(), quorum, |pub_id| {
+                self.is_verifiable_signer(pub_id)
+            })
+        {
+            warn!(
+                "{:?} Not enough signatures from known members in {:?}.",
+                self, signed_msg
+            );
+            return Err(RoutingError::NotEnoughSignatures);
+        }
 
         // TODO(MAID-1677): Remove this once messages are fully validated.
         // Expect group/section messages to be sent by at least a quorum of `min_section_size`.
@@ -1025,11 +2090,15 @@ impl Node {
             .filter_incoming(signed_msg.routing_message(), route)
         {
             FilteringResult::KnownMessageAndRoute => {
+                self.report_trace(signed_msg.routing_message(), TraceDecision::Dropped);
                 return Ok(());
             }
             frslt @ FilteringResult::KnownMessage | frslt @ FilteringResult::NewMessage => {
                 if self.in_authority(&signed_msg.routing_message().dst) {
-                    self.send_ack(signed_msg.routing_message(), route);
+                    self.report_trace(signed_msg.routing_message(), TraceDecision::Handled);
+                    if self.has_consistent_dst_view(&signed_msg) {
+                        self.send_ack(signed_msg.routing_message(), route);
+                    }
                     if signed_msg.routing_message().dst.is_multiple() {
                         // Broadcast to the rest of the section.
                         if let Err(error) =
@@ -1040,7 +2109,9 @@ impl Node {
                     }
                     if frslt == FilteringResult::NewMessage {
                         // if addressed to us, then we just queue it and return
-                        self.msg_queue.push_back(signed_msg.into_routing_message());
+                        let signers = signed_msg.signers().cloned().collect();
+                        self.msg_queue
+                            .push_back((signed_msg.into_routing_message(), signers));
                     }
                     return Ok(());
                 }
@@ -1048,11 +2119,15 @@ impl Node {
         }
 
         if self.respond_from_cache(signed_msg.routing_message(), route)? {
+            self.report_trace(signed_msg.routing_message(), TraceDecision::Handled);
             return Ok(());
         }
 
         if let Err(error) = self.send_signed_message(&signed_msg, route, &hop_name, sent_to) {
             debug!("{:?} Failed to send {:?}: {:?}", self, signed_msg, error);
+            self.report_trace(signed_msg.routing_message(), TraceDecision::Dropped);
+        } else {
+            self.report_trace(signed_msg.routing_message(), TraceDecision::Forwarded);
         }
 
         Ok(())
@@ -1061,6 +2136,7 @@ impl Node {
     fn dispatch_routing_message(
         &mut self,
         routing_msg: RoutingMessage,
+        signers: BTreeSet<PublicId>,
         outbox: &mut EventBox,
     ) -> Result<(), RoutingError> {
         use crate::messages::MessageContent::*;
@@ -1073,8 +2149,15 @@ impl Node {
                 | OtherSectionMerge(..)
                 | ExpectCandidate { .. }
                 | AcceptAsCandidate { .. }
+                | ShareCandidate { .. }
                 | CandidateApproval { .. }
                 | SectionUpdate { .. }
+                | SectionUpdateRequest(..)
+                | GetCloseGroup { .. }
+                | GetCloseGroupResponse { .. }
+                | ReportUnreachable(..)
+                | AccumulationFailed { .. }
+                | CachePrimingRequest { .. }
                 | UserMessagePart { .. } => {
                     // These messages should not be handled before node approval
                     trace!(
@@ -1082,7 +2165,7 @@ impl Node {
                         self,
                         routing_msg
                     );
-                    self.routing_msg_backlog.push(routing_msg);
+                    self.routing_msg_backlog.push((routing_msg, signers));
                     return Ok(());
                 }
                 ConnectionInfoRequest { .. } => {
@@ -1093,15 +2176,18 @@ impl Node {
                             self,
                             routing_msg
                         );
-                        self.routing_msg_backlog.push(routing_msg);
+                        self.routing_msg_backlog.push((routing_msg, signers));
                         return Ok(());
                     }
                 }
                 Relocate { .. }
                 | ConnectionInfoResponse { .. }
                 | RelocateResponse { .. }
+                | RelocateDeferred { .. }
+                | RelocateDenied { .. }
                 | Ack(..)
-                | NodeApproval { .. } => {
+                | NodeApproval { .. }
+                | TraceReport { .. } => {
                     // Handle like normal
                 }
             }
@@ -1153,6 +2239,17 @@ impl Node {
                 message_id,
                 outbox,
             ),
+            (
+                ShareCandidate {
+                    old_public_id,
+                    target_interval,
+                },
+                Section(_),
+                ManagedNode(_),
+            ) => {
+                self.handle_share_candidate(old_public_id, target_interval);
+                Ok(())
+            }
             (
                 ConnectionInfoRequest {
                     encrypted_conn_info,
@@ -1228,6 +2325,30 @@ impl Node {
                 Section(_),
                 PrefixSection(_),
             ) => self.handle_section_update(versioned_prefix, members, outbox),
+            (GetCloseGroup { name, message_id }, ManagedNode(src_name), Section(_)) => {
+                self.handle_get_close_group(name, message_id, src_name)
+            }
+            (GetCloseGroupResponse { close_group, .. }, Section(_), ManagedNode(_)) => {
+                self.handle_get_close_group_response(close_group, outbox);
+                Ok(())
+            }
+            (
+                CachePrimingRequest {
+                    prefix,
+                    max_bytes,
+                    message_id,
+                },
+                ManagedNode(src_name),
+                ManagedNode(_),
+            ) => self.handle_cache_priming_request(prefix, max_bytes, message_id, src_name),
+            (ReportUnreachable(name), Section(_), Section(_)) => {
+                self.handle_report_unreachable(name, outbox);
+                Ok(())
+            }
+            (SectionUpdateRequest(prefix), ManagedNode(_), PrefixSection(_)) => {
+                self.send_section_update(Some(prefix), false);
+                Ok(())
+            }
             (SectionSplit(ver_pfx, joining_node), PrefixSection(_), PrefixSection(_)) => {
                 self.handle_section_split(ver_pfx, joining_node, outbox)
             }
@@ -1244,6 +2365,47 @@ impl Node {
                 self.handle_other_section_merge(merge_prefix.with_version(version), section, outbox)
             }
             (Ack(ack, _), _, _) => self.handle_ack_response(ack),
+            (
+                TraceReport {
+                    ack,
+                    decision,
+                    timestamp,
+                },
+                ManagedNode(hop_name),
+                dst,
+            ) => {
+                info!(
+                    target: "routing_trace",
+                    "{:?} Trace of {:?} to {:?}: {} {:?} it at {}.",
+                    self, ack, dst, hop_name, decision, timestamp
+                );
+                Ok(())
+            }
+            (
+                AccumulationFailed {
+                    msg_id,
+                    dst: failed_dst,
+                },
+                ManagedNode(reporter),
+                src,
+            ) => {
+                // Only the members of `src` which may have accumulated the message can report it,
+                // so that other nodes can't make us drop messages which were delivered.
+                if !src.is_multiple() || !self.signature_targets(&src).contains(&&reporter) {
+                    debug!(
+                        "{:?} Ignoring report of failed accumulation of {:?} by {}, which is not \
+                         a signature target for {:?}.",
+                        self, msg_id, reporter, src
+                    );
+                    return Err(RoutingError::InvalidSource);
+                }
+                outbox.send_event(Event::AccumulationFailed {
+                    message_id: msg_id,
+                    src,
+                    dst: failed_dst,
+                });
+                Ok(())
+            }
             (
                 UserMessagePart {
                     hash,
@@ -1256,12 +2418,29 @@ impl Node {
                 dst,
             ) => {
                 self.stats.increase_user_msg_part();
-                if let Some(msg) = self
+                if let Some((msg, signers)) = self
                     .user_msg_cache
-                    .add(hash, part_count, part_index, payload)
+                    .add_signed(hash, part_count, part_index, payload, signers)
                 {
                     self.stats().count_user_message(&msg);
-                    outbox.send_event(msg.into_event(src, dst));
+                    let priming = match msg {
+                        UserMessage::Request(ref request) => {
+                            if !self.accept_request(request, src, dst) {
+                                return Ok(());
+                            }
+                            false
+                        }
+                        UserMessage::Response(ref response) => {
+                            self.cache_priming_ids.contains_key(response.message_id())
+                        }
+                        UserMessage::Tagged { .. } => false,
+                    };
+                    match msg {
+                        UserMessage::Response(response) if priming => {
+                            self.put_primed_response(response, src)
+                        }
+                        msg => outbox.send_event(msg.into_event(src, dst, signers)),
+                    }
                 }
                 Ok(())
             }
@@ -1275,6 +2454,88 @@ impl Node {
         }
     }
 
+    /// Checks a refresh against the `RefreshPolicy` for its type tag.
+    fn accept_refresh(&self, content: &[u8], msg_id: MessageId) -> bool {
+        let type_tag = msg_id.refresh_type_tag();
+        let policy = self.sig_accumulator.refresh_policy(type_tag);
+        let max_len = match policy.max_payload_len {
+            Some(max_len) => max_len,
+            None => return true,
+        };
+        match serialisation::deserialise::<(u64, Vec<u8>)>(content) {
+            Ok((tag, ref payload)) if tag == type_tag && payload.len() > max_len => {
+                debug!(
+                    "{:?} Dropping refresh {:?} with type tag {}, which exceeds its maximum \
+                     payload size.",
+                    self, msg_id, type_tag
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks `request` with the `request_validator`, if any. A rejected request is answered with
+    /// the validator's error, and counted against its client: once a client had too many requests
+    /// rejected, its further requests are dropped without a response. Refreshes are checked against
+    /// their `RefreshPolicy` first.
+    fn accept_request(
+        &mut self,
+        request: &Request,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+    ) -> bool {
+        if let Request::Refresh(ref content, msg_id) = *request {
+            if !self.accept_refresh(content, msg_id) {
+                return false;
+            }
+        }
+        let client_id = match src {
+            Authority::Client { client_id, .. } => Some(client_id),
+            _ => None,
+        };
+        if let Some(client_id) = client_id {
+            let rejected = self.rejected_requests.get(&client_id).cloned().unwrap_or(0);
+            if rejected >= MAX_REJECTED_REQUESTS {
+                debug!(
+                    "{:?} Dropping request {:?} from {:?}, who sent too many invalid requests.",
+                    self,
+                    request.message_id(),
+                    client_id
+                );
+                return false;
+            }
+        }
+
+        let error = match self.request_validator {
+            Some(ref validator) => match validator.validate(&src, &dst, request) {
+                Ok(()) => return true,
+                Err(error) => error,
+            },
+            None => return true,
+        };
+        debug!(
+            "{:?} Rejecting request {:?} from {:?}: {:?}",
+            self,
+            request.message_id(),
+            src,
+            error
+        );
+        if let Some(response) = request.error_response(error) {
+            let priority = response.priority();
+            if let Err(error) =
+                self.send_user_message(dst, src, UserMessage::Response(response), priority)
+            {
+                debug!("{:?} Failed to reject request: {:?}", self, error);
+            }
+        }
+        if let Some(client_id) = client_id {
+            let count = self.rejected_requests.get(&client_id).cloned().unwrap_or(0);
+            let _ = self.rejected_requests.insert(client_id, count + 1);
+        }
+        false
+    }
+
     fn handle_candidate_approval(
         &mut self,
         new_pub_id: PublicId,
@@ -1392,6 +2653,11 @@ impl Node {
 
         self.is_approved = true;
         outbox.send_event(Event::Connected);
+        self.group_connected = self.routing_table().our_section().len() >= self.min_section_size();
+        if self.group_connected {
+            self.request_cache_priming();
+        }
+        self.close_group_names = self.our_close_group();
         for name in self.routing_table().iter() {
             // TODO: try to remove this as safe_core/safe_vault may not require this notification
             outbox.send_event(Event::NodeAdded(*name, self.routing_table().clone()));
@@ -1399,6 +2665,12 @@ impl Node {
 
         let our_prefix = *self.our_prefix();
         self.send_section_list_signature(our_prefix, None);
+        let our_section = self
+            .peer_mgr
+            .get_pub_ids(self.routing_table().our_section());
+        for pub_id in our_section {
+            self.send_proxy_certificate_signature(pub_id);
+        }
 
         for section in sections.values() {
             for pub_id in section.iter() {
@@ -1425,6 +2697,8 @@ impl Node {
             }
         }
 
+        self.connect_to_static_contacts(outbox);
+
         info!(
             "{:?} Resource proof challenges completed. This node has been approved to join the \
              network!",
@@ -1443,7 +2717,9 @@ impl Node {
                 self, err
             );
         }
-        self.crust_service.set_service_discovery_listen(true);
+        if self.lan_discovery {
+            self.crust_service.set_service_discovery_listen(true);
+        }
 
         self.print_rt_size();
         self.stats.enable_logging();
@@ -1505,12 +2781,14 @@ impl Node {
                     elapsed.display_secs(),
                     self.our_prefix()
                 );
+                let _ = self.proven_candidates.insert(pub_id, ());
                 // We set the timer token to None so we do not send another
                 // CandidateApproval when the token fires
                 self.candidate_timer_token = None;
                 self.send_candidate_approval();
             }
             Ok(Some((_, _, elapsed))) => {
+                let _ = self.proven_candidates.insert(pub_id, ());
                 info!(
                     "{:?} Candidate {} passed our challenge in {}. Waiting to send approval to \
                      our section with {:?}.",
@@ -1557,6 +2835,7 @@ impl Node {
             }
             _ => {
                 debug!(
+                    target: "routing_relay",
                     "{:?} Illegitimate client message {:?}. Refusing to relay.",
                     self, msg
                 );
@@ -1604,33 +2883,153 @@ impl Node {
                 .add(hash, part_count, part_index, payload.clone())
             {
                 Some(UserMessage::Request(request)) => {
-                    if let Some(response) = self.response_cache.get(&request) {
-                        debug!("{:?} Found cached response to {:?}", self, request);
+                    if let Some(data_id) = request.mutated_data() {
+                        let _ = self
+                            .pending_mutations
+                            .insert(*request.message_id(), data_id);
+                    } else if let Some(response) = self.lookup_cached_response(&request) {
+                        debug!(
+                            target: "routing_cache",
+                            "{:?} Found cached response to {:?}",
+                            self, request
+                        );
 
-                        let priority = response.priority();
                         let src = Authority::ManagedNode(*self.name());
-                        let dst = routing_msg.src;
-                        let msg = UserMessage::Response(response);
-
                         self.send_ack_from(routing_msg, route, src);
-                        self.send_user_message(src, dst, msg, priority)?;
+
+                        // We stop forwarding the request here, but it may have reached other
+                        // caching nodes via earlier routes. Delay our response so that the node
+                        // closest to the destination is likely to answer first.
+                        let already_pending = self
+                            .pending_cache_responses
+                            .values()
+                            .any(|&(_, ref pending)| pending.message_id() == response.message_id());
+                        if !already_pending {
+                            let delay = self.cache_response_delay(&routing_msg.dst);
+                            let token = self.timer.schedule(delay);
+                            let _ = self
+                                .pending_cache_responses
+                                .insert(token, (routing_msg.src, response));
+                        }
 
                         return Ok(true);
                     }
                 }
 
                 Some(UserMessage::Response(response)) => {
-                    debug!("{:?} Putting {:?} in cache", self, response);
+                    if let Some(result) = response.mutation_result() {
+                        let data_id = self.pending_mutations.remove(response.message_id());
+                        if let (Some((name, tag)), &Ok(())) = (data_id, result) {
+                            debug!(
+                                target: "routing_cache",
+                                "{:?} Invalidating cached responses for {:?} ({})",
+                                self, name, tag
+                            );
+                            self.response_cache.invalidate(&name, tag);
+                            self.cache_stats.invalidations += 1;
+                        }
+                        return Ok(false);
+                    }
+
+                    // Someone else already answered, so we don't need to.
+                    let answered = self
+                        .pending_cache_responses
+                        .iter()
+                        .filter(|&(_, &(_, ref pending))| {
+                            pending.message_id() == response.message_id()
+                        })
+                        .map(|(token, _)| *token)
+                        .collect_vec();
+                    for token in answered {
+                        let _ = self.pending_cache_responses.remove(&token);
+                    }
+                    if response.is_mdata() && !self.response_cache.caches_mdata() {
+                        return Ok(false);
+                    }
+                    if let Some((name, tag, version)) = response.mdata_version() {
+                        let cached = self.response_cache.mdata_version(&name, tag);
+                        if cached.map_or(false, |cached| cached > version) {
+                            debug!(
+                                target: "routing_cache",
+                                "{:?} Not caching version {} of {:?} ({}), we hold a newer one.",
+                                self, version, name, tag
+                            );
+                            self.cache_stats.stale_responses += 1;
+                            return Ok(false);
+                        }
+                    }
+                    debug!(target: "routing_cache", "{:?} Putting {:?} in cache", self, response);
                     self.response_cache.put(response);
+                    self.cache_stats.puts += 1;
                 }
 
-                None => (),
+                Some(UserMessage::Tagged { .. }) | None => (),
             }
         }
 
         Ok(false)
     }
 
+    fn lookup_cached_response(&mut self, request: &Request) -> Option<Response> {
+        self.cache_stats.lookups += 1;
+        let response = self.response_cache.get(request);
+        if response.is_some() {
+            self.cache_stats.hits += 1;
+        }
+        response
+    }
+
+    /// Returns whether `response_cache` holds a response to `request`.
+    pub fn cache_contains(&self, request: &Request) -> bool {
+        request.is_cacheable() && self.response_cache.get(request).is_some()
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.response_cache.entry_count(),
+            ..self.cache_stats
+        }
+    }
+
+    /// Returns the peers we recently failed to connect to, with the number of consecutive failures
+    /// and the time left until we try again.
+    pub fn connect_backoffs(&self) -> BTreeMap<XorName, (u32, Duration)> {
+        self.connect_failures
+            .peek_iter()
+            .map(|(name, &(failed_at, failures))| {
+                let delay = self.connect_retry_delay(failures);
+                let remaining = delay
+                    .checked_sub(failed_at.elapsed())
+                    .unwrap_or_else(|| Duration::from_secs(0));
+                (*name, (failures, remaining))
+            })
+            .collect()
+    }
+
+    // Returns a random delay of up to `CACHE_RESPONSE_MAX_DELAY_MS`, weighted so that nodes
+    // sharing a longer prefix with `dst` tend to wait less.
+    fn cache_response_delay(&self, dst: &Authority<XorName>) -> Duration {
+        let half_max = CACHE_RESPONSE_MAX_DELAY_MS / 2;
+        let distance_bits = (XOR_NAME_BITS - self.name().common_prefix(&dst.name())) as u64;
+        let base = half_max * distance_bits / XOR_NAME_BITS as u64;
+        let jitter = rng::with_rng(|rng| rng.gen_range(0, half_max + 1));
+        Duration::from_millis(base + jitter)
+    }
+
+    fn send_cache_response(&mut self, dst: Authority<XorName>, response: Response) {
+        let priority = response.priority();
+        let src = Authority::ManagedNode(*self.name());
+        if let Err(error) =
+            self.send_user_message(src, dst, UserMessage::Response(response), priority)
+        {
+            debug!(
+                target: "routing_cache",
+                "{:?} Failed to send cached response: {:?}",
+                self, error
+            );
+        }
+    }
+
     // If this returns an error, the peer will be dropped.
     fn handle_bootstrap_request(
         &mut self,
@@ -1657,6 +3056,7 @@ impl Node {
                 .get_peer_ip_addr(&pub_id)
                 .map_err(|err| {
                     debug!(
+                        target: "routing_relay",
                         "{:?} Can't get IP address of bootstrapper {:?} : {:?}",
                         self, pub_id, err
                     );
@@ -1664,8 +3064,9 @@ impl Node {
                     err
                 })?;
 
-            if !self.peer_mgr.can_accept_client(ip) {
+            if !self.peer_mgr.can_accept_client(&pub_id, ip) {
                 debug!(
+                    target: "routing_relay",
                     "{:?} Client {:?} rejected: We cannot accept more clients.",
                     self, pub_id
                 );
@@ -1676,6 +3077,18 @@ impl Node {
                 self.disconnect_peer(&pub_id, None);
                 return Ok(());
             }
+        } else if !self.peer_mgr.can_accept_joining_node() {
+            debug!(
+                target: "routing_relay",
+                "{:?} Joining node {:?} rejected: We cannot accept more joining nodes.",
+                self, pub_id
+            );
+            self.send_direct_message(
+                pub_id,
+                DirectMessage::BootstrapResponse(Err(BootstrapResponseError::JoiningNodeLimit)),
+            );
+            self.disconnect_peer(&pub_id, None);
+            return Ok(());
         }
 
         let ser_pub_id = serialisation::serialise(&pub_id)?;
@@ -1685,6 +3098,7 @@ impl Node {
 
         if !self.is_approved {
             debug!(
+                target: "routing_relay",
                 "{:?} Client {:?} rejected: We are not approved as a node yet.",
                 self, pub_id
             );
@@ -1700,6 +3114,7 @@ impl Node {
             && self.routing_table().len() < self.min_section_size() - 1
         {
             debug!(
+                target: "routing_relay",
                 "{:?} Client {:?} rejected: Routing table has {} entries. {} required.",
                 self,
                 pub_id,
@@ -1717,6 +3132,18 @@ impl Node {
         self.peer_mgr.handle_bootstrap_request(&pub_id);
         let _ = self.dropped_clients.remove(&pub_id);
         self.send_direct_message(pub_id, DirectMessage::BootstrapResponse(Ok(())));
+        if peer_kind == CrustUser::Client {
+            let members = self
+                .peer_mgr
+                .get_pub_ids(self.routing_table().our_section());
+            let cert = self
+                .proxy_certificate
+                .for_section(*self.routing_table().our_prefix(), members);
+            self.send_direct_message(pub_id, DirectMessage::ProxyCertificate(cert));
+            for signed_msg in self.client_buffer.take(&pub_id) {
+                let _ = self.relay_to_client(&signed_msg, &pub_id);
+            }
+        }
         Ok(())
     }
 
@@ -1744,7 +3171,32 @@ impl Node {
                 "{:?} Signature check failed in CandidateInfo, so dropping peer {:?}.",
                 self, new_pub_id
             );
-            self.disconnect_peer(new_pub_id, Some(outbox));
+            self.ban_and_disconnect_peer(new_pub_id);
+            return;
+        }
+
+        // A candidate may resend its `CandidateInfo`, but only the relocation it identified with
+        // first links its old ID to this connection. Anything else is an attempt to take over
+        // another node's relocation.
+        match self.candidate_old_ids.get(new_pub_id).cloned() {
+            Some(known_old_pub_id) if known_old_pub_id != *old_pub_id => {
+                warn!(
+                    "{:?} Candidate {} identified as {} before, but now as {}, so dropping it.",
+                    self, new_pub_id, known_old_pub_id, old_pub_id
+                );
+                self.ban_and_disconnect_peer(new_pub_id);
+                return;
+            }
+            Some(_) => {
+                debug!(
+                    "{:?} Received repeated CandidateInfo from {}->{}.",
+                    self, old_pub_id, new_pub_id
+                );
+            }
+            None => {
+                let _ = self.candidate_old_ids.insert(*new_pub_id, *old_pub_id);
+                self.drop_relocated_joining_node(old_pub_id, outbox);
+            }
         }
 
         // If this is a valid node in peer_mgr but the Candidate has sent us a CandidateInfo, it
@@ -1758,6 +3210,18 @@ impl Node {
             return;
         }
 
+        // Racing relocations could have given two nodes the same name. The existing holder keeps
+        // it, and the candidate has to rejoin with a new one.
+        if new_pub_id.name() == self.name() || self.routing_table().has(new_pub_id.name()) {
+            warn!(
+                "{:?} Candidate {}->{} has the name of an existing node, so rejecting it.",
+                self, old_pub_id, new_pub_id
+            );
+            self.send_direct_message(*new_pub_id, DirectMessage::CandidateNameTaken);
+            self.disconnect_peer(new_pub_id, Some(outbox));
+            return;
+        }
+
         let (difficulty, target_size) = if self.disable_resource_proof
             || self.crust_service.is_peer_hard_coded(new_pub_id)
             || self.peer_mgr.is_joining_node(new_pub_id)
@@ -1772,7 +3236,7 @@ impl Node {
         let seed: Vec<u8> = if cfg!(feature = "use-mock-crust") {
             vec![5u8; 4]
         } else {
-            rand::thread_rng().gen_iter().take(10).collect()
+            rng::with_rng(|rng| rng.gen_iter().take(10).collect())
         };
         match self.peer_mgr.handle_candidate_info(
             old_pub_id,
@@ -1817,6 +3281,41 @@ impl Node {
         }
     }
 
+    // A single member could lie about our name being taken, so we only restart once a quorum of
+    // the section we are joining reported it.
+    fn handle_candidate_name_taken(&mut self, pub_id: PublicId, outbox: &mut EventBox) {
+        if self.is_approved || !self.joining_members.contains(&pub_id) {
+            debug!(
+                "{:?} Ignoring CandidateNameTaken from {}, as we are not joining its section.",
+                self, pub_id
+            );
+            return;
+        }
+        if self.restart_required {
+            return;
+        }
+        let _ = self.name_taken_reports.insert(pub_id);
+        if !self
+            .sig_accumulator
+            .quorum()
+            .is_reached(self.name_taken_reports.len(), self.joining_members.len())
+        {
+            debug!(
+                "{:?} {} of {} section members reported our name as taken.",
+                self,
+                self.name_taken_reports.len(),
+                self.joining_members.len()
+            );
+            return;
+        }
+        info!(
+            "{:?} Our relocated name is already taken in the section. Restarting.",
+            self
+        );
+        self.restart_required = true;
+        outbox.send_event(Event::RestartRequired);
+    }
+
     fn is_candidate_info_valid(
         &self,
         old_pub_id: &PublicId,
@@ -1863,6 +3362,7 @@ impl Node {
             Err(RoutingError::RoutingTable(RoutingTableError::AlreadyExists)) => return,
             Err(error) => {
                 debug!(
+                    target: "routing_churn",
                     "{:?} Peer {:?} was not added to the routing table: {:?}",
                     self, pub_id, error
                 );
@@ -1871,6 +3371,18 @@ impl Node {
             }
             Ok(()) => (),
         }
+        // Entries we add while joining make up the section we join, so they are trusted.
+        if self.is_approved
+            && self.quarantine_duration > Duration::from_secs(0)
+            && self.proven_candidates.remove(pub_id).is_none()
+        {
+            debug!(
+                target: "routing_churn",
+                "{:?} Quarantining {} for {}.",
+                self, pub_id, self.quarantine_duration.display_secs()
+            );
+            let _ = self.quarantined.insert(*pub_id, ());
+        }
         if !self.we_want_to_merge()
             && !self.they_want_to_merge()
             && self.routing_table().should_split()
@@ -1887,9 +3399,10 @@ impl Node {
             self.merge_if_necessary(outbox);
         }
 
-        info!("{:?} Added {} to routing table.", self, pub_id);
+        info!(target: "routing_churn", "{:?} Added {} to routing table.", self, pub_id);
         if self.is_first_node && self.routing_table().len() == 1 {
             trace!(
+                target: "routing_churn",
                 "{:?} Node approval completed. Prefixes: {:?}",
                 self,
                 self.routing_table().prefixes()
@@ -1902,6 +3415,8 @@ impl Node {
                 *pub_id.name(),
                 self.routing_table().clone(),
             ));
+            self.report_table_growth(outbox);
+            self.note_table_change(pub_id.name(), outbox);
 
             if let Some(prefix) = self.routing_table().find_section_prefix(pub_id.name()) {
                 self.send_section_list_signature(prefix, None);
@@ -1912,6 +3427,8 @@ impl Node {
                     for pfx in self.routing_table().prefixes() {
                         self.send_section_list_signature(pfx, Some(*pub_id.name()));
                     }
+                    self.share_candidate(*pub_id.name());
+                    self.send_proxy_certificate_signature(*pub_id);
                 } else {
                     self.send_section_update(Some(prefix), false);
                 }
@@ -1924,6 +3441,7 @@ impl Node {
                 .is_potential_tunnel_node(pub_id.name(), dst_id.name())
             {
                 trace!(
+                    target: "routing_churn",
                     "{:?} Asking {:?} to serve as a tunnel for {:?}",
                     self,
                     pub_id,
@@ -1991,6 +3509,10 @@ impl Node {
     }
 
     // If `msg_id` is `Some` this is sent as a response, otherwise as a request.
+    //
+    // The connection info is opaque to us: Crust includes every endpoint it knows of, including
+    // externally mapped ones, and chooses the order in which to try them when connecting. So we
+    // only ever forward it as a whole.
     fn send_connection_info(
         &mut self,
         our_pub_info: PubConnectionInfo,
@@ -1999,15 +3521,28 @@ impl Node {
         dst: Authority<XorName>,
         msg_id: Option<MessageId>,
     ) {
-        let encoded_connection_info = match serialisation::serialise(&our_pub_info) {
-            Ok(encoded_connection_info) => encoded_connection_info,
-            Err(err) => {
-                debug!(
-                    "{:?} Failed to serialise connection info for {:?}: {:?}.",
-                    self, their_pub_id, err
-                );
-                return;
-            }
+        self.send_connection_info_via_route(our_pub_info, their_pub_id, src, dst, msg_id, 0)
+    }
+
+    fn send_connection_info_via_route(
+        &mut self,
+        our_pub_info: PubConnectionInfo,
+        their_pub_id: PublicId,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        msg_id: Option<MessageId>,
+        route: u8,
+    ) {
+        let encoded_connection_info = match serialisation::serialise(&our_pub_info) {
+            Ok(encoded_connection_info) => encoded_connection_info,
+            Err(err) => {
+                debug!(
+                    target: "routing_connect",
+                    "{:?} Failed to serialise connection info for {:?}: {:?}.",
+                    self, their_pub_id, err
+                );
+                return;
+            }
         };
         let nonce = box_::gen_nonce();
         let encrypted_conn_info = box_::seal(
@@ -2016,7 +3551,7 @@ impl Node {
             their_pub_id.encrypting_public_key(),
             self.full_id.encrypting_private_key(),
         );
-        let msg_content = if let Some(msg_id) = msg_id {
+        let content = if let Some(msg_id) = msg_id {
             MessageContent::ConnectionInfoResponse {
                 encrypted_conn_info,
                 nonce: nonce.0,
@@ -2024,6 +3559,7 @@ impl Node {
                 msg_id,
             }
         } else {
+            self.schedule_connection_info_retry(their_pub_id, src, dst, route);
             MessageContent::ConnectionInfoRequest {
                 encrypted_conn_info,
                 nonce: nonce.0,
@@ -2032,14 +3568,81 @@ impl Node {
             }
         };
 
-        if let Err(err) = self.send_routing_message(src, dst, msg_content) {
+        let routing_msg = RoutingMessage {
+            src,
+            dst,
+            content,
+            trace: false,
+        };
+        if let Err(err) = self.send_routing_message_via_route(routing_msg, route, None) {
             debug!(
+                target: "routing_connect",
                 "{:?} Failed to send connection info for {:?}: {:?}.",
                 self, their_pub_id, err
             );
         }
     }
 
+    // If the response to our connection info request gets lost, we would never connect, so we
+    // resend the request unless we hear back in time.
+    fn schedule_connection_info_retry(
+        &mut self,
+        their_pub_id: PublicId,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        route: u8,
+    ) {
+        let previous = self
+            .connection_info_retries
+            .iter()
+            .filter(|&(_, &(ref pub_id, ..))| *pub_id == their_pub_id)
+            .map(|(token, _)| *token)
+            .collect_vec();
+        for token in previous {
+            let _ = self.connection_info_retries.remove(&token);
+        }
+        let token = self
+            .timer
+            .schedule(Duration::from_secs(CONNECTION_INFO_RETRY_SECS));
+        let _ = self
+            .connection_info_retries
+            .insert(token, (their_pub_id, src, dst, route));
+    }
+
+    // Resends the connection info request via the next route if we are still waiting for the
+    // response, or gives up and backs off from the peer once all attempts are used.
+    fn retry_connection_info(
+        &mut self,
+        their_pub_id: PublicId,
+        src: Authority<XorName>,
+        dst: Authority<XorName>,
+        route: u8,
+    ) {
+        let our_pub_info = match self.peer_mgr.get_peer(&their_pub_id).map(Peer::state) {
+            Some(&PeerState::ConnectionInfoReady(ref our_priv_info)) => {
+                our_priv_info.to_pub_connection_info()
+            }
+            _ => return,
+        };
+        let next_route = route + 1;
+        if next_route >= MAX_CONNECTION_INFO_ATTEMPTS {
+            debug!(
+                target: "routing_connect",
+                "{:?} Got no connection info response from {:?} after {} attempts. Giving up.",
+                self, their_pub_id, next_route
+            );
+            let _ = self.peer_mgr.remove_peer(&their_pub_id);
+            self.record_connect_failure(*their_pub_id.name());
+            return;
+        }
+        debug!(
+            target: "routing_connect",
+            "{:?} Got no connection info response from {:?}. Resending the request via route {}.",
+            self, their_pub_id, next_route
+        );
+        self.send_connection_info_via_route(our_pub_info, their_pub_id, src, dst, None, next_route);
+    }
+
     fn handle_connection_info_prepared(
         &mut self,
         result_token: u32,
@@ -2048,12 +3651,14 @@ impl Node {
         let our_connection_info = match result {
             Err(err) => {
                 error!(
+                    target: "routing_connect",
                     "{:?} Failed to prepare connection info: {:?}. Retrying.",
                     self, err
                 );
                 let new_token = match self.peer_mgr.get_new_connection_info_token(result_token) {
                     Err(error) => {
                         debug!(
+                            target: "routing_connect",
                             "{:?} Failed to prepare connection info, but no entry found in \
                              token map: {:?}",
                             self, error
@@ -2076,6 +3681,7 @@ impl Node {
             Err(error) => {
                 // This usually means we have already connected.
                 debug!(
+                    target: "routing_connect",
                     "{:?} Prepared connection info, but no entry found in token map: {:?}",
                     self, error
                 );
@@ -2088,11 +3694,16 @@ impl Node {
                 infos,
             }) => match infos {
                 None => {
-                    debug!("{:?} Prepared connection info for {:?}.", self, pub_id);
+                    debug!(
+                        target: "routing_connect",
+                        "{:?} Prepared connection info for {:?}.",
+                        self, pub_id
+                    );
                     self.send_connection_info(our_pub_info, pub_id, src, dst, None);
                 }
                 Some((our_info, their_info, msg_id)) => {
                     debug!(
+                        target: "routing_connect",
                         "{:?} Trying to connect to {:?} as {:?}.",
                         self,
                         their_info.id(),
@@ -2100,7 +3711,11 @@ impl Node {
                     );
                     self.send_connection_info(our_pub_info, pub_id, src, dst, Some(msg_id));
                     if let Err(error) = self.crust_service.connect(our_info, their_info) {
-                        trace!("{:?} Unable to connect to {:?} - {:?}", self, pub_id, error);
+                        trace!(
+                            target: "routing_connect",
+                            "{:?} Unable to connect to {:?} - {:?}",
+                            self, pub_id, error
+                        );
                     }
                 }
             },
@@ -2126,6 +3741,7 @@ impl Node {
         )?;
         if pub_id != their_connection_info.id() {
             debug!(
+                target: "routing_connect",
                 "{:?} PublicId of the sender {} does not match the id mentioned in the message \
                  {}.",
                 self,
@@ -2145,6 +3761,7 @@ impl Node {
         ) {
             Ok(Ready(our_info, their_info)) => {
                 debug!(
+                    target: "routing_connect",
                     "{:?} Already sent a connection info request to {}; resending \
                      our same details as a response.",
                     self, pub_id
@@ -2157,7 +3774,11 @@ impl Node {
                     Some(message_id),
                 );
                 if let Err(error) = self.crust_service.connect(our_info, their_info) {
-                    trace!("{:?} Unable to connect to {:?} - {:?}", self, src, error);
+                    trace!(
+                        target: "routing_connect",
+                        "{:?} Unable to connect to {:?} - {:?}",
+                        self, src, error
+                    );
                 }
             }
             Ok(Prepare(token)) => {
@@ -2203,6 +3824,7 @@ impl Node {
         )?;
         if public_id != their_connection_info.id() {
             debug!(
+                target: "routing_connect",
                 "{:?} PublicId of the sender {} does not match the id mentioned in the message \
                  {}.",
                 self,
@@ -2222,12 +3844,14 @@ impl Node {
         ) {
             Ok(Ready(our_info, their_info)) => {
                 trace!(
+                    target: "routing_connect",
                     "{:?} Received connection info response. Trying to connect to {}.",
                     self,
                     public_id
                 );
                 if let Err(error) = self.crust_service.connect(our_info, their_info) {
                     trace!(
+                        target: "routing_connect",
                         "{:?} Unable to connect to {:?} - {:?}",
                         self,
                         public_id,
@@ -2237,6 +3861,7 @@ impl Node {
             }
             Ok(Prepare(_)) | Ok(IsProxy) | Ok(IsClient) | Ok(IsJoiningNode) => {
                 debug!(
+                    target: "routing_connect",
                     "{:?} Received connection info response from {} when we haven't \
                      sent a corresponding request",
                     self, public_id
@@ -2253,6 +3878,7 @@ impl Node {
         if self.peer_mgr.can_tunnel_for(&srd_id, &dst_id) {
             if let Some((id0, id1)) = self.tunnels.consider_clients(srd_id, dst_id) {
                 debug!(
+                    target: "routing_relay",
                     "{:?} Accepted tunnel request from {} for {}.",
                     self, srd_id, dst_id
                 );
@@ -2260,6 +3886,7 @@ impl Node {
             }
         } else {
             debug!(
+                target: "routing_relay",
                 "{:?} Rejected tunnel request from {} for {}.",
                 self, srd_id, dst_id
             );
@@ -2289,6 +3916,7 @@ impl Node {
             && self.peer_mgr.tunnelling_to(&dst_id)
         {
             debug!(
+                target: "routing_relay",
                 "{:?} Adding {:?} as a tunnel node for {:?}.",
                 self, tunnel_id, dst_id
             );
@@ -2300,6 +3928,7 @@ impl Node {
             self.process_connection(dst_id, outbox);
         } else {
             debug!(
+                target: "routing_relay",
                 "{:?} Rejecting TunnelSuccess from {:?} for peer {:?}",
                 self, tunnel_id, dst_id
             );
@@ -2316,12 +3945,17 @@ impl Node {
             && self.tunnels.accept_clients(src, dst)
         {
             debug!(
+                target: "routing_relay",
                 "{:?} Agreed to act as tunnel node for {:?} - {:?}",
                 self, src, dst
             );
             self.send_direct_message(dst, DirectMessage::TunnelSuccess(src));
         } else {
-            debug!("{:?} Rejecting TunnelSelect from {} - {}.", self, src, dst);
+            debug!(
+                target: "routing_relay",
+                "{:?} Rejecting TunnelSelect from {} - {}.",
+                self, src, dst
+            );
             let message = DirectMessage::TunnelClosed(dst);
             self.send_direct_message(src, message);
         }
@@ -2330,7 +3964,11 @@ impl Node {
     /// Handles a `TunnelClosed` message from `src_id`: `dst_id` disconnected.
     fn handle_tunnel_closed(&mut self, src_id: PublicId, dst_id: PublicId, outbox: &mut EventBox) {
         if self.tunnels.remove(dst_id, src_id) {
-            debug!("{:?} Tunnel to {} via {} closed.", self, dst_id, src_id);
+            debug!(
+                target: "routing_relay",
+                "{:?} Tunnel to {} via {} closed.",
+                self, dst_id, src_id
+            );
             if !self.crust_service.is_connected(&dst_id) {
                 let _ = self.dropped_peer(&dst_id, outbox, true);
             }
@@ -2341,6 +3979,7 @@ impl Node {
     fn handle_tunnel_disconnect(&mut self, src_id: PublicId, dst_id: PublicId) {
         if self.tunnels.drop_client_pair(dst_id, src_id) {
             debug!(
+                target: "routing_relay",
                 "{:?} Closing tunnel connecting {} and {}.",
                 self, dst_id, src_id
             );
@@ -2348,6 +3987,21 @@ impl Node {
         }
     }
 
+    // If the candidate bootstrapped off us before it was relocated, we may still hold its old ID
+    // as a joining node. That entry is stale now: the candidate talks to us under its new ID, so
+    // we drop the old connection instead of keeping two connections to the same peer.
+    fn drop_relocated_joining_node(&mut self, old_pub_id: &PublicId, outbox: &mut EventBox) {
+        if let Some(&PeerState::JoiningNode) = self.peer_mgr.get_peer(old_pub_id).map(Peer::state) {
+            debug!(
+                target: "routing_connect",
+                "{:?} Joining node {} was relocated, dropping its old connection.",
+                self, old_pub_id
+            );
+            let _ = self.crust_service.disconnect(old_pub_id);
+            let _ = self.dropped_peer(old_pub_id, outbox, false);
+        }
+    }
+
     /// Disconnects from the given peer, via Crust or by dropping the tunnel node, if the peer is
     /// not a proxy, client or routing table entry.
     fn disconnect_peer(&mut self, pub_id: &PublicId, outbox: Option<&mut EventBox>) {
@@ -2425,14 +4079,33 @@ impl Node {
         dst_name: XorName,
         message_id: MessageId,
     ) -> Result<(), RoutingError> {
+        if let Some(reason) = self.next_relocation_denial.take() {
+            self.deny_relocation(relocating_node_id, proxy_name, reason, message_id);
+            return Err(RoutingError::InvalidDestination);
+        }
+
         // Validate relocating node has contacted the correct Section-X
         if *relocating_node_id.name() != dst_name {
+            self.deny_relocation(
+                relocating_node_id,
+                proxy_name,
+                RelocationDenial::MalformedId,
+                message_id,
+            );
             return Err(RoutingError::InvalidDestination);
         }
 
         let close_section = match self.routing_table().close_names(&dst_name) {
             Some(close_section) => close_section.into_iter().collect(),
-            None => return Err(RoutingError::InvalidDestination),
+            None => {
+                self.deny_relocation(
+                    relocating_node_id,
+                    proxy_name,
+                    RelocationDenial::WrongSection,
+                    message_id,
+                );
+                return Err(RoutingError::InvalidDestination);
+            }
         };
         let relocation_dst = self
             .next_relocation_dst
@@ -2453,9 +4126,38 @@ impl Node {
         self.send_routing_message(src, dst, request_content)
     }
 
+    // Tells the relocating node that we won't handle its `Relocate` request, so that it can restart
+    // instead of waiting for a response until it times out. The request's destination needn't be
+    // our section, so we respond as the section we are actually in.
+    fn deny_relocation(
+        &mut self,
+        relocating_node_id: PublicId,
+        proxy_name: XorName,
+        reason: RelocationDenial,
+        message_id: MessageId,
+    ) {
+        info!(
+            "{:?} Denying relocation of {}: {:?}.",
+            self, relocating_node_id, reason
+        );
+        let src = Authority::PrefixSection(*self.our_prefix());
+        let dst = Authority::Client {
+            client_id: relocating_node_id,
+            proxy_node_name: proxy_name,
+        };
+        let content = MessageContent::RelocateDenied { reason, message_id };
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!(
+                "{:?} Failed to deny relocation of {}: {:?}",
+                self, relocating_node_id, error
+            );
+        }
+    }
+
     // Received by Y; From X -> Y
     // Context: a node is joining our section. Sends `AcceptAsCandidate` to our section. If the
-    // network is unbalanced, sends `ExpectCandidate` on to a section with a shorter prefix.
+    // network is unbalanced, sends `ExpectCandidate` on to a section with a shorter prefix. If too
+    // many nodes joined us recently, sends `RelocateDeferred` to the joining node instead.
     fn handle_expect_candidate(
         &mut self,
         old_public_id: PublicId,
@@ -2493,6 +4195,18 @@ impl Node {
             return self.send_routing_message(src, dst, request_content);
         }
 
+        if self.is_join_throttled() {
+            info!(
+                "{:?} Too many nodes joined recently. Deferring candidate {}.",
+                self, old_public_id
+            );
+            let response_content = MessageContent::RelocateDeferred {
+                retry_after_secs: JOIN_THROTTLE_INTERVAL_SECS,
+                message_id,
+            };
+            return self.send_routing_message(relocation_dst, old_client_auth, response_content);
+        }
+
         let target_interval = self.next_relocation_interval.take().unwrap_or_else(|| {
             utils::calculate_relocation_interval(
                 self.our_prefix(),
@@ -2501,6 +4215,7 @@ impl Node {
         });
 
         self.peer_mgr.expect_candidate(old_public_id)?;
+        self.recent_joins.push_back(Instant::now());
 
         let response_content = MessageContent::AcceptAsCandidate {
             old_public_id,
@@ -2516,6 +4231,20 @@ impl Node {
         self.send_routing_message(relocation_dst, relocation_dst, response_content)
     }
 
+    // Returns whether our section already accepted `max_joins_per_interval` candidates within the
+    // last `JOIN_THROTTLE_INTERVAL_SECS`.
+    fn is_join_throttled(&mut self) -> bool {
+        let interval = Duration::from_secs(JOIN_THROTTLE_INTERVAL_SECS);
+        while self
+            .recent_joins
+            .front()
+            .map_or(false, |time| time.elapsed() >= interval)
+        {
+            let _ = self.recent_joins.pop_front();
+        }
+        self.recent_joins.len() >= self.max_joins_per_interval
+    }
+
     // Received by Y; From Y -> Y
     // Context: a node is joining our section. Sends the node our section.
     fn handle_accept_as_candidate(
@@ -2563,6 +4292,53 @@ impl Node {
         self.send_routing_message(relocation_dst, old_client_auth, response_content)
     }
 
+    // Context: a node joined our section while we are waiting for a candidate's `CandidateInfo`.
+    // Sends the candidate's details to the new member, so that it can verify the candidate if the
+    // members which accepted it churn out.
+    fn share_candidate(&mut self, name: XorName) {
+        let (old_public_id, target_interval) = match self.peer_mgr.accepted_candidate() {
+            Some(candidate) => candidate,
+            None => return,
+        };
+        let src = Authority::Section(name);
+        let dst = Authority::ManagedNode(name);
+        let content = MessageContent::ShareCandidate {
+            old_public_id,
+            target_interval,
+        };
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!(
+                "{:?} Failed to share candidate {} with {}: {:?}",
+                self, old_public_id, name, error
+            );
+        }
+    }
+
+    fn handle_share_candidate(
+        &mut self,
+        old_pub_id: PublicId,
+        target_interval: (XorName, XorName),
+    ) {
+        if old_pub_id == *self.full_id.public_id() {
+            return;
+        }
+        if !self.peer_mgr.adopt_candidate(old_pub_id, target_interval) {
+            debug!(
+                "{:?} Not adopting candidate {} shared by our section, as we have one already.",
+                self, old_pub_id
+            );
+            return;
+        }
+        info!(
+            "{:?} Adopted candidate with old name {} accepted by our section.",
+            self, old_pub_id
+        );
+        self.candidate_timer_token = Some(
+            self.timer
+                .schedule(Duration::from_secs(RESOURCE_PROOF_DURATION_SECS)),
+        );
+    }
+
     fn handle_section_update(
         &mut self,
         ver_pfx: VersionedPrefix<XorName>,
@@ -2601,7 +4377,14 @@ impl Node {
                 );
                 return Ok(());
             };
-        let members = members
+        self.connect_to_unknown_peers(members, outbox);
+        Ok(())
+    }
+
+    // Sends connection info requests to those of the given peers we don't have a valid connection
+    // to yet.
+    fn connect_to_unknown_peers(&mut self, pub_ids: Vec<PublicId>, outbox: &mut EventBox) {
+        let pub_ids = pub_ids
             .into_iter()
             .filter(|id: &PublicId| {
                 self.peer_mgr
@@ -2611,7 +4394,7 @@ impl Node {
             .collect_vec();
 
         let own_name = *self.name();
-        for pub_id in members {
+        for pub_id in pub_ids {
             if let Err(error) = self.send_connection_info_request(
                 pub_id,
                 Authority::ManagedNode(own_name),
@@ -2620,14 +4403,369 @@ impl Node {
                 ReconnectingPeer::False,
             ) {
                 debug!(
+                    target: "routing_connect",
                     "{:?} - Failed to send connection info to {:?}: {:?}",
                     self, pub_id, error
                 );
             }
         }
+    }
+
+    // Asks every section of which we know fewer than `min_section_size` members for its member
+    // list, so that we learn about and connect to the peers we're missing, rather than waiting for
+    // traffic or churn to bring them to us.
+    fn request_missing_section_members(&mut self) {
+        if self.they_want_to_merge() || self.we_want_to_merge() {
+            return;
+        }
+        let min_section_size = self.min_section_size();
+        let our_prefix = *self.our_prefix();
+        let sparse_prefixes = self
+            .routing_table()
+            .other_prefixes()
+            .into_iter()
+            .filter(|pfx| {
+                self.routing_table()
+                    .section_with_prefix(pfx)
+                    .map_or(false, |section| section.len() < min_section_size)
+            })
+            .collect_vec();
+        if self.routing_table().our_section().len() < min_section_size {
+            let our_name = *self.name();
+            self.send_get_close_group(our_name);
+        }
+        for pfx in sparse_prefixes {
+            trace!(
+                "{:?} Too few known members in {:?}. Requesting a section update.",
+                self,
+                pfx
+            );
+            let src = Authority::ManagedNode(*self.name());
+            let dst = Authority::PrefixSection(pfx);
+            let content = MessageContent::SectionUpdateRequest(our_prefix);
+            if let Err(err) = self.send_routing_message(src, dst, content) {
+                debug!(
+                    "{:?} Failed to request section update from {:?}: {:?}",
+                    self, pfx, err
+                );
+            }
+        }
+    }
+
+    // Asks the section responsible for `name` for its members.
+    fn send_get_close_group(&mut self, name: XorName) {
+        let src = Authority::ManagedNode(*self.name());
+        let dst = Authority::Section(name);
+        let content = MessageContent::GetCloseGroup {
+            name,
+            message_id: MessageId::new(),
+        };
+        if let Err(err) = self.send_routing_message(src, dst, content) {
+            debug!(
+                "{:?} Failed to send GetCloseGroup for {}: {:?}",
+                self, name, err
+            );
+        }
+    }
+
+    fn handle_get_close_group(
+        &mut self,
+        name: XorName,
+        message_id: MessageId,
+        src_name: XorName,
+    ) -> Result<(), RoutingError> {
+        let close_group = self
+            .peer_mgr
+            .get_pub_ids(self.routing_table().our_section());
+        let src = Authority::Section(name);
+        let dst = Authority::ManagedNode(src_name);
+        let content = MessageContent::GetCloseGroupResponse {
+            close_group,
+            message_id,
+        };
+        self.send_routing_message(src, dst, content)
+    }
+
+    // Asks the other members of our section for the cached chunks in our range, if `prime_cache` is
+    // enabled. They share `CACHE_PRIMING_MAX_BYTES` between them.
+    fn request_cache_priming(&mut self) {
+        if !self.prime_cache {
+            return;
+        }
+        let members = self
+            .routing_table()
+            .our_section()
+            .iter()
+            .filter(|name| *name != self.name())
+            .cloned()
+            .collect_vec();
+        if members.is_empty() {
+            return;
+        }
+        let message_id = MessageId::new();
+        let _ = self.cache_priming_ids.insert(message_id, ());
+        let prefix = *self.our_prefix();
+        let max_bytes = CACHE_PRIMING_MAX_BYTES / members.len() as u64;
+        let src = Authority::ManagedNode(*self.name());
+        for name in members {
+            let content = MessageContent::CachePrimingRequest {
+                prefix,
+                max_bytes,
+                message_id,
+            };
+            let dst = Authority::ManagedNode(name);
+            if let Err(error) = self.send_routing_message(src, dst, content) {
+                debug!(
+                    target: "routing_cache",
+                    "{:?} Failed to ask {} to prime our cache: {:?}",
+                    self, name, error
+                );
+            }
+        }
+    }
+
+    // Sends the member of our section `src_name` the hottest cached chunks matching `prefix`, as
+    // `GetIData` responses with the given message ID.
+    fn handle_cache_priming_request(
+        &mut self,
+        prefix: Prefix<XorName>,
+        max_bytes: u64,
+        message_id: MessageId,
+        src_name: XorName,
+    ) -> Result<(), RoutingError> {
+        if !self.routing_table().our_section().contains(&src_name) {
+            debug!(
+                target: "routing_cache",
+                "{:?} Ignoring cache priming request from {}, which isn't in our section.",
+                self, src_name
+            );
+            return Err(RoutingError::InvalidSource);
+        }
+        let max_bytes = cmp::min(max_bytes, CACHE_PRIMING_MAX_BYTES);
+        let chunks = self
+            .response_cache
+            .hottest_immutable_data(&prefix, max_bytes);
+        debug!(
+            target: "routing_cache",
+            "{:?} Priming the cache of {} with {} chunks.",
+            self, src_name, chunks.len()
+        );
+        let src = Authority::ManagedNode(*self.name());
+        let dst = Authority::ManagedNode(src_name);
+        for data in chunks {
+            let response = Response::GetIData {
+                res: Ok(data),
+                msg_id: message_id,
+            };
+            let priority = response.priority();
+            self.send_user_message(src, dst, UserMessage::Response(response), priority)?;
+        }
         Ok(())
     }
 
+    // Puts a chunk a member of our section sent in response to our cache priming request into our
+    // cache, if it belongs to our range.
+    fn put_primed_response(&mut self, response: Response, src: Authority<XorName>) {
+        let in_range = match response {
+            Response::GetIData {
+                res: Ok(ref data), ..
+            } => self.our_prefix().matches(data.name()),
+            _ => false,
+        };
+        if !in_range {
+            debug!(
+                target: "routing_cache",
+                "{:?} Ignoring unexpected cache priming response from {:?}: {:?}",
+                self, src, response
+            );
+            return;
+        }
+        debug!(target: "routing_cache", "{:?} Priming cache with {:?}", self, response);
+        self.response_cache.put(response);
+        self.cache_stats.puts += 1;
+    }
+
+    // Connects to those members of the received close group that belong in our routing table.
+    fn handle_get_close_group_response(
+        &mut self,
+        close_group: BTreeSet<PublicId>,
+        outbox: &mut EventBox,
+    ) {
+        let pub_ids = close_group
+            .into_iter()
+            .filter(|pub_id| self.routing_table().need_to_add(pub_id.name()).is_ok())
+            .collect_vec();
+        self.connect_to_unknown_peers(pub_ids, outbox);
+    }
+
+    // Asks our section to vote on dropping the member with the given name, whose connection we
+    // lost. Unless a quorum agrees, we keep trying to reconnect to it.
+    fn send_report_unreachable(&mut self, name: XorName) {
+        let src = Authority::Section(name);
+        let dst = Authority::Section(name);
+        let content = MessageContent::ReportUnreachable(name);
+        if let Err(error) = self.send_routing_message(src, dst, content) {
+            debug!(
+                target: "routing_churn",
+                "{:?} Failed to report {} as unreachable: {:?}",
+                self, name, error
+            );
+        }
+    }
+
+    // Drops the member with the given name, now that a quorum of our section agrees that it is
+    // unreachable, so that all our close groups stay consistent.
+    fn handle_report_unreachable(&mut self, name: XorName, outbox: &mut EventBox) {
+        if name == *self.name() {
+            debug!(
+                target: "routing_churn",
+                "{:?} Our section considers us unreachable.",
+                self
+            );
+            return;
+        }
+        let pub_id = match self.peer_mgr.get_pub_id(&name) {
+            Some(pub_id) if self.peer_mgr.is_routing_peer(pub_id) => *pub_id,
+            _ => return,
+        };
+        info!(
+            target: "routing_churn",
+            "{:?} Our section agrees that {} is unreachable. Dropping it.",
+            self, name
+        );
+        let _ = self.crust_service.disconnect(&pub_id);
+        self.dropped_tunnel_client(&pub_id);
+        self.dropped_tunnel_node(&pub_id, outbox);
+        let _ = self.dropped_peer(&pub_id, outbox, false);
+    }
+
+    // Connects to those of the configured and added contacts which belong in our routing table.
+    fn connect_to_static_contacts(&mut self, outbox: &mut EventBox) {
+        let pub_ids = self
+            .static_contacts
+            .iter()
+            .filter(|pub_id| self.routing_table().need_to_add(pub_id.name()).is_ok())
+            .cloned()
+            .collect_vec();
+        self.connect_to_unknown_peers(pub_ids, outbox);
+    }
+
+    // Returns a hash of the names we consider to be in our section.
+    fn close_group_digest(&self) -> Option<Digest256> {
+        serialisation::serialise(self.routing_table().our_section())
+            .ok()
+            .map(|bytes| sha3_256(&bytes))
+    }
+
+    // Sends the hash of our view of our section to the rest of it, so that views which have
+    // diverged, e.g. after a network partition, are detected even without any further churn.
+    fn send_close_group_digests(&mut self) {
+        let digest = match self.close_group_digest() {
+            Some(digest) => digest,
+            None => return,
+        };
+        let load = Some(self.own_load());
+        let our_name = *self.name();
+        let pub_ids = self
+            .peer_mgr
+            .get_pub_ids(self.routing_table().our_section());
+        for pub_id in pub_ids {
+            if *pub_id.name() != our_name {
+                self.send_direct_message(pub_id, DirectMessage::CloseGroupDigest(digest, load));
+            }
+        }
+    }
+
+    // Returns our current load, counting the bytes sent since we last called this.
+    fn own_load(&mut self) -> NodeLoad {
+        let total_bytes = self.stats.msg_total_bytes();
+        let bytes_sent = total_bytes.saturating_sub(self.bytes_sent_at_last_report);
+        self.bytes_sent_at_last_report = total_bytes;
+        NodeLoad {
+            relayed_clients: self.peer_mgr.client_num() as u64,
+            cache_entries: self.response_cache.entry_count() as u64,
+            bytes_sent,
+        }
+    }
+
+    // Raises `Event::SectionLoad` with the loads reported by the current members of our section.
+    fn raise_section_load(&mut self, outbox: &mut EventBox) {
+        let section = self.routing_table().our_section().clone();
+        self.section_loads = mem::replace(&mut self.section_loads, BTreeMap::new())
+            .into_iter()
+            .filter(|&(ref name, _)| section.contains(name))
+            .collect();
+        if !self.section_load_events || self.section_loads.is_empty() {
+            return;
+        }
+        let mut total = NodeLoad::default();
+        for load in self.section_loads.values() {
+            total.add(load);
+        }
+        outbox.send_event(Event::SectionLoad {
+            members: self.section_loads.len(),
+            total,
+        });
+    }
+
+    // Returns whether `pub_id` is a routing peer in our section.
+    fn is_section_peer(&self, pub_id: &PublicId) -> bool {
+        self.peer_mgr.is_routing_peer(pub_id)
+            && self.routing_table().our_section().contains(pub_id.name())
+    }
+
+    fn handle_close_group_digest(
+        &mut self,
+        pub_id: PublicId,
+        digest: Digest256,
+        load: Option<NodeLoad>,
+    ) {
+        if !self.is_approved || !self.is_section_peer(&pub_id) {
+            return;
+        }
+        if let Some(load) = load {
+            let _ = self.section_loads.insert(*pub_id.name(), load);
+        }
+        if self.close_group_digest() == Some(digest) {
+            return;
+        }
+        debug!(
+            "{:?} {} has a different view of our section. Sending ours.",
+            self, pub_id
+        );
+        let members = self
+            .peer_mgr
+            .get_pub_ids(self.routing_table().our_section());
+        self.send_direct_message(pub_id, DirectMessage::CloseGroup(members));
+    }
+
+    fn handle_close_group(
+        &mut self,
+        pub_id: PublicId,
+        members: BTreeSet<PublicId>,
+        outbox: &mut EventBox,
+    ) {
+        if !self.is_approved || !self.is_section_peer(&pub_id) {
+            return;
+        }
+        let our_prefix = *self.our_prefix();
+        let missing = members
+            .into_iter()
+            .filter(|id| {
+                id != self.full_id.public_id()
+                    && our_prefix.matches(id.name())
+                    && !self.routing_table().our_section().contains(id.name())
+            })
+            .collect_vec();
+        if !missing.is_empty() {
+            debug!(
+                "{:?} Connecting to section members {:?} known to {}.",
+                self, missing, pub_id
+            );
+            self.connect_to_unknown_peers(missing, outbox);
+        }
+    }
+
     fn handle_section_split(
         &mut self,
         ver_pfx: VersionedPrefix<XorName>,
@@ -2639,17 +4777,33 @@ impl Node {
         if split_us && !self.routing_table().has(&joining_node) {
             self.send_section_split(ver_pfx, joining_node);
         }
-        // None of the `peers_to_drop` will have been in our section, so no need to notify Routing
-        // user about them.
+        // None of the `peers_to_drop` will have been in our section, so they are reported as
+        // replaced rather than lost.
+        let old_names: BTreeSet<XorName> = self.routing_table().iter().cloned().collect();
         let (peers_to_drop, our_new_prefix) = self.peer_mgr.split_section(ver_pfx);
         if let Some(new_prefix) = our_new_prefix {
             outbox.send_event(Event::SectionSplit(new_prefix));
         }
 
         for pub_id in peers_to_drop {
-            self.disconnect_peer(&pub_id, Some(outbox));
+            if old_names.contains(pub_id.name()) {
+                outbox.send_event(Event::NodeReplaced {
+                    evicted: *pub_id.name(),
+                    added: joining_node,
+                });
+                // Let the peer know before the connection goes, so it doesn't try to reconnect,
+                // and give the message time to be sent.
+                self.send_direct_message(pub_id, DirectMessage::Disconnecting);
+                let token = self
+                    .timer
+                    .schedule(Duration::from_secs(DISCONNECT_DELAY_SECS));
+                let _ = self.pending_disconnects.insert(token, pub_id);
+            } else {
+                self.disconnect_peer(&pub_id, Some(outbox));
+            }
         }
         info!(
+            target: "routing_churn",
             "{:?} Section split for {:?} completed. Prefixes: {:?}",
             self,
             ver_pfx,
@@ -2681,6 +4835,7 @@ impl Node {
         {
             if merge_prefix == *self.our_prefix() {
                 trace!(
+                    target: "routing_churn",
                     "{:?} resend OtherSectionMerge on duplicated OwnSectionMerge",
                     self
                 );
@@ -2695,6 +4850,7 @@ impl Node {
             || merge_prefix.bit_count() >= self.our_prefix().bit_count()
         {
             debug!(
+                target: "routing_churn",
                 "{:?} Received OwnSectionMerge with merge prefix {:?} from prefix {:?}.",
                 self, merge_prefix, sender_prefix
             );
@@ -2702,6 +4858,7 @@ impl Node {
         }
         if let Some(previous_sections) = self.merge_cache.insert(sender_prefix, sections) {
             debug!(
+                target: "routing_churn",
                 "{:?} Received duplicate OwnSectionMerge from {:?}: {:?}.",
                 self, sender_prefix, previous_sections
             );
@@ -2785,6 +4942,7 @@ impl Node {
                 // TODO - the event should maybe only fire once all new connections have been made?
                 outbox.send_event(Event::SectionMerge(*versioned_prefix.prefix()));
                 info!(
+                    target: "routing_churn",
                     "{:?} Own section merge completed. Prefixes: {:?}",
                     self,
                     self.routing_table().prefixes()
@@ -2793,6 +4951,7 @@ impl Node {
                 let own_name = *self.name();
                 for needed in &needed_peers {
                     debug!(
+                        target: "routing_churn",
                         "{:?} Sending connection info to {:?} due to merging own section.",
                         self, needed
                     );
@@ -2804,6 +4963,7 @@ impl Node {
                         ReconnectingPeer::False,
                     ) {
                         debug!(
+                            target: "routing_churn",
                             "{:?} - Failed to send connection info to {:?}: {:?}",
                             self, needed, error
                         );
@@ -2838,6 +4998,7 @@ impl Node {
 
         for needed in needed_peers {
             debug!(
+                target: "routing_churn",
                 "{:?} Sending connection info to {:?} due to merging other section.",
                 self, needed
             );
@@ -2849,10 +5010,15 @@ impl Node {
                 outbox,
                 ReconnectingPeer::False,
             ) {
-                debug!("{:?} - Failed to send connection info: {:?}", self, error);
+                debug!(
+                    target: "routing_churn",
+                    "{:?} - Failed to send connection info: {:?}",
+                    self, error
+                );
             }
         }
         info!(
+            target: "routing_churn",
             "{:?} Other section merge completed. Prefixes: {:?}",
             self,
             self.routing_table().prefixes()
@@ -2896,6 +5062,8 @@ impl Node {
             let tick_period = Duration::from_secs(TICK_TIMEOUT_SECS);
             self.tick_timer_token = self.timer.schedule(tick_period);
             self.remove_expired_peers(outbox);
+            self.send_pending_connects(outbox);
+            self.check_standby_tunnels();
 
             trace!(
                 "{:?} Stats - Proxy Load: {} KiB/s",
@@ -2911,6 +5079,10 @@ impl Node {
             };
             self.merge_if_necessary(outbox);
             if self.is_approved {
+                for (name, task) in self.periodic_tasks.tick() {
+                    trace!("{:?} Running periodic task: {}.", self, name);
+                    task(self, outbox);
+                }
                 outbox.send_event(Event::Tick);
             }
             return transition;
@@ -2944,6 +5116,29 @@ impl Node {
                     .schedule(Duration::from_secs(CANDIDATE_STATUS_INTERVAL_SECS)),
             );
             self.peer_mgr.show_candidate_status();
+        } else if self.compaction_timer_token == Some(token) {
+            self.compaction_timer_token = None;
+            self.compact();
+        } else if self.send_queue_timer_token == Some(token) {
+            self.send_queue_timer_token = None;
+            self.flush_send_queue();
+        } else if self.direct_sequencer_timer_token == Some(token) {
+            self.direct_sequencer_timer_token = None;
+            for (pub_id, direct_msg) in self.direct_sequencer.take_expired() {
+                debug!(
+                    "{:?} Handling {:?} from {} without the messages it sent before.",
+                    self, direct_msg, pub_id
+                );
+                let _ = self.handle_direct_message(direct_msg, pub_id, outbox);
+            }
+            self.schedule_direct_sequencer_timer();
+        } else if let Some((dst, response)) = self.pending_cache_responses.remove(&token) {
+            self.send_cache_response(dst, response);
+        } else if let Some((pub_id, src, dst, route)) = self.connection_info_retries.remove(&token)
+        {
+            self.retry_connection_info(pub_id, src, dst, route);
+        } else if let Some(pub_id) = self.pending_disconnects.remove(&token) {
+            self.disconnect_peer(&pub_id, Some(outbox));
         } else {
             // Each token has only one purpose, so we only need to call this if none of the above
             // matched:
@@ -2953,6 +5148,244 @@ impl Node {
         Transition::Stay
     }
 
+    // The periodic work done while we are approved, run from the tick timer. New periodic work
+    // should be registered here rather than added to `handle_timeout`.
+    fn periodic_tasks(tick_period: Duration) -> PeriodicTasks<Node> {
+        let mut tasks = PeriodicTasks::new(tick_period);
+        tasks.register(
+            "connect to static contacts",
+            tick_period,
+            Node::connect_to_static_contacts,
+        );
+        tasks.register("send close group digests", tick_period, |node, _| {
+            node.send_close_group_digests()
+        });
+        tasks.register("raise section load", tick_period, Node::raise_section_load);
+        tasks.register("raise relay accounting", tick_period, |node, outbox| {
+            if !node.relay_accounting_events {
+                return;
+            }
+            let snapshot = node.relay_accounting.snapshot();
+            if !snapshot.is_empty() {
+                outbox.send_event(Event::RelayAccounting(snapshot));
+            }
+        });
+        tasks.register("request missing section members", tick_period, |node, _| {
+            node.request_missing_section_members()
+        });
+        tasks.register("report failed accumulations", tick_period, |node, _| {
+            node.report_failed_accumulations()
+        });
+        tasks.register("retire proxy", tick_period, |node, _| node.retire_proxy());
+        tasks
+    }
+
+    // Once our section is complete, asks our proxy to retire unless it is in our routing table, and
+    // disconnects from it once it acknowledged, or didn't do so in time.
+    fn retire_proxy(&mut self) {
+        if !self.retire_proxy || !self.group_connected {
+            return;
+        }
+        if let Some((pub_id, asked_at)) = self.retiring_proxy {
+            if asked_at.elapsed() >= Duration::from_secs(PROXY_RETIREMENT_TIMEOUT_SECS) {
+                debug!(
+                    target: "routing_connect",
+                    "{:?} Proxy {} didn't acknowledge its retirement in time.",
+                    self, pub_id
+                );
+                self.drop_retired_proxy(&pub_id);
+            }
+            return;
+        }
+        if let Some(pub_id) = self.peer_mgr.unneeded_proxy() {
+            debug!(target: "routing_connect", "{:?} Asking proxy {} to retire.", self, pub_id);
+            self.retiring_proxy = Some((pub_id, Instant::now()));
+            self.send_direct_message(pub_id, DirectMessage::ProxyRetirement);
+        }
+    }
+
+    /// Handles a `ProxyRetirement` from a joining node: once we have sent it everything we queued
+    /// for it, we acknowledge, and it disconnects.
+    fn handle_proxy_retirement(&mut self, pub_id: PublicId) {
+        if !self.peer_mgr.is_joining_node(&pub_id) {
+            debug!(
+                "{:?} Received ProxyRetirement from {}, which isn't our joining node.",
+                self, pub_id
+            );
+            return;
+        }
+        let _ = self.retiring_joining_nodes.insert(pub_id);
+        self.acknowledge_proxy_retirements();
+    }
+
+    // Sends `ProxyRetired` to the retiring joining nodes which have no more messages queued.
+    fn acknowledge_proxy_retirements(&mut self) {
+        let drained = self
+            .retiring_joining_nodes
+            .iter()
+            .filter(|pub_id| !self.send_queue.has_messages_for(pub_id))
+            .cloned()
+            .collect_vec();
+        for pub_id in drained {
+            let _ = self.retiring_joining_nodes.remove(&pub_id);
+            if self.peer_mgr.is_joining_node(&pub_id) {
+                self.send_direct_message(pub_id, DirectMessage::ProxyRetired);
+            }
+        }
+    }
+
+    /// Handles a `ProxyRetired` from our proxy. The proxy sends it with the lowest priority, once
+    /// nothing else for us is queued, and we handle it after the hop messages which arrived before
+    /// it, so we have received everything the proxy sent us.
+    fn handle_proxy_retired(&mut self, pub_id: PublicId) {
+        match self.retiring_proxy {
+            Some((retiring_id, _)) if retiring_id == pub_id => self.drop_retired_proxy(&pub_id),
+            _ => debug!(
+                "{:?} Received ProxyRetired from {}, which we didn't ask to retire.",
+                self, pub_id
+            ),
+        }
+    }
+
+    // Disconnects from the retiring proxy, unless it was added to our routing table in the
+    // meantime. We remove it from the peer manager first, so that losing it isn't treated as
+    // losing our bootstrap connection.
+    fn drop_retired_proxy(&mut self, pub_id: &PublicId) {
+        self.retiring_proxy = None;
+        if !self.peer_mgr.remove_unneeded_proxy(pub_id) {
+            return;
+        }
+        debug!(target: "routing_connect", "{:?} Disconnecting from retired proxy {}.", self, pub_id);
+        let _ = self.retired_proxies.insert(*pub_id, ());
+        self.link_keys.remove(pub_id);
+        self.direct_sequencer.remove(pub_id);
+        let _ = self.crust_service.disconnect(pub_id);
+    }
+
+    // Raises `Event::RoutingTableGrowth`, until our section has reached `min_section_size` members
+    // for the first time.
+    fn report_table_growth(&mut self, outbox: &mut EventBox) {
+        if self.group_connected {
+            return;
+        }
+        let needed_for_group = self
+            .min_section_size()
+            .saturating_sub(self.routing_table().our_section().len());
+        self.group_connected = needed_for_group == 0;
+        if self.group_connected {
+            self.request_cache_priming();
+        }
+        outbox.send_event(Event::RoutingTableGrowth {
+            size: self.routing_table().len(),
+            needed_for_group,
+        });
+    }
+
+    // Raises `Event::RefreshNeeded` for each node which joined or left our close group since the
+    // last change to our routing table.
+    fn note_close_group_change(&mut self, outbox: &mut EventBox) {
+        let close_group = self.our_close_group();
+        if close_group == self.close_group_names {
+            return;
+        }
+        let old_close_group = mem::replace(&mut self.close_group_names, close_group);
+        if self.refresh_needed_events {
+            for name in old_close_group.symmetric_difference(&self.close_group_names) {
+                outbox.send_event(Event::RefreshNeeded { cause: *name });
+            }
+        }
+    }
+
+    // Returns the `min_section_size` nodes closest to us, including us.
+    fn our_close_group(&self) -> BTreeSet<XorName> {
+        self.close_group(*self.name(), self.min_section_size())
+            .unwrap_or_default()
+            .into_iter()
+            .collect()
+    }
+
+    // Records a change to our routing table concerning `name`. If there were at least
+    // `churn_storm_changes` within `CHURN_STORM_INTERVAL_SECS`, gives pending section messages from
+    // the affected section more time to accumulate, and raises `Event::ChurnStormDetected` once.
+    fn note_table_change(&mut self, name: &XorName, outbox: &mut EventBox) {
+        self.note_close_group_change(outbox);
+        let interval = Duration::from_secs(CHURN_STORM_INTERVAL_SECS);
+        while self
+            .recent_table_changes
+            .front()
+            .map_or(false, |time| time.elapsed() >= interval)
+        {
+            let _ = self.recent_table_changes.pop_front();
+        }
+        self.recent_table_changes.push_back(Instant::now());
+
+        let changes = self.recent_table_changes.len();
+        if changes < self.churn_storm_changes {
+            self.in_churn_storm = false;
+            return;
+        }
+
+        let prefix = self
+            .routing_table()
+            .find_section_prefix(name)
+            .unwrap_or(*self.our_prefix());
+        self.sig_accumulator.reset_expiry(&prefix);
+        if !self.in_churn_storm {
+            self.in_churn_storm = true;
+            warn!(
+                target: "routing_churn",
+                "{:?} Detected churn storm: {} routing table changes within {} seconds.",
+                self,
+                changes,
+                CHURN_STORM_INTERVAL_SECS
+            );
+            outbox.send_event(Event::ChurnStormDetected {
+                changes,
+                interval_secs: CHURN_STORM_INTERVAL_SECS,
+            });
+        }
+    }
+
+    // Prunes state referring to departed peers and releases spare capacity held by our filters and
+    // caches after a burst of churn.
+    fn compact(&mut self) {
+        let (filter_before, filter_after) = self.routing_msg_filter.compact();
+        let (acc_before, acc_after) = self.sig_accumulator.compact();
+
+        let departed = self
+            .section_list_sigs
+            .signer_names()
+            .into_iter()
+            .filter(|name| {
+                name != self.name()
+                    && !self.routing_table().has(name)
+                    && self.peer_mgr.get_peer_by_name(name).is_none()
+            })
+            .collect_vec();
+        let section_len = self.routing_table().our_section().len();
+        for name in &departed {
+            self.section_list_sigs.remove_signatures(name, section_len);
+        }
+        self.section_list_sigs.shrink_to_fit();
+        let pruned_tokens = self.peer_mgr.prune_connection_tokens();
+
+        let before = filter_before + acc_before;
+        let after = filter_after + acc_after;
+        info!(
+            target: "routing_churn",
+            "{:?} Compacted routing metadata after {} lost peers: {} -> {} bytes. Removed \
+             signatures from {} departed nodes and {} stale connection tokens.",
+            self,
+            self.churn_count,
+            before,
+            after,
+            departed.len(),
+            pruned_tokens
+        );
+        self.stats.count_compaction(before, after);
+        self.churn_count = 0;
+    }
+
     // Drop peers to which we think we have a direct or tunnel connection, but where Crust reports
     // that we're not connected to the peer or tunnel node respectively.
     fn purge_invalid_rt_entries(&mut self, outbox: &mut EventBox) -> Transition {
@@ -3096,13 +5529,195 @@ impl Node {
         priority: u8,
     ) -> Result<(), RoutingError> {
         self.stats.count_user_message(&user_msg);
-        for part in user_msg.to_parts(priority)? {
+        for content in user_msg.to_parts(priority)? {
             self.stats.increase_user_msg_part();
-            self.send_routing_message(src, dst, part)?;
+            let routing_msg = RoutingMessage {
+                src,
+                dst,
+                content,
+                trace: self.trace_user_messages,
+            };
+            self.send_routing_message_via_route(routing_msg, 0, None)?;
         }
         Ok(())
     }
 
+    // Sends `user_msg` from us as a `ManagedNode`, for `Action::SendToGroup` and `SendToNode`.
+    fn send_as_managed_node(
+        &mut self,
+        dst: Authority<XorName>,
+        user_msg: UserMessage,
+        priority: u8,
+    ) -> Result<(), InterfaceError> {
+        let src = Authority::ManagedNode(*self.name());
+        if let UserMessage::Request(Request::Refresh(..)) = user_msg {
+            return Err(InterfaceError::BadAuthority {
+                src,
+                dst,
+                reason: "a refresh must be sent by a group authority",
+            });
+        }
+        match self.send_user_message(src, dst, user_msg, priority) {
+            Err(RoutingError::Interface(err)) => Err(err),
+            Err(_) | Ok(()) => Ok(()),
+        }
+    }
+
+    // Sends a `Refresh` from and to `src`, whose content is the serialised `type_tag` and `payload`.
+    // Its message ID is derived from `cause`, which is `None` if there was no churn event yet.
+    fn send_refresh(
+        &mut self,
+        src: Authority<XorName>,
+        type_tag: u64,
+        payload: Vec<u8>,
+        cause: Option<XorName>,
+    ) -> Result<(), InterfaceError> {
+        if !src.is_multiple() || !self.in_authority(&src) {
+            return Err(InterfaceError::BadAuthority {
+                src,
+                dst: src,
+                reason: "a refresh must be sent by a group authority we belong to",
+            });
+        }
+        let cause = match cause {
+            Some(cause) => cause,
+            None => {
+                debug!("{:?} No churn to derive the refresh's cause from.", self);
+                return Err(InterfaceError::InvalidState);
+            }
+        };
+        let policy = self.sig_accumulator.refresh_policy(type_tag);
+        if policy
+            .max_payload_len
+            .map_or(false, |max_len| payload.len() > max_len)
+        {
+            debug!(
+                "{:?} Refresh with type tag {} exceeds its maximum payload size.",
+                self, type_tag
+            );
+            return Err(InterfaceError::InvalidState);
+        }
+        let content = match serialisation::serialise(&(type_tag, payload)) {
+            Ok(content) => content,
+            Err(error) => {
+                error!("{:?} Failed to serialise refresh: {:?}", self, error);
+                return Err(InterfaceError::InvalidState);
+            }
+        };
+        let msg_id = MessageId::from_refresh_cause(&cause, type_tag);
+        let user_msg = UserMessage::Request(Request::Refresh(content, msg_id));
+        match self.send_user_message(src, src, user_msg, RELOCATE_PRIORITY) {
+            Err(RoutingError::Interface(error)) => Err(error),
+            Err(_) | Ok(()) => Ok(()),
+        }
+    }
+
+    // Returns a hash of the members of the section `dst` refers to, as per our routing table, or
+    // `None` if `dst` is a single node or doesn't correspond to exactly one section we know.
+    fn dst_section_hash(&self, dst: &Authority<XorName>) -> Option<Digest256> {
+        let members = match *dst {
+            Authority::ClientManager(ref name)
+            | Authority::NaeManager(ref name)
+            | Authority::NodeManager(ref name)
+            | Authority::Section(ref name) => self.routing_table().get_section(name)?,
+            Authority::PrefixSection(ref prefix) => {
+                self.routing_table().section_with_prefix(prefix)?
+            }
+            Authority::ManagedNode(_) | Authority::Client { .. } => return None,
+        };
+        serialisation::serialise(members)
+            .ok()
+            .map(|bytes| sha3_256(&bytes))
+    }
+
+    // Returns whether the sender of `signed_msg` had the same view of the destination section as
+    // we have. If not, the inconsistency is reported and the message should not be acknowledged,
+    // so that the sender resends it once the churn has settled.
+    fn has_consistent_dst_view(&mut self, signed_msg: &SignedMessage) -> bool {
+        let their_hash = match signed_msg.dst_section_hash() {
+            Some(hash) => *hash,
+            None => return true,
+        };
+        let routing_msg = signed_msg.routing_message();
+        if self.dst_section_hash(&routing_msg.dst) == Some(their_hash) {
+            return true;
+        }
+        warn!(
+            "{:?} {:?} from {:?} to {:?} was sent with a different view of the destination \
+             section. Not acknowledging it.",
+            self, routing_msg.content, routing_msg.src, routing_msg.dst
+        );
+        self.stats.count_inconsistent_dst_view();
+        false
+    }
+
+    // Tells the source authorities of user messages which failed to reach quorum in time, so that
+    // they can retry them. Internal messages are resent by their own mechanisms.
+    fn report_failed_accumulations(&mut self) {
+        // All parts of a user message share its ID, so report each message only once.
+        let failed: BTreeSet<_> = self
+            .sig_accumulator
+            .take_failed()
+            .into_iter()
+            .filter_map(|routing_msg| match routing_msg.content {
+                MessageContent::UserMessagePart { msg_id, .. } => {
+                    Some((msg_id, routing_msg.src, routing_msg.dst))
+                }
+                _ => None,
+            })
+            .collect();
+        for (msg_id, src, dst) in failed {
+            debug!(
+                "{:?} {:?} from {:?} to {:?} failed to reach quorum in time.",
+                self, msg_id, src, dst
+            );
+            self.send_accumulation_failed(src, msg_id, dst);
+        }
+    }
+
+    fn send_accumulation_failed(
+        &mut self,
+        src: Authority<XorName>,
+        msg_id: MessageId,
+        dst: Authority<XorName>,
+    ) {
+        let content = MessageContent::AccumulationFailed { msg_id, dst };
+        let our_auth = Authority::ManagedNode(*self.name());
+        if let Err(error) = self.send_routing_message(our_auth, src, content) {
+            debug!(
+                "{:?} Failed to report failed accumulation: {:?}",
+                self, error
+            );
+        }
+    }
+
+    // If `routing_msg` requests tracing, reports what we did with it back to its source.
+    fn report_trace(&mut self, routing_msg: &RoutingMessage, decision: TraceDecision) {
+        if !routing_msg.trace {
+            return;
+        }
+        let ack = match Ack::compute(routing_msg) {
+            Ok(ack) => ack,
+            Err(error) => {
+                debug!("{:?} Failed to create trace report: {:?}", self, error);
+                return;
+            }
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let src = Authority::ManagedNode(*self.name());
+        let content = MessageContent::TraceReport {
+            ack,
+            decision,
+            timestamp,
+        };
+        if let Err(error) = self.send_routing_message(src, routing_msg.src, content) {
+            debug!("{:?} Failed to send trace report: {:?}", self, error);
+        }
+    }
+
     // Send signed_msg on route. Hop is the name of the peer we received this from, or our name if
     // we are the first sender or the proxy for a client or joining node.
     //
@@ -3130,8 +5745,19 @@ impl Node {
             }
         }
 
-        let (new_sent_to, target_pub_ids) =
-            self.get_targets(signed_msg.routing_message(), route, hop, sent_to)?;
+        let targets = self.get_targets(signed_msg.routing_message(), route, hop, sent_to);
+        let reachable = match targets {
+            Ok((_, ref target_pub_ids)) => target_pub_ids
+                .iter()
+                .any(|pub_id| self.is_directly_reachable(pub_id)),
+            Err(_) => false,
+        };
+        if !reachable && self.is_proxy_sending_for_client(signed_msg, hop) {
+            if let Some(second_proxy) = self.second_proxy(&dst) {
+                return self.relay_via_second_proxy(signed_msg, route, second_proxy);
+            }
+        }
+        let (new_sent_to, target_pub_ids) = targets?;
 
         for target_pub_id in target_pub_ids {
             self.send_signed_msg_to_peer(
@@ -3144,6 +5770,70 @@ impl Node {
         Ok(())
     }
 
+    // Returns whether we can send to `pub_id` directly or through a tunnel.
+    fn is_directly_reachable(&self, pub_id: &PublicId) -> bool {
+        self.crust_service.is_connected(pub_id) || self.tunnels.tunnel_for(pub_id).is_some()
+    }
+
+    // Returns whether we are the proxy sending `signed_msg` on behalf of one of our clients, to a
+    // destination other than ourselves.
+    fn is_proxy_sending_for_client(&self, signed_msg: &SignedMessage, hop: &XorName) -> bool {
+        let routing_msg = signed_msg.routing_message();
+        match routing_msg.src {
+            Authority::Client {
+                ref proxy_node_name,
+                ..
+            } => {
+                hop == self.name()
+                    && proxy_node_name == self.name()
+                    && !self.in_authority(&routing_msg.dst)
+            }
+            _ => false,
+        }
+    }
+
+    // Returns the connected routing peer closest to `dst`, which we can use as a second proxy for
+    // client messages we can't route ourselves.
+    fn second_proxy(&self, dst: &Authority<XorName>) -> Option<PublicId> {
+        self.routing_table()
+            .iter()
+            .sorted_by(|&lhs, &rhs| dst.name().cmp_distance(lhs, rhs))
+            .into_iter()
+            .filter_map(|name| self.peer_mgr.get_pub_id(name))
+            .find(|pub_id| self.crust_service.is_connected(pub_id))
+            .cloned()
+    }
+
+    // Sends a client's message we can't route ourselves to `second_proxy`, marked as relayed. The
+    // second proxy routes it as any other message, but as it isn't the client's proxy, it never
+    // relays it again, which limits the detour to a single hop.
+    fn relay_via_second_proxy(
+        &mut self,
+        signed_msg: &SignedMessage,
+        route: u8,
+        second_proxy: PublicId,
+    ) -> Result<(), RoutingError> {
+        debug!(
+            target: "routing_relay",
+            "{:?} Can't route {:?} from our client. Relaying it via {}.",
+            self, signed_msg, second_proxy
+        );
+        let priority = signed_msg.priority();
+        if self.filter_outgoing_routing_msg(signed_msg.routing_message(), &second_proxy, route) {
+            return Ok(());
+        }
+        let mut hop_msg = HopMessage::new(
+            signed_msg.clone(),
+            route,
+            BTreeSet::new(),
+            self.full_id.signing_private_key(),
+        )?;
+        hop_msg.relayed = true;
+        let bytes = serialisation::serialise(&Message::Hop(hop_msg))?;
+        self.queue_send(TrafficClass::ClientRelay, &second_proxy, bytes, priority);
+        Ok(())
+    }
+
     // Filter, then convert the message to a `Hop` or `TunnelHop` `Message` and serialise.
     // Send this byte string.
     fn send_signed_msg_to_peer(
@@ -3157,7 +5847,7 @@ impl Node {
         let routing_msg = signed_msg.routing_message().clone();
 
         let (pub_id, bytes) = if self.crust_service.is_connected(&target) {
-            let serialised = self.to_hop_bytes(signed_msg, route, sent_to)?;
+            let serialised = self.cached_hop_bytes(signed_msg, route, sent_to)?;
             (target, serialised)
         } else if let Some(&tunnel_id) = self.tunnels.tunnel_for(&target) {
             let serialised = self.to_tunnel_hop_bytes(signed_msg, route, sent_to, target)?;
@@ -3172,11 +5862,46 @@ impl Node {
             return Ok(());
         };
         if !self.filter_outgoing_routing_msg(&routing_msg, &target, route) {
-            self.send_or_drop(&pub_id, bytes, priority);
+            let class = if routing_msg.src.is_client() || routing_msg.dst.is_client() {
+                TrafficClass::ClientRelay
+            } else if self.in_authority(&routing_msg.src) {
+                TrafficClass::Own
+            } else {
+                TrafficClass::Forward
+            };
+            self.queue_send(class, &pub_id, bytes, priority);
         }
         Ok(())
     }
 
+    // Queues the message in the given traffic class and sends whatever the send queue releases.
+    fn queue_send(&mut self, class: TrafficClass, pub_id: &PublicId, bytes: Vec<u8>, priority: u8) {
+        let dropped = self.send_queue.push(class, *pub_id, bytes, priority);
+        if dropped > 0 {
+            debug!(
+                "{:?} Send queue for {:?} is full, dropped {} messages.",
+                self, class, dropped
+            );
+        }
+        self.flush_send_queue();
+    }
+
+    // Sends queued messages until the send budget is used up, and if any remain, schedules another
+    // attempt once it has been replenished.
+    fn flush_send_queue(&mut self) {
+        while let Some(msg) = self.send_queue.pop() {
+            self.send_or_drop(&msg.pub_id, msg.bytes, msg.priority);
+        }
+        self.stats.set_send_queue_depths(self.send_queue.depths());
+        if !self.send_queue.is_empty() && self.send_queue_timer_token.is_none() {
+            let delay = Duration::from_millis(SEND_INTERVAL_MS);
+            self.send_queue_timer_token = Some(self.timer.schedule(delay));
+        }
+        if !self.retiring_joining_nodes.is_empty() {
+            self.acknowledge_proxy_retirements();
+        }
+    }
+
     // Wraps the signed message in a `HopMessage` and sends it on.
     //
     // In the case that the `pub_id` is unknown, an ack is sent and the message dropped.
@@ -3187,6 +5912,7 @@ impl Node {
     ) -> Result<(), RoutingError> {
         let priority = signed_msg.priority();
         let is_client = self.peer_mgr.is_client(pub_id);
+        let mut buffered = false;
 
         let result = if is_client || self.peer_mgr.is_joining_node(pub_id) {
             // If the message being relayed is a data response, update the client's
@@ -3208,10 +5934,23 @@ impl Node {
             )?;
             let message = Message::Hop(hop_msg);
             let raw_bytes = serialisation::serialise(&message)?;
-            self.send_or_drop(pub_id, raw_bytes, priority);
+            if self.relay_accounting_events && is_client {
+                self.relay_accounting
+                    .add_to_client(*pub_id, raw_bytes.len());
+            }
+            self.queue_send(TrafficClass::ClientRelay, pub_id, raw_bytes, priority);
+            Ok(())
+        } else if self.client_buffer.push(pub_id, signed_msg) {
+            debug!(
+                target: "routing_relay",
+                "{:?} Holding message {:?} for disconnected client {}.",
+                self, signed_msg, pub_id
+            );
+            buffered = true;
             Ok(())
         } else {
             debug!(
+                target: "routing_relay",
                 "{:?} Client connection not found for message {:?}.",
                 self, signed_msg
             );
@@ -3219,7 +5958,7 @@ impl Node {
         };
 
         // Acknowledge the message so that the sender doesn't retry.
-        if is_client || result.is_err() {
+        if is_client || buffered || result.is_err() {
             let hop = *self.name();
             self.send_ack_from(signed_msg.routing_message(), 0, Authority::ManagedNode(hop));
         }
@@ -3230,8 +5969,22 @@ impl Node {
     /// Returns the peer that is responsible for collecting signatures to verify a message; this
     /// may be us or another node. If our signature is not required, this returns `None`.
     fn get_signature_target(&self, src: &Authority<XorName>, route: u8) -> Option<XorName> {
+        if let Authority::ManagedNode(_) | Authority::Client { .. } = *src {
+            return Some(*self.name());
+        }
+        let list = self.signature_targets(src);
+        if !list.contains(&self.name()) {
+            None
+        } else {
+            Some(*list[route as usize % list.len()])
+        }
+    }
+
+    // Returns the names of the nodes which accumulate the signatures of the group or section
+    // authority `src` for the different routes, in order.
+    fn signature_targets(&self, src: &Authority<XorName>) -> Vec<&XorName> {
         use crate::Authority::*;
-        let list: Vec<&XorName> = match *src {
+        match *src {
             ClientManager(_) | NaeManager(_) | NodeManager(_) => {
                 let mut v = self
                     .routing_table()
@@ -3252,13 +6005,7 @@ impl Node {
                 .filter(|name| pfx.matches(name))
                 .chain(iter::once(self.name()))
                 .sorted_by(|&lhs, &rhs| src.name().cmp_distance(lhs, rhs)),
-            ManagedNode(_) | Client { .. } => return Some(*self.name()),
-        };
-
-        if !list.contains(&self.name()) {
-            None
-        } else {
-            Some(*list[route as usize % list.len()])
+            ManagedNode(_) | Client { .. } => vec![],
         }
     }
 
@@ -3339,6 +6086,27 @@ impl Node {
         }
     }
 
+    // Returns `to_hop_bytes` for the given message, reusing the result if we recently serialised
+    // the same message with the same route and `sent_to` list.
+    fn cached_hop_bytes(
+        &mut self,
+        signed_msg: SignedMessage,
+        route: u8,
+        sent_to: BTreeSet<XorName>,
+    ) -> Result<Vec<u8>, RoutingError> {
+        let key = (
+            sha3_256(&serialisation::serialise(&signed_msg)?),
+            route,
+            sent_to,
+        );
+        if let Some(bytes) = self.hop_bytes_cache.get(&key) {
+            return Ok(bytes.clone());
+        }
+        let bytes = self.to_hop_bytes(signed_msg, route, key.2.clone())?;
+        let _ = self.hop_bytes_cache.insert(key, bytes.clone());
+        Ok(bytes)
+    }
+
     // Wrap the `signed_msg` with a `HopMessage`, then wrap that with `Message::TunnelHop`.
     // Serialise the result to a byte string.
     fn to_tunnel_hop_bytes(
@@ -3383,7 +6151,11 @@ impl Node {
             let mut to_sign = match serialisation::serialise(&old_and_new_pub_ids) {
                 Ok(result) => result,
                 Err(error) => {
-                    error!("Failed to serialise public IDs: {:?}", error);
+                    error!(
+                        target: "routing_connect",
+                        "Failed to serialise public IDs: {:?}",
+                        error
+                    );
                     return;
                 }
             };
@@ -3397,6 +6169,7 @@ impl Node {
                 *proxy_node_name
             } else {
                 warn!(
+                    target: "routing_connect",
                     "{:?} No proxy found, so unable to send CandidateInfo.",
                     self
                 );
@@ -3456,6 +6229,35 @@ impl Node {
             _ => (),
         }
 
+        if self.peer_mgr.get_peer(&their_public_id).is_none() {
+            if self.is_connect_backing_off(&their_name) {
+                trace!(
+                    target: "routing_connect",
+                    "{:?} Not connecting to {:?} yet, as the last attempt failed.",
+                    self,
+                    their_name
+                );
+                return Ok(());
+            }
+            if self.peer_mgr.connecting_num() >= self.max_concurrent_connects {
+                if !self
+                    .pending_connects
+                    .iter()
+                    .any(|&(ref pub_id, ..)| *pub_id == their_public_id)
+                {
+                    trace!(
+                        target: "routing_connect",
+                        "{:?} Too many connection attempts in progress. Queueing {:?}.",
+                        self,
+                        their_name
+                    );
+                    self.pending_connects
+                        .push((their_public_id, src, dst, reconnecting));
+                }
+                return Ok(());
+            }
+        }
+
         // This will insert the peer if peer is not in peer_mgr and flag them to `valid`
         if let Some(token) =
             self.peer_mgr
@@ -3471,6 +6273,7 @@ impl Node {
             }
             state => {
                 trace!(
+                    target: "routing_connect",
                     "{:?} Not sending connection info request to {:?}. State: {:?}",
                     self,
                     their_name,
@@ -3480,6 +6283,7 @@ impl Node {
             }
         };
         trace!(
+            target: "routing_connect",
             "{:?} Resending connection info request to {:?}",
             self,
             their_name
@@ -3496,12 +6300,15 @@ impl Node {
         outbox: &mut EventBox,
         mut try_reconnect: bool,
     ) -> bool {
+        self.link_keys.remove(pub_id);
+        self.direct_sequencer.remove(pub_id);
         let (peer, removal_result) = match self.peer_mgr.remove_peer(pub_id) {
             Some(result) => result,
             None => return true,
         };
 
         if let Ok(removal_details) = removal_result {
+            let _ = self.quarantined.remove(pub_id);
             if !self.dropped_routing_node(peer.name(), removal_details, outbox) {
                 return false;
             }
@@ -3567,12 +6374,20 @@ impl Node {
         outbox: &mut EventBox,
     ) -> bool {
         info!(
+            target: "routing_churn",
             "{:?} Dropped {} from the routing table.",
             self, details.name
         );
 
         if self.is_approved {
             outbox.send_event(Event::NodeLost(details.name, self.routing_table().clone()));
+            self.note_table_change(&details.name, outbox);
+        }
+
+        self.churn_count += 1;
+        if self.churn_count >= CHURN_BURST_SIZE && self.compaction_timer_token.is_none() {
+            let delay = Duration::from_secs(COMPACTION_DELAY_SECS);
+            self.compaction_timer_token = Some(self.timer.schedule(delay));
         }
 
         self.merge_if_necessary(outbox);
@@ -3585,14 +6400,16 @@ impl Node {
             self.reset_su_timer();
             let section_len = self.routing_table().our_section().len();
             self.section_list_sigs.remove_signatures(name, section_len);
+            self.proxy_certificate.remove_signer(name);
         }
 
         if self.routing_table().is_empty() {
-            debug!("{:?} Lost all routing connections.", self);
+            debug!(target: "routing_churn", "{:?} Lost all routing connections.", self);
             if !self.is_first_node {
                 outbox.send_event(Event::RestartRequired);
                 return false;
             }
+            outbox.send_event(Event::Disconnected);
         }
 
         true
@@ -3607,7 +6424,11 @@ impl Node {
             let dst = Authority::PrefixSection(prefix);
             let content = MessageContent::SectionSplit(our_ver_pfx, joining_node);
             if let Err(err) = self.send_routing_message(src, dst, content) {
-                debug!("{:?} Failed to send SectionSplit: {:?}.", self, err);
+                debug!(
+                    target: "routing_churn",
+                    "{:?} Failed to send SectionSplit: {:?}.",
+                    self, err
+                );
             }
         }
     }
@@ -3620,17 +6441,40 @@ impl Node {
             let src = Authority::PrefixSection(sender_prefix);
             let dst = Authority::PrefixSection(sender_prefix.popped());
             debug!(
+                target: "routing_churn",
                 "{:?} Sending OwnSectionMerge from {:?} to {:?} with content {:?}",
                 self, src, dst, content
             );
             if let Err(err) = self.send_routing_message(src, dst, content) {
-                debug!("{:?} Failed to send OwnSectionMerge: {:?}.", self, err);
+                debug!(
+                    target: "routing_churn",
+                    "{:?} Failed to send OwnSectionMerge: {:?}.",
+                    self, err
+                );
             }
         }
     }
 
     fn remove_expired_peers(&mut self, outbox: &mut EventBox) {
+        let unvouched = self.peer_mgr.unvouched_peers();
+        let stalled = self.peer_mgr.stalled_connects();
         for pub_id in self.peer_mgr.remove_expired_peers() {
+            if unvouched.contains(&pub_id) {
+                debug!(
+                    target: "routing_connect",
+                    "{:?} No section vouched for {} in time. Dropping its connection.",
+                    self, pub_id
+                );
+            }
+            if stalled.contains(&pub_id) {
+                debug!(
+                    target: "routing_connect",
+                    "{:?} Failed to connect to {} directly or via a tunnel in time. Giving up.",
+                    self, pub_id
+                );
+                self.record_connect_failure(*pub_id.name());
+                outbox.send_event(Event::ConnectFailed(*pub_id.name()));
+            }
             debug!("{:?} Disconnecting from timed out peer {:?}", self, pub_id);
             // We've already removed from peer manager but this helps clean out
             // tunnel or direct connection to expired peer
@@ -3651,11 +6495,16 @@ impl Node {
         for target in &targets {
             let dst = Authority::PrefixSection(*target);
             debug!(
+                target: "routing_churn",
                 "{:?} Sending OtherSectionMerge from {:?} to {:?} with content {:?}",
                 self, src, dst, content
             );
             if let Err(err) = self.send_routing_message(src, dst, content.clone()) {
-                debug!("{:?} Failed to send OtherSectionMerge: {:?}.", self, err);
+                debug!(
+                    target: "routing_churn",
+                    "{:?} Failed to send OtherSectionMerge: {:?}.",
+                    self, err
+                );
             }
         }
     }
@@ -3663,6 +6512,7 @@ impl Node {
     fn dropped_tunnel_client(&mut self, pub_id: &PublicId) {
         for other_id in self.tunnels.drop_client(pub_id) {
             trace!(
+                target: "routing_relay",
                 "{:?} Closing tunnel client connection between {} and {}",
                 self,
                 pub_id,
@@ -3673,6 +6523,68 @@ impl Node {
         }
     }
 
+    /// Switches to the standby tunnel to `pub_id`, if we have one, after losing the direct
+    /// connection. Returns whether we did.
+    fn fail_over_to_standby(&mut self, pub_id: PublicId, outbox: &mut EventBox) -> bool {
+        let tunnel_id = match self.tunnels.fail_over(&pub_id) {
+            Some(tunnel_id) => tunnel_id,
+            None => return false,
+        };
+        debug!(
+            target: "routing_connect",
+            "{:?} Lost direct connection to {}, failing over to tunnel via {}.",
+            self, pub_id, tunnel_id
+        );
+        self.link_keys.remove(&pub_id);
+        self.peer_mgr.correct_state_to_tunnel(&pub_id);
+        self.dropped_tunnel_client(&pub_id);
+        self.dropped_tunnel_node(&pub_id, outbox);
+        true
+    }
+
+    /// Fails over to the standby tunnel to `src` as well, if `src` started sending via it: the
+    /// peer lost our direct connection, even if we didn't notice yet.
+    fn follow_fail_over(&mut self, src: &PublicId, tunnel_id: &PublicId, outbox: &mut EventBox) {
+        if self.tunnels.standby_for(src) != Some(tunnel_id) {
+            return;
+        }
+        let _ = self.crust_service.disconnect(src);
+        let _ = self.fail_over_to_standby(*src, outbox);
+    }
+
+    /// Closes the standby tunnels which can't be used anymore: those whose peer or tunnel node
+    /// left our routing table, or whose tunnel node we are not directly connected to. Also closes
+    /// the expired ones, so that they don't keep taking up the tunnel nodes' capacity.
+    fn check_standby_tunnels(&mut self) {
+        for (dst_id, tunnel_id) in self.tunnels.remove_expired_standby() {
+            debug!(
+                target: "routing_relay",
+                "{:?} Closing expired standby tunnel for {} via {}.",
+                self, dst_id, tunnel_id
+            );
+            if self.crust_service.is_connected(&tunnel_id) {
+                self.send_direct_message(tunnel_id, DirectMessage::TunnelDisconnect(dst_id));
+            }
+        }
+        for (dst_id, tunnel_id) in self.tunnels.standby_tunnels() {
+            let usable = self.peer_mgr.is_routing_peer(&dst_id)
+                && self.peer_mgr.is_routing_peer(&tunnel_id)
+                && self.crust_service.is_connected(&tunnel_id);
+            if usable {
+                continue;
+            }
+            let _ = self.tunnels.remove_standby_for(&dst_id);
+            debug!(
+                target: "routing_relay",
+                "{:?} Closing standby tunnel for {} via {}.",
+                self, dst_id, tunnel_id
+            );
+            if self.crust_service.is_connected(&tunnel_id) {
+                self.send_direct_message(tunnel_id, DirectMessage::TunnelDisconnect(dst_id));
+            }
+        }
+    }
+
     fn dropped_tunnel_node(&mut self, pub_id: &PublicId, outbox: &mut EventBox) {
         let peers = self
             .tunnels
@@ -3687,6 +6599,7 @@ impl Node {
         for (dst_id, valid) in peers {
             let _ = self.dropped_peer(&dst_id, outbox, false);
             debug!(
+                target: "routing_relay",
                 "{:?} Lost tunnel for peer {:?}. Requesting new tunnel.",
                 self, dst_id
             );
@@ -3700,18 +6613,35 @@ impl Node {
         self.is_first_node || !self.routing_table().is_empty()
     }
 
+    // Returns whether we are a member of the `NaeManager` for `name`, or an error if we are not an
+    // approved node with a routing table yet.
+    fn is_nae_manager_for(&self, name: &XorName) -> Result<bool, InterfaceError> {
+        if !self.is_approved || !self.is_proper() {
+            return Err(InterfaceError::InvalidState);
+        }
+        Ok(self
+            .routing_table()
+            .in_authority(&Authority::NaeManager(*name)))
+    }
+
     fn send_direct_message(&mut self, dst_id: PublicId, direct_message: DirectMessage) {
         self.stats().count_direct_message(&direct_message);
 
+        let seq = self.direct_sequencer.next_seq(dst_id);
         if let Some(&tunnel_id) = self.tunnels.tunnel_for(&dst_id) {
             let message = Message::TunnelDirect {
                 content: direct_message,
+                seq,
                 src: *self.full_id.public_id(),
                 dst: dst_id,
             };
             self.send_message(&tunnel_id, message);
         } else {
-            self.send_message(&dst_id, Message::Direct(direct_message));
+            let message = Message::Direct {
+                content: direct_message,
+                seq,
+            };
+            self.send_message(&dst_id, message);
         }
     }
 
@@ -3719,6 +6649,16 @@ impl Node {
         self.routing_table().our_prefix()
     }
 
+    // Routing table peers and our proxy can only be dropped with the section's agreement, so we
+    // just ignore their oversized messages. Everyone else is banned.
+    fn penalise_oversized_message(&mut self, pub_id: &PublicId) {
+        if self.peer_mgr.is_routing_peer(pub_id) || self.peer_mgr.is_proxy(pub_id) {
+            warn!("{:?} Dropping oversized message from {}.", self, pub_id);
+        } else {
+            self.ban_and_disconnect_peer(pub_id);
+        }
+    }
+
     // While this can theoretically be called as a result of a misbehaving client or node, we're
     // actually only blocking clients from bootstrapping from that IP (see
     // `handle_bootstrap_accept()`). This behaviour will change when we refactor the codebase to
@@ -3752,12 +6692,38 @@ impl Base for Node {
         }
     }
 
+    fn explain_authority(&self, auth: &Authority<XorName>) -> AuthorityExplanation {
+        match *auth {
+            Authority::Client { ref client_id, .. } => AuthorityExplanation {
+                in_authority: self.in_authority(auth),
+                reason: format!(
+                    "the client's ID is {}, and ours is {}",
+                    client_id,
+                    self.full_id.public_id()
+                ),
+            },
+            _ if !self.is_proper() => AuthorityExplanation {
+                in_authority: false,
+                reason: "our routing table is still empty".to_string(),
+            },
+            _ => self.routing_table().explain_authority(auth),
+        }
+    }
+
     fn close_group(&self, name: XorName, count: usize) -> Option<Vec<XorName>> {
         self.routing_table()
             .closest_names(&name, count)
             .map(|names| names.into_iter().cloned().collect_vec())
     }
 
+    fn proxy_name(&self) -> Option<XorName> {
+        self.peer_mgr.get_proxy_name().cloned()
+    }
+
+    fn seal_message(&self, pub_id: &PublicId, bytes: Vec<u8>) -> Result<Vec<u8>, RoutingError> {
+        self.link_keys.seal(pub_id, bytes)
+    }
+
     fn handle_lost_peer(&mut self, pub_id: PublicId, outbox: &mut EventBox) -> Transition {
         if self.peer_mgr.get_peer(&pub_id).is_none() {
             return Transition::Stay;
@@ -3765,10 +6731,31 @@ impl Base for Node {
 
         debug!("{:?} Received LostPeer - {}", self, pub_id);
 
+        if self.peer_mgr.is_routing_peer(&pub_id) {
+            if self.fail_over_to_standby(pub_id, outbox) {
+                return Transition::Stay;
+            }
+            if self.standby_tunnels && self.tunnels.tunnel_for(&pub_id).is_some() {
+                // We already followed the peer over to our standby tunnel.
+                return Transition::Stay;
+            }
+        }
+
+        let report = self.is_approved
+            && self.peer_mgr.is_routing_peer(&pub_id)
+            && self.routing_table().our_section().contains(pub_id.name());
+        // Only a client which lost its connection may bootstrap off us again, not one we dropped.
+        if self.peer_mgr.is_client(&pub_id) {
+            self.client_buffer.client_dropped(pub_id);
+        }
+
         self.dropped_tunnel_client(&pub_id);
         self.dropped_tunnel_node(&pub_id, outbox);
 
         if self.dropped_peer(&pub_id, outbox, true) {
+            if report {
+                self.send_report_unreachable(*pub_id.name());
+            }
             Transition::Stay
         } else {
             Transition::Terminate
@@ -3779,6 +6766,10 @@ impl Base for Node {
         &mut self.stats
     }
 
+    fn capture(&mut self) -> &mut Capture {
+        &mut self.capture
+    }
+
     fn min_section_size(&self) -> usize {
         self.routing_table().min_section_size()
     }
@@ -3796,6 +6787,17 @@ impl Node {
         let _ = self.purge_invalid_rt_entries(&mut EventBuf::new());
     }
 
+    /// Handles `bytes` as if they were received from `pub_id`.
+    #[cfg(feature = "fuzzing")]
+    pub fn fuzz_dispatch(
+        &mut self,
+        pub_id: PublicId,
+        bytes: Vec<u8>,
+        outbox: &mut EventBox,
+    ) -> Result<(), RoutingError> {
+        self.handle_new_message(pub_id, bytes, outbox)
+    }
+
     pub fn get_timed_out_tokens(&mut self) -> Vec<u64> {
         self.timer.get_timed_out_tokens()
     }
@@ -3814,6 +6816,14 @@ impl Node {
         }
     }
 
+    pub fn is_quarantined(&self, pub_id: &PublicId) -> bool {
+        self.quarantined.contains_key(pub_id)
+    }
+
+    pub fn has_held_direct_messages(&self) -> bool {
+        self.direct_sequencer.time_to_expiry().is_some()
+    }
+
     pub fn get_banned_client_ips(&self) -> BTreeSet<IpAddr> {
         self.banned_client_ips
             .peek_iter()
@@ -3829,6 +6839,19 @@ impl Node {
         self.next_relocation_interval = Some(interval);
     }
 
+    pub fn deny_next_relocation(&mut self, reason: RelocationDenial) {
+        self.next_relocation_denial = Some(reason);
+    }
+
+    pub fn report_accumulation_failed(
+        &mut self,
+        src: Authority<XorName>,
+        msg_id: MessageId,
+        dst: Authority<XorName>,
+    ) {
+        self.send_accumulation_failed(src, msg_id, dst);
+    }
+
     pub fn has_unnormalised_routing_conn(&self, excludes: &BTreeSet<XorName>) -> bool {
         self.peer_mgr.has_unnormalised_routing_conn(excludes)
     }
@@ -3905,7 +6928,9 @@ impl Bootstrapped for Node {
             Client { .. } => vec![],
         };
 
-        let signed_msg = SignedMessage::new(routing_msg, &self.full_id, sending_names)?;
+        let dst_section_hash = self.dst_section_hash(&routing_msg.dst);
+        let mut signed_msg = SignedMessage::new(routing_msg, &self.full_id, sending_names)?;
+        signed_msg.set_dst_section_hash(dst_section_hash);
 
         match self.get_signature_target(&signed_msg.routing_message().src, route) {
             None => Ok(()),
@@ -46,6 +46,7 @@ pub struct Stats {
     msg_expect_candidate: usize,
     msg_accept_as_candidate: usize,
     msg_refresh: usize,
+    msg_tagged: usize,
     msg_connection_info_req: usize,
     msg_connection_info_rsp: usize,
     msg_section_update: usize,
@@ -53,9 +54,24 @@ pub struct Stats {
     msg_own_section_merge: usize,
     msg_other_section_merge: usize,
     msg_relocate_rsp: usize,
+    msg_relocate_deferred: usize,
+    msg_relocate_denied: usize,
     msg_candidate_approval: usize,
     msg_node_approval: usize,
     msg_ack: usize,
+    msg_trace_report: usize,
+    msg_section_update_req: usize,
+    msg_get_close_group: usize,
+    msg_get_close_group_rsp: usize,
+    msg_report_unreachable: usize,
+    msg_accumulation_failed: usize,
+    msg_cache_priming: usize,
+    /// Messages received for our section which were sent with a different view of its members.
+    inconsistent_dst_views: usize,
+    /// Messages received from peers which falsely claimed to be sent by us.
+    forged_claimant: usize,
+    /// Messages from our clients which weren't validly signed by them.
+    unverified_client_msgs: usize,
 
     pub msg_user_parts: u64,
     msg_put_idata: UserMessageStats,
@@ -84,6 +100,13 @@ pub struct Stats {
     msg_total: usize,
     msg_total_bytes: u64,
 
+    /// Number of compaction passes run after bursts of churn.
+    compactions: usize,
+    /// Approximate bytes held by routing metadata before and after the latest compaction.
+    last_compaction: (usize, usize),
+    /// Messages waiting to be sent: own messages, routing forwards and client relays.
+    send_queue_depths: [usize; 3],
+
     should_log: bool,
 }
 
@@ -145,6 +168,7 @@ impl Stats {
                 Request::GetAccountInfo { .. } => self.msg_get_account_info.inc_request(),
                 Request::Refresh(..) => self.msg_refresh += 1,
             },
+            UserMessage::Tagged { .. } => self.msg_tagged += 1,
             UserMessage::Response(ref response) => match *response {
                 Response::PutIData { ref res, .. } => self.msg_put_idata.inc_response(res.is_ok()),
                 Response::GetIData { ref res, .. } => self.msg_get_idata.inc_response(res.is_ok()),
@@ -209,17 +233,28 @@ impl Stats {
         match msg.content {
             MessageContent::Relocate { .. } => self.msg_relocate += 1,
             MessageContent::ExpectCandidate { .. } => self.msg_expect_candidate += 1,
-            MessageContent::AcceptAsCandidate { .. } => self.msg_accept_as_candidate += 1,
+            MessageContent::AcceptAsCandidate { .. } | MessageContent::ShareCandidate { .. } => {
+                self.msg_accept_as_candidate += 1
+            }
             MessageContent::ConnectionInfoRequest { .. } => self.msg_connection_info_req += 1,
             MessageContent::ConnectionInfoResponse { .. } => self.msg_connection_info_rsp += 1,
             MessageContent::SectionUpdate { .. } => self.msg_section_update += 1,
+            MessageContent::SectionUpdateRequest(..) => self.msg_section_update_req += 1,
+            MessageContent::GetCloseGroup { .. } => self.msg_get_close_group += 1,
+            MessageContent::GetCloseGroupResponse { .. } => self.msg_get_close_group_rsp += 1,
+            MessageContent::ReportUnreachable(..) => self.msg_report_unreachable += 1,
+            MessageContent::AccumulationFailed { .. } => self.msg_accumulation_failed += 1,
+            MessageContent::CachePrimingRequest { .. } => self.msg_cache_priming += 1,
             MessageContent::SectionSplit(..) => self.msg_section_split += 1,
             MessageContent::OwnSectionMerge(..) => self.msg_own_section_merge += 1,
             MessageContent::OtherSectionMerge(..) => self.msg_other_section_merge += 1,
             MessageContent::RelocateResponse { .. } => self.msg_relocate_rsp += 1,
+            MessageContent::RelocateDeferred { .. } => self.msg_relocate_deferred += 1,
+            MessageContent::RelocateDenied { .. } => self.msg_relocate_denied += 1,
             MessageContent::Ack(..) => self.msg_ack += 1,
             MessageContent::CandidateApproval { .. } => self.msg_candidate_approval += 1,
             MessageContent::NodeApproval { .. } => self.msg_node_approval += 1,
+            MessageContent::TraceReport { .. } => self.msg_trace_report += 1,
             MessageContent::UserMessagePart { .. } => return, // Counted as request/response.
         }
         self.increment_msg_total();
@@ -236,8 +271,23 @@ impl Stats {
             ResourceProofResponse { .. } => self.msg_direct_resource_proof_rsp += 1,
             ResourceProofResponseReceipt => self.msg_direct_resource_proof_rsp_receipt += 1,
             ProxyRateLimitExceeded { .. } => self.msg_direct_proxy_rate_limit_exceed += 1,
-            BootstrapRequest(_) | BootstrapResponse(_) | TunnelRequest(_) | TunnelSuccess(_)
-            | TunnelSelect(_) | TunnelClosed(_) | TunnelDisconnect(_) => self.msg_other += 1,
+            BootstrapRequest(_)
+            | BootstrapResponse(_)
+            | TunnelRequest(_)
+            | TunnelSuccess(_)
+            | TunnelSelect(_)
+            | TunnelClosed(_)
+            | TunnelDisconnect(_)
+            | LinkEncryption { .. }
+            | Disconnecting
+            | CloseGroupDigest(..)
+            | CloseGroup(_)
+            | CandidateNameTaken
+            | ProxyCertificateSignature(_)
+            | ProxyCertificate(_)
+            | ProxyClosing(_)
+            | ProxyRetirement
+            | ProxyRetired => self.msg_other += 1,
         }
         self.increment_msg_total();
     }
@@ -246,6 +296,41 @@ impl Stats {
         self.msg_total_bytes += len as u64;
     }
 
+    /// Returns the total number of bytes sent.
+    pub fn msg_total_bytes(&self) -> u64 {
+        self.msg_total_bytes
+    }
+
+    pub fn count_inconsistent_dst_view(&mut self) {
+        self.inconsistent_dst_views += 1;
+    }
+
+    pub fn count_forged_claimant(&mut self) {
+        self.forged_claimant += 1;
+    }
+
+    pub fn count_unverified_client_msg(&mut self) {
+        self.unverified_client_msgs += 1;
+    }
+
+    /// Records a compaction pass which shrank routing metadata from `before` to `after` bytes.
+    pub fn count_compaction(&mut self, before: usize, after: usize) {
+        self.compactions += 1;
+        self.last_compaction = (before, after);
+        if self.should_log {
+            info!(target: "routing_stats",
+                  "Stats - Compaction #{} - routing metadata reduced from {} to {} bytes",
+                  self.compactions,
+                  self.last_compaction.0,
+                  self.last_compaction.1);
+        }
+    }
+
+    /// Records the number of messages waiting in each traffic class of the send queue.
+    pub fn set_send_queue_depths(&mut self, depths: [usize; 3]) {
+        self.send_queue_depths = depths;
+    }
+
     pub fn enable_logging(&mut self) {
         self.should_log = true;
     }
@@ -257,12 +342,26 @@ impl Stats {
         if self.should_log && self.msg_total % MSG_LOG_COUNT == 0 {
             info!(target: "routing_stats",
                   "Stats - Sent {} messages in total, comprising {} bytes, {} uncategorised, \
-                   routes/failed: {:?}/{}",
+                   routes/failed: {:?}/{}, inconsistent destination views: {}, received forged \
+                   as ours: {}, unverified from clients: {}",
                   self.msg_total,
                   self.msg_total_bytes,
                   self.msg_other,
                   self.routes,
-                  self.unacked_msgs);
+                  self.unacked_msgs,
+                  self.inconsistent_dst_views,
+                  self.forged_claimant,
+                  self.unverified_client_msgs);
+            info!(target: "routing_stats",
+                  "Stats - Compactions: {}, last: {}/{} bytes",
+                  self.compactions,
+                  self.last_compaction.0,
+                  self.last_compaction.1);
+            info!(target: "routing_stats",
+                  "Stats - Send queue (own/forward/client relay): {}/{}/{}",
+                  self.send_queue_depths[0],
+                  self.send_queue_depths[1],
+                  self.send_queue_depths[2]);
             info!(target: "routing_stats",
                   "Stats - Direct - CandidateInfo: {}, MessageSignature: {}, \
                    ResourceProof: {}/{}/{}, SectionListSignature: {}, ProxyRateLimitExceeded: {}",
@@ -274,14 +373,20 @@ impl Stats {
                   self.msg_direct_sls,
                   self.msg_direct_proxy_rate_limit_exceed);
             info!(target: "routing_stats",
-                  "Stats - Hops (Request/Response) - Relocate: {}/{}, ExpectCandidate: {}, \
-                   AcceptAsCandidate: {}, SectionUpdate: {}, SectionSplit: {}, \
+                  "Stats - Hops (Request/Response) - Relocate: {}/{} ({} deferred, {} denied), \
+                   ExpectCandidate: {}, \
+                   AcceptAsCandidate: {}, SectionUpdate: {}/{}, SectionSplit: {}, \
                    OwnSectionMerge: {}, OtherSectionMerge: {}, ConnectionInfo: {}/{}, \
-                   CandidateApproval: {}, NodeApproval: {}, Ack: {}",
+                   CandidateApproval: {}, NodeApproval: {}, Ack: {}, TraceReport: {}, \
+                   GetCloseGroup: {}/{}, ReportUnreachable: {}, AccumulationFailed: {}, \
+                   CachePriming: {}",
                   self.msg_relocate,
                   self.msg_relocate_rsp,
+                  self.msg_relocate_deferred,
+                  self.msg_relocate_denied,
                   self.msg_expect_candidate,
                   self.msg_accept_as_candidate,
+                  self.msg_section_update_req,
                   self.msg_section_update,
                   self.msg_section_split,
                   self.msg_own_section_merge,
@@ -290,7 +395,13 @@ impl Stats {
                   self.msg_connection_info_rsp,
                   self.msg_candidate_approval,
                   self.msg_node_approval,
-                  self.msg_ack);
+                  self.msg_ack,
+                  self.msg_trace_report,
+                  self.msg_get_close_group,
+                  self.msg_get_close_group_rsp,
+                  self.msg_report_unreachable,
+                  self.msg_accumulation_failed,
+                  self.msg_cache_priming);
             info!(target: "routing_stats",
                   "Stats - User (total parts: {}) (Request/Success/Failure) - \
                    PutIData: {}, \
@@ -312,7 +423,8 @@ impl Stats {
                    InsAuthKey: {}, \
                    DelAuthKey: {}, \
                    GetAccountInfo: {}, \
-                   Refresh: {}",
+                   Refresh: {}, \
+                   Tagged: {}",
                   self.msg_user_parts,
                   self.msg_put_idata,
                   self.msg_get_idata,
@@ -333,7 +445,8 @@ impl Stats {
                   self.msg_ins_auth_key,
                   self.msg_del_auth_key,
                   self.msg_get_account_info,
-                  self.msg_refresh);
+                  self.msg_refresh,
+                  self.msg_tagged);
         }
     }
 }
@@ -0,0 +1,99 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::crust::{ConnectionInfoResult, CrustError, CrustUser};
+use crate::id::PublicId;
+use crate::{CrustEvent, PrivConnectionInfo};
+use std::net::SocketAddr;
+
+/// An event of the transport layer, as handled by the routing states.
+///
+/// Crust's events are translated into these as they are taken off the Crust channel, so that the
+/// states don't depend on Crust's event type and can be driven with events made up in tests.
+#[derive(Debug)]
+pub enum TransportEvent {
+    /// A peer bootstrapped off us.
+    BootstrapAccept(PublicId, CrustUser),
+    /// We bootstrapped off the peer at the given address.
+    BootstrapConnect(PublicId, SocketAddr),
+    /// We failed to bootstrap off any of our contacts.
+    BootstrapFailed,
+    /// Our listener started on the given port.
+    ListenerStarted(u16),
+    /// Our listener failed to start.
+    ListenerFailed,
+    /// Our connection info for the given token was prepared.
+    ConnectionInfoPrepared {
+        result_token: u32,
+        result: Result<PrivConnectionInfo, CrustError>,
+    },
+    /// We connected to the peer.
+    ConnectSuccess(PublicId),
+    /// We failed to connect to the peer.
+    ConnectFailure(PublicId),
+    /// We lost our connection to the peer.
+    LostPeer(PublicId),
+    /// We received a message from the peer.
+    NewMessage(PublicId, CrustUser, Vec<u8>),
+    /// The given message to the peer was too large to be sent.
+    WriteMsgSizeProhibitive(PublicId, Vec<u8>),
+}
+
+impl From<CrustEvent<PublicId>> for TransportEvent {
+    fn from(event: CrustEvent<PublicId>) -> TransportEvent {
+        match event {
+            CrustEvent::BootstrapAccept(pub_id, peer_kind) => {
+                TransportEvent::BootstrapAccept(pub_id, peer_kind)
+            }
+            CrustEvent::BootstrapConnect(pub_id, addr) => {
+                TransportEvent::BootstrapConnect(pub_id, addr)
+            }
+            CrustEvent::BootstrapFailed => TransportEvent::BootstrapFailed,
+            CrustEvent::ListenerStarted(port) => TransportEvent::ListenerStarted(port),
+            CrustEvent::ListenerFailed => TransportEvent::ListenerFailed,
+            CrustEvent::ConnectionInfoPrepared(ConnectionInfoResult {
+                result_token,
+                result,
+            }) => TransportEvent::ConnectionInfoPrepared {
+                result_token,
+                result,
+            },
+            CrustEvent::ConnectSuccess(pub_id) => TransportEvent::ConnectSuccess(pub_id),
+            CrustEvent::ConnectFailure(pub_id) => TransportEvent::ConnectFailure(pub_id),
+            CrustEvent::LostPeer(pub_id) => TransportEvent::LostPeer(pub_id),
+            CrustEvent::NewMessage(pub_id, peer_kind, bytes) => {
+                TransportEvent::NewMessage(pub_id, peer_kind, bytes)
+            }
+            CrustEvent::WriteMsgSizeProhibitive(pub_id, bytes) => {
+                TransportEvent::WriteMsgSizeProhibitive(pub_id, bytes)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+
+    #[test]
+    fn crust_events_are_translated() {
+        let pub_id = *FullId::new().public_id();
+        match TransportEvent::from(CrustEvent::NewMessage(pub_id, CrustUser::Node, vec![1, 2])) {
+            TransportEvent::NewMessage(id, CrustUser::Node, bytes) => {
+                assert_eq!(id, pub_id);
+                assert_eq!(bytes, vec![1, 2]);
+            }
+            event => panic!("Unexpected event {:?}", event),
+        }
+        match TransportEvent::from(CrustEvent::LostPeer(pub_id)) {
+            TransportEvent::LostPeer(id) => assert_eq!(id, pub_id),
+            event => panic!("Unexpected event {:?}", event),
+        }
+    }
+}
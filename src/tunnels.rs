@@ -8,13 +8,23 @@
 
 use crate::id::PublicId;
 use crate::message_filter::MessageFilter;
+#[cfg(feature = "use-mock-crust")]
+use fake_clock::FakeClock as Instant;
 use itertools::Itertools;
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeSet, HashMap};
 use std::time::Duration;
+#[cfg(not(feature = "use-mock-crust"))]
+use std::time::Instant;
 
 /// The maximum number of pairs of nodes that this node will act as a tunnel for.
 const MAX_TUNNEL_CLIENT_PAIRS: usize = 40;
+/// How long we keep a tunnel as a standby route after connecting to its peer directly. Until then
+/// it takes up one of its tunnel node's `MAX_TUNNEL_CLIENT_PAIRS`.
+pub const STANDBY_TUNNEL_SECS: u64 = 5 * 60;
+/// The maximum number of standby tunnels we keep, so that we don't take up too many of the
+/// client pairs that tunnel nodes can serve.
+const MAX_STANDBY_TUNNELS: usize = MAX_TUNNEL_CLIENT_PAIRS / 4;
 
 /// A container for managing tunnel connections.
 ///
@@ -25,6 +35,10 @@ const MAX_TUNNEL_CLIENT_PAIRS: usize = 40;
 pub struct Tunnels {
     /// Maps the peer we failed to directly connect to to the one that acts as a tunnel.
     tunnels: HashMap<PublicId, PublicId>,
+    /// Maps peers we are now directly connected to to the tunnel we used before, and when we
+    /// connected. We keep it open as a second route for a while, to fail over to if the direct
+    /// connection is lost.
+    standby: HashMap<PublicId, (PublicId, Instant)>,
     /// Contains peers that are looking for a tunnel, with the lower ID first. Only once it sends
     /// a message to the latter via us, the pair is moved to `clients`.
     new_clients: MessageFilter<(PublicId, PublicId)>,
@@ -131,9 +145,69 @@ impl Tunnels {
                 return true;
             }
         }
+        if let Entry::Occupied(entry) = self.standby.entry(dst_id) {
+            if entry.get().0 == tunnel_id {
+                let _ = entry.remove();
+                return true;
+            }
+        }
         false
     }
 
+    /// Stops using the tunnel to `dst_id`, now that we are directly connected, but keeps it as a
+    /// standby route. Returns the tunnel node, or `None` if there was no tunnel or we already keep
+    /// `MAX_STANDBY_TUNNELS`.
+    pub fn keep_as_standby(&mut self, dst_id: PublicId) -> Option<PublicId> {
+        if self.standby.len() >= MAX_STANDBY_TUNNELS {
+            return None;
+        }
+        let tunnel_id = self.tunnels.remove(&dst_id)?;
+        let _ = self.standby.insert(dst_id, (tunnel_id, Instant::now()));
+        Some(tunnel_id)
+    }
+
+    /// Switches the messages to `dst_id`, whose direct connection was lost, to its standby tunnel.
+    /// Returns the tunnel node, if there was one.
+    pub fn fail_over(&mut self, dst_id: &PublicId) -> Option<PublicId> {
+        let (tunnel_id, _) = self.standby.remove(dst_id)?;
+        let _ = self.tunnels.insert(*dst_id, tunnel_id);
+        Some(tunnel_id)
+    }
+
+    /// Returns the node acting as our standby tunnel to `dst_id`, if any.
+    pub fn standby_for(&self, dst_id: &PublicId) -> Option<&PublicId> {
+        self.standby.get(dst_id).map(|(tunnel_id, _)| tunnel_id)
+    }
+
+    /// Removes and returns the standby tunnel to `dst_id`, if any.
+    pub fn remove_standby_for(&mut self, dst_id: &PublicId) -> Option<PublicId> {
+        self.standby.remove(dst_id).map(|(tunnel_id, _)| tunnel_id)
+    }
+
+    /// Returns all standby tunnels, as pairs of the destination and the tunnel node.
+    pub fn standby_tunnels(&self) -> Vec<(PublicId, PublicId)> {
+        self.standby
+            .iter()
+            .map(|(&dst_id, &(tunnel_id, _))| (dst_id, tunnel_id))
+            .collect()
+    }
+
+    /// Removes the standby tunnels kept for `STANDBY_TUNNEL_SECS`, and returns them as pairs of
+    /// the destination and the tunnel node.
+    pub fn remove_expired_standby(&mut self) -> Vec<(PublicId, PublicId)> {
+        let expiry = Duration::from_secs(STANDBY_TUNNEL_SECS);
+        let expired = self
+            .standby
+            .iter()
+            .filter(|&(_, &(_, since))| since.elapsed() >= expiry)
+            .map(|(&dst_id, &(tunnel_id, _))| (dst_id, tunnel_id))
+            .collect_vec();
+        for (dst_id, _) in &expired {
+            let _ = self.standby.remove(dst_id);
+        }
+        expired
+    }
+
     /// Removes and the peer that is acting as a tunnel for the given peer, if any.
     pub fn remove_tunnel_for(&mut self, dst_id: &PublicId) -> Option<PublicId> {
         self.tunnels.remove(dst_id)
@@ -145,8 +219,10 @@ impl Tunnels {
     }
 
     /// Removes the given tunnel node and returns a list of all peers it was acting as a tunnel
-    /// for.
+    /// for. Standby tunnels via the node are removed as well, but not returned, as we are still
+    /// directly connected to their peers.
     pub fn remove_tunnel(&mut self, tunnel_id: &PublicId) -> Vec<PublicId> {
+        self.standby.retain(|_, &mut (id, _)| id != *tunnel_id);
         let dst_ids = self
             .tunnels
             .iter()
@@ -179,6 +255,7 @@ impl Default for Tunnels {
     fn default() -> Tunnels {
         Tunnels {
             tunnels: HashMap::new(),
+            standby: HashMap::new(),
             new_clients: MessageFilter::with_expiry_duration(Duration::from_secs(60)),
             clients: BTreeSet::new(),
         }
@@ -189,6 +266,7 @@ impl Default for Tunnels {
 mod tests {
     use super::*;
     use crate::id::FullId;
+    use fake_clock::FakeClock;
     use itertools::Itertools;
 
     #[test]
@@ -225,6 +303,57 @@ mod tests {
         assert_eq!(Some(&sorted_ids[4]), tunnels.tunnel_for(&sorted_ids[3]));
     }
 
+    #[test]
+    fn standby_test() {
+        let mut sorted_ids = vec![];
+        for _ in 0..3 {
+            sorted_ids.push(*FullId::new().public_id());
+        }
+        sorted_ids.sort();
+
+        let mut tunnels: Tunnels = Default::default();
+        // Peer 0 is acting as a tunnel for 1 and 2, until we connect to them directly.
+        let _ = tunnels.add(sorted_ids[1], sorted_ids[0]);
+        let _ = tunnels.add(sorted_ids[2], sorted_ids[0]);
+        assert_eq!(Some(sorted_ids[0]), tunnels.keep_as_standby(sorted_ids[1]));
+        assert_eq!(Some(sorted_ids[0]), tunnels.keep_as_standby(sorted_ids[2]));
+        assert_eq!(None, tunnels.tunnel_for(&sorted_ids[1]));
+        assert_eq!(0, tunnels.tunnel_count());
+
+        // Losing the direct connection to 1 switches it back to the tunnel.
+        assert_eq!(Some(sorted_ids[0]), tunnels.fail_over(&sorted_ids[1]));
+        assert_eq!(Some(&sorted_ids[0]), tunnels.tunnel_for(&sorted_ids[1]));
+        assert_eq!(None, tunnels.fail_over(&sorted_ids[1]));
+
+        // Losing the tunnel node only reports 1, but drops the standby tunnel to 2 as well.
+        assert_eq!(vec![sorted_ids[1]], tunnels.remove_tunnel(&sorted_ids[0]));
+        assert!(tunnels.standby_tunnels().is_empty());
+    }
+
+    #[test]
+    fn standby_expiry_test() {
+        let tunnel_id = *FullId::new().public_id();
+        let mut tunnels: Tunnels = Default::default();
+        let dst_ids = (0..(MAX_STANDBY_TUNNELS + 1))
+            .map(|_| *FullId::new().public_id())
+            .collect_vec();
+        for dst_id in &dst_ids {
+            let _ = tunnels.add(*dst_id, tunnel_id);
+        }
+
+        // Only up to `MAX_STANDBY_TUNNELS` are kept.
+        for dst_id in &dst_ids[..MAX_STANDBY_TUNNELS] {
+            assert_eq!(Some(tunnel_id), tunnels.keep_as_standby(*dst_id));
+        }
+        assert_eq!(None, tunnels.keep_as_standby(dst_ids[MAX_STANDBY_TUNNELS]));
+
+        FakeClock::advance_time(STANDBY_TUNNEL_SECS * 1000 - 1);
+        assert!(tunnels.remove_expired_standby().is_empty());
+        FakeClock::advance_time(1);
+        assert_eq!(MAX_STANDBY_TUNNELS, tunnels.remove_expired_standby().len());
+        assert!(tunnels.standby_tunnels().is_empty());
+    }
+
     #[test]
     fn clients_test() {
         let mut sorted_ids = vec![];
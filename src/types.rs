@@ -6,14 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::xor_name::XorName;
+use crate::rng;
+use crate::xor_name::{XorName, XOR_NAME_LEN};
 use maidsafe_utilities::event_sender::MaidSafeObserver;
-#[cfg(any(test, feature = "use-mock-crust"))]
-use maidsafe_utilities::SeededRng;
-#[cfg(all(not(test), not(feature = "use-mock-crust")))]
-use rand;
-#[cfg(any(test, feature = "use-mock-crust"))]
-use rand::Rng;
+use tiny_keccak::sha3_256;
 
 pub type RoutingActionSender = MaidSafeObserver<crate::action::Action>;
 
@@ -27,16 +23,8 @@ pub struct MessageId(XorName);
 
 impl MessageId {
     /// Generate a new `MessageId` with random content.
-    #[cfg(any(test, feature = "use-mock-crust"))]
     pub fn new() -> MessageId {
-        let mut rng = SeededRng::thread_rng();
-        MessageId(rng.gen())
-    }
-
-    /// Generate a new `MessageId` with random content.
-    #[cfg(all(not(test), not(feature = "use-mock-crust")))]
-    pub fn new() -> MessageId {
-        MessageId(rand::random())
+        MessageId(rng::random())
     }
 
     /// Generate a `MessageId` with value 0. This should only be used for messages where there is
@@ -57,6 +45,27 @@ impl MessageId {
         MessageId(name)
     }
 
+    /// Generate a `MessageId` for a refresh with the given type tag, triggered by the churn of the
+    /// node with name `cause`. All members of a group derive the same ID for the same refresh.
+    ///
+    /// The last eight bytes are the type tag itself, so that the refresh's parts can be accumulated
+    /// according to its type's `RefreshPolicy` before the refresh is reassembled.
+    pub fn from_refresh_cause(cause: &XorName, type_tag: u64) -> MessageId {
+        let mut bytes = cause.0.to_vec();
+        bytes.extend_from_slice(&type_tag.to_be_bytes());
+        let mut name = sha3_256(&bytes);
+        name[XOR_NAME_LEN - 8..].copy_from_slice(&type_tag.to_be_bytes());
+        MessageId(XorName(name))
+    }
+
+    /// Returns the type tag of a refresh with this ID, as generated by `from_refresh_cause`. For
+    /// any other ID, the result is meaningless.
+    pub(crate) fn refresh_type_tag(&self) -> u64 {
+        let mut type_tag = [0; 8];
+        type_tag.copy_from_slice(&(self.0).0[XOR_NAME_LEN - 8..]);
+        u64::from_be_bytes(type_tag)
+    }
+
     /// Generate the reverse of the given `MessageId`.
     pub fn from_reverse(name: &MessageId) -> MessageId {
         let MessageId(XorName(mut name_mut)) = *name;
@@ -98,6 +107,19 @@ mod tests {
         assert_eq!(vec_bytes[0], 0);
     }
 
+    #[test]
+    fn refresh_cause() {
+        let cause = XorName([1; XOR_NAME_LEN]);
+        let message_id = MessageId::from_refresh_cause(&cause, 7);
+        assert_eq!(message_id, MessageId::from_refresh_cause(&cause, 7));
+        assert_ne!(message_id, MessageId::from_refresh_cause(&cause, 8));
+        assert_ne!(
+            message_id,
+            MessageId::from_refresh_cause(&XorName([2; XOR_NAME_LEN]), 7)
+        );
+        assert_eq!(message_id.refresh_type_tag(), 7);
+    }
+
     #[test]
     fn decrement() {
         let message_id = MessageId::decrement_first_byte(&MessageId(XorName([0; XOR_NAME_LEN])));
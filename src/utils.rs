@@ -82,6 +82,11 @@ pub fn format_binary_array<V: AsRef<[u8]>>(input: V) -> String {
 /// In the case where only one close node is provided (in initial network setup scenario):
 ///
 /// [`current_name`, 1st closest node id]
+///
+/// Each member sends the resulting `ExpectCandidate` with its own signature, and the message is
+/// only accumulated and sent on if a quorum of them computed the same destination. So a single
+/// malicious member can't bias the placement. Honest members agree as long as they agree on the
+/// two nodes closest to `current_name`, regardless of how the rest of their close groups differ.
 pub fn calculate_relocation_dst(mut close_nodes: Vec<XorName>, current_name: &XorName) -> XorName {
     close_nodes.sort_by(|a, b| current_name.cmp_distance(a, b));
     let combined: Vec<u8> = iter::once(current_name)
@@ -210,4 +215,41 @@ mod tests {
         let invalid_relocated_name = XorName(sha3_256(&invalid_combined));
         assert_ne!(invalid_relocated_name, actual_relocated_name);
     }
+
+    #[test]
+    fn calculate_relocation_dst_agreement() {
+        let original_name: XorName = rand::random();
+        let mut close_nodes: Vec<XorName> = (0..8).map(|_| rand::random()).collect();
+        let expected = super::calculate_relocation_dst(close_nodes.clone(), &original_name);
+
+        // Members that see the close nodes in a different order compute the same destination.
+        close_nodes.reverse();
+        assert_eq!(
+            expected,
+            super::calculate_relocation_dst(close_nodes.clone(), &original_name)
+        );
+
+        // So do members that don't know all of the further nodes, or know other ones.
+        close_nodes.sort_by(|a, b| original_name.cmp_distance(a, b));
+        close_nodes.truncate(3);
+        let further_node = unwrap!(close_nodes.pop());
+        assert_eq!(
+            expected,
+            super::calculate_relocation_dst(close_nodes.clone(), &original_name)
+        );
+        close_nodes.push(further_node);
+        close_nodes.push(!original_name);
+        assert_eq!(
+            expected,
+            super::calculate_relocation_dst(close_nodes.clone(), &original_name)
+        );
+
+        // But a member that disagrees on one of the two closest nodes computes a different one,
+        // and its `ExpectCandidate` won't accumulate with the others.
+        let _ = close_nodes.remove(0);
+        assert_ne!(
+            expected,
+            super::calculate_relocation_dst(close_nodes, &original_name)
+        );
+    }
 }
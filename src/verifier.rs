@@ -0,0 +1,344 @@
+// Copyright 2018 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::action::Action;
+use crate::id::PublicId;
+use crate::messages::{DirectMessage, HopMessage, Quorum};
+use crate::types::RoutingActionSender;
+use maidsafe_utilities::thread::{self, Joiner};
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// The maximum number of messages waiting to be checked. Further messages are dropped until the
+/// workers catch up, and are resent by their senders.
+const MAX_JOBS: usize = 1000;
+
+struct Job {
+    id: u64,
+    pub_id: PublicId,
+    hop_msg: HopMessage,
+    min_section_size: usize,
+    quorum: Quorum,
+}
+
+/// A message from a peer, ready to be handled in the order it arrived in.
+pub enum Verified {
+    /// A hop message, with whether its signatures are valid.
+    Hop(HopMessage, bool),
+    /// A direct message, with its sequence number, which arrived while earlier hop messages were
+    /// being checked.
+    Direct(DirectMessage, u32),
+}
+
+enum Pending {
+    Hop(u64, Option<(HopMessage, bool)>),
+    Direct(DirectMessage, u32),
+}
+
+impl Pending {
+    fn is_ready(&self) -> bool {
+        match *self {
+            Pending::Hop(_, ref result) => result.is_some(),
+            Pending::Direct(..) => true,
+        }
+    }
+}
+
+/// Checks the signatures of incoming hop messages on a pool of worker threads.
+///
+/// A worker passes each checked message back to the event loop as `Action::HopMessageVerified`.
+/// As workers finish in any order, `complete` holds a message back until all the earlier ones from
+/// the same peer are checked, so that each peer's messages are still handled in order. Direct
+/// messages from a peer with hop messages being checked are held back in the same way.
+pub struct Verifier {
+    // Declared before `workers`, so that it is dropped first: this ends the workers' loops, which
+    // the `Joiner`s then wait for.
+    job_tx: Sender<Job>,
+    workers: Vec<Joiner>,
+    next_id: u64,
+    /// The number of messages handed to the workers and not completed yet.
+    jobs: usize,
+    max_jobs: usize,
+    /// For each peer, its messages being checked in the order they arrived, together with the
+    /// ones which wait for an earlier one.
+    pending: HashMap<PublicId, VecDeque<Pending>>,
+}
+
+impl Verifier {
+    /// Creates a verifier with `worker_count` threads. With none, `is_enabled` returns `false` and
+    /// the caller is expected to check messages itself.
+    pub fn new(action_sender: RoutingActionSender, worker_count: usize) -> Self {
+        let (job_tx, job_rx) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let workers = (0..worker_count)
+            .map(|_| {
+                let job_rx = Arc::clone(&job_rx);
+                let action_sender = action_sender.clone();
+                thread::named("Verifier", move || run(&job_rx, &action_sender))
+            })
+            .collect();
+        Verifier {
+            job_tx,
+            workers,
+            next_id: 0,
+            jobs: 0,
+            max_jobs: MAX_JOBS,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.workers.is_empty()
+    }
+
+    /// Hands `hop_msg` from `pub_id` over to the workers. The signed message is checked against
+    /// `min_section_size` and `quorum`. Returns `false` if the message was dropped because too many
+    /// are waiting to be checked already.
+    pub fn verify(
+        &mut self,
+        pub_id: PublicId,
+        hop_msg: HopMessage,
+        min_section_size: usize,
+        quorum: Quorum,
+    ) -> bool {
+        if self.jobs >= self.max_jobs {
+            return false;
+        }
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let job = Job {
+            id,
+            pub_id,
+            hop_msg,
+            min_section_size,
+            quorum,
+        };
+        if self.job_tx.send(job).is_err() {
+            error!(
+                "Verifier workers stopped; dropping message from {}.",
+                pub_id
+            );
+            return false;
+        }
+        self.jobs += 1;
+        self.pending
+            .entry(pub_id)
+            .or_insert_with(VecDeque::new)
+            .push_back(Pending::Hop(id, None));
+        true
+    }
+
+    /// Holds `direct_msg` from `pub_id` back if hop messages from the same peer are still being
+    /// checked, or returns it to be handled right away otherwise.
+    pub fn hold_direct_message(
+        &mut self,
+        pub_id: PublicId,
+        direct_msg: DirectMessage,
+        seq: u32,
+    ) -> Option<(DirectMessage, u32)> {
+        match self.pending.get_mut(&pub_id) {
+            Some(pending) => {
+                pending.push_back(Pending::Direct(direct_msg, seq));
+                None
+            }
+            None => Some((direct_msg, seq)),
+        }
+    }
+
+    /// Accepts the result of the job `id`, and returns the messages from `pub_id` which can now be
+    /// handled, in order.
+    pub fn complete(
+        &mut self,
+        id: u64,
+        pub_id: PublicId,
+        hop_msg: HopMessage,
+        valid: bool,
+    ) -> Vec<Verified> {
+        self.jobs = self.jobs.saturating_sub(1);
+        let mut ready = Vec::new();
+        let is_empty = if let Some(pending) = self.pending.get_mut(&pub_id) {
+            let job = pending.iter_mut().find(|pending| match **pending {
+                Pending::Hop(job_id, _) => job_id == id,
+                Pending::Direct(..) => false,
+            });
+            if let Some(&mut Pending::Hop(_, ref mut result)) = job {
+                *result = Some((hop_msg, valid));
+            }
+            while pending.front().map_or(false, Pending::is_ready) {
+                match pending.pop_front() {
+                    Some(Pending::Hop(_, Some((hop_msg, valid)))) => {
+                        ready.push(Verified::Hop(hop_msg, valid))
+                    }
+                    Some(Pending::Direct(direct_msg, seq)) => {
+                        ready.push(Verified::Direct(direct_msg, seq))
+                    }
+                    Some(Pending::Hop(_, None)) | None => (),
+                }
+            }
+            pending.is_empty()
+        } else {
+            false
+        };
+        if is_empty {
+            let _ = self.pending.remove(&pub_id);
+        }
+        ready
+    }
+}
+
+fn run(job_rx: &Mutex<Receiver<Job>>, action_sender: &RoutingActionSender) {
+    loop {
+        // Only hold the lock while waiting, so that the other workers can take the next job.
+        let job = match job_rx.lock().map(|job_rx| job_rx.recv()) {
+            Ok(Ok(job)) => job,
+            _ => return,
+        };
+        let valid = job
+            .hop_msg
+            .verify(job.pub_id.signing_public_key())
+            .and_then(|()| {
+                job.hop_msg
+                    .content
+                    .check_integrity(job.min_section_size, job.quorum)
+            })
+            .is_ok();
+        let action = Action::HopMessageVerified {
+            id: job.id,
+            pub_id: job.pub_id,
+            hop_msg: job.hop_msg,
+            valid,
+        };
+        if action_sender.send(action).is_err() {
+            // The event loop stopped.
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::FullId;
+    use crate::messages::{MessageContent, RoutingMessage, SignedMessage};
+    use crate::routing_table::{Authority, Prefix};
+    use maidsafe_utilities::event_sender::MaidSafeEventCategory;
+    use rand;
+    use std::collections::BTreeSet;
+
+    fn hop_message(full_id: &FullId) -> HopMessage {
+        let routing_msg = RoutingMessage {
+            src: Authority::ManagedNode(*full_id.public_id().name()),
+            dst: Authority::ClientManager(rand::random()),
+            content: MessageContent::SectionSplit(
+                Prefix::new(0, rand::random()).with_version(0),
+                rand::random(),
+            ),
+            trace: false,
+        };
+        let signed_msg = unwrap!(SignedMessage::new(routing_msg, full_id, vec![]));
+        unwrap!(HopMessage::new(
+            signed_msg,
+            0,
+            BTreeSet::new(),
+            full_id.signing_private_key(),
+        ))
+    }
+
+    #[test]
+    fn keeps_order_per_peer() {
+        let (action_tx, action_rx) = mpsc::channel();
+        let (category_tx, _category_rx) = mpsc::channel();
+        let action_sender =
+            RoutingActionSender::new(action_tx, MaidSafeEventCategory::Routing, category_tx);
+        let mut verifier = Verifier::new(action_sender, 2);
+        assert!(verifier.is_enabled());
+
+        let full_id = FullId::new();
+        let pub_id = *full_id.public_id();
+        let mut contents = Vec::new();
+        for _ in 0..3 {
+            let hop_msg = hop_message(&full_id);
+            contents.push(hop_msg.content.clone());
+            verifier.verify(pub_id, hop_msg, 1, Quorum::default());
+        }
+        let mut results: Vec<_> = (0..3)
+            .map(|_| match unwrap!(action_rx.recv()) {
+                Action::HopMessageVerified {
+                    id,
+                    pub_id,
+                    hop_msg,
+                    valid,
+                } => (id, pub_id, hop_msg, valid),
+                action => panic!("Unexpected {:?}", action),
+            })
+            .collect();
+        results.sort_by_key(|&(id, ..)| id);
+
+        // Results arriving out of order are held back until the earlier ones are in.
+        let (id, pub_id, hop_msg, valid) = unwrap!(results.pop());
+        assert!(verifier.complete(id, pub_id, hop_msg, valid).is_empty());
+        let mut ready = Vec::new();
+        for (id, pub_id, hop_msg, valid) in results {
+            ready.extend(verifier.complete(id, pub_id, hop_msg, valid));
+        }
+        let ready_contents: Vec<_> = ready
+            .into_iter()
+            .map(|verified| match verified {
+                Verified::Hop(hop_msg, true) => hop_msg.content,
+                _ => panic!("Unexpected result"),
+            })
+            .collect();
+        assert_eq!(ready_contents, contents);
+        assert!(verifier.pending.is_empty());
+    }
+
+    #[test]
+    fn holds_direct_messages_and_limits_jobs() {
+        let (action_tx, action_rx) = mpsc::channel();
+        let (category_tx, _category_rx) = mpsc::channel();
+        let action_sender =
+            RoutingActionSender::new(action_tx, MaidSafeEventCategory::Routing, category_tx);
+        let mut verifier = Verifier::new(action_sender, 1);
+        verifier.max_jobs = 1;
+
+        let full_id = FullId::new();
+        let pub_id = *full_id.public_id();
+        let other_pub_id = *FullId::new().public_id();
+
+        // Without hop messages being checked, direct messages are handled right away.
+        assert!(verifier
+            .hold_direct_message(pub_id, DirectMessage::Disconnecting, 0)
+            .is_some());
+
+        assert!(verifier.verify(pub_id, hop_message(&full_id), 1, Quorum::default()));
+        assert!(!verifier.verify(pub_id, hop_message(&full_id), 1, Quorum::default()));
+        assert!(verifier
+            .hold_direct_message(pub_id, DirectMessage::Disconnecting, 1)
+            .is_none());
+        assert!(verifier
+            .hold_direct_message(other_pub_id, DirectMessage::Disconnecting, 0)
+            .is_some());
+
+        let ready = match unwrap!(action_rx.recv()) {
+            Action::HopMessageVerified {
+                id,
+                pub_id,
+                hop_msg,
+                valid,
+            } => verifier.complete(id, pub_id, hop_msg, valid),
+            action => panic!("Unexpected {:?}", action),
+        };
+        match ready.as_slice() {
+            [Verified::Hop(_, true), Verified::Direct(DirectMessage::Disconnecting, 1)] => (),
+            _ => panic!("Unexpected results"),
+        }
+        assert!(verifier.pending.is_empty());
+        assert!(verifier.verify(pub_id, hop_message(&full_id), 1, Quorum::default()));
+    }
+}
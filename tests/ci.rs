@@ -360,7 +360,12 @@ fn core() {
                         assert!(result.is_ok());
                     }
 
-                    TestEvent(index, Event::Request { request, src, dst }) => {
+                    TestEvent(
+                        index,
+                        Event::Request {
+                            request, src, dst, ..
+                        },
+                    ) => {
                         // A node received request from the client. Reply with a success.
                         if let Request::PutMData { msg_id, .. } = request {
                             let node = &mut nodes[index].node;
@@ -472,6 +477,7 @@ fn core() {
                                 },
                             src: Authority::Client { .. },
                             dst: Authority::ClientManager(name),
+                            ..
                         },
                     ) => {
                         let src = Authority::ClientManager(name);
@@ -484,7 +490,12 @@ fn core() {
                             requester,
                         ));
                     }
-                    TestEvent(index, Event::Request { request, src, dst }) => {
+                    TestEvent(
+                        index,
+                        Event::Request {
+                            request, src, dst, ..
+                        },
+                    ) => {
                         if let Request::PutMData { msg_id, .. } = request {
                             unwrap!(nodes[index].node.send_put_mdata_response(
                                 dst,
@@ -613,6 +624,7 @@ fn core() {
                             },
                         src: Authority::Client { .. },
                         dst: Authority::ClientManager(name),
+                        ..
                     },
                 ) => {
                     let src = Authority::ClientManager(name);
@@ -625,7 +637,12 @@ fn core() {
                         requester,
                     ));
                 }
-                TestEvent(index, Event::Request { request, src, dst }) => {
+                TestEvent(
+                    index,
+                    Event::Request {
+                        request, src, dst, ..
+                    },
+                ) => {
                     if let Request::PutMData { msg_id, .. } = request {
                         if 2 * (index + 1) < MIN_SECTION_SIZE {
                             unwrap!(nodes[index].node.send_put_mdata_response(
@@ -679,7 +696,12 @@ fn core() {
                         assert!(result.is_ok());
                         let _ = sent_ids.insert(message_id);
                     }
-                    TestEvent(index, Event::Request { request, src, dst }) => {
+                    TestEvent(
+                        index,
+                        Event::Request {
+                            request, src, dst, ..
+                        },
+                    ) => {
                         // A node received request from the client. Reply with a success.
                         if let Request::PutMData { msg_id, .. } = request {
                             unwrap!(nodes[index].node.send_put_mdata_response(
@@ -7,17 +7,19 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    create_connected_clients, create_connected_nodes_until_split, gen_immutable_data, poll_all,
-    TestNode,
+    create_connected_clients, create_connected_nodes, create_connected_nodes_until_split,
+    gen_immutable_data, poll_all, poll_and_resend, TestNode,
 };
 use fake_clock::FakeClock;
 use rand::Rng;
 use routing::mock_crust::Network;
 use routing::rate_limiter_consts::RATE;
+use routing::test_consts::CACHE_RESPONSE_MAX_DELAY_MS;
 use routing::{
-    Authority, Event, EventStream, ImmutableData, MessageId, Prefix, Request, Response,
-    MAX_IMMUTABLE_DATA_SIZE_IN_BYTES,
+    Authority, BootstrapConfig, DevConfig, Event, EventStream, ImmutableData, MessageId,
+    MutableData, Prefix, Request, Response, MAX_IMMUTABLE_DATA_SIZE_IN_BYTES,
 };
+use std::collections::BTreeSet;
 use std::sync::mpsc;
 
 // Generate random immutable data, but make sure the first node in the given
@@ -77,6 +79,7 @@ fn response_caching() {
                         },
                     src: req_src,
                     dst: req_dst,
+                    ..
                 }) => {
                     if req_data_id == data_id && req_message_id == message_id {
                         unwrap!(node.inner.send_get_idata_response(
@@ -121,6 +124,11 @@ fn response_caching() {
 
     let _ = poll_all(&mut nodes, &mut clients);
 
+    // The proxy node delays its cached response.
+    expect_no_event!(clients[0]);
+    FakeClock::advance_time(CACHE_RESPONSE_MAX_DELAY_MS + 1);
+    let _ = poll_all(&mut nodes, &mut clients);
+
     // The client should receive the response...
     expect_any_event!(
         clients[0],
@@ -142,3 +150,107 @@ fn response_caching() {
         expect_no_event!(node);
     }
 }
+
+#[test]
+fn mdata_responses_are_not_cached_by_default() {
+    let network = Network::new(8, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], true);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    // As in `response_caching`, the proxy node must not be in the data's section.
+    let prefix = Prefix::new(1, nodes[0].name());
+    let name = loop {
+        let name = rng.gen();
+        if !prefix.matches(&name) {
+            break name;
+        }
+    };
+    let tag = 10_000;
+    let mut owners = BTreeSet::new();
+    let _ = owners.insert(*clients[0].full_id.public_id().signing_public_key());
+    let data = unwrap!(MutableData::new(
+        name,
+        tag,
+        Default::default(),
+        Default::default(),
+        owners,
+    ));
+    let message_id = MessageId::new();
+    let dst = Authority::NaeManager(name);
+    unwrap!(clients[0].inner.get_mdata(dst, name, tag, message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    for node in &mut *nodes {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request {
+                request: Request::GetMData { msg_id, .. },
+                src,
+                dst,
+                ..
+            } = event
+            {
+                unwrap!(node
+                    .inner
+                    .send_get_mdata_response(dst, src, Ok(data.clone()), msg_id));
+            }
+        }
+    }
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    expect_any_event!(
+        clients[0],
+        Event::Response {
+            response: Response::GetMData { res: Ok(ref res_data), msg_id },
+            ..
+        } if *res_data == data && msg_id == message_id
+    );
+
+    // `TestCache` doesn't opt in to caching `MutableData`, so the proxy node didn't put it.
+    let stats = unwrap!(nodes[0].inner.cache_stats());
+    assert_eq!(stats.puts, 0);
+}
+
+#[test]
+fn new_node_primes_its_cache() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let data: Vec<_> = (0..4).map(|_| gen_immutable_data(&mut rng, 8)).collect();
+
+    // One member of the section has the data cached.
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config.clone())
+            .cached_data(&data)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+
+    let dev_config = DevConfig {
+        prime_cache: true,
+        ..DevConfig::default()
+    };
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .cache(true)
+            .dev_config(dev_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+
+    // Once it joined the complete section, the new node can answer requests for the data.
+    let new_node = unwrap!(nodes.last());
+    for data in &data {
+        let request = Request::GetIData {
+            name: *data.name(),
+            msg_id: MessageId::new(),
+        };
+        assert!(new_node.inner.cache_contains(&request));
+    }
+    let stats = unwrap!(new_node.inner.cache_stats());
+    assert_eq!(stats.puts, data.len() as u64);
+}
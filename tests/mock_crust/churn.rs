@@ -335,6 +335,7 @@ impl ExpectedPuts {
                     request: Request::PutIData { data, msg_id },
                     src,
                     dst,
+                    ..
                 } = event
                 {
                     let name = *data.name();
@@ -391,6 +392,7 @@ impl ExpectedPuts {
                     response: Response::GetIData { res, msg_id },
                     src,
                     dst,
+                    ..
                 } = event
                 {
                     let data = unwrap!(res);
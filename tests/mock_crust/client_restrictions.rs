@@ -21,6 +21,33 @@ use routing::{
 };
 use std::time::Duration;
 
+/// A client's message claiming to have been relayed by a second proxy is rejected by its proxy,
+/// which bans the client.
+#[test]
+fn reject_client_message_claiming_relay() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let mut rng = network.new_rng();
+
+    let data = gen_immutable_data(&mut rng, 8);
+    clients[0].inner.set_claim_relayed(true);
+    unwrap!(clients[0].inner.get_idata(
+        Authority::NaeManager(*data.name()),
+        *data.name(),
+        MessageId::new()
+    ));
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(unwrap!(clients.last_mut()), Event::Terminate);
+    for node in &mut nodes {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request { .. } = event {
+                panic!("{} received {:?}.", node.name(), event);
+            }
+        }
+    }
+}
+
 /// Connect a client to the network then send an invalid message.
 /// Expect the client will be disconnected and banned;
 #[test]
@@ -56,6 +83,7 @@ fn ban_malicious_client() {
     );
     clients.push(client);
     let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(unwrap!(clients.last_mut()), Event::BootstrapFailed);
     expect_next_event!(unwrap!(clients.last_mut()), Event::Terminate);
 }
 
@@ -77,6 +105,7 @@ fn only_one_client_per_ip() {
     );
     clients.push(client);
     let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(unwrap!(clients.last_mut()), Event::BootstrapFailed);
     expect_next_event!(unwrap!(clients.last_mut()), Event::Terminate);
 }
 
@@ -99,6 +128,7 @@ fn reconnect_disconnected_client() {
         full_id.clone(),
     )];
     let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(unwrap!(clients.last_mut()), Event::BootstrapFailed);
     expect_next_event!(unwrap!(clients.last_mut()), Event::Terminate);
 
     let _ = clients.remove(0);
@@ -6,9 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{create_connected_nodes, poll_all, verify_invariant_for_all_nodes, TestNode};
+use super::{
+    create_connected_clients, create_connected_nodes, gen_immutable_data, poll_all,
+    poll_and_resend, verify_invariant_for_all_nodes, TestClient, TestNode,
+};
 use routing::mock_crust::Network;
-use routing::{Event, EventStream};
+use routing::{
+    Authority, BootstrapConfig, DevConfig, Event, EventStream, MessageId, Request, Response,
+};
 
 // Drop node at index and verify its own section receives NodeLost.
 fn drop_node(nodes: &mut Vec<TestNode>, index: usize) {
@@ -58,3 +63,185 @@ fn node_restart() {
 
     expect_next_event!(nodes[0], Event::RestartRequired);
 }
+
+#[test]
+fn first_node_disconnected() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, 2);
+
+    drop_node(&mut nodes, 1);
+
+    // The first node keeps running, but reports that it is on its own again.
+    expect_next_event!(nodes[0], Event::Disconnected);
+}
+
+#[test]
+fn proxy_drop_notifies_clients() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let mut clients = create_connected_clients(&network, &mut nodes, 2);
+
+    // Dropping the proxy shuts it down gracefully, so it tells its clients about the others.
+    drop(nodes.remove(0));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    for client in &mut clients {
+        match client.try_next_ev() {
+            Ok(Event::ProxyClosing(endpoints)) => assert_eq!(endpoints.len(), nodes.len()),
+            event => panic!("Expected Event::ProxyClosing, got {:?}", event),
+        }
+        expect_next_event!(client, Event::Terminate);
+    }
+}
+
+#[test]
+fn client_bootstraps_again_after_proxy_drop() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let contacts = [nodes[0].handle.endpoint(), nodes[1].handle.endpoint()];
+    let dev_config = DevConfig {
+        client_rebootstrap: Some(true),
+        ..DevConfig::default()
+    };
+    let mut clients = vec![TestClient::new_with_dev_config(
+        &network,
+        Some(BootstrapConfig::with_contacts(&contacts)),
+        None,
+        dev_config,
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+    let proxy = unwrap!(nodes
+        .iter()
+        .position(|node| clients[0].handle.is_connected(&node.handle)));
+
+    // The request isn't acknowledged yet when the proxy goes away.
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, *data.name(), message_id));
+    drop(nodes.remove(proxy));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    expect_any_event!(
+        clients[0],
+        Event::RequestFailed {
+            message_id: failed_id,
+            dst: failed_dst,
+        } if failed_id == message_id && failed_dst == dst
+    );
+    expect_any_event!(clients[0], Event::Connected);
+    assert!(nodes
+        .iter()
+        .any(|node| clients[0].handle.is_connected(&node.handle)));
+
+    // Requests reach the network through the new proxy.
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.get_idata(dst, *data.name(), message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        expect_any_event!(
+            node,
+            Event::Request {
+                request: Request::GetIData { msg_id, .. },
+                ..
+            } if msg_id == message_id
+        );
+    }
+}
+
+#[test]
+fn client_bootstraps_off_contacts_from_closing_proxy() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let dev_config = DevConfig {
+        client_rebootstrap: Some(true),
+        ..DevConfig::default()
+    };
+    // The proxy is the client's only contact, so it can only find another one through the proxy.
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    let mut clients = vec![TestClient::new_with_dev_config(
+        &network,
+        Some(bootstrap_config),
+        None,
+        dev_config,
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+    assert!(clients[0].handle.is_connected(&nodes[0].handle));
+
+    drop(nodes.remove(0));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    expect_next_event!(clients[0], Event::ProxyClosing(_));
+    expect_any_event!(clients[0], Event::Connected);
+    assert!(nodes
+        .iter()
+        .any(|node| clients[0].handle.is_connected(&node.handle)));
+}
+
+#[test]
+fn proxy_holds_messages_for_client_which_lost_connection() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let dev_config = DevConfig {
+        client_grace_period_secs: Some(60),
+        ..DevConfig::default()
+    };
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .dev_config(dev_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+    let proxy = nodes.len() - 1;
+
+    let dev_config = DevConfig {
+        client_rebootstrap: Some(true),
+        ..DevConfig::default()
+    };
+    let contacts = [nodes[proxy].handle.endpoint()];
+    let mut clients = vec![TestClient::new_with_dev_config(
+        &network,
+        Some(BootstrapConfig::with_contacts(&contacts)),
+        None,
+        dev_config,
+    )];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    // The proxy notices the lost connection before the response arrives.
+    network.lost_connection(nodes[proxy].handle.endpoint(), clients[0].handle.endpoint());
+    let _ = poll_all(&mut nodes, &mut []);
+    let data = gen_immutable_data(&mut rng, 1024);
+    let src = Authority::ManagedNode(nodes[0].name());
+    let dst = Authority::Client {
+        client_id: *clients[0].full_id.public_id(),
+        proxy_node_name: nodes[proxy].name(),
+    };
+    let message_id = MessageId::new();
+    unwrap!(nodes[0]
+        .inner
+        .send_get_idata_response(src, dst, Ok(data), message_id));
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // Once the client bootstraps off the proxy again, it receives the response.
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_any_event!(clients[0], Event::Connected);
+    expect_any_event!(
+        clients[0],
+        Event::Response {
+            response: Response::GetIData { msg_id, .. },
+            ..
+        } if msg_id == message_id
+    );
+}
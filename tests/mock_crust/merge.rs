@@ -48,7 +48,10 @@ fn merge(prefix_lengths: Vec<usize>) {
         for node in &mut *nodes {
             while let Ok(event) = node.try_next_ev() {
                 match event {
-                    Event::NodeAdded(..) | Event::NodeLost(..) | Event::Tick => (),
+                    Event::NodeAdded(..)
+                    | Event::NodeLost(..)
+                    | Event::RoutingTableGrowth { .. }
+                    | Event::Tick => (),
                     Event::SectionMerge(prefix) => {
                         if prefix.bit_count() == 0 {
                             merge_events_missing -= 1;
@@ -19,14 +19,21 @@ mod utils;
 pub use self::utils::{
     add_connected_nodes_until_split, create_connected_clients, create_connected_nodes,
     create_connected_nodes_until_split, gen_bytes, gen_immutable_data, gen_range, gen_range_except,
-    poll_all, poll_and_resend, remove_nodes_which_failed_to_connect, sort_nodes_by_distance_to,
-    verify_invariant_for_all_nodes, Nodes, TestClient, TestNode,
+    poll_all, poll_and_resend, poll_and_resend_with_workers, remove_nodes_which_failed_to_connect,
+    sort_nodes_by_distance_to, verify_invariant_for_all_nodes, Nodes, TestClient, TestNode,
 };
 use fake_clock::FakeClock;
+use rand::Rng;
+use routing::capture::{CaptureReader, Direction};
 use routing::mock_crust::{Endpoint, Network};
-use routing::test_consts::JOINING_NODE_TIMEOUT_SECS;
-use routing::{BootstrapConfig, Event, EventStream, Prefix, XorName, XOR_NAME_LEN};
+use routing::test_consts::{ACK_TIMEOUT_SECS, JOINING_NODE_TIMEOUT_SECS, TICK_TIMEOUT_SECS};
+use routing::{
+    Authority, BootstrapConfig, ClientError, DevConfig, Event, EventStream, MessageId, Prefix,
+    RelocationDenial, Response, XorName, XOR_NAME_LEN,
+};
 use std::collections::BTreeSet;
+use std::env;
+use std::fs;
 
 pub const MIN_SECTION_SIZE: usize = 8;
 
@@ -39,6 +46,18 @@ fn test_nodes(percentage_size: usize) {
     verify_invariant_for_all_nodes(&mut nodes);
 }
 
+#[test]
+fn seed_node_starts_network() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut node = TestNode::builder(&network)
+        .first()
+        .endpoint(Endpoint(0))
+        .create();
+    let _ = node.poll();
+    expect_next_event!(node, Event::NetworkStarted);
+    assert_eq!(node.routing_table().len(), 0);
+}
+
 #[test]
 fn disconnect_on_rebootstrap() {
     let network = Network::new(MIN_SECTION_SIZE, None);
@@ -54,6 +73,7 @@ fn disconnect_on_rebootstrap() {
     let _ = poll_all(&mut nodes, &mut []);
     // When retrying to bootstrap, we should have disconnected from the bootstrap node.
     assert!(!unwrap!(nodes.last()).handle.is_connected(&nodes[1].handle));
+    expect_next_event!(unwrap!(nodes.last_mut()), Event::BootstrapFailed);
     expect_next_event!(unwrap!(nodes.last_mut()), Event::Terminate);
 }
 
@@ -76,7 +96,277 @@ fn more_than_section_size_nodes() {
 fn client_connects_to_nodes() {
     let network = Network::new(MIN_SECTION_SIZE, None);
     let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
-    let _ = create_connected_clients(&network, &mut nodes, 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    assert_eq!(
+        unwrap!(clients[0].inner.proxy_name()),
+        Some(nodes[0].name())
+    );
+}
+
+#[test]
+fn nodes_report_listener_port() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    for node in &mut nodes {
+        assert!(unwrap!(node.inner.listener_port()).is_some());
+    }
+}
+
+#[test]
+fn nodes_estimate_network_size() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    for node in &mut nodes {
+        // With a single section, every node knows the whole network.
+        let estimate = unwrap!(node.inner.network_size_estimate());
+        assert_eq!(estimate, Some(((MIN_SECTION_SIZE + 1) as u64, true)));
+    }
+}
+
+#[test]
+fn nodes_report_peer_info() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let names: BTreeSet<_> = nodes.iter().skip(1).map(TestNode::name).collect();
+
+    let peers = unwrap!(nodes[0].inner.peer_info());
+    let peer_names: BTreeSet<_> = peers.iter().map(|info| *info.pub_id.name()).collect();
+    assert_eq!(peer_names, names);
+    for info in peers {
+        assert!(info.routing);
+        assert!(!info.relayed);
+        assert_eq!(info.ip.is_some(), !info.tunnelled);
+    }
+}
+
+#[test]
+fn nodes_report_nae_manager_membership() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let name: XorName = rng.gen();
+    sort_nodes_by_distance_to(&mut nodes, &name);
+    for (index, node) in nodes.iter_mut().enumerate() {
+        let is_manager = unwrap!(node.inner.is_nae_manager_for(name));
+        assert_eq!(is_manager, index < MIN_SECTION_SIZE);
+    }
+}
+
+#[test]
+fn nodes_with_the_same_rng_seed_generate_the_same_ids() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let seed: [u32; 4] = rng.gen();
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let node = TestNode::builder(&network).first().rng_seed(seed).create();
+        // The node seeded the generator of the current thread, which message IDs come from.
+        ids.push((node.id(), MessageId::new(), MessageId::new()));
+    }
+    assert_eq!((ids[0].1, ids[0].2), (ids[1].1, ids[1].2));
+
+    // The real crypto library was already seeded when the network was created, so only the mock
+    // one derives the keys from the node's seed.
+    if cfg!(feature = "use-mock-crypto") {
+        assert_eq!(ids[0].0, ids[1].0);
+        let other_seed = [seed[0].wrapping_add(1), seed[1], seed[2], seed[3]];
+        let node = TestNode::builder(&network)
+            .first()
+            .rng_seed(other_seed)
+            .create();
+        assert_ne!(node.id(), ids[0].0);
+    }
+}
+
+#[test]
+fn node_with_verifier_threads_joins() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    let dev_config = DevConfig {
+        verifier_threads: Some(2),
+        ..DevConfig::default()
+    };
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .dev_config(dev_config)
+            .create(),
+    );
+    // The new node receives its approval and the section's updates as hop messages, which its
+    // verifier threads check.
+    poll_and_resend_with_workers(&mut nodes, &mut []);
+    let node = unwrap!(nodes.last_mut());
+    expect_any_event!(node, Event::Connected);
+    verify_invariant_for_all_nodes(&mut nodes);
+}
+
+#[test]
+fn node_retires_its_proxy() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![2, 2, 2, 2], false);
+    let proxy_name = nodes[0].name();
+    let proxy_handle = nodes[0].handle.clone();
+    let bootstrap_config = BootstrapConfig::with_contacts(&[proxy_handle.endpoint()]);
+
+    // Relocate the new node into the section opposite its proxy's, so that the proxy doesn't
+    // become one of its routing table entries.
+    let mut relocation_dst = proxy_name;
+    relocation_dst.0[0] ^= 0b1100_0000;
+    for node in &mut *nodes {
+        node.inner.set_next_relocation_dst(relocation_dst);
+    }
+    let dev_config = DevConfig {
+        retire_proxy: Some(true),
+        ..DevConfig::default()
+    };
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .dev_config(dev_config)
+            .create(),
+    );
+    let _ = poll_all(&mut nodes, &mut []);
+    let new_id = unwrap!(nodes.last()).id();
+    expect_any_event!(unwrap!(nodes.last_mut()), Event::Connected);
+    assert!(unwrap!(nodes.last()).handle.is_connected(&proxy_handle));
+
+    // The proxy still relays responses to the node while the node asks it to retire. The node
+    // only disconnects once it has received all of them.
+    let src = Authority::ManagedNode(proxy_name);
+    let dst = Authority::Client {
+        client_id: new_id,
+        proxy_node_name: proxy_name,
+    };
+    let message_ids: BTreeSet<_> = (0..5).map(|_| MessageId::new()).collect();
+    for &message_id in &message_ids {
+        unwrap!(nodes[0].inner.send_get_idata_response(
+            src,
+            dst,
+            Err(ClientError::NoSuchData),
+            message_id
+        ));
+    }
+    FakeClock::advance_time(TICK_TIMEOUT_SECS * 1000 + 1);
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let node = unwrap!(nodes.last_mut());
+    let mut received_ids = BTreeSet::new();
+    while let Ok(event) = node.inner.try_next_ev() {
+        if let Event::Response {
+            response: Response::GetIData { msg_id, .. },
+            ..
+        } = event
+        {
+            let _ = received_ids.insert(msg_id);
+        }
+    }
+    assert_eq!(received_ids, message_ids);
+    assert!(!node.handle.is_connected(&proxy_handle));
+    verify_invariant_for_all_nodes(&mut nodes);
+}
+
+#[test]
+fn node_is_told_its_relocation_was_denied() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    for node in &mut *nodes {
+        node.inner
+            .deny_next_relocation(RelocationDenial::WrongSection);
+    }
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .create(),
+    );
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // The section's denial accumulates at the joining node, which gives up instead of waiting.
+    let mut node = unwrap!(nodes.pop());
+    expect_any_event!(node, Event::JoinDenied(RelocationDenial::WrongSection));
+    expect_next_event!(node, Event::RestartRequired);
+    verify_invariant_for_all_nodes(&mut nodes);
+}
+
+#[test]
+fn new_node_handles_proxy_messages_without_waiting() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .create(),
+    );
+
+    // The mock network doesn't reorder messages, so nothing is held back unless the sequence
+    // numbers the new node expects from its proxy are off.
+    while poll_all(&mut nodes, &mut []) {
+        assert!(nodes
+            .iter()
+            .all(|node| !node.inner.has_held_direct_messages()));
+        FakeClock::advance_time(ACK_TIMEOUT_SECS * 1000 + 1);
+    }
+    expect_any_event!(unwrap!(nodes.last_mut()), Event::Connected);
+    verify_invariant_for_all_nodes(&mut nodes);
+}
+
+#[test]
+fn new_neighbours_are_quarantined() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    let our_section = nodes[0].name();
+    let mut other_section = our_section;
+    other_section.0[0] ^= 0b1000_0000;
+    let quarantine_secs = 60;
+
+    // The observer's routing table entries are all added while it joins, so it trusts them.
+    for node in &mut *nodes {
+        node.inner.set_next_relocation_dst(our_section);
+    }
+    let dev_config = DevConfig {
+        quarantine_secs: Some(quarantine_secs),
+        ..DevConfig::default()
+    };
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config.clone())
+            .dev_config(dev_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+    let observer = nodes.len() - 1;
+    expect_any_event!(nodes[observer], Event::Connected);
+    let ids: Vec<_> = nodes.iter().map(TestNode::id).collect();
+    assert!(ids
+        .iter()
+        .all(|id| !nodes[observer].inner.is_quarantined(id)));
+
+    // A node joining our section passed the observer's resource proof, unlike one joining the
+    // neighbouring section.
+    for &dst in &[our_section, other_section] {
+        for node in &mut *nodes {
+            node.inner.set_next_relocation_dst(dst);
+        }
+        nodes.push(
+            TestNode::builder(&network)
+                .bootstrap_config(bootstrap_config.clone())
+                .create(),
+        );
+        poll_and_resend(&mut nodes, &mut []);
+        expect_any_event!(unwrap!(nodes.last_mut()), Event::Connected);
+    }
+    let member_id = nodes[nodes.len() - 2].id();
+    let neighbour_id = nodes[nodes.len() - 1].id();
+    assert!(nodes[observer].routing_table().has(neighbour_id.name()));
+    assert!(!nodes[observer].inner.is_quarantined(&member_id));
+    assert!(nodes[observer].inner.is_quarantined(&neighbour_id));
+
+    FakeClock::advance_time(quarantine_secs * 1000 + 1);
+    assert!(!nodes[observer].inner.is_quarantined(&neighbour_id));
+    verify_invariant_for_all_nodes(&mut nodes);
 }
 
 #[test]
@@ -96,6 +386,30 @@ fn node_joins_in_front() {
     verify_invariant_for_all_nodes(&mut nodes);
 }
 
+#[test]
+fn node_joins_over_reordered_connections() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE);
+    // Shuffle the messages between all nodes, including the one joining below.
+    let endpoints: Vec<_> = (0..MIN_SECTION_SIZE + 1).map(Endpoint).collect();
+    for &sender in &endpoints {
+        for &receiver in &endpoints {
+            network.reorder_connection(sender, receiver);
+        }
+    }
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .endpoint(Endpoint(MIN_SECTION_SIZE))
+            .create(),
+    );
+
+    let _ = poll_all(&mut nodes, &mut []);
+
+    verify_invariant_for_all_nodes(&mut nodes);
+}
+
 #[test]
 fn multiple_joining_nodes() {
     let network = Network::new(MIN_SECTION_SIZE, None);
@@ -181,6 +495,74 @@ fn check_close_names_for_min_section_size_nodes() {
     assert!(close_sections_complete);
 }
 
+#[test]
+fn close_group_to_name() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, 2 * MIN_SECTION_SIZE);
+    let name: XorName = rng.gen();
+    sort_nodes_by_distance_to(&mut nodes, &name);
+
+    let close_group = nodes[..MIN_SECTION_SIZE]
+        .iter()
+        .map(|node| node.name())
+        .collect::<Vec<_>>();
+    for node in &nodes[..MIN_SECTION_SIZE] {
+        assert!(node.inner.is_closest(&name));
+        assert_eq!(Some(close_group.clone()), node.inner.close_group_to(name));
+    }
+    for node in &nodes[MIN_SECTION_SIZE..] {
+        assert!(!node.inner.is_closest(&name));
+    }
+}
+
+#[test]
+fn nodes_capture_their_messages() {
+    let network = Network::new(MIN_SECTION_SIZE, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, MIN_SECTION_SIZE + 1);
+    let (sender, recipient) = (nodes[0].name(), nodes[1].name());
+
+    // A capture can't be started in a directory which doesn't exist.
+    let dir = env::temp_dir();
+    let missing = dir
+        .join(format!("routing_{}", rng.gen::<u64>()))
+        .join("capture");
+    assert!(nodes[0].inner.start_capture(missing).is_err());
+
+    let paths: Vec<_> = (0..2)
+        .map(|_| dir.join(format!("routing_capture_{}", rng.gen::<u64>())))
+        .collect();
+    for (node, path) in nodes.iter_mut().zip(&paths) {
+        unwrap!(node.inner.start_capture(path.clone()));
+    }
+    let src = Authority::ManagedNode(sender);
+    let dst = Authority::ManagedNode(recipient);
+    unwrap!(nodes[0]
+        .inner
+        .send_user_message(src, dst, 42, vec![1, 2, 3], MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut().take(2) {
+        node.inner.stop_capture();
+    }
+
+    let records = |path| {
+        let records: Vec<_> = unwrap!(CaptureReader::open(path))
+            .map(|record| unwrap!(record))
+            .collect();
+        let _ = fs::remove_file(path);
+        records
+    };
+    let sent = records(&paths[0]);
+    assert!(sent
+        .iter()
+        .any(|record| record.direction == Direction::Sent && record.peer == recipient));
+    let received = records(&paths[1]);
+    assert!(received
+        .iter()
+        .any(|record| record.direction == Direction::Received && record.peer == sender));
+}
+
 // The newly connected nodes are expected to have each other as `RoutingConnection::Proxy/Joining`.
 // After the `JOINING_NODE_TIMEOUT_SECS` expires, they shall normalise the connection type to direct
 // which is what that `has_unnormalised_routing_conn` checks.
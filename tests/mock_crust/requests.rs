@@ -7,12 +7,20 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    create_connected_clients, create_connected_nodes, gen_bytes, gen_immutable_data, poll_all,
+    create_connected_clients, create_connected_nodes, create_connected_nodes_until_split,
+    gen_bytes, gen_immutable_data, poll_all, poll_and_resend, Nodes, TestClient, TestNode,
 };
-use routing::mock_crust::Network;
+use fake_clock::FakeClock;
+use itertools::Itertools;
+use maidsafe_utilities::serialisation;
+use rand::Rng;
+use routing::mock_crust::{Endpoint, Network};
+use routing::test_consts::{MAX_REJECTED_REQUESTS, TICK_TIMEOUT_SECS};
 use routing::{
-    Authority, ClientError, Event, EventStream, ImmutableData, MessageId, Request, Response,
+    Authority, BootstrapConfig, ClientError, DevConfig, Event, EventStream, ImmutableData,
+    InterfaceError, MessageId, Request, RequestValidator, Response, XorName, Xorable,
 };
+use std::collections::BTreeSet;
 
 #[test]
 fn successful_put_request() {
@@ -60,6 +68,458 @@ fn successful_put_request() {
     assert!(2 * request_received_count > min_section_size);
 }
 
+#[test]
+fn put_data_is_sent_to_client_manager() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = unwrap!(clients[0].inner.put_data(data.clone()));
+
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let dst = Authority::ClientManager(clients[0].name());
+    let mut request_received_count = 0;
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request {
+                request: Request::PutIData { msg_id, .. },
+                ..
+            } = event
+            {
+                if msg_id == message_id {
+                    request_received_count += 1;
+                    break;
+                }
+            }
+        }
+    }
+
+    assert!(2 * request_received_count > min_section_size);
+}
+
+#[test]
+fn request_sent_while_bootstrapping() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let contact = nodes[0].handle.endpoint();
+    let mut clients = vec![TestClient::new(
+        &network,
+        Some(BootstrapConfig::with_contacts(&[contact])),
+        None,
+    )];
+
+    // The client hasn't bootstrapped yet, so the request is held until it has.
+    let dst = Authority::ClientManager(clients[0].name());
+    let data = gen_immutable_data(&mut rng, 1024);
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.put_idata(dst, data, message_id));
+
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    let mut request_received_count = 0;
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request {
+                request: Request::PutIData { msg_id, .. },
+                ..
+            } = event
+            {
+                if msg_id == message_id {
+                    request_received_count += 1;
+                    break;
+                }
+            }
+        }
+    }
+
+    assert!(2 * request_received_count > min_section_size);
+}
+
+#[test]
+fn request_to_group_from_node() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let mut clients: Vec<TestClient> = Vec::new();
+
+    let sender = nodes[0].name();
+    let name = rng.gen();
+    let message_id = MessageId::new();
+    let request = Request::GetIData {
+        name,
+        msg_id: message_id,
+    };
+    unwrap!(nodes[0].inner.send_request_to_group(name, request, 2));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let dst = Authority::NaeManager(name);
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(&dst)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                    request: Request::GetIData { msg_id, .. },
+                    src: Authority::ManagedNode(src_name),
+                    dst: request_dst,
+                    ..
+                }) if msg_id == message_id => {
+                    assert_eq!(src_name, sender);
+                    assert_eq!(request_dst, dst);
+                    break;
+                }
+                Ok(_) => (),
+                _ => panic!("Event::Request not received"),
+            }
+        }
+    }
+
+    let refresh = Request::Refresh(vec![], MessageId::new());
+    match nodes[0].inner.send_request_to_group(name, refresh, 2) {
+        Err(InterfaceError::BadAuthority { .. }) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn refresh_with_bad_authorities_is_rejected() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let name = nodes[0].name();
+    let src = Authority::NaeManager(name);
+    let invalid_dsts = vec![Authority::ClientManager(name), Authority::ManagedNode(name)];
+    for dst in invalid_dsts {
+        match nodes[0]
+            .inner
+            .send_refresh_request(src, dst, vec![], MessageId::new())
+        {
+            Err(InterfaceError::BadAuthority { .. }) => (),
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    let dst = Authority::NaeManager(name);
+    match nodes[0].inner.send_refresh_request(
+        Authority::ManagedNode(name),
+        dst,
+        vec![],
+        MessageId::new(),
+    ) {
+        Err(InterfaceError::BadAuthority { .. }) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+    unwrap!(nodes[0]
+        .inner
+        .send_refresh_request(src, dst, vec![], MessageId::new()));
+}
+
+#[test]
+fn refresh_with_cause() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let name = nodes[0].name();
+    match nodes[0]
+        .inner
+        .send_refresh(Authority::ManagedNode(name), 7, vec![1, 2, 3], None)
+    {
+        Err(InterfaceError::BadAuthority { .. }) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+
+    let src = Authority::NaeManager(name);
+    let cause = nodes[1].name();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        unwrap!(node.inner.send_refresh(src, 7, vec![1, 2, 3], Some(cause)));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let expected_id = MessageId::from_refresh_cause(&cause, 7);
+    let expected_content = unwrap!(serialisation::serialise(&(7u64, vec![1u8, 2, 3])));
+    let senders: BTreeSet<_> = nodes
+        .iter()
+        .filter(|n| n.is_recipient(&src))
+        .map(TestNode::name)
+        .collect();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                    request: Request::Refresh(content, msg_id),
+                    signers,
+                    ..
+                }) => {
+                    assert_eq!(msg_id, expected_id);
+                    assert_eq!(content, expected_content);
+                    // The event names the members whose signatures the refresh accumulated.
+                    assert!(!signers.is_empty());
+                    assert!(signers.iter().all(|signer| senders.contains(signer.name())));
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => panic!("{} did not receive the refresh.", node.name()),
+            }
+        }
+    }
+}
+
+#[test]
+fn refresh_with_churn_event_as_cause() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let src = Authority::NaeManager(nodes[0].name());
+    match nodes[0].inner.send_refresh(src, 7, vec![1, 2, 3], None) {
+        Err(InterfaceError::InvalidState) => (),
+        result => panic!("Unexpected result {:?}", result),
+    }
+
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+    let new_name = unwrap!(nodes.last()).name();
+
+    // Each member derives the cause from the `NodeAdded` event it received for the new node.
+    for node in nodes
+        .iter_mut()
+        .filter(|n| n.is_recipient(&src) && n.name() != new_name)
+    {
+        while node.try_next_ev().is_ok() {}
+        unwrap!(node.inner.send_refresh(src, 7, vec![1, 2, 3], None));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let expected_id = MessageId::from_refresh_cause(&new_name, 7);
+    for node in nodes
+        .iter_mut()
+        .filter(|n| n.is_recipient(&src) && n.name() != new_name)
+    {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::Request {
+                    request: Request::Refresh(_, msg_id),
+                    ..
+                }) => {
+                    assert_eq!(msg_id, expected_id);
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => panic!("{} did not receive the refresh.", node.name()),
+            }
+        }
+    }
+}
+
+#[test]
+fn refresh_needed_for_each_close_group_change() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+    let dev_config = DevConfig {
+        refresh_needed_events: Some(true),
+        ..DevConfig::default()
+    };
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .dev_config(dev_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+    let observer_name = unwrap!(nodes.last()).name();
+    while unwrap!(nodes.last_mut()).try_next_ev().is_ok() {}
+
+    let close_group = |nodes: &[TestNode]| {
+        nodes
+            .iter()
+            .map(TestNode::name)
+            .sorted_by(|lhs, rhs| observer_name.cmp_distance(lhs, rhs))
+            .into_iter()
+            .take(min_section_size)
+            .collect::<BTreeSet<_>>()
+    };
+    let old_close_group = close_group(&nodes);
+
+    // The node closest to the observer leaves, and the next closest one joins its close group.
+    let closest = unwrap!(nodes
+        .iter()
+        .map(TestNode::name)
+        .filter(|name| *name != observer_name)
+        .min_by(|lhs, rhs| observer_name.cmp_distance(lhs, rhs)));
+    nodes.retain(|node| node.name() != closest);
+    poll_and_resend(&mut nodes, &mut []);
+    let changed = old_close_group
+        .symmetric_difference(&close_group(&nodes))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    assert_eq!(changed.len(), 2);
+
+    let mut causes = Vec::new();
+    while let Ok(event) = unwrap!(nodes.last_mut()).try_next_ev() {
+        if let Event::RefreshNeeded { cause } = event {
+            causes.push(cause);
+        }
+    }
+    assert_eq!(causes.len(), changed.len());
+    assert_eq!(causes.into_iter().collect::<BTreeSet<_>>(), changed);
+}
+
+#[test]
+fn user_message_from_group() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes(&network, min_section_size + 1);
+
+    let src = Authority::NaeManager(nodes[0].name());
+    let dst_name = nodes[1].name();
+    let dst = Authority::ManagedNode(dst_name);
+    let msg_id = MessageId::new();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        unwrap!(node
+            .inner
+            .send_user_message(src, dst, 42, vec![1, 2, 3], msg_id));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+
+    // The group's messages are accumulated, so the message is delivered exactly once.
+    let mut received = 0;
+    for node in nodes.iter_mut().filter(|n| n.name() == dst_name) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::UserMessage {
+                tag,
+                payload,
+                src: msg_src,
+                dst: msg_dst,
+            } = event
+            {
+                assert_eq!(tag, 42);
+                assert_eq!(payload, vec![1, 2, 3]);
+                assert_eq!(msg_src, src);
+                assert_eq!(msg_dst, dst);
+                received += 1;
+            }
+        }
+    }
+    assert_eq!(received, 1);
+}
+
+#[test]
+fn accumulation_failure_is_only_accepted_from_source_section() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    for node in nodes.iter_mut() {
+        while node.try_next_ev().is_ok() {}
+    }
+
+    let src = Authority::NaeManager(nodes[0].name());
+    let dst = Authority::ManagedNode(nodes[1].name());
+    let msg_id = MessageId::new();
+
+    // A node outside the source section can't make its members believe a message failed.
+    let forger = unwrap!(nodes
+        .iter()
+        .position(|node| !node.routing_table().our_prefix().matches(&src.name())));
+    nodes[forger]
+        .inner
+        .report_accumulation_failed(src, msg_id, dst);
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::AccumulationFailed { .. } = event {
+                panic!("{} accepted a forged report.", node.name());
+            }
+        }
+    }
+
+    // The closest member to the group's name accumulates its signatures for at least one route.
+    nodes[0].inner.report_accumulation_failed(src, msg_id, dst);
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        loop {
+            match node.try_next_ev() {
+                Ok(Event::AccumulationFailed {
+                    message_id,
+                    src: failed_src,
+                    dst: failed_dst,
+                }) => {
+                    assert_eq!(message_id, msg_id);
+                    assert_eq!(failed_src, src);
+                    assert_eq!(failed_dst, dst);
+                    break;
+                }
+                Ok(_) => (),
+                Err(_) => panic!("{} did not receive the report.", node.name()),
+            }
+        }
+    }
+}
+
+#[test]
+fn proxy_accounts_relayed_traffic() {
+    let min_section_size = 8;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes(&network, min_section_size);
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    let dev_config = DevConfig {
+        relay_accounting_events: Some(true),
+        ..DevConfig::default()
+    };
+    nodes.push(
+        TestNode::builder(&network)
+            .bootstrap_config(bootstrap_config)
+            .dev_config(dev_config)
+            .create(),
+    );
+    poll_and_resend(&mut nodes, &mut []);
+    let proxy = nodes.len() - 1;
+    expect_any_event!(nodes[proxy], Event::Connected);
+
+    let proxy_config = BootstrapConfig::with_contacts(&[nodes[proxy].handle.endpoint()]);
+    let mut clients = vec![TestClient::new(&network, Some(proxy_config), None)];
+    let _ = poll_all(&mut nodes, &mut clients);
+    expect_next_event!(clients[0], Event::Connected);
+
+    let data = gen_immutable_data(&mut rng, 1024);
+    let dst = Authority::NaeManager(*data.name());
+    unwrap!(clients[0]
+        .inner
+        .get_idata(dst, *data.name(), MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    FakeClock::advance_time(TICK_TIMEOUT_SECS * 1000 + 1);
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    let client_id = *clients[0].full_id.public_id();
+    loop {
+        match nodes[proxy].try_next_ev() {
+            Ok(Event::RelayAccounting(counters)) => {
+                let traffic = counters[&client_id];
+                assert!(traffic.msgs_from_client >= 1);
+                assert!(traffic.bytes_from_client > 0);
+                break;
+            }
+            Ok(_) => (),
+            Err(_) => panic!("The proxy did not report the relayed traffic."),
+        }
+    }
+}
+
 #[test]
 fn successful_get_request() {
     let min_section_size = 8;
@@ -92,6 +552,7 @@ fn successful_get_request() {
                         },
                     src,
                     dst,
+                    ..
                 }) => {
                     request_received_count += 1;
                     if data.name() == req_name && message_id == req_message_id {
@@ -176,6 +637,7 @@ fn failed_get_request() {
                         },
                     src,
                     dst,
+                    ..
                 }) => {
                     request_received_count += 1;
                     if data.name() == req_name && message_id == *req_message_id {
@@ -260,6 +722,7 @@ fn disconnect_on_get_request() {
                         },
                     src,
                     dst,
+                    ..
                 }) => {
                     request_received_count += 1;
                     if data.name() == req_name && message_id == *req_message_id {
@@ -302,3 +765,163 @@ fn disconnect_on_get_request() {
         }
     }
 }
+
+/// Rejects large immutable data and all refreshes.
+struct TestValidator;
+
+impl RequestValidator for TestValidator {
+    fn validate(
+        &self,
+        _src: &Authority<XorName>,
+        _dst: &Authority<XorName>,
+        request: &Request,
+    ) -> Result<(), ClientError> {
+        match *request {
+            Request::PutIData { ref data, .. } if data.value().len() > 100 => {
+                Err(ClientError::DataTooLarge)
+            }
+            Request::Refresh(..) => Err(ClientError::InvalidOperation),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn request_validator() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    let mut rng = network.new_rng();
+    let mut nodes = Nodes(vec![TestNode::builder(&network)
+        .first()
+        .endpoint(Endpoint(0))
+        .request_validator(Box::new(TestValidator))
+        .create()]);
+    let _ = nodes[0].poll();
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    for i in 1..(min_section_size + 1) {
+        nodes.push(
+            TestNode::builder(&network)
+                .bootstrap_config(bootstrap_config.clone())
+                .endpoint(Endpoint(i))
+                .request_validator(Box::new(TestValidator))
+                .create(),
+        );
+        poll_and_resend(&mut nodes, &mut []);
+    }
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+    let dst = Authority::ClientManager(clients[0].name());
+    for node in nodes.iter_mut() {
+        while node.try_next_ev().is_ok() {}
+    }
+
+    // Valid requests are raised.
+    let data = gen_immutable_data(&mut rng, 100);
+    let message_id = MessageId::new();
+    unwrap!(clients[0].inner.put_idata(dst, data, message_id));
+    let _ = poll_all(&mut nodes, &mut clients);
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&dst)) {
+        expect_any_event!(
+            node,
+            Event::Request {
+                request: Request::PutIData { msg_id, .. },
+                ..
+            } if msg_id == message_id
+        );
+    }
+
+    // Invalid requests are answered with the validator's error instead.
+    for _ in 0..MAX_REJECTED_REQUESTS {
+        let data = gen_immutable_data(&mut rng, 1024);
+        let message_id = MessageId::new();
+        unwrap!(clients[0].inner.put_idata(dst, data, message_id));
+        let _ = poll_all(&mut nodes, &mut clients);
+        expect_any_event!(
+            clients[0],
+            Event::Response {
+                response: Response::PutIData {
+                    res: Err(ClientError::DataTooLarge),
+                    msg_id,
+                },
+                ..
+            } if msg_id == message_id
+        );
+    }
+
+    // The client had too many requests rejected: its further ones are dropped without a response.
+    let data = gen_immutable_data(&mut rng, 100);
+    unwrap!(clients[0].inner.put_idata(dst, data, MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+    while let Ok(event) = clients[0].try_next_ev() {
+        if let Event::Response { .. } = event {
+            panic!("Unexpected {:?}", event);
+        }
+    }
+    for node in nodes.iter_mut() {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request { .. } = event {
+                panic!("Unexpected {:?}", event);
+            }
+        }
+    }
+
+    // Refreshes are checked by the validator as well.
+    let src = Authority::NaeManager(nodes[0].name());
+    let cause = nodes[1].name();
+    for node in nodes.iter_mut().filter(|n| n.is_recipient(&src)) {
+        unwrap!(node.inner.send_refresh(src, 7, vec![1, 2, 3], Some(cause)));
+    }
+    let _ = poll_all(&mut nodes, &mut []);
+    for node in nodes.iter_mut() {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request { .. } = event {
+                panic!("Unexpected {:?}", event);
+            }
+        }
+    }
+}
+
+#[test]
+fn client_message_detours_via_second_proxy() {
+    let network = Network::new(8, None);
+    let mut rng = network.new_rng();
+    let mut nodes = create_connected_nodes_until_split(&network, vec![1, 1], false);
+    let mut clients = create_connected_clients(&network, &mut nodes, 1);
+
+    let proxy_prefix = *nodes[0].routing_table().our_prefix();
+    let data = loop {
+        let data = gen_immutable_data(&mut rng, 8);
+        if !proxy_prefix.matches(data.name()) {
+            break data;
+        }
+    };
+    let dst = Authority::NaeManager(*data.name());
+
+    // The proxy can't reach the data's section anymore, but hasn't noticed yet, so it still has
+    // the section's members as its routing targets.
+    let proxy_endpoint = nodes[0].handle.endpoint();
+    for node in &nodes[1..] {
+        if !proxy_prefix.matches(&node.name()) {
+            network.unreported_lost_connection(proxy_endpoint, node.handle.endpoint());
+        }
+    }
+    unwrap!(clients[0]
+        .inner
+        .get_idata(dst, *data.name(), MessageId::new()));
+    let _ = poll_all(&mut nodes, &mut clients);
+
+    // It relayed the request via a member of its own section instead of dropping it.
+    let mut received = 0;
+    for node in nodes.iter_mut().filter(|node| node.is_recipient(&dst)) {
+        while let Ok(event) = node.try_next_ev() {
+            if let Event::Request {
+                request: Request::GetIData { name, .. },
+                ..
+            } = event
+            {
+                assert_eq!(name, *data.name());
+                received += 1;
+            }
+        }
+    }
+    assert_ne!(received, 0);
+}
@@ -8,13 +8,16 @@
 
 use super::{
     add_connected_nodes_until_split, create_connected_nodes, poll_all, poll_and_resend,
-    verify_invariant_for_all_nodes, TestNode,
+    verify_invariant_for_all_nodes, Nodes, TestNode,
 };
 use fake_clock::FakeClock;
 use itertools::Itertools;
 use routing::mock_crust::{crust, Endpoint, Network};
-use routing::test_consts::CONNECTED_PEER_TIMEOUT_SECS;
-use routing::{BootstrapConfig, Event, EventStream, Prefix, PublicId, XorName, XOR_NAME_LEN};
+use routing::test_consts::{CONNECTED_PEER_TIMEOUT_SECS, STANDBY_TUNNEL_SECS};
+use routing::{
+    Authority, BootstrapConfig, DevConfig, Event, EventStream, MessageId, Prefix, PublicId,
+    XorName, XOR_NAME_LEN,
+};
 
 #[test]
 fn failing_connections_ring() {
@@ -262,7 +265,7 @@ fn verify_tunnel_switch(nodes: &mut Vec<TestNode>, node: usize, client_1: usize,
                 assert!(name == nodes[node].name() || name == nodes[client_2].name());
                 assert_eq!(event_count, 2);
             }
-            Event::Tick => {}
+            Event::RoutingTableGrowth { .. } | Event::Tick => {}
             _ => panic!(
                 "{:?} received unexpected event {:?}",
                 nodes[client_1].name(),
@@ -281,7 +284,7 @@ fn verify_tunnel_switch(nodes: &mut Vec<TestNode>, node: usize, client_1: usize,
                 assert_eq!(name, nodes[client_1].name());
                 assert_eq!(event_count, 1);
             }
-            Event::Tick => {}
+            Event::RoutingTableGrowth { .. } | Event::Tick => {}
             _ => panic!(
                 "{:?} received unexpected event {:?}",
                 nodes[client_2].name(),
@@ -438,3 +441,85 @@ fn avoid_tunnelling_when_proxying() {
         ))
     );
 }
+
+#[test]
+fn standby_tunnel_fail_over() {
+    let min_section_size = 5;
+    let network = Network::new(min_section_size, None);
+    network.block_connection(Endpoint(2), Endpoint(3));
+    network.block_connection(Endpoint(3), Endpoint(2));
+
+    let mut nodes = Nodes(vec![TestNode::builder(&network)
+        .first()
+        .endpoint(Endpoint(0))
+        .create()]);
+    let _ = nodes[0].poll();
+    let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
+    for i in 1..min_section_size {
+        let dev_config = DevConfig {
+            standby_tunnels: Some(i == 2 || i == 3),
+            ..DevConfig::default()
+        };
+        nodes.push(
+            TestNode::builder(&network)
+                .bootstrap_config(bootstrap_config.clone())
+                .endpoint(Endpoint(i))
+                .dev_config(dev_config)
+                .create(),
+        );
+        poll_and_resend(&mut nodes, &mut []);
+    }
+    verify_invariant_for_all_nodes(&mut nodes);
+    let (id_2, id_3) = (nodes[2].id(), nodes[3].id());
+    let tunnel_node = unwrap!(locate_tunnel_node(&nodes, id_2, id_3));
+
+    // The direct connection succeeds late: both keep the tunnel as a standby route.
+    network.unblock_connection(Endpoint(2), Endpoint(3));
+    network.unblock_connection(Endpoint(3), Endpoint(2));
+    network.late_connection(Endpoint(2), Endpoint(3));
+    let _ = poll_all(&mut nodes, &mut []);
+    assert!(nodes[tunnel_node].inner.has_tunnel_clients(id_2, id_3));
+    while nodes.iter_mut().any(|node| node.try_next_ev().is_ok()) {}
+
+    // Only node 2 notices the connection is lost. Node 3 follows it over to the tunnel as soon as
+    // a message arrives through it.
+    network.send_crust_event(Endpoint(2), crust::Event::LostPeer(id_3));
+    let _ = poll_all(&mut nodes, &mut []);
+    let dst = Authority::ManagedNode(nodes[3].name());
+    unwrap!(nodes[2].inner.send_user_message(
+        Authority::ManagedNode(nodes[2].name()),
+        dst,
+        42,
+        vec![1, 2, 3],
+        MessageId::new(),
+    ));
+    let _ = poll_all(&mut nodes, &mut []);
+
+    let mut received = false;
+    while let Ok(event) = nodes[3].try_next_ev() {
+        match event {
+            Event::UserMessage { tag: 42, .. } => received = true,
+            Event::NodeLost(..) => panic!("Node 3 lost a node."),
+            _ => (),
+        }
+    }
+    assert!(received);
+    while let Ok(event) = nodes[2].try_next_ev() {
+        if let Event::NodeLost(..) = event {
+            panic!("Node 2 lost a node.");
+        }
+    }
+    assert!(nodes[2].routing_table().has(&nodes[3].name()));
+    assert!(nodes[3].routing_table().has(&nodes[2].name()));
+    assert!(nodes[tunnel_node].inner.has_tunnel_clients(id_2, id_3));
+    verify_invariant_for_all_nodes(&mut nodes);
+
+    // After connecting directly again, the standby tunnel is only kept for a limited time.
+    network.late_connection(Endpoint(2), Endpoint(3));
+    let _ = poll_all(&mut nodes, &mut []);
+    assert!(nodes[tunnel_node].inner.has_tunnel_clients(id_2, id_3));
+    FakeClock::advance_time(STANDBY_TUNNEL_SECS * 1000 + 1);
+    let _ = poll_all(&mut nodes, &mut []);
+    assert!(!nodes[tunnel_node].inner.has_tunnel_clients(id_2, id_3));
+    verify_invariant_for_all_nodes(&mut nodes);
+}
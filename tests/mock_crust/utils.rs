@@ -16,8 +16,8 @@ use routing::mock_crust::{self, Endpoint, Network, ServiceHandle};
 use routing::test_consts::{ACK_TIMEOUT_SECS, CONNECTING_PEER_TIMEOUT_SECS};
 use routing::{
     verify_network_invariant, Authority, BootstrapConfig, Cache, Client, Config, DevConfig, Event,
-    EventStream, FullId, ImmutableData, Node, NullCache, Prefix, PublicId, Request, Response,
-    RoutingTable, XorName, Xorable,
+    EventStream, FullId, ImmutableData, Node, NullCache, Prefix, PublicId, Request,
+    RequestValidator, Response, RoutingTable, XorName, Xorable,
 };
 use std::cell::RefCell;
 use std::collections::{BTreeSet, HashMap};
@@ -37,6 +37,11 @@ const MAX_POLL_CALLS: usize = 1000;
 // Duration clients expect a response by.
 const CLIENT_MSG_EXPIRY_DUR_SECS: u64 = 90;
 
+// Number of times to find nothing to poll, with a pause in between, before concluding that the
+// nodes' verifier threads have no more results to deliver.
+const WORKER_IDLE_POLLS: usize = 3;
+const WORKER_POLL_PAUSE_MILLIS: u64 = 10;
+
 // -----  Random number generation  -----
 
 pub fn gen_range<T: Rng>(rng: &mut T, low: usize, high: usize) -> usize {
@@ -59,11 +64,37 @@ pub fn gen_range_except<T: Rng>(
     x
 }
 
-fn create_config(network: &Network<PublicId>) -> Config {
+// Returns the config for a node or client, with the settings the tests rely on, unless
+// `dev_config` overrides them.
+fn create_config(network: &Network<PublicId>, dev_config: DevConfig) -> Config {
     Config {
         dev: Some(DevConfig {
             min_section_size: Some(network.min_section_size()),
-            ..DevConfig::default()
+            // Messages are expected to be handled as soon as they are delivered.
+            verifier_threads: dev_config.verifier_threads.or(Some(0)),
+            // The connections between the nodes are checked, so nodes keep their proxies.
+            retire_proxy: dev_config.retire_proxy.or(Some(false)),
+            // The networks are too small to do without the signatures of new nodes.
+            quarantine_secs: dev_config.quarantine_secs.or(Some(0)),
+            // Only the tests which check these events enable them.
+            relay_accounting_events: dev_config.relay_accounting_events.or(Some(false)),
+            refresh_needed_events: dev_config.refresh_needed_events.or(Some(false)),
+            section_load_events: dev_config.section_load_events.or(Some(false)),
+            // Clients are expected to terminate when they lose their proxy.
+            client_rebootstrap: dev_config.client_rebootstrap.or(Some(false)),
+            // Messages for lost clients are expected to be dropped right away.
+            client_grace_period_secs: dev_config.client_grace_period_secs.or(Some(0)),
+            // The tests add nodes far faster than a real network would.
+            max_joins_per_interval: dev_config.max_joins_per_interval.or(Some(std::usize::MAX)),
+            // For the same reason, they churn far faster than real networks.
+            churn_storm_changes: dev_config.churn_storm_changes.or(Some(std::usize::MAX)),
+            // Messages are expected to be delivered as soon as they are sent.
+            send_budget: dev_config.send_budget.or(Some(std::u64::MAX)),
+            // A failed bootstrap is expected to terminate straight away.
+            bootstrap_retry_rounds: dev_config.bootstrap_retry_rounds.or(Some(0)),
+            // Mock Crust doesn't implement service discovery.
+            lan_discovery: dev_config.lan_discovery.or(Some(false)),
+            ..dev_config
         }),
     }
 }
@@ -130,24 +161,39 @@ impl TestNode {
             bootstrap_config: None,
             endpoint: None,
             cache: Box::new(NullCache),
+            rng_seed: None,
+            dev_config: DevConfig::default(),
+            request_validator: None,
         }
     }
 
+    #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
     pub fn new(
         network: &Network<PublicId>,
         first_node: bool,
         bootstrap_config: Option<BootstrapConfig>,
         endpoint: Option<Endpoint>,
         cache: Box<Cache>,
+        rng_seed: Option<[u32; 4]>,
+        dev_config: DevConfig,
+        request_validator: Option<Box<RequestValidator>>,
     ) -> Self {
         let handle = network.new_service_handle(bootstrap_config, endpoint);
-        let config = create_config(network);
+        let config = create_config(network, dev_config);
         let node = mock_crust::make_current(&handle, || {
-            unwrap!(Node::builder()
+            let builder = Node::builder()
                 .cache(cache)
                 .first(first_node)
-                .config(config)
-                .create())
+                .config(config);
+            let builder = match rng_seed {
+                Some(seed) => builder.rng_seed(seed),
+                None => builder,
+            };
+            let builder = match request_validator {
+                Some(validator) => builder.request_validator(validator),
+                None => builder,
+            };
+            unwrap!(builder.create())
         });
 
         TestNode {
@@ -186,6 +232,9 @@ pub struct TestNodeBuilder<'a> {
     bootstrap_config: Option<BootstrapConfig>,
     endpoint: Option<Endpoint>,
     cache: Box<Cache>,
+    rng_seed: Option<[u32; 4]>,
+    dev_config: DevConfig,
+    request_validator: Option<Box<RequestValidator>>,
 }
 
 impl<'a> TestNodeBuilder<'a> {
@@ -214,6 +263,31 @@ impl<'a> TestNodeBuilder<'a> {
         self
     }
 
+    /// Uses a cache which already holds `data`.
+    pub fn cached_data(mut self, data: &[ImmutableData]) -> Self {
+        let cache = TestCache::new();
+        for data in data {
+            let _ = cache.0.borrow_mut().insert(*data.name(), data.clone());
+        }
+        self.cache = Box::new(cache);
+        self
+    }
+
+    pub fn rng_seed(mut self, seed: [u32; 4]) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    pub fn dev_config(mut self, dev_config: DevConfig) -> Self {
+        self.dev_config = dev_config;
+        self
+    }
+
+    pub fn request_validator(mut self, validator: Box<RequestValidator>) -> Self {
+        self.request_validator = Some(validator);
+        self
+    }
+
     pub fn create(self) -> TestNode {
         TestNode::new(
             self.network,
@@ -221,6 +295,9 @@ impl<'a> TestNodeBuilder<'a> {
             self.bootstrap_config,
             self.endpoint,
             self.cache,
+            self.rng_seed,
+            self.dev_config,
+            self.request_validator,
         )
     }
 }
@@ -250,7 +327,33 @@ impl TestClient {
         full_id: FullId,
     ) -> Self {
         let duration = Duration::from_secs(CLIENT_MSG_EXPIRY_DUR_SECS);
-        Self::new_impl(network, bootstrap_config, endpoint, full_id, duration)
+        let dev_config = DevConfig::default();
+        Self::new_impl(
+            network,
+            bootstrap_config,
+            endpoint,
+            full_id,
+            duration,
+            dev_config,
+        )
+    }
+
+    pub fn new_with_dev_config(
+        network: &Network<PublicId>,
+        bootstrap_config: Option<BootstrapConfig>,
+        endpoint: Option<Endpoint>,
+        dev_config: DevConfig,
+    ) -> Self {
+        let full_id = FullId::new();
+        let duration = Duration::from_secs(CLIENT_MSG_EXPIRY_DUR_SECS);
+        Self::new_impl(
+            network,
+            bootstrap_config,
+            endpoint,
+            full_id,
+            duration,
+            dev_config,
+        )
     }
 
     pub fn new_with_expire_duration(
@@ -260,7 +363,15 @@ impl TestClient {
         duration: Duration,
     ) -> Self {
         let full_id = FullId::new();
-        Self::new_impl(network, bootstrap_config, endpoint, full_id, duration)
+        let dev_config = DevConfig::default();
+        Self::new_impl(
+            network,
+            bootstrap_config,
+            endpoint,
+            full_id,
+            duration,
+            dev_config,
+        )
     }
 
     fn new_impl(
@@ -269,13 +380,14 @@ impl TestClient {
         endpoint: Option<Endpoint>,
         full_id: FullId,
         duration: Duration,
+        dev_config: DevConfig,
     ) -> Self {
         let handle = network.new_service_handle(bootstrap_config.clone(), endpoint);
         let client = mock_crust::make_current(&handle, || {
             unwrap!(Client::new(
                 Some(full_id.clone()),
                 bootstrap_config,
-                create_config(network),
+                create_config(network, dev_config),
                 duration,
             ))
         });
@@ -324,6 +436,10 @@ impl Cache for TestCache {
             let _ = self.0.borrow_mut().insert(*data.name(), data);
         }
     }
+
+    fn immutable_data(&self) -> Vec<ImmutableData> {
+        self.0.borrow().values().cloned().collect()
+    }
 }
 
 // -----  poll_all, create_connected_...  -----
@@ -352,11 +468,40 @@ pub fn poll_all(nodes: &mut [TestNode], clients: &mut [TestClient]) -> bool {
     panic!("Polling has been called {} times.", MAX_POLL_CALLS);
 }
 
+/// Like `poll_all`, but also waits for the results of the nodes' verifier threads, which arrive in
+/// real time rather than when messages are delivered.
+pub fn poll_all_with_workers(nodes: &mut [TestNode], clients: &mut [TestClient]) -> bool {
+    let mut result = false;
+    let mut idle_polls = 0;
+    while idle_polls < WORKER_IDLE_POLLS {
+        if poll_all(nodes, clients) {
+            result = true;
+            idle_polls = 0;
+        } else {
+            idle_polls += 1;
+            thread::sleep(Duration::from_millis(WORKER_POLL_PAUSE_MILLIS));
+        }
+    }
+    result
+}
+
 /// Polls and processes all events, until there are no unacknowledged messages left.
 pub fn poll_and_resend(nodes: &mut [TestNode], clients: &mut [TestClient]) {
+    poll_and_resend_using(nodes, clients, poll_all)
+}
+
+/// Like `poll_and_resend`, for nodes whose verifier threads are enabled.
+pub fn poll_and_resend_with_workers(nodes: &mut [TestNode], clients: &mut [TestClient]) {
+    poll_and_resend_using(nodes, clients, poll_all_with_workers)
+}
+
+fn poll_and_resend_using<F>(nodes: &mut [TestNode], clients: &mut [TestClient], poll: F)
+where
+    F: Fn(&mut [TestNode], &mut [TestClient]) -> bool,
+{
     let mut fired_connecting_peer_timeout = false;
     for _ in 0..MAX_POLL_CALLS {
-        if poll_all(nodes, clients) {
+        if poll(nodes, clients) {
             // Once each route is polled, advance time to trigger the following route.
             FakeClock::advance_time(ACK_TIMEOUT_SECS * 1000 + 1);
         } else if !fired_connecting_peer_timeout {
@@ -414,6 +559,7 @@ pub fn create_connected_nodes_with_cache(
             .create(),
     );
     let _ = nodes[0].poll();
+    expect_next_event!(nodes[0], Event::NetworkStarted);
 
     let bootstrap_config = BootstrapConfig::with_contacts(&[nodes[0].handle.endpoint()]);
 
@@ -441,8 +587,11 @@ pub fn create_connected_nodes_with_cache(
             match event {
                 Event::NodeAdded(..) => node_added_count += 1,
                 Event::NodeLost(..)
+                | Event::NodeReplaced { .. }
+                | Event::ConnectFailed(..)
                 | Event::SectionSplit(..)
                 | Event::RestartRequired
+                | Event::RoutingTableGrowth { .. }
                 | Event::Tick => (),
                 event => panic!("Got unexpected event: {:?}", event),
             }
@@ -585,6 +734,9 @@ pub fn add_connected_nodes_until_split(
             match event {
                 Event::NodeAdded(..)
                 | Event::NodeLost(..)
+                | Event::NodeReplaced { .. }
+                | Event::ConnectFailed(..)
+                | Event::RoutingTableGrowth { .. }
                 | Event::Tick
                 | Event::SectionSplit(..) => (),
                 event => panic!("Got unexpected event: {:?}", event),